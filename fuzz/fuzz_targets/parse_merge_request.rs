@@ -0,0 +1,21 @@
+//! `cargo +nightly fuzz run parse_merge_request`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The binary crate has no library target, so the grammar is compiled in directly.
+#[allow(dead_code)]
+#[path = "../../src/title.rs"]
+mod title;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(mr) = title::parse_merge_request(&mut &*input) {
+        // Whatever parses must survive a round trip through the canonical form.
+        let canonical = mr.to_string();
+        assert_eq!(title::parse_merge_request(&mut canonical.as_str()), Ok(mr));
+    }
+});
@@ -0,0 +1,274 @@
+use std::fmt;
+
+use anyhow::Context;
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TokenKind {
+    /// A personal, project, group or impersonation access token.
+    Personal,
+    /// A CI job token (`CI_JOB_TOKEN`).
+    Job,
+    /// An OAuth2 bearer token.
+    #[value(name = "oauth2")]
+    OAuth2,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Personal => f.write_str("personal access token"),
+            TokenKind::Job => f.write_str("job token"),
+            TokenKind::OAuth2 => f.write_str("oauth2 token"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    Flag,
+    Env(&'static str),
+    Keyring,
+    /// Typed in when `auth login` asked for it.
+    Prompt,
+    /// No token is needed because nothing is sent under `--replay`.
+    Replay,
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::Flag => f.write_str("--token"),
+            TokenSource::Env(var) => f.write_str(var),
+            TokenSource::Keyring => f.write_str("the OS keyring"),
+            TokenSource::Prompt => f.write_str("the prompt"),
+            TokenSource::Replay => f.write_str("the replay fixtures"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Credentials {
+    pub token: String,
+    pub kind: TokenKind,
+    pub source: TokenSource,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("kind", &self.kind)
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A place a token is looked for when there is no `--token`.
+#[derive(Debug, Clone, Copy)]
+enum Lookup {
+    Env(&'static str, TokenKind),
+    /// The token stored with `auth login`.
+    Keyring,
+}
+
+impl Lookup {
+    fn source(self) -> TokenSource {
+        match self {
+            Lookup::Env(var, _) => TokenSource::Env(var),
+            Lookup::Keyring => TokenSource::Keyring,
+        }
+    }
+}
+
+/// Where tokens are looked for after `--token`, in order of precedence.
+///
+/// `CI_JOB_TOKEN` is only honoured inside a pipeline (when `CI` is set), so a
+/// stale job token exported in a local shell never shadows the developer's own.
+const CHAIN: &[Lookup] = &[
+    Lookup::Env("GITLAB_TOKEN", TokenKind::Personal),
+    Lookup::Env("ACCESS_TOKEN", TokenKind::Personal),
+    Lookup::Env("GITLAB_IMPERSONATION_TOKEN", TokenKind::Personal),
+    Lookup::Keyring,
+    Lookup::Env("CI_JOB_TOKEN", TokenKind::Job),
+    Lookup::Env("GITLAB_OAUTH_TOKEN", TokenKind::OAuth2),
+];
+
+fn read_env(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+const KEYRING_SERVICE: &str = "gitlab-helper";
+
+fn keyring_entry(host: &str) -> anyhow::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, host).context("failed to open the OS keyring")
+}
+
+fn read_keyring(host: &str) -> Option<String> {
+    match keyring_entry(host).and_then(|entry| Ok(entry.get_password()?)) {
+        Ok(token) => Some(token),
+        Err(err) => {
+            tracing::debug!("no token in the OS keyring for {host}: {err:#}");
+            None
+        }
+    }
+}
+
+pub fn store(host: &str, token: &str) -> anyhow::Result<()> {
+    keyring_entry(host)?
+        .set_password(token)
+        .context("failed to store the token in the OS keyring")
+}
+
+pub fn forget(host: &str) -> anyhow::Result<bool> {
+    match keyring_entry(host)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(err) => Err(err).context("failed to remove the token from the OS keyring"),
+    }
+}
+
+pub fn resolve(
+    host: &str,
+    explicit: Option<String>,
+    kind: Option<TokenKind>,
+) -> anyhow::Result<Credentials> {
+    resolve_from(
+        explicit,
+        kind,
+        std::env::var("CI").is_ok(),
+        read_env,
+        || read_keyring(host),
+    )
+}
+
+fn resolve_from(
+    explicit: Option<String>,
+    kind: Option<TokenKind>,
+    in_ci: bool,
+    env: impl Fn(&str) -> Option<String>,
+    keyring: impl Fn() -> Option<String>,
+) -> anyhow::Result<Credentials> {
+    if let Some(token) = explicit {
+        return Ok(Credentials {
+            token,
+            kind: kind.unwrap_or(TokenKind::Personal),
+            source: TokenSource::Flag,
+        });
+    }
+
+    for &lookup in CHAIN {
+        let (token, default_kind) = match lookup {
+            Lookup::Env("CI_JOB_TOKEN", _) if !in_ci => continue,
+            Lookup::Env(var, default_kind) => (env(var), default_kind),
+            Lookup::Keyring => (keyring(), TokenKind::Personal),
+        };
+        if let Some(token) = token {
+            return Ok(Credentials {
+                token,
+                kind: kind.unwrap_or(default_kind),
+                source: lookup.source(),
+            });
+        }
+    }
+
+    let tried = std::iter::once(TokenSource::Flag)
+        .chain(CHAIN.iter().map(|lookup| lookup.source()))
+        .map(|source| source.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    anyhow::bail!("No GitLab token found (tried {tried}); CI_JOB_TOKEN is only used when CI is set")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Resolves with every environment variable in `set` holding its own name.
+    fn source(set: &[&str], keyring: bool, in_ci: bool) -> Option<TokenSource> {
+        resolve_from(
+            None,
+            None,
+            in_ci,
+            |var| set.contains(&var).then(|| var.to_owned()),
+            || keyring.then(|| "stored".to_owned()),
+        )
+        .ok()
+        .map(|credentials| credentials.source)
+    }
+
+    #[test]
+    fn the_flag_wins() {
+        let credentials = resolve_from(
+            Some("flag".to_owned()),
+            None,
+            true,
+            |var| Some(var.to_owned()),
+            || Some("stored".to_owned()),
+        )
+        .unwrap();
+        assert_eq!(credentials.source, TokenSource::Flag);
+        assert_eq!(credentials.kind, TokenKind::Personal);
+    }
+
+    #[test]
+    fn sources_are_tried_in_order() {
+        let all = [
+            "GITLAB_TOKEN",
+            "ACCESS_TOKEN",
+            "GITLAB_IMPERSONATION_TOKEN",
+            "CI_JOB_TOKEN",
+            "GITLAB_OAUTH_TOKEN",
+        ];
+        let expected = [
+            TokenSource::Env("GITLAB_TOKEN"),
+            TokenSource::Env("ACCESS_TOKEN"),
+            TokenSource::Env("GITLAB_IMPERSONATION_TOKEN"),
+            TokenSource::Keyring,
+            TokenSource::Env("CI_JOB_TOKEN"),
+            TokenSource::Env("GITLAB_OAUTH_TOKEN"),
+        ];
+        // Take away the winner each time and the next one in line takes over.
+        let mut set = all.to_vec();
+        let mut keyring = true;
+        for expected in expected {
+            assert_eq!(source(&set, keyring, true), Some(expected));
+            match expected {
+                TokenSource::Keyring => keyring = false,
+                _ => {
+                    set.remove(0);
+                }
+            }
+        }
+        assert_eq!(source(&set, keyring, true), None);
+    }
+
+    #[test]
+    fn the_job_token_is_only_used_in_ci() {
+        assert_eq!(
+            source(&["CI_JOB_TOKEN", "GITLAB_OAUTH_TOKEN"], false, false),
+            Some(TokenSource::Env("GITLAB_OAUTH_TOKEN"))
+        );
+        assert_eq!(source(&["CI_JOB_TOKEN"], false, false), None);
+        let job = resolve_from(
+            None,
+            None,
+            true,
+            |var| (var == "CI_JOB_TOKEN").then(|| var.to_owned()),
+            || None,
+        );
+        assert_eq!(job.unwrap().kind, TokenKind::Job);
+    }
+
+    #[test]
+    fn the_error_lists_the_sources_in_the_order_they_are_tried() {
+        let err = resolve_from(None, None, false, |_| None, || None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No GitLab token found (tried --token, GITLAB_TOKEN, ACCESS_TOKEN, \
+             GITLAB_IMPERSONATION_TOKEN, the OS keyring, CI_JOB_TOKEN, GITLAB_OAUTH_TOKEN); \
+             CI_JOB_TOKEN is only used when CI is set"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use serde::Deserialize;
+
+/// Ends every message of a commit the helper makes, so they can be told apart.
+const GENERATED_BY: &str = "Generated-by: gitlab-ci-helper";
+
+/// The `[commits]` settings for the commits the helper makes itself.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommitConfig {
+    /// Who the commits are authored by, and in the local clone committed by;
+    /// the token's user if unset.
+    pub name: Option<String>,
+    pub email: Option<String>,
+    /// Credit whoever ran the job, as CI knows them, with `Co-authored-by`.
+    pub co_authored_by: bool,
+}
+
+impl Default for CommitConfig {
+    fn default() -> Self {
+        CommitConfig {
+            name: None,
+            email: None,
+            co_authored_by: true,
+        }
+    }
+}
+
+impl CommitConfig {
+    /// The configured identity, if there is a full one.
+    pub fn identity(&self) -> Option<(&str, &str)> {
+        Some((self.name.as_deref()?, self.email.as_deref()?))
+    }
+
+    /// The user who ran the job, unless that is who the commit is by anyway.
+    fn co_author(&self) -> Option<String> {
+        if !self.co_authored_by {
+            return None;
+        }
+        let name = std::env::var("GITLAB_USER_NAME").ok()?;
+        let email = std::env::var("GITLAB_USER_EMAIL").ok()?;
+        (self.email.as_deref() != Some(email.as_str())).then(|| format!("{name} <{email}>"))
+    }
+
+    /// `message` with the trailers that tie the commit to the helper, who
+    /// ran it and what it was for.
+    pub fn message(&self, message: &str, jira_id: Option<&str>) -> String {
+        trailed(message, self.co_author().as_deref(), jira_id)
+    }
+}
+
+fn trailed(message: &str, co_author: Option<&str>, jira_id: Option<&str>) -> String {
+    let mut trailers = Vec::new();
+    if let Some(co_author) = co_author {
+        trailers.push(format!("Co-authored-by: {co_author}"));
+    }
+    if let Some(jira_id) = jira_id {
+        trailers.push(format!("Jira: {jira_id}"));
+    }
+    trailers.push(GENERATED_BY.to_owned());
+    let trailers: Vec<_> = trailers
+        .into_iter()
+        .filter(|trailer| !message.lines().any(|line| line.trim() == trailer))
+        .collect();
+    if trailers.is_empty() {
+        return message.to_owned();
+    }
+    format!("{}\n\n{}", message.trim_end(), trailers.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailers_follow_a_blank_line() {
+        assert_eq!(
+            trailed(
+                "Bump the version\n",
+                Some("Alice <alice@example.com>"),
+                Some("JIRA-1")
+            ),
+            "Bump the version\n\nCo-authored-by: Alice <alice@example.com>\nJira: JIRA-1\nGenerated-by: gitlab-ci-helper"
+        );
+    }
+
+    #[test]
+    fn trailers_are_not_repeated() {
+        let message = "Bump\n\nGenerated-by: gitlab-ci-helper";
+        assert_eq!(trailed(message, None, None), message);
+    }
+}
@@ -0,0 +1,174 @@
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::endpoints::{CreateProjectBadge, EditProjectBadge, ProjectBadges};
+use crate::workflow::Context;
+
+/// A project badge. GitLab fills in placeholders such as `%{project_path}`
+/// and `%{default_branch}` in both URLs, so one list fits every project.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Badge {
+    pub name: String,
+    /// Fills in whichever of the URLs is not set.
+    pub preset: Option<Preset>,
+    pub link_url: Option<String>,
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    /// The pipeline status of the default branch.
+    Pipeline,
+    /// The test coverage of the default branch.
+    Coverage,
+    /// The latest release.
+    Release,
+}
+
+impl Preset {
+    fn urls(self) -> (&'static str, &'static str) {
+        match self {
+            Preset::Pipeline => (
+                "https://%{gitlab_server}/%{project_path}/-/commits/%{default_branch}",
+                "https://%{gitlab_server}/%{project_path}/badges/%{default_branch}/pipeline.svg",
+            ),
+            Preset::Coverage => (
+                "https://%{gitlab_server}/%{project_path}/-/commits/%{default_branch}",
+                "https://%{gitlab_server}/%{project_path}/badges/%{default_branch}/coverage.svg",
+            ),
+            Preset::Release => (
+                "https://%{gitlab_server}/%{project_path}/-/releases",
+                "https://%{gitlab_server}/%{project_path}/-/badges/release.svg",
+            ),
+        }
+    }
+}
+
+impl Badge {
+    /// The link and image URLs, with the preset filling in the ones not set.
+    pub fn urls(&self) -> anyhow::Result<(String, String)> {
+        let preset = self.preset.map(Preset::urls);
+        let link = self
+            .link_url
+            .clone()
+            .or_else(|| preset.map(|(link, _)| link.to_owned()));
+        let image = self
+            .image_url
+            .clone()
+            .or_else(|| preset.map(|(_, image)| image.to_owned()));
+        match (link, image) {
+            (Some(link), Some(image)) => Ok((link, image)),
+            _ => anyhow::bail!(
+                "badge {} needs a preset or both link_url and image_url",
+                self.name
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentBadge {
+    id: u64,
+    #[serde(default)]
+    name: Option<String>,
+    link_url: String,
+    image_url: String,
+    kind: String,
+}
+
+/// Adds the badges `project` does not show yet and updates the URLs of the
+/// ones that changed, matching them by name. Badges of a group are only
+/// reported, since only the group can change them.
+pub fn apply(ctx: &Context, project: &str, badges: &[Badge]) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let endpoint = ProjectBadges {
+        project: project.into(),
+    };
+    let current: Vec<CurrentBadge> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    let mut changes = Vec::new();
+    for badge in badges {
+        let (link_url, image_url) = badge.urls()?;
+        let existing = current
+            .iter()
+            .find(|current| current.name.as_deref() == Some(&badge.name));
+        match existing {
+            Some(existing) if existing.link_url == link_url && existing.image_url == image_url => {}
+            Some(existing) if existing.kind != "project" => {
+                tracing::warn!(
+                    project,
+                    "badge {} comes from a group; change its URLs there",
+                    badge.name
+                );
+            }
+            existing => changes.push((
+                badge,
+                existing.map(|existing| existing.id),
+                link_url,
+                image_url,
+            )),
+        }
+    }
+    if changes.is_empty() {
+        return Ok("badges in sync".to_owned());
+    }
+    let plan: Vec<_> = changes
+        .iter()
+        .map(|(badge, id, _, image_url)| match id {
+            Some(_) => format!("update badge {} to {image_url}", badge.name),
+            None => format!("add badge {} ({image_url})", badge.name),
+        })
+        .collect();
+    ctx.confirm(project, &plan)?;
+    for (badge, id, link_url, image_url) in &changes {
+        match *id {
+            Some(id) => api::ignore(EditProjectBadge {
+                project: project.into(),
+                id,
+                link_url,
+                image_url,
+            })
+            .query(client)?,
+            None => api::ignore(CreateProjectBadge {
+                project: project.into(),
+                name: &badge.name,
+                link_url,
+                image_url,
+            })
+            .query(client)?,
+        }
+    }
+    Ok(format!("applied {} badge(s)", changes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn badge(preset: Option<Preset>, link_url: Option<&str>) -> Badge {
+        Badge {
+            name: "pipeline".to_owned(),
+            preset,
+            link_url: link_url.map(str::to_owned),
+            image_url: None,
+        }
+    }
+
+    #[test]
+    fn the_preset_fills_in_the_urls_not_set() {
+        let (link, image) = badge(Some(Preset::Pipeline), Some("https://ci.example.com"))
+            .urls()
+            .unwrap();
+        assert_eq!(link, "https://ci.example.com");
+        assert!(image.ends_with("/badges/%{default_branch}/pipeline.svg"));
+    }
+
+    #[test]
+    fn a_badge_without_a_preset_needs_both_urls() {
+        let err = badge(None, Some("https://ci.example.com"))
+            .urls()
+            .unwrap_err();
+        assert!(err.to_string().contains("badge pipeline"), "{err}");
+    }
+}
@@ -0,0 +1,212 @@
+use std::time::{Duration, Instant};
+
+use gitlab::api::common::path_escaped;
+use gitlab::api::projects::pipelines::{CreatePipeline, PipelineJobs, PipelineVariable, Pipelines};
+use gitlab::api::projects::repository::branches::{CreateBranch, DeleteBranch};
+use gitlab::api::projects::repository::commits::{CompareCommits, MergeRequests};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::cancel;
+use crate::client::Client;
+use crate::progress::Progress;
+use crate::workflow::Context;
+
+/// Set on the pipelines bisect triggers, so `rules` can skip the other jobs.
+const JOB_VARIABLE: &str = "GITLAB_HELPER_BISECT_JOB";
+
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    #[serde(default)]
+    commits: Vec<Commit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    id: String,
+    short_id: String,
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Job {
+    name: String,
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    web_url: String,
+}
+
+/// How long to wait on each pipeline.
+pub struct Polling {
+    pub poll: Duration,
+    pub wait: Duration,
+}
+
+/// The final status of `job` in `pipeline`, once it has one.
+fn job_status(
+    client: &Client,
+    project: &str,
+    pipeline: u64,
+    job: &str,
+    polling: &Polling,
+) -> anyhow::Result<String> {
+    let started = Instant::now();
+    let progress = Progress::spinner(&format!("waiting on {job} in pipeline {pipeline}"));
+    loop {
+        let endpoint = PipelineJobs::builder()
+            .project(project)
+            .pipeline(pipeline)
+            .build()?;
+        let jobs: Vec<Job> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        let Some(found) = jobs.into_iter().find(|found| found.name == job) else {
+            anyhow::bail!("pipeline {pipeline} has no {job} job");
+        };
+        if matches!(
+            found.status.as_str(),
+            "success" | "failed" | "canceled" | "skipped" | "manual"
+        ) {
+            return Ok(found.status);
+        }
+        anyhow::ensure!(
+            started.elapsed() + polling.poll <= polling.wait,
+            "{job} in pipeline {pipeline} is still {} after {:?}",
+            found.status,
+            started.elapsed()
+        );
+        progress.set_message(found.status);
+        progress.wait(polling.poll);
+        cancel::check()?;
+    }
+}
+
+/// Whether `job` passes on `commit`, reusing a pipeline that already ran
+/// there or triggering one from a temporary branch.
+fn passes(
+    client: &Client,
+    project: &str,
+    commit: &Commit,
+    job: &str,
+    polling: &Polling,
+) -> anyhow::Result<bool> {
+    let endpoint = Pipelines::builder()
+        .project(project)
+        .sha(commit.id.as_str())
+        .build()?;
+    let pipelines: Vec<Pipeline> = api::paged(endpoint, api::Pagination::Limit(1)).query(client)?;
+    let status = match pipelines.first() {
+        Some(pipeline) => job_status(client, project, pipeline.id, job, polling)?,
+        None => {
+            // Pipelines run on refs, not commits.
+            let branch = format!("bisect/{}", commit.short_id);
+            let create = CreateBranch::builder()
+                .project(project)
+                .branch(branch.as_str())
+                .ref_(commit.id.as_str())
+                .build()?;
+            api::ignore(create).query(client)?;
+            let result = CreatePipeline::builder()
+                .project(project)
+                .ref_(branch.as_str())
+                .variables(
+                    [PipelineVariable::builder()
+                        .key(JOB_VARIABLE)
+                        .value(job)
+                        .build()?]
+                    .into_iter(),
+                )
+                .build()
+                .map_err(anyhow::Error::from)
+                .and_then(|endpoint| Ok(endpoint.query(client)?))
+                .and_then(|pipeline: Pipeline| {
+                    job_status(client, project, pipeline.id, job, polling)
+                });
+            // Unlike the other branch endpoints the name is not escaped for us.
+            let delete = DeleteBranch::builder()
+                .project(project)
+                .branch(path_escaped(&branch).to_string())
+                .build()?;
+            if let Err(err) = api::ignore(delete).query(client) {
+                tracing::warn!("failed to delete {branch}: {err}");
+            }
+            result?
+        }
+    };
+    tracing::info!(
+        project,
+        "{job} {status} on {} {}",
+        commit.short_id,
+        commit.title
+    );
+    match status.as_str() {
+        "success" => Ok(true),
+        "failed" => Ok(false),
+        status => anyhow::bail!(
+            "{job} was {status} on {}, so it says nothing about the commit",
+            commit.short_id
+        ),
+    }
+}
+
+/// Finds the first commit after `good` up to `bad` on which `job` fails,
+/// and prints it with the merge request that brought it in.
+pub fn run(
+    ctx: &Context,
+    project: &str,
+    good: &str,
+    bad: &str,
+    job: &str,
+    polling: &Polling,
+) -> anyhow::Result<()> {
+    let client = ctx.client;
+    let comparison: Comparison = CompareCommits::builder()
+        .project(project)
+        .from(good)
+        .to(bad)
+        .build()?
+        .query(client)?;
+    let commits = comparison.commits;
+    anyhow::ensure!(
+        !commits.is_empty(),
+        "{bad} has no commits that {good} lacks"
+    );
+    let steps = commits.len().ilog2() + 1;
+    ctx.confirm(
+        project,
+        &[format!(
+            "run {job} on up to {steps} of the {} commits between {good} and {bad}",
+            commits.len()
+        )],
+    )?;
+
+    // Everything before `first` passes and `commits[last]` fails.
+    let (mut first, mut last) = (0, commits.len() - 1);
+    while first < last {
+        let middle = (first + last) / 2;
+        if passes(client, project, &commits[middle], job, polling)? {
+            first = middle + 1;
+        } else {
+            last = middle;
+        }
+    }
+
+    let culprit = &commits[first];
+    println!("{} {}", culprit.id, culprit.title);
+    let endpoint = MergeRequests::builder()
+        .project(project)
+        .sha(culprit.id.as_str())
+        .build()?;
+    let merge_requests: Vec<MergeRequest> = endpoint.query(client)?;
+    for mr in merge_requests {
+        println!("!{} {}", mr.iid, mr.web_url);
+    }
+    Ok(())
+}
@@ -0,0 +1,352 @@
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use gitlab::api::issues::{IssueMilestone, IssueState, ProjectIssues};
+use gitlab::api::projects::issues::IssueResourceLabelEvents;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::endpoints::ProjectBoards;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BoardFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+struct Named {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Milestone {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct List {
+    label: Option<Named>,
+    position: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Board {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    hide_backlog_list: bool,
+    #[serde(default)]
+    hide_closed_list: bool,
+    milestone: Option<Milestone>,
+    #[serde(default)]
+    labels: Vec<Named>,
+    #[serde(default)]
+    lists: Vec<List>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    iid: u64,
+    title: String,
+    #[serde(default)]
+    labels: Vec<String>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelEvent {
+    action: String,
+    label: Option<Named>,
+    created_at: DateTime<Utc>,
+}
+
+/// An issue in a column, since when it has been there.
+struct Card {
+    iid: u64,
+    title: String,
+    since: DateTime<Utc>,
+}
+
+struct Column {
+    name: String,
+    cards: Vec<Card>,
+}
+
+impl Column {
+    /// The days each card has been in the column, longest first.
+    fn ages(&self, now: DateTime<Utc>) -> Vec<i64> {
+        let mut ages: Vec<_> = self
+            .cards
+            .iter()
+            .map(|card| (now - card.since).num_days())
+            .collect();
+        ages.sort_unstable_by(|a, b| b.cmp(a));
+        ages
+    }
+
+    fn median_age(&self, now: DateTime<Utc>) -> Option<i64> {
+        let ages = self.ages(now);
+        (!ages.is_empty()).then(|| ages[ages.len() / 2])
+    }
+
+    fn oldest(&self) -> Option<&Card> {
+        self.cards.iter().min_by_key(|card| card.since)
+    }
+}
+
+fn find_board(client: &Client, project: &str, board: &str) -> anyhow::Result<Board> {
+    let endpoint = ProjectBoards {
+        project: project.into(),
+    };
+    let boards: Vec<Board> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    let names: Vec<_> = boards.iter().map(|found| found.name.clone()).collect();
+    boards
+        .into_iter()
+        .find(|found| found.name == board || found.id.to_string() == board)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "no board named {board:?}; the boards are: {}",
+                if names.is_empty() {
+                    "none".to_owned()
+                } else {
+                    names.join(", ")
+                }
+            )
+        })
+}
+
+fn issues(
+    client: &Client,
+    project: &str,
+    board: &Board,
+    state: IssueState,
+) -> anyhow::Result<Vec<Issue>> {
+    let mut endpoint = ProjectIssues::builder();
+    endpoint
+        .project(project)
+        .state(state)
+        .labels(board.labels.iter().map(|label| label.name.as_str()));
+    if let Some(milestone) = &board.milestone {
+        endpoint.milestone_id(IssueMilestone::Named(milestone.title.as_str().into()));
+    }
+    Ok(api::paged(endpoint.build()?, api::Pagination::All).query(client)?)
+}
+
+/// When `label` was last put on `issue`, or when the issue was opened.
+fn labelled_at(
+    client: &Client,
+    project: &str,
+    issue: &Issue,
+    label: &str,
+) -> anyhow::Result<DateTime<Utc>> {
+    let endpoint = IssueResourceLabelEvents::builder()
+        .project(project)
+        .issue(issue.iid)
+        .build()?;
+    let events: Vec<LabelEvent> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    Ok(events
+        .iter()
+        .filter(|event| {
+            event.action == "add"
+                && event
+                    .label
+                    .as_ref()
+                    .is_some_and(|found| found.name == label)
+        })
+        .map(|event| event.created_at)
+        .max()
+        .unwrap_or(issue.created_at))
+}
+
+/// The open issues of `board` by column, as the board shows them: in every
+/// label list whose label they have, or in Open if in none.
+fn columns(client: &Client, project: &str, board: &Board) -> anyhow::Result<Vec<Column>> {
+    let mut lists: Vec<_> = board.lists.iter().collect();
+    lists.sort_by_key(|list| list.position);
+    let labels: Vec<_> = lists
+        .iter()
+        .filter_map(|list| list.label.as_ref().map(|label| label.name.as_str()))
+        .collect();
+    if labels.len() < lists.len() {
+        tracing::warn!(
+            board = board.name,
+            "only label lists are reported; assignee, milestone and iteration lists are left out"
+        );
+    }
+
+    let open = issues(client, project, board, IssueState::Opened)?;
+    let mut columns = Vec::new();
+    if !board.hide_backlog_list {
+        columns.push(Column {
+            name: "Open".to_owned(),
+            cards: open
+                .iter()
+                .filter(|issue| {
+                    !issue
+                        .labels
+                        .iter()
+                        .any(|label| labels.contains(&label.as_str()))
+                })
+                .map(|issue| Card {
+                    iid: issue.iid,
+                    title: issue.title.clone(),
+                    since: issue.created_at,
+                })
+                .collect(),
+        });
+    }
+    for label in labels {
+        let mut cards = Vec::new();
+        for issue in open
+            .iter()
+            .filter(|issue| issue.labels.iter().any(|found| found == label))
+        {
+            cards.push(Card {
+                iid: issue.iid,
+                title: issue.title.clone(),
+                since: labelled_at(client, project, issue, label)?,
+            });
+        }
+        columns.push(Column {
+            name: label.to_owned(),
+            cards,
+        });
+    }
+    Ok(columns)
+}
+
+fn days(days: Option<i64>) -> String {
+    days.map_or("-".to_owned(), |days| format!("{days}d"))
+}
+
+fn markdown(board: &str, columns: &[Column], closed: Option<usize>, now: DateTime<Utc>) -> String {
+    let mut out = format!(
+        "# {board}\n\n_As of {}_\n\n| Column | Issues | Median age | Oldest |\n| --- | --- | --- | --- |\n",
+        now.format("%Y-%m-%d %H:%M UTC")
+    );
+    for column in columns {
+        let oldest = column.oldest().map_or("-".to_owned(), |card| {
+            format!(
+                "#{} ({})",
+                card.iid,
+                days(Some((now - card.since).num_days()))
+            )
+        });
+        out.push_str(&format!(
+            "| {} | {} | {} | {oldest} |\n",
+            column.name,
+            column.cards.len(),
+            days(column.median_age(now))
+        ));
+    }
+    if let Some(closed) = closed {
+        out.push_str(&format!("| Closed | {closed} | - | - |\n"));
+    }
+    for column in columns.iter().filter(|column| !column.cards.is_empty()) {
+        out.push_str(&format!("\n## {}\n\n", column.name));
+        let mut cards: Vec<_> = column.cards.iter().collect();
+        cards.sort_by_key(|card| card.since);
+        for card in cards {
+            out.push_str(&format!(
+                "- #{} {} ({})\n",
+                card.iid,
+                card.title,
+                days(Some((now - card.since).num_days()))
+            ));
+        }
+    }
+    out
+}
+
+fn json(board: &str, columns: &[Column], closed: Option<usize>, now: DateTime<Utc>) -> String {
+    let columns: Vec<_> = columns
+        .iter()
+        .map(|column| {
+            serde_json::json!({
+                "name": column.name,
+                "count": column.cards.len(),
+                "median_age_days": column.median_age(now),
+                "issues": column.cards.iter().map(|card| serde_json::json!({
+                    "iid": card.iid,
+                    "title": card.title,
+                    "age_days": (now - card.since).num_days(),
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    let report = serde_json::json!({
+        "board": board,
+        "at": now.to_rfc3339(),
+        "columns": columns,
+        "closed": closed,
+    });
+    serde_json::to_string_pretty(&report).expect("values always serialize")
+}
+
+/// Prints the columns of `board` of `project` with how many issues are in
+/// each and for how long, for standups and release meetings.
+pub fn report(
+    client: &Client,
+    project: &str,
+    board: &str,
+    format: BoardFormat,
+) -> anyhow::Result<()> {
+    let board = find_board(client, project, board)?;
+    let columns = columns(client, project, &board)?;
+    let closed = if board.hide_closed_list {
+        None
+    } else {
+        Some(issues(client, project, &board, IssueState::Closed)?.len())
+    };
+    let now = Utc::now();
+    let report = match format {
+        BoardFormat::Markdown => markdown(&board.name, &columns, closed, now),
+        BoardFormat::Json => json(&board.name, &columns, closed, now),
+    };
+    println!("{report}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(iid: u64, since: &str) -> Card {
+        Card {
+            iid,
+            title: format!("Issue {iid}"),
+            since: since.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn the_summary_row_has_the_median_and_the_oldest_card() {
+        let now = "2024-05-20T12:00:00Z".parse().unwrap();
+        let columns = [
+            Column {
+                name: "Doing".to_owned(),
+                cards: vec![
+                    card(1, "2024-05-19T12:00:00Z"),
+                    card(2, "2024-05-10T12:00:00Z"),
+                    card(3, "2024-05-17T12:00:00Z"),
+                ],
+            },
+            Column {
+                name: "Review".to_owned(),
+                cards: Vec::new(),
+            },
+        ];
+
+        let report = markdown("Release 1.4", &columns, Some(12), now);
+
+        assert!(
+            report.contains("| Doing | 3 | 3d | #2 (10d) |\n"),
+            "{report}"
+        );
+        assert!(report.contains("| Review | 0 | - | - |\n"), "{report}");
+        assert!(report.contains("| Closed | 12 | - | - |\n"), "{report}");
+        assert!(report.contains("## Doing\n\n- #2 Issue 2 (10d)\n- #3 Issue 3 (3d)\n"));
+        assert!(!report.contains("## Review"));
+    }
+}
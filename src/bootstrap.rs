@@ -0,0 +1,159 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::badges::{self, Badge};
+use crate::labels::{self, Label};
+use crate::settings::{self, Desired};
+use crate::variables::{self, SetVariable};
+use crate::webhooks::{self, AddHook, HookEvent};
+use crate::workflow::Context;
+
+pub const DEFAULT_PATH: &str = "bootstrap.toml";
+
+/// The standard setup of a new project; see `bootstrap.toml.example`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Template {
+    /// The same settings `audit-settings` checks.
+    #[serde(default)]
+    settings: Desired,
+    #[serde(default)]
+    labels: Vec<Label>,
+    #[serde(default)]
+    variables: Vec<Variable>,
+    #[serde(default)]
+    badges: Vec<Badge>,
+    #[serde(default)]
+    webhooks: Vec<Webhook>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Variable {
+    key: String,
+    value: Option<String>,
+    /// Read the value from this environment variable, so secrets stay out of the file.
+    value_env: Option<String>,
+    #[serde(default)]
+    protected: bool,
+    #[serde(default)]
+    masked: bool,
+    environment_scope: Option<String>,
+}
+
+impl Variable {
+    fn resolve(&self) -> anyhow::Result<SetVariable> {
+        let value = match (&self.value, &self.value_env) {
+            (Some(value), None) => value.clone(),
+            (None, Some(name)) => std::env::var(name)
+                .with_context(|| format!("variable {} needs ${name}", self.key))?,
+            _ => anyhow::bail!(
+                "variable {} needs exactly one of value and value_env",
+                self.key
+            ),
+        };
+        Ok(SetVariable {
+            key: self.key.clone(),
+            value,
+            protected: self.protected,
+            masked: self.masked,
+            environment_scope: self.environment_scope.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Webhook {
+    url: String,
+    events: Vec<HookEvent>,
+    /// The environment variable holding the secret token.
+    secret_env: Option<String>,
+    #[serde(default)]
+    insecure_ssl: bool,
+}
+
+impl Webhook {
+    fn resolve(&self) -> anyhow::Result<AddHook> {
+        let secret = self
+            .secret_env
+            .as_ref()
+            .map(|name| {
+                std::env::var(name).with_context(|| format!("webhook {} needs ${name}", self.url))
+            })
+            .transpose()?;
+        Ok(AddHook {
+            url: self.url.clone(),
+            events: self.events.clone(),
+            secret,
+            insecure_ssl: self.insecure_ssl,
+        })
+    }
+}
+
+/// A template with the variable values and webhook secrets read from the
+/// environment, so a missing secret stops the run before any project is touched.
+pub struct Setup {
+    pub settings: Desired,
+    pub labels: Vec<Label>,
+    pub variables: Vec<SetVariable>,
+    pub badges: Vec<Badge>,
+    pub webhooks: Vec<AddHook>,
+}
+
+impl Setup {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let template: Template =
+            toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))?;
+        Ok(Setup {
+            variables: template
+                .variables
+                .iter()
+                .map(Variable::resolve)
+                .collect::<anyhow::Result<_>>()?,
+            webhooks: template
+                .webhooks
+                .iter()
+                .map(Webhook::resolve)
+                .collect::<anyhow::Result<_>>()?,
+            settings: template.settings,
+            labels: template.labels,
+            badges: template.badges,
+        })
+    }
+}
+
+/// Applies `setup` (read from `path`) to `project`, section by section.
+/// Every section only changes what differs, so running it again is harmless.
+pub fn run(ctx: &Context, project: &str, setup: &Setup, path: &Path) -> anyhow::Result<String> {
+    let mut done = vec![format!(
+        "settings: {}",
+        settings::audit(ctx, project, &setup.settings, path, true)?
+    )];
+    if !setup.labels.is_empty() {
+        done.push(labels::sync(ctx, project, &setup.labels)?);
+    }
+    if !setup.variables.is_empty() {
+        let plan: Vec<_> = setup
+            .variables
+            .iter()
+            .map(|variable| format!("set the {} variable", variable.key))
+            .collect();
+        ctx.confirm(project, &plan)?;
+        for variable in &setup.variables {
+            variables::set(ctx.client, project, variable)?;
+        }
+        done.push(format!("set {} variable(s)", setup.variables.len()));
+    }
+    if !setup.badges.is_empty() {
+        done.push(badges::apply(ctx, project, &setup.badges)?);
+    }
+    for webhook in &setup.webhooks {
+        done.push(webhooks::add(ctx, project, webhook)?);
+    }
+    Ok(done.join("; "))
+}
@@ -0,0 +1,96 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use crate::duration;
+use crate::platform;
+
+#[derive(Debug, Clone, Args)]
+pub struct CacheOptions {
+    /// Always go to the API instead of the on-disk response cache.
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+    /// How long cached responses are served without revalidating them.
+    #[arg(
+        long,
+        global = true,
+        env = "GITLAB_HELPER_CACHE_TTL",
+        value_parser = duration::parse,
+        default_value = "5m"
+    )]
+    pub cache_ttl: Duration,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub stored_at: u64,
+    pub etag: Option<String>,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// On-disk cache for read-only API responses, keyed by request URL and token.
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+pub fn default_dir() -> Option<PathBuf> {
+    platform::cache_dir()
+}
+
+impl Cache {
+    pub fn new(options: &CacheOptions) -> Option<Self> {
+        if options.no_cache {
+            return None;
+        }
+        let dir = default_dir()?;
+        Some(Cache {
+            dir,
+            ttl: options.cache_ttl,
+        })
+    }
+
+    /// The token is part of the key so that responses never leak between users.
+    pub fn key(&self, url: &str, token: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        token.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    pub fn get(&self, key: &str) -> Option<Entry> {
+        let contents = std::fs::read(self.path(key)).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    pub fn is_fresh(&self, entry: &Entry) -> bool {
+        now().saturating_sub(entry.stored_at) < self.ttl.as_secs()
+    }
+
+    /// Stores `entry`, or refreshes it after a 304 Not Modified revalidation.
+    pub fn put(&self, key: &str, mut entry: Entry) {
+        entry.stored_at = now();
+        let write = || -> std::io::Result<()> {
+            std::fs::create_dir_all(&self.dir)?;
+            std::fs::write(self.path(key), serde_json::to_vec(&entry)?)
+        };
+        if let Err(err) = write() {
+            tracing::debug!("failed to write response cache entry {key}: {err}");
+        }
+    }
+}
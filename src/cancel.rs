@@ -0,0 +1,79 @@
+use std::sync::OnceLock;
+
+/// The signal that asked the run to stop, once one did.
+static SIGNAL: OnceLock<&'static str> = OnceLock::new();
+
+#[derive(Debug, thiserror::Error)]
+#[error("stopped by {signal}")]
+pub struct Cancelled {
+    signal: &'static str,
+}
+
+impl Cancelled {
+    /// The conventional exit code of a process ended by the signal.
+    pub fn exit_code(&self) -> u8 {
+        match self.signal {
+            "SIGTERM" => 143,
+            _ => 130,
+        }
+    }
+}
+
+async fn signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+            let _ = tokio::signal::ctrl_c().await;
+            return "SIGINT";
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+/// Listens for Ctrl-C and SIGTERM, as runners send when a job times out. The
+/// first one lets the step in flight finish and then stops the run; a second
+/// one ends the process at once.
+pub fn install() {
+    let listener = std::thread::Builder::new()
+        .name("signals".to_owned())
+        .spawn(|| {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => return tracing::warn!("cannot listen for Ctrl-C: {err}"),
+            };
+            runtime.block_on(async {
+                let first = signal().await;
+                let _ = SIGNAL.set(first);
+                tracing::warn!("{first}: stopping after the current step; again to stop now");
+                let second = signal().await;
+                std::process::exit(Cancelled { signal: second }.exit_code().into());
+            })
+        });
+    if let Err(err) = listener {
+        tracing::warn!("cannot listen for Ctrl-C: {err}");
+    }
+}
+
+pub fn requested() -> bool {
+    SIGNAL.get().is_some()
+}
+
+/// Fails once a signal asked the run to stop, for checking between steps.
+pub fn check() -> Result<(), Cancelled> {
+    match SIGNAL.get() {
+        Some(&signal) => Err(Cancelled { signal }),
+        None => Ok(()),
+    }
+}
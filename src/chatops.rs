@@ -0,0 +1,263 @@
+use gitlab::api::{
+    self,
+    projects::{
+        merge_requests::{CreateMergeRequest, EditMergeRequest, MergeRequest},
+        repository::branches::CreateBranch,
+    },
+    Query,
+};
+use serde::Deserialize;
+use winnow::{
+    ascii::{space0, space1},
+    combinator::{alt, eof, preceded, rest, terminated},
+    error::{StrContext, StrContextValue},
+    prelude::*,
+    token::take_till,
+};
+
+use crate::endpoints::CherryPickCommit;
+use crate::hooks::Event;
+use crate::workflow::Context;
+use crate::{emergency, title};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command<'a> {
+    Backport { target: &'a str },
+    Retitle { title: &'a str },
+    CutPatch,
+}
+
+impl Command<'_> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Backport { .. } => "backport",
+            Command::Retitle { .. } => "retitle",
+            Command::CutPatch => "cut-patch",
+        }
+    }
+}
+
+fn parse_backport<'a>(input: &mut &'a str) -> PResult<Command<'a>> {
+    preceded(("backport", space1), take_till(1.., char::is_whitespace))
+        .context(StrContext::Label("backport target"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "a branch name",
+        )))
+        .map(|target| Command::Backport { target })
+        .parse_next(input)
+}
+
+fn parse_retitle<'a>(input: &mut &'a str) -> PResult<Command<'a>> {
+    preceded(("retitle", space1), rest)
+        .map(|title: &str| Command::Retitle {
+            title: title.trim(),
+        })
+        .parse_next(input)
+}
+
+fn parse_cut_patch<'a>(input: &mut &'a str) -> PResult<Command<'a>> {
+    alt(("cut-patch", "emergency-patch"))
+        .value(Command::CutPatch)
+        .parse_next(input)
+}
+
+/// Parses a single `/command args` comment line.
+pub fn parse_command<'a>(input: &mut &'a str) -> PResult<Command<'a>> {
+    preceded(
+        (space0, '/'),
+        terminated(
+            alt((parse_backport, parse_retitle, parse_cut_patch)),
+            (space0, eof),
+        ),
+    )
+    .context(StrContext::Label("command"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "/backport <branch>, /retitle <title> or /cut-patch",
+    )))
+    .parse_next(input)
+}
+
+const COMMANDS: &[&str] = &["backport", "retitle", "cut-patch", "emergency-patch"];
+
+/// Every line of a comment that invokes one of our commands, parsed. Only
+/// lines that start with `/` outside code blocks count, so quoted output such
+/// as our own replies never triggers anything; other `/` lines are left alone
+/// since they are usually GitLab quick actions.
+pub fn commands(note: &str) -> Vec<Result<Command<'_>, String>> {
+    let mut fenced = false;
+    note.lines()
+        .filter(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                fenced = !fenced;
+                return false;
+            }
+            !fenced
+                && line
+                    .strip_prefix('/')
+                    .and_then(|line| line.split_whitespace().next())
+                    .is_some_and(|name| COMMANDS.contains(&name))
+        })
+        // The parser's own message quotes the line; the context alone says what is wrong.
+        .map(|line| {
+            parse_command
+                .parse(line.trim_end())
+                .map_err(|err| err.inner().to_string())
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequestDetails {
+    title: String,
+    state: String,
+    merge_commit_sha: Option<String>,
+    squash_commit_sha: Option<String>,
+}
+
+/// Where a command was posted and by whom.
+pub struct Origin {
+    pub project: u64,
+    pub iid: u64,
+    pub user_id: u64,
+}
+
+fn backport(ctx: &Context, origin: &Origin, target: &str) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let project = origin.project.to_string();
+    let mr: MergeRequestDetails = MergeRequest::builder()
+        .project(origin.project)
+        .merge_request(origin.iid)
+        .build()?
+        .query(client)?;
+    anyhow::ensure!(mr.state == "merged", "!{} is not merged yet", origin.iid);
+    let Some(sha) = mr.squash_commit_sha.or(mr.merge_commit_sha) else {
+        anyhow::bail!("!{} has no merge commit to cherry-pick", origin.iid);
+    };
+
+    let branch = format!("backport/{}-to-{}", origin.iid, target.replace('/', "-"));
+    let create_branch = CreateBranch::builder()
+        .project(origin.project)
+        .branch(&branch)
+        .ref_(target)
+        .build()?;
+    let event = Event::BranchCreated {
+        project: &project,
+        branch: &branch,
+        ref_: target,
+    };
+    ctx.hooked(&event, || Ok(api::ignore(create_branch).query(client)?))?;
+    api::ignore(CherryPickCommit {
+        project: origin.project.into(),
+        sha: &sha,
+        branch: &branch,
+    })
+    .query(client)?;
+    let title = format!("{} [backport to {target}]", mr.title);
+    let mr = CreateMergeRequest::builder()
+        .project(origin.project)
+        .source_branch(&branch)
+        .target_branch(target)
+        .title(&title)
+        .description(format!("Backport of !{} onto `{target}`.", origin.iid))
+        .assignee(origin.user_id)
+        .build()?;
+    let event = Event::MrCreated {
+        project: &project,
+        source_branch: &branch,
+        target_branch: target,
+        title: &title,
+    };
+    ctx.hooked(&event, || Ok(api::ignore(mr).query(client)?))?;
+    Ok(format!(
+        "opened a backport of !{} from `{branch}` into `{target}`",
+        origin.iid
+    ))
+}
+
+fn retitle(ctx: &Context, origin: &Origin, new_title: &str) -> anyhow::Result<String> {
+    title::lint(new_title).map_err(|err| anyhow::anyhow!("invalid title:\n{err}"))?;
+    let edit = EditMergeRequest::builder()
+        .project(origin.project)
+        .merge_request(origin.iid)
+        .title(new_title)
+        .build()?;
+    api::ignore(edit).query(ctx.client)?;
+    Ok(format!("retitled to `{new_title}`"))
+}
+
+pub fn execute(ctx: &Context, origin: &Origin, command: &Command) -> anyhow::Result<String> {
+    match command {
+        Command::Backport { target } => backport(ctx, origin, target),
+        Command::Retitle { title } => retitle(ctx, origin, title),
+        Command::CutPatch => {
+            let patch = emergency::Patch::new(origin.user_id);
+            emergency::run(ctx, &origin.project.to_string(), &patch)
+                .map(|summary| format!("emergency patch created: {summary}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_command() {
+        assert_eq!(
+            commands("/backport release/1.2.0\n/retitle  fix (AB-1): typo \n/cut-patch"),
+            vec![
+                Ok(Command::Backport {
+                    target: "release/1.2.0"
+                }),
+                Ok(Command::Retitle {
+                    title: "fix (AB-1): typo"
+                }),
+                Ok(Command::CutPatch),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_commands_in_code_blocks() {
+        let note = "see below\n```\n/backport dev\n```\n~~~\n/cut-patch\n~~~";
+        assert_eq!(commands(note), vec![]);
+    }
+
+    #[test]
+    fn ignores_quoted_and_indented_lines() {
+        assert_eq!(
+            commands("> /backport dev\n    /cut-patch\n /retitle x"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_commands_and_quick_actions_alone() {
+        assert_eq!(
+            commands("/assign @me\n/label ~bug\n/frobnicate now"),
+            vec![]
+        );
+        assert_eq!(commands("backport dev"), vec![]);
+    }
+
+    #[test]
+    fn bad_arguments_are_errors_that_do_not_quote_the_comment() {
+        for note in ["/backport", "/backport dev extra", "/cut-patch now"] {
+            let [result] = commands(note).try_into().unwrap();
+            let err = result.unwrap_err();
+            assert!(err.starts_with("invalid command"), "{err:?}");
+            assert!(!err.contains('^'), "{err:?} quotes {note:?}");
+            assert!(commands(&err).is_empty(), "{err:?} would trigger a command");
+        }
+    }
+
+    #[test]
+    fn our_own_replies_never_trigger_commands() {
+        let [Err(err)] = <[_; 1]>::try_from(commands("/backport")).unwrap() else {
+            panic!("/backport without a target parsed");
+        };
+        let reply = format!("@someone\n\n:x:\n```\n{err}\n```");
+        assert_eq!(commands(&reply), vec![]);
+    }
+}
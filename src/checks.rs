@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::Args;
+
+use crate::codequality;
+use crate::junit;
+use crate::redact;
+
+/// Report files the check commands write their checks to, besides failing.
+#[derive(Debug, Clone, Default, Args)]
+pub struct Reports {
+    /// Also write the checks as a JUnit report, e.g. for `artifacts:reports:junit`.
+    #[arg(long, value_name = "PATH")]
+    pub junit: Option<PathBuf>,
+    /// Also write the failed checks as a Code Quality report, e.g. for
+    /// `artifacts:reports:codequality`.
+    #[arg(long, value_name = "PATH")]
+    pub codequality: Option<PathBuf>,
+}
+
+impl Reports {
+    /// Writes `cases` to every report asked for, as the checks of `suite`.
+    pub fn write(&self, suite: &str, cases: &[Case]) -> anyhow::Result<()> {
+        let reports = [
+            (&self.junit, junit::render(suite, cases)),
+            (&self.codequality, codequality::render(suite, cases)),
+        ];
+        for (path, report) in reports {
+            if let Some(path) = path {
+                std::fs::write(path, redact::redact(&report).as_bytes())
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where in the repository a check failed.
+pub struct Location {
+    pub path: String,
+    pub line: u64,
+}
+
+/// One check, and why it failed if it did.
+pub struct Case {
+    pub name: String,
+    pub failure: Option<String>,
+    pub location: Option<Location>,
+}
+
+impl Case {
+    pub fn passed(name: impl Into<String>) -> Self {
+        Case {
+            name: name.into(),
+            failure: None,
+            location: None,
+        }
+    }
+
+    pub fn failed(name: impl Into<String>, failure: impl Into<String>) -> Self {
+        Case {
+            name: name.into(),
+            failure: Some(failure.into()),
+            location: None,
+        }
+    }
+
+    pub fn at(mut self, path: impl Into<String>, line: u64) -> Self {
+        self.location = Some(Location {
+            path: path.into(),
+            line,
+        });
+        self
+    }
+}
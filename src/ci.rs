@@ -0,0 +1,681 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use clap::ValueEnum;
+use gitlab::api::{self, projects, Query};
+use serde::Deserialize;
+
+use crate::checks::{Case, Reports};
+use crate::client::{self, Client};
+use crate::endpoints::{GroupVariables, LintCiConfig, ProjectVariables};
+use crate::table;
+
+#[derive(Debug, Deserialize)]
+struct Lint {
+    valid: bool,
+    #[serde(default)]
+    errors: Vec<String>,
+    #[serde(default)]
+    warnings: Vec<String>,
+    #[serde(default)]
+    merged_yaml: Option<String>,
+}
+
+/// Lints `content` in the context of `project`, so that `include`s resolve
+/// the same way they would in a pipeline.
+fn lint_content(
+    client: &Client,
+    project: &str,
+    path: &Path,
+    content: &str,
+    dry_run: bool,
+    ref_: Option<&str>,
+) -> anyhow::Result<Lint> {
+    let lint: Lint = LintCiConfig {
+        project: project.into(),
+        content,
+        dry_run,
+        ref_,
+        include_jobs: false,
+    }
+    .query(client)?;
+
+    for warning in &lint.warnings {
+        tracing::warn!("{warning}");
+    }
+    if !lint.valid {
+        for error in &lint.errors {
+            eprintln!("{}: {error}", path.display());
+        }
+        anyhow::bail!(
+            "{} is not valid: {} error(s)",
+            path.display(),
+            lint.errors.len()
+        );
+    }
+    Ok(lint)
+}
+
+fn read(path: &Path) -> anyhow::Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+/// Lints `path` and prints the merged configuration.
+pub fn lint(
+    client: &Client,
+    project: &str,
+    path: &Path,
+    dry_run: bool,
+    ref_: Option<&str>,
+) -> anyhow::Result<()> {
+    let lint = lint_content(client, project, path, &read(path)?, dry_run, ref_)?;
+    if let Some(merged) = lint.merged_yaml {
+        print!("{merged}");
+    }
+    tracing::info!("{} is valid", path.display());
+    Ok(())
+}
+
+/// Stages, jobs with their `needs`, and includes of the merged configuration.
+const CI_CONFIG: &str = r#"
+query($project: ID!, $content: String!, $sha: String) {
+  ciConfig(projectPath: $project, content: $content, sha: $sha) {
+    status
+    errors
+    includes { type location }
+    stages {
+      nodes {
+        name
+        groups { nodes { jobs { nodes { name needs { nodes { name } } } } } }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CiConfigData {
+    ci_config: Option<CiConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CiConfig {
+    status: Option<String>,
+    #[serde(default)]
+    errors: Vec<String>,
+    #[serde(default)]
+    includes: Vec<Include>,
+    stages: Option<Nodes<Stage>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Include {
+    #[serde(rename = "type")]
+    kind: String,
+    location: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Stage {
+    name: String,
+    groups: Nodes<Group>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Group {
+    jobs: Nodes<Job>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Job {
+    name: String,
+    needs: Option<Nodes<Need>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Need {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    path_with_namespace: String,
+}
+
+/// The root of the include graph.
+const ROOT: &str = ".gitlab-ci.yml";
+
+struct Graph {
+    /// Jobs by stage, in pipeline order.
+    stages: Vec<(String, Vec<String>)>,
+    /// `(needed, needing)` job pairs.
+    needs: Vec<(String, String)>,
+    includes: Vec<String>,
+}
+
+impl Graph {
+    fn new(config: CiConfig) -> Self {
+        let mut needs = Vec::new();
+        let stages = config
+            .stages
+            .map(|stages| stages.nodes)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|stage| {
+                let jobs = stage
+                    .groups
+                    .nodes
+                    .into_iter()
+                    .flat_map(|group| group.jobs.nodes)
+                    .map(|job| {
+                        for need in job.needs.map(|needs| needs.nodes).unwrap_or_default() {
+                            needs.push((need.name, job.name.clone()));
+                        }
+                        job.name
+                    })
+                    .collect();
+                (stage.name, jobs)
+            })
+            .collect();
+        let includes = config
+            .includes
+            .into_iter()
+            .map(|include| format!("{}: {}", include.kind, include.location))
+            .collect();
+        Graph {
+            stages,
+            needs,
+            includes,
+        }
+    }
+
+    fn dot(&self) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("digraph ci {\n  rankdir=LR;\n  node [shape=box];\n");
+        for (index, (stage, jobs)) in self.stages.iter().enumerate() {
+            writeln!(
+                out,
+                "  subgraph cluster_{index} {{\n    label={};",
+                quote(stage)
+            )
+            .unwrap();
+            for job in jobs {
+                writeln!(out, "    {};", quote(job)).unwrap();
+            }
+            out.push_str("  }\n");
+        }
+        for (needed, needing) in &self.needs {
+            writeln!(out, "  {} -> {};", quote(needed), quote(needing)).unwrap();
+        }
+        for include in &self.includes {
+            writeln!(
+                out,
+                "  {} -> {} [style=dashed];\n  {} [shape=note];",
+                quote(ROOT),
+                quote(include),
+                quote(include)
+            )
+            .unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn mermaid(&self) -> String {
+        let label = |text: &str| format!("\"{}\"", text.replace('"', "#quot;"));
+        let jobs: Vec<_> = self.stages.iter().flat_map(|(_, jobs)| jobs).collect();
+        let id = |job: &str| {
+            jobs.iter()
+                .position(|known| *known == job)
+                .map_or_else(|| "missing".to_owned(), |index| format!("job{index}"))
+        };
+        let mut out = String::from("flowchart LR\n");
+        for (index, (stage, jobs)) in self.stages.iter().enumerate() {
+            writeln!(out, "  subgraph stage{index}[{}]", label(stage)).unwrap();
+            for job in jobs {
+                writeln!(out, "    {}[{}]", id(job), label(job)).unwrap();
+            }
+            out.push_str("  end\n");
+        }
+        for (needed, needing) in &self.needs {
+            writeln!(out, "  {} --> {}", id(needed), id(needing)).unwrap();
+        }
+        if !self.includes.is_empty() {
+            writeln!(out, "  root[{}]", label(ROOT)).unwrap();
+        }
+        for (index, include) in self.includes.iter().enumerate() {
+            writeln!(out, "  root -.-> include{index}[/{}/]", label(include)).unwrap();
+        }
+        out
+    }
+}
+
+fn project_file(client: &Client, project: &str, ref_: &str) -> anyhow::Result<String> {
+    let endpoint = projects::repository::files::FileRaw::builder()
+        .project(project)
+        .file_path(ROOT)
+        .ref_(ref_)
+        .build()?;
+    let content = api::raw(endpoint).query(client)?;
+    String::from_utf8(content).with_context(|| format!("{ROOT} in {project} is not UTF-8"))
+}
+
+/// Prints the merged CI configuration of `project` as a graph, from `path`
+/// or else from the project's own `.gitlab-ci.yml` at `ref_`.
+pub fn graph(
+    client: &Client,
+    project: &str,
+    path: Option<&Path>,
+    ref_: Option<&str>,
+    format: GraphFormat,
+) -> anyhow::Result<()> {
+    let content = match path {
+        Some(path) => read(path)?,
+        None => project_file(client, project, ref_.unwrap_or("HEAD"))?,
+    };
+    let full_path = projects::Project::builder()
+        .project(project)
+        .build()?
+        .query(client)
+        .map(|project: Project| project.path_with_namespace)?;
+    let data: CiConfigData = client.graphql(
+        CI_CONFIG,
+        serde_json::json!({ "project": full_path, "content": content, "sha": ref_ }),
+    )?;
+    let Some(config) = data.ci_config else {
+        anyhow::bail!("project {full_path} is not visible to this token");
+    };
+    if !config.errors.is_empty() {
+        anyhow::bail!(
+            "the CI configuration is not valid: {}",
+            config.errors.join("; ")
+        );
+    }
+    tracing::debug!(status = ?config.status, "CI configuration resolved");
+
+    let graph = Graph::new(config);
+    print!(
+        "{}",
+        match format {
+            GraphFormat::Dot => graph.dot(),
+            GraphFormat::Mermaid => graph.mermaid(),
+        }
+    );
+    Ok(())
+}
+
+/// Variables GitLab or the runner's shell provide besides the `CI_*`,
+/// `GITLAB_*` and `FF_*` families.
+const PREDEFINED: &[&str] = &[
+    "CHAT_CHANNEL",
+    "CHAT_INPUT",
+    "CHAT_USER_ID",
+    "KUBECONFIG",
+    "TRIGGER_PAYLOAD",
+    "HOME",
+    "HOSTNAME",
+    "IFS",
+    "LANG",
+    "OLDPWD",
+    "PATH",
+    "PWD",
+    "RANDOM",
+    "SHELL",
+    "TERM",
+    "TMPDIR",
+    "UID",
+    "USER",
+];
+
+fn is_predefined(name: &str) -> bool {
+    ["CI_", "GITLAB_", "FF_"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || name == "CI"
+        || PREDEFINED.contains(&name)
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The names referenced as `$NAME` or `${NAME}` on `line`; `$$` is an escaped dollar.
+fn references(line: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('$') {
+        rest = &rest[start + 1..];
+        if let Some(escaped) = rest.strip_prefix('$') {
+            rest = escaped;
+            continue;
+        }
+        let braced = rest.strip_prefix('{');
+        let name_start = braced.unwrap_or(rest);
+        if !name_start.starts_with(is_name_start) {
+            continue;
+        }
+        let len = name_start
+            .find(|c| !is_name_char(c))
+            .unwrap_or(name_start.len());
+        names.push(&name_start[..len]);
+        rest = &name_start[len..];
+    }
+    names
+}
+
+/// Names a script assigns itself, as in `export NAME=...` or `NAME=$(...)`.
+fn assignments(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| c.is_whitespace() || c == ';')
+        .filter_map(|word| {
+            let word = word.trim_start_matches(['"', '\'']);
+            let (name, _) = word.split_once('=')?;
+            (name.starts_with(is_name_start) && name.chars().all(is_name_char)).then_some(name)
+        })
+}
+
+/// What a top-level key of a CI configuration defines and references.
+#[derive(Debug, Default)]
+struct JobVariables {
+    defined: BTreeSet<String>,
+    used: BTreeSet<String>,
+    extends: Vec<String>,
+}
+
+/// The names of an `extends:` value, either inline or as a list item.
+fn extended(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|name| name.trim().trim_matches(['"', '\'']).to_owned())
+        .filter(|name| !name.is_empty())
+}
+
+/// Everything `name` defines, including what the jobs it extends define.
+fn defined_in<'a>(
+    jobs: &'a BTreeMap<String, JobVariables>,
+    name: &str,
+    seen: &mut BTreeSet<&'a str>,
+) -> BTreeSet<&'a str> {
+    let Some((name, job)) = jobs.get_key_value(name) else {
+        return BTreeSet::new();
+    };
+    if !seen.insert(name) {
+        return BTreeSet::new();
+    }
+    let mut defined: BTreeSet<_> = job.defined.iter().map(String::as_str).collect();
+    for parent in &job.extends {
+        defined.extend(defined_in(jobs, parent, seen));
+    }
+    defined
+}
+
+/// Variables referenced by each job of a merged CI configuration that neither
+/// the job, the jobs it extends, nor the global `variables` and `default`
+/// define.
+fn undefined_references(merged: &str) -> BTreeMap<String, BTreeSet<String>> {
+    let mut jobs: BTreeMap<String, JobVariables> = BTreeMap::new();
+    let mut job = String::new();
+    // The indentation of the `variables:` or `extends:` key whose block we are in.
+    let mut variables_indent = None;
+    let mut extends_indent = None;
+    for line in merged.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if variables_indent.is_some_and(|block| indent <= block) {
+            variables_indent = None;
+        }
+        // A block list may sit at the indentation of its key.
+        let item = trimmed.starts_with("- ");
+        if extends_indent.is_some_and(|block| indent < block || indent == block && !item) {
+            extends_indent = None;
+        }
+        if indent == 0 {
+            job = trimmed
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .trim_matches(['"', '\''])
+                .to_owned();
+        }
+        let current = jobs.entry(job.clone()).or_default();
+        if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .filter(|_| extends_indent.is_some())
+        {
+            current.extends.extend(extended(item));
+        } else if let Some((key, value)) = trimmed.split_once(':') {
+            let key = key.trim_matches(['"', '\'', ' ']);
+            if variables_indent.is_some() {
+                current.defined.insert(key.to_owned());
+            } else if key == "variables" {
+                variables_indent = Some(indent);
+            } else if key == "extends" && indent > 0 {
+                current.extends.extend(extended(value));
+                extends_indent = Some(indent);
+            }
+        }
+        current
+            .defined
+            .extend(assignments(trimmed).map(str::to_owned));
+        current
+            .used
+            .extend(references(trimmed).into_iter().map(str::to_owned));
+    }
+
+    let global: BTreeSet<&str> = ["variables", "default"]
+        .iter()
+        .flat_map(|name| defined_in(&jobs, name, &mut BTreeSet::new()))
+        .collect();
+    let mut undefined: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (name, job) in &jobs {
+        let defined = defined_in(&jobs, name, &mut BTreeSet::new());
+        for variable in &job.used {
+            let variable = variable.as_str();
+            if !defined.contains(variable) && !global.contains(variable) && !is_predefined(variable)
+            {
+                undefined
+                    .entry(variable.to_owned())
+                    .or_default()
+                    .insert(name.clone());
+            }
+        }
+    }
+    undefined
+}
+
+#[derive(Debug, Deserialize)]
+struct Variable {
+    key: String,
+}
+
+/// Keys of the variables of `project` and of every group above it; a
+/// listing the token may not read is skipped with a warning.
+fn configured_variables(client: &Client, project: &str) -> anyhow::Result<BTreeSet<String>> {
+    let full_path = projects::Project::builder()
+        .project(project)
+        .build()?
+        .query(client)
+        .map(|project: Project| project.path_with_namespace)?;
+    let mut keys = BTreeSet::new();
+    let mut collect = |what: &str, result: Result<Vec<Variable>, _>| match result {
+        Ok(variables) => keys.extend(variables.into_iter().map(|variable| variable.key)),
+        Err(err) if client::status(&err) == Some(http::StatusCode::FORBIDDEN) => {
+            tracing::warn!("cannot read the variables of {what}; treating them as unknown")
+        }
+        Err(err) => tracing::warn!("failed to list the variables of {what}: {err}"),
+    };
+    collect(
+        &full_path,
+        api::paged(
+            ProjectVariables {
+                project: project.into(),
+            },
+            api::Pagination::All,
+        )
+        .query(client),
+    );
+    let groups: Vec<_> = full_path.split('/').collect();
+    for depth in 1..groups.len() {
+        let group = groups[..depth].join("/");
+        collect(
+            &group,
+            api::paged(
+                GroupVariables {
+                    group: group.as_str().into(),
+                },
+                api::Pagination::All,
+            )
+            .query(client),
+        );
+    }
+    Ok(keys)
+}
+
+/// Reports variables `path` uses that neither it, the project and its groups,
+/// nor GitLab itself define.
+pub fn check_vars(
+    client: &Client,
+    project: &str,
+    path: &Path,
+    reports: &Reports,
+) -> anyhow::Result<()> {
+    let content = read(path)?;
+    let lint = lint_content(client, project, path, &content, false, None)?;
+    let merged = lint.merged_yaml.unwrap_or_default();
+    let configured = configured_variables(client, project)?;
+    let mut undefined = undefined_references(&merged);
+    undefined.retain(|name, _| !configured.contains(name));
+    let cases: Vec<_> = if undefined.is_empty() {
+        vec![Case::passed("every variable is defined")]
+    } else {
+        undefined
+            .iter()
+            .map(|(name, jobs)| {
+                let jobs: Vec<_> = jobs.iter().map(String::as_str).collect();
+                // Included files have no line here; the variable may well be used there.
+                let line = content
+                    .lines()
+                    .position(|line| {
+                        line.contains(&format!("${name}")) || line.contains(&format!("${{{name}}}"))
+                    })
+                    .map_or(1, |index| index as u64 + 1);
+                Case::failed(
+                    format!("${name}"),
+                    format!("used by {} but defined nowhere", jobs.join(", ")),
+                )
+                .at(path.to_string_lossy(), line)
+            })
+            .collect()
+    };
+    reports.write("ci-check-vars", &cases)?;
+    if undefined.is_empty() {
+        tracing::info!("every variable {} uses is defined", path.display());
+        return Ok(());
+    }
+    let rows: Vec<_> = undefined
+        .iter()
+        .map(|(name, jobs)| {
+            let jobs: Vec<_> = jobs.iter().map(String::as_str).collect();
+            [format!("${name}"), jobs.join(", ")]
+        })
+        .collect();
+    println!("{}", table::render(["VARIABLE", "USED BY"], &rows));
+    anyhow::bail!(
+        "{} variable(s) are likely undefined; dotenv artifacts and instance variables are not checked",
+        undefined.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn undefined(merged: &str) -> Vec<(String, Vec<String>)> {
+        undefined_references(merged)
+            .into_iter()
+            .map(|(name, jobs)| (name, jobs.into_iter().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn variables_of_one_job_do_not_define_them_for_another() {
+        let merged = "\
+build:
+  variables:
+    TARGET: release
+  script:
+    - make $TARGET
+test:
+  script:
+    - ./test $TARGET
+";
+        assert_eq!(
+            undefined(merged),
+            vec![("TARGET".to_owned(), vec!["test".to_owned()])]
+        );
+    }
+
+    #[test]
+    fn global_default_and_extended_variables_are_defined() {
+        let merged = "\
+variables:
+  REGISTRY: registry.example.com
+default:
+  before_script:
+    - export TAG=latest
+.deploy:
+  variables:
+    ENVIRONMENT: staging
+deploy:
+  extends: .deploy
+  script:
+    - deploy $REGISTRY:$TAG $ENVIRONMENT
+promote:
+  extends:
+  - .deploy
+  script:
+    - promote $ENVIRONMENT
+smoke:
+  extends: [\".deploy\"]
+  script:
+    - curl $ENVIRONMENT $MISSING
+";
+        assert_eq!(
+            undefined(merged),
+            vec![("MISSING".to_owned(), vec!["smoke".to_owned()])]
+        );
+    }
+
+    #[test]
+    fn shell_assignments_and_escapes_count() {
+        let merged = "\
+job:
+  script:
+    - VERSION=$(cat VERSION); echo $VERSION $$HOME ${CI_COMMIT_SHA}
+";
+        assert_eq!(undefined(merged), vec![]);
+    }
+}
@@ -0,0 +1,583 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use bytes::Bytes;
+use clap::Args;
+use gitlab::api::{self, Query};
+use serde::{de::DeserializeOwned, Deserialize};
+use url::Url;
+
+use crate::auth::{Credentials, TokenKind};
+use crate::cache::{self, Cache};
+use crate::duration;
+use crate::fixtures::{Recorder, Replay};
+use crate::metrics;
+
+/// The first retry waits this long, doubling with every further attempt.
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct NetworkOptions {
+    /// Extra PEM-encoded CA certificate(s) to trust, e.g. an internal root CA.
+    #[arg(
+        long = "ca-cert",
+        global = true,
+        env = "GITLAB_CA_CERT",
+        value_delimiter = ','
+    )]
+    pub ca_certs: Vec<PathBuf>,
+    /// Skip TLS certificate verification entirely.
+    #[arg(long, global = true)]
+    pub insecure: bool,
+    /// Proxy for all GitLab traffic; HTTPS_PROXY, HTTP_PROXY and NO_PROXY are honoured otherwise.
+    #[arg(long, global = true)]
+    pub proxy: Option<String>,
+    /// Timeout for each individual API request.
+    #[arg(long, global = true, value_parser = duration::parse, default_value = "30s")]
+    pub timeout: Duration,
+    /// Abort the whole command cleanly once this much time has passed, e.g. `120s`.
+    #[arg(long, global = true, value_parser = duration::parse)]
+    pub deadline: Option<Duration>,
+    /// Retry GET requests this many times on 429, 5xx and connection errors.
+    #[arg(long, global = true, default_value_t = 2)]
+    pub retries: u32,
+    /// Write every API response to DIR as a JSON fixture, e.g. against a sandbox project.
+    /// GraphQL is not recorded, so reads that can use REST do.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub record_fixtures: Option<PathBuf>,
+    /// Answer API reads from the fixtures in DIR and stub every change and notification.
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        conflicts_with = "record_fixtures"
+    )]
+    pub replay: Option<PathBuf>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestError {
+    #[error("communication with gitlab: {0}")]
+    Communication(#[from] reqwest::Error),
+    #[error("`http` error: {0}")]
+    Http(#[from] http::Error),
+    #[error("invalid token header: {0}")]
+    Header(#[from] http::header::InvalidHeaderValue),
+    #[error("the --deadline for this command has passed")]
+    DeadlineExceeded,
+    #[error("replay: {0}")]
+    Replay(String),
+    /// A step done in the local clone instead; see `--local-git`.
+    #[error("local git: {0}")]
+    Git(String),
+}
+
+/// Whether `err` means the command ran out of time, as opposed to a failure
+/// reported by GitLab itself.
+pub fn is_timeout(err: &api::ApiError<RestError>) -> bool {
+    match err {
+        api::ApiError::Client { source } => match source {
+            RestError::DeadlineExceeded => true,
+            RestError::Communication(err) => err.is_timeout(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+pub struct Client {
+    http: reqwest::blocking::Client,
+    rest_url: Url,
+    graphql_url: Url,
+    credentials: Credentials,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    retries: u32,
+    cache: Option<Cache>,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
+}
+
+impl Client {
+    pub fn new(
+        host: &str,
+        credentials: Credentials,
+        options: &NetworkOptions,
+    ) -> anyhow::Result<Self> {
+        let deadline = options.deadline.map(|deadline| Instant::now() + deadline);
+        let mut builder = reqwest::blocking::Client::builder();
+
+        let mut ca_certs = options.ca_certs.clone();
+        // Runners on instances with a private CA expose it to every job.
+        if let Some(ci_ca) = std::env::var_os("CI_SERVER_TLS_CA_FILE") {
+            ca_certs.push(ci_ca.into());
+        }
+        for path in &ca_certs {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("failed to read CA certificate {}", path.display()))?;
+            for cert in reqwest::Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("invalid PEM in {}", path.display()))?
+            {
+                builder = builder.add_root_certificate(cert);
+            }
+            tracing::debug!("trusting CA certificate(s) from {}", path.display());
+        }
+        if options.insecure {
+            tracing::warn!("TLS certificate verification is disabled");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(proxy) = &options.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("invalid proxy URL {proxy}"))?,
+            );
+        }
+
+        // An explicit scheme is allowed so the helper can talk to a local mock server.
+        let base = if host.contains("://") {
+            host.trim_end_matches('/').to_owned()
+        } else {
+            format!("https://{host}")
+        };
+        let rest_url = Url::parse(&format!("{base}/api/v4/"))?;
+        let recorder = options
+            .record_fixtures
+            .as_deref()
+            .map(|dir| Recorder::new(dir, rest_url.clone()))
+            .transpose()?;
+        let replay = options
+            .replay
+            .as_deref()
+            .map(|dir| Replay::new(dir, rest_url.clone()))
+            .transpose()?;
+        let client = Client {
+            http: builder.build().context("failed to build the HTTP client")?,
+            graphql_url: Url::parse(&format!("{base}/api/graphql"))?,
+            rest_url,
+            credentials,
+            timeout: options.timeout,
+            deadline,
+            retries: options.retries,
+            cache: None,
+            recorder,
+            replay,
+        };
+
+        tracing::info!(
+            source = %client.credentials.source,
+            kind = %client.credentials.kind,
+            "authenticating to {host}"
+        );
+        client.check_connection().with_context(|| {
+            format!(
+                "failed to authenticate with the {} from {}",
+                client.credentials.kind, client.credentials.source
+            )
+        })?;
+        Ok(client)
+    }
+
+    /// Serves read-only requests from the on-disk response cache from now on.
+    pub fn with_cache(mut self, cache: Option<Cache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    fn check_connection(&self) -> Result<(), api::ApiError<RestError>> {
+        match self.credentials.kind {
+            TokenKind::Job => api::ignore(api::job::Job::builder().build().unwrap()).query(self),
+            TokenKind::Personal | TokenKind::OAuth2 => {
+                api::ignore(api::users::CurrentUser::builder().build().unwrap()).query(self)
+            }
+        }
+    }
+
+    /// When `--deadline` runs out, if it was given.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The timeout for the next request, or `None` once the deadline has passed.
+    fn request_timeout(&self) -> Option<Duration> {
+        match self.deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                (!remaining.is_zero()).then(|| remaining.min(self.timeout))
+            }
+            None => Some(self.timeout),
+        }
+    }
+
+    fn set_auth_header(&self, headers: &mut http::HeaderMap) -> Result<(), RestError> {
+        let (name, value) = match self.credentials.kind {
+            TokenKind::Personal => ("private-token", self.credentials.token.clone()),
+            TokenKind::Job => ("job-token", self.credentials.token.clone()),
+            TokenKind::OAuth2 => (
+                "authorization",
+                format!("Bearer {}", self.credentials.token),
+            ),
+        };
+        let mut value = http::HeaderValue::from_str(&value)?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+        Ok(())
+    }
+}
+
+/// The HTTP status GitLab answered with, if the error came from GitLab itself.
+pub fn status(err: &api::ApiError<RestError>) -> Option<http::StatusCode> {
+    match err {
+        api::ApiError::GitlabService { status, .. }
+        | api::ApiError::GitlabWithStatus { status, .. }
+        | api::ApiError::GitlabObjectWithStatus { status, .. }
+        | api::ApiError::GitlabUnrecognizedWithStatus { status, .. } => Some(*status),
+        _ => None,
+    }
+}
+
+/// What to do about `err`, when it has a common cause that its message
+/// alone does not make obvious.
+pub fn hint(err: &api::ApiError<RestError>) -> Option<&'static str> {
+    if let api::ApiError::DataType { .. } = err {
+        return Some(
+            "GitLab answered in a shape the helper does not know; \
+             an older GitLab may lack the field or endpoint this needs",
+        );
+    }
+    let message = match err {
+        api::ApiError::GitlabWithStatus { msg, .. } => msg.to_lowercase(),
+        api::ApiError::GitlabObjectWithStatus { obj, .. }
+        | api::ApiError::GitlabUnrecognizedWithStatus { obj, .. } => obj.to_string().to_lowercase(),
+        _ => String::new(),
+    };
+    match status(err)? {
+        http::StatusCode::FORBIDDEN if message.contains("insufficient_scope") => Some(
+            "the token lacks a scope this needs; use one with the `api` scope, \
+             or `read_api` for commands that only read",
+        ),
+        http::StatusCode::FORBIDDEN
+            if message.contains("protected") || message.contains("not allowed to push") =>
+        {
+            Some(
+                "the branch is protected; go through a merge request, \
+                 or have a maintainer allow your role to push to it",
+            )
+        }
+        http::StatusCode::FORBIDDEN => Some(
+            "the token's user lacks the role for this; most changes need Developer, \
+             settings and protected branches need Maintainer",
+        ),
+        http::StatusCode::NOT_FOUND if message.contains("project not found") => Some(
+            "check the project path for typos; GitLab says the same \
+             when the token's user cannot see a private project",
+        ),
+        http::StatusCode::BAD_REQUEST | http::StatusCode::CONFLICT
+            if message.contains("already exists") =>
+        {
+            Some(
+                "an earlier run probably left it behind; finish that run with --resume, \
+                 or remove it with rollback before trying again",
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The hint for the first GitLab error in the chain of `err`.
+pub fn explain(err: &anyhow::Error) -> Option<&'static str> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<api::ApiError<RestError>>())
+        .and_then(hint)
+}
+
+#[derive(Deserialize)]
+struct GraphqlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphqlError {
+    message: String,
+}
+
+impl Client {
+    /// A request to a service other than GitLab, such as a chat webhook, with
+    /// the same CA certificates, proxy, timeout and deadline as API calls.
+    pub fn external(
+        &self,
+        method: http::Method,
+        url: &str,
+    ) -> anyhow::Result<reqwest::blocking::RequestBuilder> {
+        let timeout = self.request_timeout().ok_or(RestError::DeadlineExceeded)?;
+        Ok(self.http.request(method, url).timeout(timeout))
+    }
+
+    /// Whether `--replay` stands in for GitLab, so nothing may leave the machine.
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Whether reads with a REST equivalent should go through `graphql`. Job
+    /// tokens only work with REST, and fixtures are only recorded and
+    /// replayed for REST.
+    pub fn accepts_graphql(&self) -> bool {
+        self.credentials.kind != TokenKind::Job && self.recorder.is_none() && self.replay.is_none()
+    }
+
+    /// Runs a read-only GraphQL query; mutations keep going through REST.
+    pub fn graphql<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> anyhow::Result<T> {
+        anyhow::ensure!(
+            self.replay.is_none(),
+            "GraphQL queries cannot be replayed; only REST fixtures are recorded"
+        );
+        anyhow::ensure!(
+            self.credentials.kind != TokenKind::Job,
+            "the GraphQL API does not accept job tokens; provide a personal access token"
+        );
+        let timeout = self.request_timeout().ok_or(RestError::DeadlineExceeded)?;
+        let payload = serde_json::json!({ "query": query, "variables": variables });
+        let cached = self.cache.as_ref().map(|cache| {
+            let key = cache.key(
+                &format!("{}#{payload}", self.graphql_url),
+                &self.credentials.token,
+            );
+            let entry = cache.get(&key).filter(|entry| cache.is_fresh(entry));
+            (cache, key, entry)
+        });
+        let body = match cached {
+            Some((_, _, Some(entry))) => entry.body,
+            _ => {
+                let mut headers = http::HeaderMap::new();
+                self.set_auth_header(&mut headers)?;
+                let body = self
+                    .http
+                    .post(self.graphql_url.clone())
+                    .headers(headers)
+                    .timeout(timeout)
+                    .json(&payload)
+                    .send()?
+                    .error_for_status()?
+                    .text()?;
+                if let Some((cache, key, None)) = cached {
+                    cache.put(
+                        &key,
+                        cache::Entry {
+                            stored_at: 0,
+                            etag: None,
+                            status: 200,
+                            headers: Vec::new(),
+                            body: body.clone(),
+                        },
+                    );
+                }
+                body
+            }
+        };
+        let rsp: GraphqlResponse<T> = serde_json::from_str(&body)?;
+        if !rsp.errors.is_empty() {
+            let messages = rsp
+                .errors
+                .into_iter()
+                .map(|err| err.message)
+                .collect::<Vec<_>>();
+            anyhow::bail!("GraphQL query failed: {}", messages.join("; "));
+        }
+        rsp.data
+            .ok_or_else(|| anyhow::anyhow!("GraphQL query returned no data"))
+    }
+}
+
+impl api::RestClient for Client {
+    type Error = RestError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, api::ApiError<Self::Error>> {
+        tracing::debug!("REST api call {endpoint}");
+        Ok(self.rest_url.join(endpoint)?)
+    }
+}
+
+fn is_transient(status: http::StatusCode) -> bool {
+    status == http::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// GitLab's rate limiter says how long to back off in seconds.
+fn retry_after(rsp: &reqwest::blocking::Response) -> Option<Duration> {
+    let seconds = rsp
+        .headers()
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+fn cached_response(entry: &cache::Entry) -> Result<http::Response<Bytes>, RestError> {
+    let mut rsp = http::Response::builder().status(entry.status);
+    for (key, value) in &entry.headers {
+        rsp = rsp.header(key, value);
+    }
+    Ok(rsp.body(Bytes::from(entry.body.clone()))?)
+}
+
+impl Client {
+    fn execute(
+        &self,
+        mut request: reqwest::blocking::Request,
+    ) -> Result<http::Response<Bytes>, RestError> {
+        let span = tracing::debug_span!(
+            "http",
+            method = %request.method(),
+            path = request.url().path(),
+            status = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+        let (method, url) = (request.method().clone(), request.url().clone());
+        let retries = if method == reqwest::Method::GET {
+            self.retries
+        } else {
+            0
+        };
+        let mut attempt = 0;
+        let rsp = loop {
+            let retry = (attempt < retries).then(|| request.try_clone()).flatten();
+            let started = Instant::now();
+            let rsp = self.http.execute(request);
+            metrics::observe(
+                "gitlab_helper_api_request_duration_seconds",
+                started.elapsed(),
+            );
+            if !rsp
+                .as_ref()
+                .is_ok_and(|rsp| rsp.status().is_success() || rsp.status().is_redirection())
+            {
+                metrics::increment("gitlab_helper_api_errors_total", &[]);
+            }
+            let Some(next) = retry else { break rsp };
+            let backoff = RETRY_BACKOFF * 2u32.pow(attempt);
+            let wait = match &rsp {
+                Ok(rsp) if is_transient(rsp.status()) => retry_after(rsp).unwrap_or(backoff),
+                Err(err) if err.is_connect() || err.is_timeout() => backoff,
+                _ => break rsp,
+            };
+            // Waiting past the deadline would only turn this error into a timeout.
+            if self
+                .deadline
+                .is_some_and(|deadline| Instant::now() + wait >= deadline)
+            {
+                break rsp;
+            }
+            tracing::warn!(
+                attempt = attempt + 1,
+                "GET {} failed, retrying in {wait:?}",
+                url.path()
+            );
+            std::thread::sleep(wait);
+            request = next;
+            attempt += 1;
+        };
+        let rsp = rsp?;
+        span.record("status", rsp.status().as_u16());
+
+        let mut http_rsp = http::Response::builder()
+            .status(rsp.status())
+            .version(rsp.version());
+        let headers = http_rsp.headers_mut().unwrap();
+        for (key, value) in rsp.headers() {
+            headers.insert(key, value.clone());
+        }
+        let http_rsp = http_rsp.body(rsp.bytes()?)?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&method, &url, &http_rsp);
+        }
+        Ok(http_rsp)
+    }
+
+    /// GET requests go through the response cache: fresh entries are served
+    /// as-is, stale ones are revalidated with their ETag.
+    fn execute_cached(
+        &self,
+        cache: &Cache,
+        mut request: reqwest::blocking::Request,
+    ) -> Result<http::Response<Bytes>, RestError> {
+        let key = cache.key(request.url().as_str(), &self.credentials.token);
+        let cached = cache.get(&key);
+        if let Some(entry) = &cached {
+            if cache.is_fresh(entry) {
+                tracing::debug!("serving {} from the response cache", request.url());
+                return cached_response(entry);
+            }
+            if let Some(etag) = entry.etag.as_deref().and_then(|etag| etag.parse().ok()) {
+                request
+                    .headers_mut()
+                    .insert(http::header::IF_NONE_MATCH, etag);
+            }
+        }
+
+        let rsp = self.execute(request)?;
+        if rsp.status() == http::StatusCode::NOT_MODIFIED {
+            if let Some(entry) = cached {
+                let rsp = cached_response(&entry)?;
+                cache.put(&key, entry);
+                return Ok(rsp);
+            }
+        }
+        if rsp.status().is_success() {
+            if let Ok(body) = std::str::from_utf8(rsp.body()) {
+                let headers = rsp
+                    .headers()
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        Some((key.to_string(), value.to_str().ok()?.to_owned()))
+                    })
+                    .collect();
+                cache.put(
+                    &key,
+                    cache::Entry {
+                        stored_at: 0,
+                        etag: rsp
+                            .headers()
+                            .get(http::header::ETAG)
+                            .and_then(|etag| etag.to_str().ok())
+                            .map(str::to_owned),
+                        status: rsp.status().as_u16(),
+                        headers,
+                        body: body.to_owned(),
+                    },
+                );
+            }
+        }
+        Ok(rsp)
+    }
+}
+
+impl api::Client for Client {
+    fn rest(
+        &self,
+        mut request: http::request::Builder,
+        body: Vec<u8>,
+    ) -> Result<http::Response<Bytes>, api::ApiError<Self::Error>> {
+        let call = || -> Result<_, RestError> {
+            if let Some(replay) = &self.replay {
+                return replay.respond(&request.body(body)?);
+            }
+            let timeout = self.request_timeout().ok_or(RestError::DeadlineExceeded)?;
+            self.set_auth_header(request.headers_mut().unwrap())?;
+            let mut request: reqwest::blocking::Request = request.body(body)?.try_into()?;
+            *request.timeout_mut() = Some(timeout);
+            match &self.cache {
+                Some(cache) if request.method() == reqwest::Method::GET => {
+                    self.execute_cached(cache, request)
+                }
+                _ => self.execute(request),
+            }
+        };
+        call().map_err(api::ApiError::client)
+    }
+}
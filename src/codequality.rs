@@ -0,0 +1,73 @@
+use serde_json::json;
+
+use crate::checks::Case;
+
+/// FNV-1a, which stays the same across runs and builds, so GitLab can tell
+/// the findings a merge request adds from those already on its target.
+fn fingerprint(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// The failed `cases` as a Code Quality report, for the merge request
+/// widget. Checks of the merge request as a whole, such as its title, have
+/// no file of their own and point at the repository root.
+pub fn render(suite: &str, cases: &[Case]) -> String {
+    let issues: Vec<_> = cases
+        .iter()
+        .filter_map(|case| {
+            let failure = case.failure.as_ref()?;
+            let (path, line) = case
+                .location
+                .as_ref()
+                .map_or((".", 1), |location| (location.path.as_str(), location.line));
+            Some(json!({
+                "description": failure,
+                "check_name": format!("{suite}: {}", case.name),
+                "fingerprint": fingerprint(&format!("{suite}\0{}\0{failure}\0{path}", case.name)),
+                "severity": "major",
+                "location": { "path": path, "lines": { "begin": line } },
+            }))
+        })
+        .collect();
+    serde_json::to_string_pretty(&issues).expect("values always serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_failures_are_reported_where_they_are() {
+        let cases = [
+            Case::passed("title"),
+            Case::failed(
+                "secrets.env:3: possible AWS access key",
+                "possible AWS access key",
+            )
+            .at("secrets.env", 3),
+            Case::failed("migrations", "needs the ~db label"),
+        ];
+
+        let report: serde_json::Value =
+            serde_json::from_str(&render("check-diff", &cases)).unwrap();
+
+        let issues = report.as_array().unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(
+            issues[0]["location"],
+            json!({ "path": "secrets.env", "lines": { "begin": 3 } })
+        );
+        assert_eq!(issues[1]["location"]["path"], ".");
+        assert_eq!(issues[1]["check_name"], "check-diff: migrations");
+        assert_ne!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+    }
+
+    #[test]
+    fn fingerprints_are_stable() {
+        assert_eq!(fingerprint(""), "cbf29ce484222325");
+        assert_eq!(fingerprint("a"), "af63dc4c8601ec8c");
+    }
+}
@@ -0,0 +1,67 @@
+use clap::{Args, ValueEnum};
+use gitlab::api::projects::repository::commits::{CommitStatusState, CreateCommitStatus};
+use gitlab::api::{self, Query};
+
+use crate::client::Client;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum State {
+    Pending,
+    Running,
+    Success,
+    Failed,
+    Canceled,
+}
+
+impl From<State> for CommitStatusState {
+    fn from(state: State) -> Self {
+        match state {
+            State::Pending => CommitStatusState::Pending,
+            State::Running => CommitStatusState::Running,
+            State::Success => CommitStatusState::Success,
+            State::Failed => CommitStatusState::Failed,
+            State::Canceled => CommitStatusState::Canceled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SetStatus {
+    #[arg(long, env = "CI_COMMIT_SHA")]
+    pub sha: String,
+    /// The check's name on the merge request, e.g. `jira-check`.
+    #[arg(long)]
+    pub name: String,
+    #[arg(long, value_enum)]
+    pub state: State,
+    /// Where the details of the check are.
+    #[arg(long)]
+    pub target_url: Option<String>,
+    #[arg(long)]
+    pub description: Option<String>,
+    /// The branch or tag of the commit, if it is on several.
+    #[arg(long = "ref")]
+    pub ref_: Option<String>,
+}
+
+/// Publishes `status` on its commit, where GitLab shows it next to the
+/// pipeline's jobs.
+pub fn set(client: &Client, project: &str, status: &SetStatus) -> anyhow::Result<()> {
+    let mut endpoint = CreateCommitStatus::builder();
+    endpoint
+        .project(project)
+        .commit(status.sha.as_str())
+        .state(status.state)
+        .name(status.name.as_str());
+    if let Some(url) = &status.target_url {
+        endpoint.target_url(url.as_str());
+    }
+    if let Some(description) = &status.description {
+        endpoint.description(description.as_str());
+    }
+    if let Some(ref_) = &status.ref_ {
+        endpoint.ref_(ref_.as_str());
+    }
+    api::ignore(endpoint.build()?).query(client)?;
+    Ok(())
+}
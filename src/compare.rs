@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use gitlab::api::projects::repository::commits::{CompareCommits, MergeRequests};
+use gitlab::api::Query;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::table;
+
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    #[serde(default)]
+    commits: Vec<Commit>,
+    #[serde(default)]
+    diffs: Vec<Diff>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    id: String,
+    short_id: String,
+    title: String,
+    author_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diff {
+    new_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    title: String,
+    web_url: String,
+}
+
+fn comparison(client: &Client, project: &str, from: &str, to: &str) -> anyhow::Result<Comparison> {
+    let endpoint = CompareCommits::builder()
+        .project(project)
+        .from(from)
+        .to(to)
+        .build()?;
+    Ok(endpoint.query(client)?)
+}
+
+/// The merge requests that brought in `commits`, each once, by IID.
+fn merge_requests(
+    client: &Client,
+    project: &str,
+    commits: &[Commit],
+) -> anyhow::Result<BTreeMap<u64, MergeRequest>> {
+    let mut found = BTreeMap::new();
+    for commit in commits {
+        let endpoint = MergeRequests::builder()
+            .project(project)
+            .sha(commit.id.as_str())
+            .build()?;
+        let merge_requests: Vec<MergeRequest> = endpoint.query(client)?;
+        for mr in merge_requests {
+            found.entry(mr.iid).or_insert(mr);
+        }
+    }
+    Ok(found)
+}
+
+/// The top-level directory of `path`, or `/` for files at the root.
+fn directory(path: &str) -> &str {
+    path.split_once('/').map_or("/", |(directory, _)| directory)
+}
+
+fn render(
+    from: &str,
+    to: &str,
+    comparison: &Comparison,
+    mrs: &BTreeMap<u64, MergeRequest>,
+) -> String {
+    let mut out = format!("### Changes in `{to}` since `{from}`\n\n");
+    let mut authors = BTreeMap::<&str, usize>::new();
+    for commit in &comparison.commits {
+        *authors.entry(&commit.author_name).or_default() += 1;
+    }
+    let mut authors: Vec<_> = authors.into_iter().collect();
+    authors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let authors: Vec<_> = authors
+        .iter()
+        .map(|(author, count)| format!("{author} ({count})"))
+        .collect();
+    out.push_str(&format!(
+        "**{} commit(s)** by {}\n",
+        comparison.commits.len(),
+        if authors.is_empty() {
+            "nobody".to_owned()
+        } else {
+            authors.join(", ")
+        }
+    ));
+
+    if !comparison.diffs.is_empty() {
+        let mut directories = BTreeMap::<&str, Vec<&str>>::new();
+        for diff in &comparison.diffs {
+            directories
+                .entry(directory(&diff.new_path))
+                .or_default()
+                .push(&diff.new_path);
+        }
+        out.push_str(&format!(
+            "\n#### {} changed file(s)\n\n",
+            comparison.diffs.len()
+        ));
+        for (directory, paths) in directories {
+            out.push_str(&format!("- `{directory}` ({})\n", paths.len()));
+            for path in paths {
+                out.push_str(&format!("  - `{path}`\n"));
+            }
+        }
+    }
+
+    if !mrs.is_empty() {
+        out.push_str("\n#### Merge requests\n\n");
+        for mr in mrs.values() {
+            out.push_str(&format!("- [!{}]({}) {}\n", mr.iid, mr.web_url, mr.title));
+        }
+    }
+    out
+}
+
+/// The commits `to` has that `from` does not, as `short_id title`.
+pub fn commit_titles(
+    client: &Client,
+    project: &str,
+    from: &str,
+    to: &str,
+) -> anyhow::Result<Vec<String>> {
+    let comparison = comparison(client, project, from, to)?;
+    Ok(comparison
+        .commits
+        .into_iter()
+        .map(|commit| format!("{} {}", commit.short_id, commit.title))
+        .collect())
+}
+
+/// A Markdown summary of what `to` has that `from` does not.
+pub fn summary(client: &Client, project: &str, from: &str, to: &str) -> anyhow::Result<String> {
+    let comparison = comparison(client, project, from, to)?;
+    let mrs = merge_requests(client, project, &comparison.commits)?;
+    Ok(render(from, to, &comparison, &mrs))
+}
+
+/// Prints the commits `to` has that `from` does not, or a Markdown summary
+/// of them with `summary`.
+pub fn run(
+    client: &Client,
+    project: &str,
+    from: &str,
+    to: &str,
+    summary: bool,
+) -> anyhow::Result<()> {
+    if summary {
+        print!("{}", self::summary(client, project, from, to)?);
+        return Ok(());
+    }
+    let comparison = comparison(client, project, from, to)?;
+    let rows: Vec<_> = comparison
+        .commits
+        .into_iter()
+        .map(|commit| [commit.short_id, commit.author_name, commit.title])
+        .collect();
+    println!("{}", table::render(["COMMIT", "AUTHOR", "TITLE"], &rows));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(author: &str) -> Commit {
+        Commit {
+            id: String::new(),
+            short_id: String::new(),
+            title: String::new(),
+            author_name: author.to_owned(),
+        }
+    }
+
+    #[test]
+    fn the_summary_groups_files_by_directory() {
+        let comparison = Comparison {
+            commits: vec![commit("bob"), commit("alice"), commit("alice")],
+            diffs: ["src/a.rs", "Cargo.toml", "src/b.rs"]
+                .map(|path| Diff {
+                    new_path: path.to_owned(),
+                })
+                .into(),
+        };
+        let mrs = BTreeMap::from([(
+            7,
+            MergeRequest {
+                iid: 7,
+                title: "Fix it".to_owned(),
+                web_url: "https://gitlab.example.com/mr/7".to_owned(),
+            },
+        )]);
+        assert_eq!(
+            render("release/1.2.3", "master", &comparison, &mrs),
+            "### Changes in `master` since `release/1.2.3`
+
+**3 commit(s)** by alice (2), bob (1)
+
+#### 3 changed file(s)
+
+- `/` (1)
+  - `Cargo.toml`
+- `src` (2)
+  - `src/a.rs`
+  - `src/b.rs`
+
+#### Merge requests
+
+- [!7](https://gitlab.example.com/mr/7) Fix it
+"
+        );
+    }
+}
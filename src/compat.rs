@@ -0,0 +1,186 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use gitlab::api::projects::merge_requests::notes::CreateMergeRequestNote;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::checks::{Case, Reports};
+use crate::client::Client;
+use crate::hooks;
+use crate::redact;
+use crate::template::{self, Vars};
+
+/// How much of the checker's output makes it into the note: its last lines,
+/// where the verdict usually is.
+const OUTPUT_LINES: usize = 100;
+
+/// The `[check_compat]` section: the command that tells whether `head`
+/// breaks the API of `base`.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompatConfig {
+    /// The program and its arguments, with `{{ base }}` and `{{ head }}`,
+    /// e.g. `["cargo", "semver-checks", "--baseline-rev", "{{ base }}"]`.
+    /// It runs in the job's checkout of `head` and fails on a breaking change.
+    #[serde(deserialize_with = "hooks::command")]
+    pub command: Vec<String>,
+    #[serde(deserialize_with = "hooks::timeout")]
+    pub timeout: Duration,
+}
+
+impl Default for CompatConfig {
+    fn default() -> Self {
+        CompatConfig {
+            command: Vec::new(),
+            timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+/// How an external command exited, and everything it wrote.
+pub struct Outcome {
+    pub passed: bool,
+    pub output: String,
+}
+
+fn spawn_reader(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = pipe.read_to_end(&mut output);
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+/// Runs `command`, killing it after `timeout`, with its stdout and stderr
+/// captured rather than interleaved with ours.
+pub fn run_captured(command: &[String], timeout: Duration) -> anyhow::Result<Outcome> {
+    let Some((program, args)) = command.split_first() else {
+        anyhow::bail!("the command needs at least a program");
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start `{program}`"))?;
+    let stdout = spawn_reader(child.stdout.take().unwrap());
+    let stderr = spawn_reader(child.stderr.take().unwrap());
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("failed to wait for `{program}`"))?
+        {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("killed `{program}` after {timeout:?}");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let output = [stdout, stderr]
+        .map(|reader| reader.join().unwrap_or_default())
+        .join("");
+    Ok(Outcome {
+        passed: status.success(),
+        output,
+    })
+}
+
+/// The last `OUTPUT_LINES` lines of `output`.
+fn tail(output: &str) -> String {
+    let lines: Vec<_> = output.trim_end().lines().collect();
+    let skipped = lines.len().saturating_sub(OUTPUT_LINES);
+    let mut tail = lines[skipped..].join("\n");
+    if skipped > 0 {
+        tail.insert_str(0, &format!("[{skipped} earlier line(s) left out]\n"));
+    }
+    tail
+}
+
+fn note(base: &str, head: &str, outcome: &Outcome) -> String {
+    let verdict = if outcome.passed {
+        format!(":white_check_mark: `{head}` keeps the API of `{base}`.")
+    } else {
+        format!(":x: `{head}` breaks the API of `{base}`; a patch release may not.")
+    };
+    let output = tail(&outcome.output);
+    if output.is_empty() {
+        return format!("**check-compat** {verdict}");
+    }
+    format!(
+        "**check-compat** {verdict}\n\n<details><summary>Checker output</summary>\n\n```\n{output}\n```\n\n</details>"
+    )
+}
+
+/// Runs the configured compatibility checker on `head` against `base`, posts
+/// its verdict on merge request `iid` if there is one, and fails if `head`
+/// breaks compatibility.
+pub fn check(
+    client: &Client,
+    project: &str,
+    base: &str,
+    head: &str,
+    iid: Option<u64>,
+    config: &CompatConfig,
+    reports: &Reports,
+) -> anyhow::Result<()> {
+    let vars = Vars::from([
+        ("base".to_owned(), base.to_owned()),
+        ("head".to_owned(), head.to_owned()),
+    ]);
+    let command = config
+        .command
+        .iter()
+        .map(|arg| template::render(arg, &vars))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    anyhow::ensure!(
+        !command.is_empty(),
+        "set `check_compat.command` to the compatibility checker to run"
+    );
+    let outcome = run_captured(&command, config.timeout)?;
+
+    let name = format!("{head} is compatible with {base}");
+    let case = if outcome.passed {
+        Case::passed(name)
+    } else {
+        Case::failed(name, tail(&outcome.output))
+    };
+    reports.write("check-compat", &[case])?;
+    if let Some(iid) = iid {
+        let body = note(base, head, &outcome);
+        let endpoint = CreateMergeRequestNote::builder()
+            .project(project)
+            .merge_request(iid)
+            .body(redact::redact(&body))
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    anyhow::ensure!(
+        outcome.passed,
+        "{head} breaks compatibility with {base}:\n{}",
+        tail(&outcome.output)
+    );
+    tracing::info!(project, "{head} is compatible with {base}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_end_of_long_output_is_kept() {
+        let output: String = (1..=150).map(|i| format!("line {i}\n")).collect();
+        let tail = tail(&output);
+        assert!(tail.starts_with("[50 earlier line(s) left out]\nline 51\n"));
+        assert!(tail.ends_with("line 150"));
+    }
+}
@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::mr_rules;
+use crate::template::{self, Vars};
+
+/// A `[[components]]` entry: a part of a monorepo that is built on its own.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Component {
+    pub name: String,
+    /// Globs like in `mr_rules`, e.g. `services/billing/` and the libraries it uses.
+    pub paths: Vec<String>,
+}
+
+impl Component {
+    /// The name as a CI/CD variable name: `web-app` is `WEB_APP`.
+    fn variable(&self) -> String {
+        self.name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+/// The components that own any of `paths`, in the configured order.
+pub fn affected<'a>(
+    components: &'a [Component],
+    paths: &[String],
+) -> anyhow::Result<Vec<&'a Component>> {
+    let mut affected = Vec::new();
+    for component in components {
+        if mr_rules::any_matches(&component.paths, paths)? {
+            affected.push(component);
+        }
+    }
+    Ok(affected)
+}
+
+/// The components merge request `iid` changes.
+pub fn changed<'a>(
+    client: &Client,
+    project: &str,
+    iid: u64,
+    components: &'a [Component],
+) -> anyhow::Result<Vec<&'a Component>> {
+    anyhow::ensure!(
+        !components.is_empty(),
+        "no [[components]] in the config file"
+    );
+    let paths = mr_rules::changed_paths(client, project, iid)?;
+    affected(components, &paths)
+}
+
+/// A dotenv report: `CHANGED_COMPONENTS` lists the changed components, and
+/// `CHANGED_<NAME>` is `true` or `false` for each of `components`.
+fn dotenv(components: &[Component], changed: &[&Component]) -> String {
+    let names: Vec<_> = changed
+        .iter()
+        .map(|component| component.name.as_str())
+        .collect();
+    let mut out = format!("CHANGED_COMPONENTS={}\n", names.join(","));
+    for component in components {
+        let is_changed = names.contains(&component.name.as_str());
+        out.push_str(&format!("CHANGED_{}={is_changed}\n", component.variable()));
+    }
+    out
+}
+
+/// Prints the components merge request `iid` changes, one per line, and
+/// writes them to `dotenv_path` for `artifacts:reports:dotenv` if set.
+pub fn run(
+    client: &Client,
+    project: &str,
+    iid: u64,
+    components: &[Component],
+    dotenv_path: Option<&Path>,
+) -> anyhow::Result<()> {
+    let changed = changed(client, project, iid, components)?;
+    for component in &changed {
+        println!("{}", component.name);
+    }
+    if let Some(path) = dotenv_path {
+        std::fs::write(path, dotenv(components, &changed))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// GitLab refuses to run a child pipeline without jobs.
+const NO_CHANGES: &str = "no-changed-components:
+  script:
+    - echo \"No component changed\"
+";
+
+/// A child pipeline with `template` rendered once per changed component,
+/// with `{{ component }}` set to its name and `{{ component_path }}` to
+/// its first path, after `header` for what the jobs share, like `stages`.
+pub fn child_pipeline(
+    header: Option<&str>,
+    template: &str,
+    changed: &[&Component],
+) -> anyhow::Result<String> {
+    let mut out = String::from("# Generated by gitlab-helper generate-child-pipeline.\n");
+    if let Some(header) = header {
+        out.push_str(header);
+        out.push('\n');
+    }
+    if changed.is_empty() {
+        out.push_str(NO_CHANGES);
+    }
+    for component in changed {
+        let path = component
+            .paths
+            .first()
+            .map_or("", |path| path.trim_end_matches('/'));
+        let vars = Vars::from([
+            ("component".to_owned(), component.name.clone()),
+            ("component_path".to_owned(), path.to_owned()),
+        ]);
+        let jobs = template::render(template, &vars)
+            .with_context(|| format!("the template does not render for {}", component.name))?;
+        out.push_str(&jobs);
+        if !jobs.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(name: &str, paths: &[&str]) -> Component {
+        Component {
+            name: name.to_owned(),
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn shared_paths_affect_every_component_using_them() {
+        let components = [
+            component("billing", &["services/billing/", "libs/common/"]),
+            component("web-app", &["web/", "libs/common/"]),
+            component("docs", &["docs/"]),
+        ];
+        let changed = affected(&components, &["libs/common/money.rs".to_owned()]).unwrap();
+        let names: Vec<_> = changed
+            .iter()
+            .map(|component| component.name.as_str())
+            .collect();
+        assert_eq!(names, ["billing", "web-app"]);
+        assert_eq!(
+            dotenv(&components, &changed),
+            "CHANGED_COMPONENTS=billing,web-app\nCHANGED_BILLING=true\nCHANGED_WEB_APP=true\nCHANGED_DOCS=false\n"
+        );
+    }
+
+    #[test]
+    fn the_template_is_repeated_per_changed_component() {
+        let billing = component("billing", &["services/billing/"]);
+        let template = "build-{{ component }}:\n  script: make -C {{ component_path }}\n";
+        assert_eq!(
+            child_pipeline(Some("stages: [build]\n"), template, &[&billing]).unwrap(),
+            "# Generated by gitlab-helper generate-child-pipeline.\nstages: [build]\n\nbuild-billing:\n  script: make -C services/billing\n"
+        );
+        let unchanged = child_pipeline(None, template, &[]).unwrap();
+        assert!(unchanged.contains("no-changed-components:"), "{unchanged}");
+    }
+}
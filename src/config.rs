@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::authorship::CommitConfig;
+use crate::badges::Badge;
+use crate::compat::CompatConfig;
+use crate::components::Component;
+use crate::confluence::ConfluenceConfig;
+use crate::diff_check::DiffConfig;
+use crate::emergency::PatchConfig;
+use crate::hooks::Hooks;
+use crate::i18n::Locale;
+use crate::incident::IncidentConfig;
+use crate::jira::JiraConfig;
+use crate::labels::Label;
+use crate::merge::MergeConfig;
+use crate::mr_rules::PathRule;
+use crate::notify::NotifyConfig;
+use crate::oncall::OnCall;
+use crate::platform;
+use crate::policy::PolicyConfig;
+use crate::propagate::PropagateConfig;
+use crate::protect::ProtectConfig;
+use crate::provenance::ProvenanceConfig;
+use crate::status_page::StatusPageConfig;
+
+pub const DEFAULT_PATH: &str = ".gitlab-ci-helper.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The GitLab instance, e.g. `gitlab.example.com`.
+    #[serde(default, deserialize_with = "host")]
+    pub host: Option<String>,
+    /// Project IDs or paths that multi-project commands run against.
+    #[serde(default, deserialize_with = "projects")]
+    pub projects: Vec<String>,
+    /// A group whose projects (including subgroups) are used when `projects` is empty.
+    pub group: Option<String>,
+    /// The language of generated MR descriptions and notifications.
+    #[serde(default)]
+    pub locale: Locale,
+    #[serde(default)]
+    pub serve: ServeConfig,
+    #[serde(default)]
+    pub commits: CommitConfig,
+    /// External commands run around workflow steps.
+    #[serde(default)]
+    pub hooks: Hooks,
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    #[serde(default)]
+    pub protect: ProtectConfig,
+    /// The labels `labels sync` keeps every project in line with.
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// The badges `badges apply` adds to every project.
+    #[serde(default)]
+    pub badges: Vec<Badge>,
+    #[serde(default)]
+    pub check_diff: DiffConfig,
+    #[serde(default)]
+    pub check_compat: CompatConfig,
+    /// What `check-mr` requires of merge requests that touch certain paths.
+    #[serde(default)]
+    pub mr_rules: Vec<PathRule>,
+    /// The parts of a monorepo `changed-components` tells apart.
+    #[serde(default)]
+    pub components: Vec<Component>,
+    pub oncall: Option<OnCall>,
+    pub incident: Option<IncidentConfig>,
+    pub status_page: Option<StatusPageConfig>,
+    pub confluence: Option<ConfluenceConfig>,
+    pub jira: Option<JiraConfig>,
+    #[serde(default)]
+    pub merge: MergeConfig,
+    #[serde(default)]
+    pub emergency_patch: PatchConfig,
+    #[serde(default)]
+    pub propagate: PropagateConfig,
+    #[serde(default)]
+    pub provenance: ProvenanceConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    /// Settings that `--profile NAME` lays over the rest, e.g. another host.
+    #[serde(default)]
+    pub profile: BTreeMap<String, toml::Table>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServeConfig {
+    /// Address the webhook listener binds to.
+    #[serde(deserialize_with = "listen")]
+    pub listen: String,
+    /// Comment on merge requests whose title breaks the naming convention.
+    pub lint_titles: bool,
+    /// Retitle merge requests opened with the title GitLab derives from the
+    /// branch to the one `suggest-title` suggests.
+    pub suggest_titles: bool,
+    /// Run `/emergency-patch` and friends when they are posted as MR comments.
+    pub slash_commands: bool,
+    /// Open a merge request from a merged hotfix branch into this branch.
+    #[serde(deserialize_with = "branch")]
+    pub merge_back_target: Option<String>,
+    /// The least role a commenter needs in the project to run slash commands.
+    pub command_role: Role,
+    /// The least role needed for `/cut-patch`, which branches off the latest release.
+    pub patch_role: Role,
+}
+
+/// A project role, as far as the bot's permission checks are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Developer,
+    Maintainer,
+    Owner,
+}
+
+impl Role {
+    pub fn access_level(self) -> u64 {
+        match self {
+            Role::Developer => 30,
+            Role::Maintainer => 40,
+            Role::Owner => 50,
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Role::Developer => "Developer",
+            Role::Maintainer => "Maintainer",
+            Role::Owner => "Owner",
+        })
+    }
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        ServeConfig {
+            listen: "0.0.0.0:8080".to_owned(),
+            lint_titles: true,
+            suggest_titles: false,
+            slash_commands: true,
+            merge_back_target: Some("dev".to_owned()),
+            command_role: Role::Developer,
+            patch_role: Role::Maintainer,
+        }
+    }
+}
+
+/// Deserializes a string that `check` accepts, so that a bad value is
+/// reported with the line and column it appears on.
+pub fn checked<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    check: impl FnOnce(&str) -> Result<(), String>,
+) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    check(&value).map_err(de::Error::custom)?;
+    Ok(value)
+}
+
+fn check_host(host: &str) -> Result<(), String> {
+    let url = if host.contains("://") {
+        url::Url::parse(host).ok()
+    } else {
+        url::Url::parse(&format!("https://{host}")).ok()
+    };
+    match url {
+        Some(url) if url.host().is_some() && url.path() == "/" && url.query().is_none() => Ok(()),
+        _ => Err(format!(
+            "{host:?} is not a host name; use e.g. \"gitlab.example.com\""
+        )),
+    }
+}
+
+fn host<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    checked(deserializer, check_host).map(Some)
+}
+
+fn projects<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let projects = Vec::<String>::deserialize(deserializer)?;
+    if let Some(project) = projects
+        .iter()
+        .find(|project| project.is_empty() || project.contains(char::is_whitespace))
+    {
+        return Err(de::Error::custom(format!(
+            "{project:?} is not a project ID or path"
+        )));
+    }
+    Ok(projects)
+}
+
+fn listen<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    checked(deserializer, |listen| {
+        listen
+            .parse::<std::net::SocketAddr>()
+            .map(|_| ())
+            .map_err(|_| format!("{listen:?} is not an address like \"0.0.0.0:8080\""))
+    })
+}
+
+/// Git's rules for ref names (`git check-ref-format`), minus the rarely hit ones.
+pub fn check_branch_name(name: &str) -> Result<(), String> {
+    let invalid = name.is_empty()
+        || name == "@"
+        || name.starts_with(['/', '-'])
+        || name.ends_with(['/', '.'])
+        || name.ends_with(".lock")
+        || name.contains("..")
+        || name.contains("//")
+        || name.contains("@{")
+        || name
+            .chars()
+            .any(|c| c.is_ascii_control() || " ~^:?*[\\".contains(c));
+    if invalid {
+        Err(format!("{name:?} is not a valid branch name"))
+    } else {
+        Ok(())
+    }
+}
+
+fn branch<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    checked(deserializer, check_branch_name).map(Some)
+}
+
+/// Lays `overlay` over `base`: tables are merged key by key, anything else
+/// is replaced.
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+impl Config {
+    /// Loads `path`, or `.gitlab-ci-helper.toml` from the working directory,
+    /// or else `config.toml` from the user's config directory, if either
+    /// exists; a missing default file is the same as an empty config. The
+    /// `profile` section of that name, if given, overrides the rest.
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> anyhow::Result<Self> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match platform::config_dir().map(|dir| dir.join("config.toml")) {
+                Some(user) if !Path::new(DEFAULT_PATH).exists() && user.exists() => (user, false),
+                _ => (PathBuf::from(DEFAULT_PATH), false),
+            },
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if !explicit && err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        let config: Config =
+            toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))?;
+        tracing::debug!("loaded configuration from {}", path.display());
+        match profile {
+            Some(name) => config
+                .profile(&contents, name)
+                .with_context(|| format!("invalid {}", path.display())),
+            None => Ok(config),
+        }
+    }
+
+    /// The config with profile `name` of `contents` applied.
+    fn profile(&self, contents: &str, name: &str) -> anyhow::Result<Self> {
+        let Some(overlay) = self.profile.get(name) else {
+            let known: Vec<_> = self.profile.keys().cloned().collect();
+            anyhow::bail!(
+                "no profile named {name:?}; defined: {}",
+                if known.is_empty() {
+                    "none".to_owned()
+                } else {
+                    known.join(", ")
+                }
+            );
+        };
+        let mut table: toml::Table = toml::from_str(contents)?;
+        table.remove("profile");
+        merge(&mut table, overlay.clone());
+        let config = Config::deserialize(table).with_context(|| format!("in profile {name}"))?;
+        tracing::debug!("using profile {name}");
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_names() {
+        for (host, valid) in [
+            ("gitlab.example.com", true),
+            ("https://gitlab.example.com", true),
+            ("http://localhost:8080/", true),
+            ("gitlab.example.com:8443", true),
+            ("10.0.0.7", true),
+            ("gitlab.example.com/group", false),
+            ("https://gitlab.example.com/api/v4", false),
+            ("gitlab.example.com?private_token=x", false),
+            ("", false),
+            ("https://", false),
+            ("gitlab example com", false),
+        ] {
+            assert_eq!(check_host(host).is_ok(), valid, "{host:?}");
+        }
+    }
+
+    #[test]
+    fn branch_names() {
+        for (name, valid) in [
+            ("master", true),
+            ("release/1.2.3", true),
+            ("feature/AB-12_fix.typo", true),
+            ("", false),
+            ("@", false),
+            ("a..b", false),
+            ("x.lock", false),
+            ("x@{1}", false),
+            ("release/", false),
+            ("/release", false),
+            ("-b", false),
+            ("trailing.", false),
+            ("a//b", false),
+            ("has space", false),
+            ("a~1", false),
+            ("a^", false),
+            ("a:b", false),
+            ("what?", false),
+            ("glob*", false),
+            ("[x", false),
+            ("back\\slash", false),
+            ("tab\tbed", false),
+        ] {
+            assert_eq!(check_branch_name(name).is_ok(), valid, "{name:?}");
+        }
+    }
+
+    const PROFILES: &str = r#"
+host = "gitlab.example.com"
+projects = ["group/app"]
+
+[merge]
+squash = true
+
+[profile.staging]
+host = "gitlab-sandbox.example.com"
+projects = ["sandbox/app"]
+merge = { remove_source_branch = true }
+"#;
+
+    #[test]
+    fn a_profile_overrides_and_merges_into_the_rest() {
+        let config: Config = toml::from_str(PROFILES).unwrap();
+        let staging = config.profile(PROFILES, "staging").unwrap();
+        assert_eq!(staging.host.as_deref(), Some("gitlab-sandbox.example.com"));
+        assert_eq!(staging.projects, ["sandbox/app"]);
+        assert_eq!(staging.merge.squash, Some(true));
+        assert!(staging.merge.remove_source_branch);
+    }
+
+    #[test]
+    fn an_unknown_profile_lists_the_defined_ones() {
+        let config: Config = toml::from_str(PROFILES).unwrap();
+        let err = config.profile(PROFILES, "prod").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no profile named \"prod\"; defined: staging"
+        );
+    }
+}
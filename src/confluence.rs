@@ -0,0 +1,241 @@
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::oncall::api_key;
+use crate::template::{self, Vars};
+
+/// The `[confluence]` section: the wiki space release notes are published to.
+/// Cloud sites authenticate with `CONFLUENCE_USER` and `CONFLUENCE_API_TOKEN`,
+/// Data Center with a personal access token in `CONFLUENCE_API_TOKEN` alone.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfluenceConfig {
+    /// The wiki, e.g. `https://example.atlassian.net/wiki`.
+    pub url: String,
+    /// The key of the space, e.g. `ENG`.
+    pub space: String,
+    /// The ID of the page the release notes go under; the space's top level if unset.
+    pub parent: Option<String>,
+    /// The page title, with `{{ project }}` and `{{ version }}`.
+    #[serde(default = "default_title")]
+    pub title: String,
+}
+
+fn default_title() -> String {
+    "{{ project }} {{ version }} release notes".to_owned()
+}
+
+#[derive(Debug, Deserialize)]
+struct Search {
+    results: Vec<Page>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Page {
+    id: String,
+    version: Option<Version>,
+    #[serde(rename = "_links")]
+    links: Links,
+}
+
+#[derive(Debug, Deserialize)]
+struct Version {
+    number: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Links {
+    base: Option<String>,
+    webui: Option<String>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `text` with its Markdown links and code spans as XHTML.
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    loop {
+        let link = rest.find('[').and_then(|start| {
+            let middle = start + rest[start..].find("](")?;
+            let end = middle + rest[middle..].find(')')?;
+            Some((start, middle, end))
+        });
+        let code = rest
+            .find('`')
+            .and_then(|start| Some((start, start + 1 + rest[start + 1..].find('`')?)));
+        match (link, code) {
+            (Some((start, middle, end)), code)
+                if code.is_none_or(|(code_start, _)| start < code_start) =>
+            {
+                out.push_str(&escape(&rest[..start]));
+                out.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(&rest[middle + 2..end]),
+                    inline(&rest[start + 1..middle])
+                ));
+                rest = &rest[end + 1..];
+            }
+            (_, Some((start, end))) => {
+                out.push_str(&escape(&rest[..start]));
+                out.push_str(&format!("<code>{}</code>", escape(&rest[start + 1..end])));
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                out.push_str(&escape(rest));
+                return out;
+            }
+        }
+    }
+}
+
+/// The Markdown that release notes are written in — headings, bullet lists,
+/// links and code spans — in Confluence's storage format.
+fn storage(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut in_list = false;
+    for line in markdown.lines() {
+        let line = line.trim_end();
+        let item = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "));
+        if in_list && item.is_none() {
+            out.push_str("</ul>");
+            in_list = false;
+        }
+        if let Some(item) = item {
+            if !in_list {
+                out.push_str("<ul>");
+                in_list = true;
+            }
+            out.push_str(&format!("<li>{}</li>", inline(item)));
+            continue;
+        }
+        let level = line.chars().take_while(|&c| c == '#').count();
+        match line[level..].strip_prefix(' ') {
+            Some(heading) if (1..=6).contains(&level) => {
+                out.push_str(&format!("<h{level}>{}</h{level}>", inline(heading)));
+            }
+            _ if line.is_empty() => {}
+            _ => out.push_str(&format!("<p>{}</p>", inline(line))),
+        }
+    }
+    if in_list {
+        out.push_str("</ul>");
+    }
+    out
+}
+
+fn authorized(
+    request: reqwest::blocking::RequestBuilder,
+) -> anyhow::Result<reqwest::blocking::RequestBuilder> {
+    let token = api_key("CONFLUENCE_API_TOKEN")?;
+    Ok(match std::env::var("CONFLUENCE_USER") {
+        Ok(user) => request.basic_auth(user, Some(token)),
+        Err(_) => request.bearer_auth(token),
+    })
+}
+
+fn send<T: serde::de::DeserializeOwned>(
+    request: reqwest::blocking::RequestBuilder,
+    what: &str,
+) -> anyhow::Result<T> {
+    authorized(request)?
+        .send()
+        .and_then(|rsp| rsp.error_for_status())
+        .and_then(|rsp| rsp.json())
+        .with_context(|| format!("failed to {what}"))
+}
+
+/// Publishes the release notes of `version` as a page of the configured
+/// space, updating the page if one of that title exists, and returns its URL.
+pub fn publish(
+    client: &Client,
+    config: &ConfluenceConfig,
+    project: &str,
+    version: &str,
+    notes: &str,
+) -> anyhow::Result<String> {
+    let vars = Vars::from([
+        ("project".to_owned(), project.to_owned()),
+        ("version".to_owned(), version.to_owned()),
+    ]);
+    let title = template::render(&config.title, &vars)?;
+    if client.is_replaying() {
+        tracing::info!("replay: not publishing {title:?} to Confluence");
+        return Ok(title);
+    }
+    let api = format!("{}/rest/api/content", config.url.trim_end_matches('/'));
+    let search: Search = send(
+        client.external(http::Method::GET, &api)?.query(&[
+            ("spaceKey", config.space.as_str()),
+            ("title", title.as_str()),
+            ("expand", "version"),
+        ]),
+        &format!("look for {title:?} in Confluence"),
+    )?;
+
+    let body = storage(notes);
+    let page: Page = match search.results.into_iter().next() {
+        Some(existing) => {
+            let number = existing.version.map_or(1, |version| version.number) + 1;
+            let page = serde_json::json!({
+                "type": "page",
+                "title": title,
+                "version": { "number": number },
+                "body": { "storage": { "value": body, "representation": "storage" } },
+            });
+            send(
+                client
+                    .external(http::Method::PUT, &format!("{api}/{}", existing.id))?
+                    .json(&page),
+                &format!("update {title:?} in Confluence"),
+            )?
+        }
+        None => {
+            let mut page = serde_json::json!({
+                "type": "page",
+                "title": title,
+                "space": { "key": config.space },
+                "body": { "storage": { "value": body, "representation": "storage" } },
+            });
+            if let Some(parent) = &config.parent {
+                page["ancestors"] = serde_json::json!([{ "id": parent }]);
+            }
+            send(
+                client.external(http::Method::POST, &api)?.json(&page),
+                &format!("create {title:?} in Confluence"),
+            )?
+        }
+    };
+    Ok(match (page.links.base, page.links.webui) {
+        (Some(base), Some(webui)) => format!("{base}{webui}"),
+        _ => format!("{} (page {})", title, page.id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_notes_convert_to_storage_format() {
+        let notes = "# Release notes for v1.2.0\n\n## Fixes\n\n\
+                     - Fix <login> (`JIRA-1`) ([!7](https://gitlab.example.com/p/-/merge_requests/7)) @alice\n";
+        assert_eq!(
+            storage(notes),
+            "<h1>Release notes for v1.2.0</h1><h2>Fixes</h2><ul><li>Fix &lt;login&gt; \
+             (<code>JIRA-1</code>) (<a href=\"https://gitlab.example.com/p/-/merge_requests/7\">!7</a>) \
+             @alice</li></ul>"
+        );
+    }
+
+    #[test]
+    fn unclosed_markup_is_left_as_text() {
+        assert_eq!(inline("a [b] `c"), "a [b] `c");
+    }
+}
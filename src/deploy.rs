@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use chrono::NaiveDate;
+use clap::{Args, ValueEnum};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::endpoints::{AddDeployKey, CreateDeployToken};
+use crate::redact;
+use crate::variables::{self, SetVariable};
+use crate::workflow::Context;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TokenScope {
+    ReadRepository,
+    ReadRegistry,
+    WriteRegistry,
+    ReadPackageRegistry,
+    WritePackageRegistry,
+}
+
+impl TokenScope {
+    fn name(self) -> &'static str {
+        match self {
+            TokenScope::ReadRepository => "read_repository",
+            TokenScope::ReadRegistry => "read_registry",
+            TokenScope::WriteRegistry => "write_registry",
+            TokenScope::ReadPackageRegistry => "read_package_registry",
+            TokenScope::WritePackageRegistry => "write_package_registry",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct Store {
+    /// Store the secret as a CI/CD variable of this project instead of printing it.
+    #[arg(long, value_name = "PROJECT")]
+    pub store_in: Option<String>,
+    /// The name of that variable.
+    #[arg(long, requires = "store_in")]
+    pub variable: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CreateToken {
+    pub name: String,
+    /// What the token may do, e.g. `--scope read_repository,read_registry`.
+    #[arg(long = "scope", value_enum, value_delimiter = ',', required = true)]
+    pub scopes: Vec<TokenScope>,
+    /// GitLab picks `gitlab+deploy-token-N` if unset.
+    #[arg(long)]
+    pub username: Option<String>,
+    /// The day the token stops working, e.g. `2025-12-31`.
+    #[arg(long)]
+    pub expires_at: Option<NaiveDate>,
+    #[command(flatten)]
+    pub store: Store,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct AddKey {
+    pub title: String,
+    /// An existing public key; otherwise an ed25519 key pair is generated with `ssh-keygen`.
+    #[arg(long, value_name = "PATH")]
+    pub public_key: Option<PathBuf>,
+    /// Allow pushing, not just pulling.
+    #[arg(long)]
+    pub can_push: bool,
+    /// The day the key stops working, e.g. `2025-12-31`.
+    #[arg(long)]
+    pub expires_at: Option<NaiveDate>,
+    #[command(flatten)]
+    pub store: Store,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeployToken {
+    username: String,
+    token: String,
+}
+
+/// Prints the value of `variable`, or sets it in the `--store-in` project.
+fn hand_over(ctx: &Context, store: &Store, variable: SetVariable) -> anyhow::Result<String> {
+    let Some(project) = &store.store_in else {
+        println!("{}", variable.value);
+        return Ok(format!("printed {}; it is not shown again", variable.key));
+    };
+    variables::set(ctx.client, project, &variable)?;
+    Ok(format!("stored {} in {project}", variable.key))
+}
+
+pub fn create_token(ctx: &Context, project: &str, token: &CreateToken) -> anyhow::Result<String> {
+    let variable = token.store.variable.as_deref().unwrap_or("DEPLOY_TOKEN");
+    let scopes: Vec<_> = token.scopes.iter().map(|scope| scope.name()).collect();
+    let mut plan = vec![format!(
+        "create deploy token {} ({})",
+        token.name,
+        scopes.join(", ")
+    )];
+    if let Some(target) = &token.store.store_in {
+        plan.push(format!(
+            "set the {variable} and {variable}_USERNAME variables of {target}"
+        ));
+    }
+    ctx.confirm(project, &plan)?;
+
+    let created: DeployToken = CreateDeployToken {
+        project: project.into(),
+        name: &token.name,
+        scopes: &scopes,
+        username: token.username.as_deref(),
+        expires_at: token.expires_at,
+    }
+    .query(ctx.client)?;
+    redact::register(&created.token);
+
+    if let Some(target) = &token.store.store_in {
+        let username = SetVariable {
+            key: format!("{variable}_USERNAME"),
+            value: created.username.clone(),
+            protected: false,
+            masked: false,
+            environment_scope: None,
+        };
+        variables::set(ctx.client, target, &username)?;
+    } else {
+        tracing::info!("deploy token username: {}", created.username);
+    }
+    let secret = SetVariable {
+        key: variable.to_owned(),
+        value: created.token,
+        protected: false,
+        masked: true,
+        environment_scope: None,
+    };
+    hand_over(ctx, &token.store, secret)
+}
+
+/// A fresh ed25519 key pair, as the private and the public key file's contents.
+fn generate_key(title: &str) -> anyhow::Result<(String, String)> {
+    let dir = std::env::temp_dir().join(format!("gitlab-helper-key-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("id_ed25519");
+    let result = std::process::Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", title, "-f"])
+        .arg(&path)
+        .status()
+        .context("ssh-keygen is needed to generate a key; pass --public-key to use an existing one")
+        .and_then(|status| {
+            anyhow::ensure!(status.success(), "ssh-keygen failed with {status}");
+            Ok((
+                std::fs::read_to_string(&path)?,
+                std::fs::read_to_string(path.with_extension("pub"))?,
+            ))
+        });
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+pub fn add_key(ctx: &Context, project: &str, key: &AddKey) -> anyhow::Result<String> {
+    anyhow::ensure!(
+        key.public_key.is_none() || key.store.store_in.is_none(),
+        "--store-in needs a generated key; the private half of --public-key is yours"
+    );
+    let variable = key.store.variable.as_deref().unwrap_or("DEPLOY_KEY");
+    let access = if key.can_push {
+        "read-write"
+    } else {
+        "read-only"
+    };
+    let mut plan = vec![format!("add {access} deploy key {}", key.title)];
+    if let Some(target) = &key.store.store_in {
+        plan.push(format!("set the {variable} variable of {target}"));
+    }
+    ctx.confirm(project, &plan)?;
+
+    let (private, public) = match &key.public_key {
+        Some(path) => {
+            let public = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            (None, public)
+        }
+        None => {
+            let (private, public) = generate_key(&key.title)?;
+            (Some(private), public)
+        }
+    };
+    api::ignore(AddDeployKey {
+        project: project.into(),
+        title: &key.title,
+        key: public.trim(),
+        can_push: key.can_push,
+        expires_at: key.expires_at,
+    })
+    .query(ctx.client)?;
+
+    let Some(private) = private else {
+        return Ok(format!("added deploy key {}", key.title));
+    };
+    redact::register(&private);
+    // A private key spans several lines, which GitLab cannot mask, so at
+    // least keep it from pipelines of unprotected branches.
+    let secret = SetVariable {
+        key: variable.to_owned(),
+        value: private,
+        protected: true,
+        masked: false,
+        environment_scope: None,
+    };
+    hand_over(ctx, &key.store, secret)
+}
@@ -0,0 +1,121 @@
+use gitlab::api::projects::merge_requests::discussions::MergeRequestDiscussions;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::checks::{Case, Reports};
+use crate::client::Client;
+
+#[derive(Debug, Deserialize)]
+struct Discussion {
+    id: String,
+    notes: Vec<Note>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+    body: String,
+    author: Author,
+    #[serde(default)]
+    resolvable: bool,
+    #[serde(default)]
+    resolved: bool,
+    #[serde(default)]
+    position: Option<Position>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Author {
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Position {
+    new_path: Option<String>,
+    new_line: Option<u64>,
+    old_path: Option<String>,
+    old_line: Option<u64>,
+}
+
+impl Position {
+    /// The line of the diff the note is on, on the new side if it has one.
+    fn line(&self) -> Option<(&str, u64)> {
+        match (&self.new_path, self.new_line, &self.old_path, self.old_line) {
+            (Some(path), Some(line), _, _) | (_, _, Some(path), Some(line)) => Some((path, line)),
+            _ => None,
+        }
+    }
+}
+
+/// The first line of `body`, cut short for a test case name.
+fn excerpt(body: &str) -> String {
+    let line = body.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(60) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_owned(),
+    }
+}
+
+/// Checks that every resolvable discussion of merge request `iid` is
+/// resolved, and fails listing those that are not, after writing one check
+/// per discussion to `reports`.
+pub fn check(client: &Client, project: &str, iid: u64, reports: &Reports) -> anyhow::Result<()> {
+    let endpoint = MergeRequestDiscussions::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?;
+    let discussions: Vec<Discussion> = api::paged(endpoint, api::Pagination::All).query(client)?;
+
+    let mut cases = Vec::new();
+    let mut unresolved = Vec::new();
+    for discussion in &discussions {
+        let Some(first) = discussion.notes.first().filter(|note| note.resolvable) else {
+            // System notes and plain comments have nothing to resolve.
+            continue;
+        };
+        let name = format!("@{}: {}", first.author.username, excerpt(&first.body));
+        let open = discussion
+            .notes
+            .iter()
+            .any(|note| note.resolvable && !note.resolved);
+        if !open {
+            cases.push(Case::passed(name));
+            continue;
+        }
+        let mut case = Case::failed(&name, first.body.clone());
+        let line = first.position.as_ref().and_then(Position::line);
+        if let Some((path, line)) = line {
+            case = case.at(path, line);
+        }
+        cases.push(case);
+        unresolved.push(match line {
+            Some((path, line)) => format!("{name} ({path}:{line}, discussion {})", discussion.id),
+            None => format!("{name} (discussion {})", discussion.id),
+        });
+    }
+    reports.write("check-discussions", &cases)?;
+    for discussion in &unresolved {
+        tracing::error!("unresolved: {discussion}");
+    }
+    anyhow::ensure!(
+        unresolved.is_empty(),
+        "{} of {} discussion(s) of !{iid} are unresolved",
+        unresolved.len(),
+        cases.len()
+    );
+    tracing::info!(project, iid, "every discussion is resolved");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_notes_are_cut_at_the_first_line() {
+        assert_eq!(
+            excerpt("Rename this\n\nIt shadows the other one"),
+            "Rename this"
+        );
+        assert_eq!(excerpt(&"a".repeat(70)), format!("{}...", "a".repeat(60)));
+    }
+}
@@ -0,0 +1,392 @@
+use std::net::TcpListener;
+
+use gitlab::api::{self, personal_access_tokens, projects, Pagination, Query};
+use serde::Deserialize;
+
+use crate::auth::{self, TokenKind};
+use crate::client::{self, Client, NetworkOptions, RestError};
+use crate::config::Config;
+use crate::emergency::{self, Patch};
+use crate::table;
+
+/// GitLab's Developer role, the least that can push branches and open MRs.
+const DEVELOPER: u64 = 30;
+
+/// What went wrong and how to fix it.
+struct Problem {
+    details: String,
+    hint: String,
+}
+
+fn problem(details: impl ToString, hint: impl Into<String>) -> Problem {
+    Problem {
+        details: details.to_string(),
+        hint: hint.into(),
+    }
+}
+
+type Outcome = Result<String, Problem>;
+
+#[derive(Default)]
+struct Report {
+    checks: Vec<(String, Option<Outcome>)>,
+}
+
+impl Report {
+    fn check(&mut self, name: impl Into<String>, outcome: Outcome) {
+        self.checks.push((name.into(), Some(outcome)));
+    }
+
+    fn skip(&mut self, name: impl Into<String>) {
+        self.checks.push((name.into(), None));
+    }
+
+    fn print(&self) -> usize {
+        let rows: Vec<_> = self
+            .checks
+            .iter()
+            .map(|(name, outcome)| match outcome {
+                Some(Ok(details)) => [name.clone(), "ok".to_owned(), details.clone()],
+                Some(Err(problem)) => [name.clone(), "failed".to_owned(), problem.details.clone()],
+                None => [
+                    name.clone(),
+                    "skipped".to_owned(),
+                    "an earlier check failed".to_owned(),
+                ],
+            })
+            .collect();
+        println!("{}", table::render(["CHECK", "RESULT", "DETAILS"], &rows));
+
+        let problems: Vec<_> = self
+            .checks
+            .iter()
+            .filter_map(|(name, outcome)| match outcome {
+                Some(Err(problem)) => Some((name, problem)),
+                _ => None,
+            })
+            .collect();
+        if !problems.is_empty() {
+            println!("\nTo fix:");
+            for (name, problem) in &problems {
+                println!("  - {name}: {}", problem.hint);
+            }
+        }
+        problems.len()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Access {
+    access_level: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Permissions {
+    project_access: Option<Access>,
+    group_access: Option<Access>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    path_with_namespace: String,
+    permissions: Permissions,
+}
+
+#[derive(Debug, Deserialize)]
+struct Token {
+    scopes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectHook {
+    url: String,
+    note_events: bool,
+    merge_requests_events: bool,
+    #[serde(default)]
+    alert_status: Option<String>,
+}
+
+fn connection_hint(err: &anyhow::Error, host: &str) -> String {
+    for cause in err.chain() {
+        if let Some(api::ApiError::Client {
+            source: RestError::Communication(err),
+        }) = cause.downcast_ref::<api::ApiError<RestError>>()
+        {
+            if format!("{err:?}").contains("certificate") {
+                return "the TLS certificate is not trusted; pass the internal CA with --ca-cert"
+                    .to_owned();
+            }
+            if err.is_connect() || err.is_timeout() {
+                return format!(
+                    "{host} is not reachable from here; check --host and the proxy settings"
+                );
+            }
+        }
+        if let Some(err) = cause.downcast_ref::<api::ApiError<RestError>>() {
+            if client::status(err) == Some(http::StatusCode::UNAUTHORIZED) {
+                return "the token is invalid, expired or revoked; create a new one".to_owned();
+            }
+        }
+    }
+    "see the error above".to_owned()
+}
+
+fn token_scopes(client: &Client) -> Outcome {
+    let endpoint = personal_access_tokens::PersonalAccessTokenSelf::builder()
+        .build()
+        .unwrap();
+    let token: Token = endpoint
+        .query(client)
+        .map_err(|err| problem(err, "reading a token's scopes needs GitLab 16.0 or later"))?;
+    if token.scopes.iter().any(|scope| scope == "api") {
+        Ok(token.scopes.join(", "))
+    } else {
+        Err(problem(
+            format!("scopes: {}", token.scopes.join(", ")),
+            "changing branches and merge requests needs a token with the api scope",
+        ))
+    }
+}
+
+fn project_access(client: &Client, project: &str) -> Result<Project, Problem> {
+    let endpoint = projects::Project::builder()
+        .project(project)
+        .build()
+        .unwrap();
+    endpoint.query(client).map_err(|err| {
+        problem(
+            err,
+            format!("check the project path and that the token's user is a member of {project}"),
+        )
+    })
+}
+
+fn write_permission(project: &Project) -> Outcome {
+    let level = [
+        &project.permissions.project_access,
+        &project.permissions.group_access,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|access| access.access_level)
+    .max()
+    .unwrap_or(0);
+    if level >= DEVELOPER {
+        Ok(format!("access level {level}"))
+    } else {
+        Err(problem(
+            format!("access level {level}"),
+            format!(
+                "ask a maintainer of {} for at least the Developer role",
+                project.path_with_namespace
+            ),
+        ))
+    }
+}
+
+fn target_branches(client: &Client, project: &str) -> Outcome {
+    let mut missing = Vec::new();
+    for target in Patch::new(0).targets {
+        let endpoint = projects::repository::branches::Branch::builder()
+            .project(project)
+            .branch(&target)
+            .build()
+            .unwrap();
+        match api::ignore(endpoint).query(client) {
+            Ok(()) => {}
+            Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => {
+                missing.push(target)
+            }
+            Err(err) => return Err(problem(err, "see the error above")),
+        }
+    }
+    let latest = emergency::latest_release(client, project)
+        .map_err(|err| problem(err, "push a release/x.y.z branch to patch"))?;
+    if missing.is_empty() {
+        Ok(format!("latest release/{latest}"))
+    } else {
+        Err(problem(
+            format!("missing {}", missing.join(", ")),
+            "emergency patches are merged back into these branches; create them",
+        ))
+    }
+}
+
+fn webhooks(client: &Client, project: &str) -> Outcome {
+    let endpoint = projects::hooks::Hooks::builder()
+        .project(project)
+        .build()
+        .unwrap();
+    let hooks: Vec<ProjectHook> = api::paged(endpoint, Pagination::All)
+        .query(client)
+        .map_err(|err| problem(err, "listing webhooks needs the Maintainer role"))?;
+    let hooks: Vec<_> = hooks
+        .into_iter()
+        .filter(|hook| hook.note_events || hook.merge_requests_events)
+        .collect();
+    if hooks.is_empty() {
+        return Err(problem(
+            "no webhook sends comment or merge request events",
+            format!("add one under Settings > Webhooks in {project} pointing at this helper"),
+        ));
+    }
+    let disabled: Vec<_> = hooks
+        .iter()
+        .filter(|hook| {
+            hook.alert_status
+                .as_deref()
+                .is_some_and(|status| status != "executable")
+        })
+        .collect();
+    match disabled.first() {
+        Some(hook) => Err(problem(
+            format!("{} is disabled", hook.url),
+            "GitLab disables webhooks after failed deliveries; make the helper reachable and re-enable it",
+        )),
+        None => Ok(hooks
+            .iter()
+            .map(|hook| hook.url.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")),
+    }
+}
+
+fn listener(listen: &str) -> Outcome {
+    TcpListener::bind(listen)
+        .map(|_| format!("{listen} is free"))
+        .map_err(|err| {
+            problem(
+                err,
+                format!("stop whatever is bound to {listen} or change serve.listen"),
+            )
+        })
+}
+
+/// Everything `doctor` needs to know about the invocation.
+pub struct Options<'a> {
+    pub host: &'a str,
+    pub token: Option<String>,
+    pub token_kind: Option<TokenKind>,
+    pub network: &'a NetworkOptions,
+    pub config: &'a Config,
+    pub serve: bool,
+}
+
+/// Runs every check, printing a remediation hint for each one that fails.
+pub fn run(
+    options: Options,
+    projects: impl FnOnce(&Client) -> anyhow::Result<Vec<String>>,
+) -> anyhow::Result<()> {
+    let mut report = Report::default();
+    let host = options.host;
+
+    report.check(
+        "GITLAB_USER_ID",
+        match std::env::var("GITLAB_USER_ID") {
+            Ok(id) if id.parse::<u64>().is_ok() => Ok(id),
+            Ok(id) => Err(problem(
+                format!("{id:?} is not a number"),
+                "set it to your numeric user id, shown on your GitLab profile",
+            )),
+            Err(_) => Err(problem(
+                "not set",
+                "emergency-patch assigns its merge requests to this user id; export it",
+            )),
+        },
+    );
+    if options.serve {
+        report.check("webhook listener", listener(&options.config.serve.listen));
+    }
+
+    let credentials = match auth::resolve(host, options.token, options.token_kind) {
+        Ok(credentials) => {
+            report.check(
+                "token",
+                Ok(format!("{} from {}", credentials.kind, credentials.source)),
+            );
+            Some(credentials)
+        }
+        Err(err) => {
+            report.check(
+                "token",
+                Err(problem(
+                    err,
+                    "pass --token, export GITLAB_TOKEN or run `gitlab-helper auth login`",
+                )),
+            );
+            None
+        }
+    };
+    let kind = credentials.as_ref().map(|credentials| credentials.kind);
+    let client =
+        credentials.and_then(
+            |credentials| match Client::new(host, credentials, options.network) {
+                Ok(client) => {
+                    report.check(
+                        format!("connection to {host}"),
+                        Ok("authenticated".to_owned()),
+                    );
+                    Some(client)
+                }
+                Err(err) => {
+                    let hint = connection_hint(&err, host);
+                    report.check(
+                        format!("connection to {host}"),
+                        Err(problem(format!("{err:#}"), hint)),
+                    );
+                    None
+                }
+            },
+        );
+    let Some(client) = client else {
+        if kind.is_none() {
+            report.skip(format!("connection to {host}"));
+        }
+        report.skip("projects");
+        return finish(&report);
+    };
+
+    if kind == Some(TokenKind::Personal) {
+        report.check("token scopes", token_scopes(&client));
+    }
+    let projects = match projects(&client) {
+        Ok(projects) => projects,
+        Err(err) => {
+            report.check(
+                "projects",
+                Err(problem(
+                    format!("{err:#}"),
+                    "pass --project or --group, or set projects in the config",
+                )),
+            );
+            return finish(&report);
+        }
+    };
+    for project in &projects {
+        let name = |check: &str| format!("{check} ({project})");
+        match project_access(&client, project) {
+            Ok(found) => {
+                report.check(name("visibility"), Ok(found.path_with_namespace.clone()));
+                report.check(name("write permission"), write_permission(&found));
+                report.check(name("target branches"), target_branches(&client, project));
+                if options.serve {
+                    report.check(name("webhooks"), webhooks(&client, project));
+                }
+            }
+            Err(problem) => {
+                report.check(name("visibility"), Err(problem));
+            }
+        }
+    }
+    finish(&report)
+}
+
+fn finish(report: &Report) -> anyhow::Result<()> {
+    let failed = report.print();
+    anyhow::ensure!(
+        failed == 0,
+        "{failed} of {} checks failed",
+        report.checks.len()
+    );
+    Ok(())
+}
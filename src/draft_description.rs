@@ -0,0 +1,210 @@
+use gitlab::api::projects::merge_requests::{EditMergeRequest, MergeRequest, MergeRequestCommits};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+use winnow::combinator::opt;
+use winnow::prelude::*;
+
+use crate::i18n;
+use crate::title::{self, Kind};
+use crate::workflow::Context;
+
+#[derive(Debug, Deserialize)]
+struct Mr {
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    short_id: String,
+    title: String,
+    #[serde(default)]
+    message: String,
+}
+
+/// The kind of a commit title, if it has one, and the title without it;
+/// the Jira ID is optional, unlike in merge request titles.
+fn classify(commit_title: &str) -> (Option<Kind>, &str) {
+    let mut input = commit_title;
+    let parsed = (
+        title::parse_kind,
+        opt(title::parse_jira_id),
+        title::parse_title,
+    )
+        .map(|(kind, _, title)| (kind, title))
+        .parse_next(&mut input);
+    match parsed {
+        Ok((kind, title)) => (Some(kind), title),
+        Err(_) => (None, commit_title.trim()),
+    }
+}
+
+/// The commits as Markdown lists grouped by kind, their bodies indented
+/// under them.
+fn summarize(commits: &[Commit]) -> String {
+    let (mut features, mut fixes, mut other) = (Vec::new(), Vec::new(), Vec::new());
+    for commit in commits {
+        let (kind, summary) = classify(&commit.title);
+        let mut entry = format!("- {summary} (`{}`)", commit.short_id);
+        let body = commit
+            .message
+            .split_once('\n')
+            .map_or("", |(_, body)| body.trim());
+        for line in body.lines() {
+            entry.push('\n');
+            if !line.trim().is_empty() {
+                entry.push_str("  ");
+                entry.push_str(line.trim_end());
+            }
+        }
+        match kind {
+            Some(Kind::Feature) => features.push(entry),
+            Some(Kind::Fix) => fixes.push(entry),
+            None => other.push(entry),
+        }
+    }
+
+    let messages = i18n::messages();
+    let mut groups = Vec::new();
+    for (heading, entries) in [
+        (messages.features, features),
+        (messages.fixes, fixes),
+        (messages.other_changes, other),
+    ] {
+        if !entries.is_empty() {
+            groups.push(format!("**{heading}**\n\n{}", entries.join("\n")));
+        }
+    }
+    groups.join("\n\n")
+}
+
+/// `description` with `summary` under the "What does this change do?"
+/// heading, which is added if missing. `None` if the section is already
+/// written, so nobody's words are replaced.
+fn fill(description: &str, summary: &str) -> Option<String> {
+    let heading = i18n::messages().what_heading;
+    let Some(start) = description.find(heading) else {
+        let description = description.trim_end();
+        let separator = if description.is_empty() { "" } else { "\n\n" };
+        return Some(format!("{description}{separator}{heading}\n\n{summary}\n"));
+    };
+    let body_start = start + heading.len();
+    let rest = &description[body_start..];
+    // The section runs to the next heading.
+    let end = rest
+        .match_indices('\n')
+        .map(|(at, _)| at + 1)
+        .find(|&at| rest[at..].starts_with('#'))
+        .unwrap_or(rest.len());
+    if !rest[..end].trim().is_empty() {
+        return None;
+    }
+    let after = &rest[end..];
+    let separator = if after.is_empty() { "\n" } else { "\n\n" };
+    Some(format!(
+        "{}\n\n{summary}{separator}{after}",
+        &description[..body_start]
+    ))
+}
+
+/// Fills the "What does this change do?" section of merge request `iid`
+/// from its commits, unless someone already has.
+pub fn draft(ctx: &Context, project: &str, iid: u64) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let endpoint = MergeRequest::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?;
+    let mr: Mr = endpoint.query(client)?;
+    let endpoint = MergeRequestCommits::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?;
+    let mut commits: Vec<Commit> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    if commits.is_empty() {
+        return Ok(format!("!{iid} has no commits to describe"));
+    }
+    // Newest first from the API; the description reads better in order.
+    commits.reverse();
+
+    let summary = summarize(&commits);
+    let Some(description) = fill(mr.description.as_deref().unwrap_or_default(), &summary) else {
+        return Ok(format!(
+            "the description of !{iid} already says what it does"
+        ));
+    };
+    ctx.confirm(
+        project,
+        &[format!(
+            "describe !{iid} from its {} commit(s)",
+            commits.len()
+        )],
+    )?;
+    let endpoint = EditMergeRequest::builder()
+        .project(project)
+        .merge_request(iid)
+        .description(description)
+        .build()?;
+    api::ignore(endpoint).query(client)?;
+    Ok(format!(
+        "described !{iid} from its {} commit(s)",
+        commits.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(short_id: &str, message: &str) -> Commit {
+        Commit {
+            short_id: short_id.to_owned(),
+            title: message.lines().next().unwrap().to_owned(),
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn commits_are_grouped_by_kind() {
+        let summary = summarize(&[
+            commit(
+                "1a2b",
+                "fix (ABC-1): Handle empty pages\n\nThe API returns []\nfor them.",
+            ),
+            commit("3c4d", "Bump dependencies"),
+            commit("5e6f", "feat: Export boards"),
+        ]);
+
+        assert_eq!(
+            summary,
+            "**Features**\n\n- Export boards (`5e6f`)\n\n\
+             **Fixes**\n\n- Handle empty pages (`1a2b`)\n  The API returns []\n  for them.\n\n\
+             **Other changes**\n\n- Bump dependencies (`3c4d`)"
+        );
+    }
+
+    #[test]
+    fn only_an_empty_section_is_filled() {
+        let template =
+            "### Why?\n\nBecause.\n\n### What does this change do?\n\n### How to test this change?";
+        assert_eq!(
+            fill(template, "- Things").unwrap(),
+            "### Why?\n\nBecause.\n\n### What does this change do?\n\n- Things\n\n### How to test this change?"
+        );
+
+        let written = template.replace("do?\n\n", "do?\n\nIt does things.\n\n");
+        assert_eq!(fill(&written, "- Things"), None);
+    }
+
+    #[test]
+    fn a_missing_section_is_appended() {
+        assert_eq!(
+            fill("Closes #3\n", "- Things").unwrap(),
+            "Closes #3\n\n### What does this change do?\n\n- Things\n"
+        );
+        assert_eq!(
+            fill("", "- Things").unwrap(),
+            "### What does this change do?\n\n- Things\n"
+        );
+    }
+}
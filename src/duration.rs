@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use winnow::{
+    ascii::digit1,
+    combinator::{alt, cut_err, eof, repeat, terminated},
+    error::{StrContext, StrContextValue},
+    prelude::*,
+};
+
+fn parse_unit(input: &mut &str) -> PResult<u64> {
+    alt((
+        "ms".value(1),
+        "s".value(1_000),
+        "m".value(60_000),
+        "h".value(3_600_000),
+    ))
+    .context(StrContext::Label("unit"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "ms, s, m or h",
+    )))
+    .parse_next(input)
+}
+
+fn parse_component(input: &mut &str) -> PResult<u64> {
+    // Past the digits only a unit may follow, so say that instead of backtracking.
+    (digit1.parse_to::<u64>(), cut_err(parse_unit))
+        .map(|(amount, millis)| amount.saturating_mul(millis))
+        .parse_next(input)
+}
+
+/// Parses durations such as `90s`, `2m` or `1h30m`.
+pub fn parse(input: &str) -> Result<Duration, String> {
+    terminated(
+        repeat(1.., parse_component).fold(|| 0u64, u64::saturating_add),
+        eof,
+    )
+    .map(Duration::from_millis)
+    .parse(input.trim())
+    .map_err(|err| err.to_string())
+}
+
+/// Formats a duration the way `parse` reads it, to the second, e.g. `1h30m`.
+pub fn format(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let parts = [
+        (seconds / 3_600, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+    let formatted: String = parts
+        .iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect();
+    if formatted.is_empty() {
+        "0s".to_owned()
+    } else {
+        formatted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_units() {
+        assert_eq!(parse("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse("2h"), Ok(Duration::from_secs(7_200)));
+    }
+
+    #[test]
+    fn components_add_up() {
+        assert_eq!(parse("1h30m"), Ok(Duration::from_secs(5_400)));
+        assert_eq!(parse("1m30s500ms"), Ok(Duration::from_millis(90_500)));
+        assert_eq!(parse(" 45s "), Ok(Duration::from_secs(45)));
+    }
+
+    #[test]
+    fn zero_is_a_duration_with_a_unit() {
+        assert_eq!(parse("0s"), Ok(Duration::ZERO));
+        assert!(parse("0").is_err());
+    }
+
+    #[test]
+    fn bare_numbers_need_a_unit() {
+        for input in ["90", "1h30", "30 s"] {
+            let err = parse(input).unwrap_err();
+            assert!(err.contains("ms, s, m or h"), "{input:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn huge_durations_saturate_instead_of_wrapping() {
+        assert_eq!(
+            parse("18446744073709551615h"),
+            Ok(Duration::from_millis(u64::MAX))
+        );
+        assert_eq!(
+            parse("18446744073709551615ms1ms"),
+            Ok(Duration::from_millis(u64::MAX))
+        );
+        // Beyond u64 the amount itself does not parse.
+        assert!(parse("99999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for input in ["", "soon", "-5s", "1.5h", "5x", "s", "1h 30m"] {
+            assert!(parse(input).is_err(), "{input:?} parsed");
+        }
+    }
+
+    #[test]
+    fn formatting_reads_back() {
+        for input in ["4h", "1h30m", "45m5s", "26h1s", "0s"] {
+            assert_eq!(format(parse(input).unwrap()), input);
+        }
+        assert_eq!(format(Duration::from_millis(1_500)), "1s");
+    }
+}
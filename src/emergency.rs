@@ -0,0 +1,436 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gitlab::api::{
+    self,
+    projects::{merge_requests::CreateMergeRequest, repository},
+    Query,
+};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::client::Client;
+use crate::compare;
+use crate::config;
+use crate::duration;
+use crate::endpoints::CherryPickCommit;
+use crate::freeze;
+use crate::git;
+use crate::hooks::Event;
+use crate::i18n;
+use crate::incident;
+use crate::journal::Resource;
+use crate::origin;
+use crate::snapshot;
+use crate::template::{self, Vars};
+use crate::workflow::{Context, Run};
+
+#[derive(Debug, Deserialize)]
+struct Branch {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    id: String,
+    short_id: String,
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Created {
+    iid: u64,
+    web_url: String,
+}
+
+fn description(emergency_patch: &str) -> String {
+    let messages = i18n::messages();
+    format!(
+        "{}
+
+{}
+```bash
+git pull origin {emergency_patch} && git checkout {emergency_patch}
+```
+
+{}
+```bash
+gitlab-helper start-fix --jira JIRA-ID --summary \"what it fixes\"
+```
+
+{}
+
+{}
+
+{}
+
+{}",
+        messages.emergency_intro,
+        messages.switch_branch,
+        messages.start_fix,
+        messages.checklist,
+        messages.why_heading,
+        messages.what_heading,
+        messages.test_heading
+    )
+}
+
+/// The versions of the `release/x.y.z` branches of `project`, newest first.
+pub fn releases(client: &Client, project: &str) -> anyhow::Result<Vec<semver::Version>> {
+    let branches = repository::branches::Branches::builder()
+        .project(project)
+        .regex(r"release/\d+\.\d+\.\d+")
+        .build()?;
+    let branches: Vec<Branch> = branches.query(client)?;
+    // The regex is only a search, so it also matches e.g. `release/1.2.3/docs`.
+    let mut releases: Vec<_> = branches
+        .iter()
+        .filter_map(|branch| branch.name.strip_prefix("release/"))
+        .filter_map(|version| semver::Version::parse(version).ok())
+        .collect();
+    releases.sort_by(|a, b| b.cmp(a));
+    Ok(releases)
+}
+
+/// The highest version among the `release/x.y.z` branches of `project`.
+pub fn latest_release(client: &Client, project: &str) -> anyhow::Result<semver::Version> {
+    let Some(latest_release) = releases(client, project)?.into_iter().next() else {
+        anyhow::bail!("No branches found based on the release/x.x.x pattern")
+    };
+    Ok(latest_release)
+}
+
+/// The `[emergency_patch]` defaults for the command-line options.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PatchConfig {
+    /// The branches the merge requests go into; `master` and `dev` if empty.
+    #[serde(deserialize_with = "branches")]
+    pub targets: Vec<String>,
+    /// Used unless `--description-template` is passed.
+    pub description_template: Option<PathBuf>,
+    /// How long the merge requests may stay open, e.g. `4h`; see `check-sla`.
+    #[serde(deserialize_with = "sla")]
+    pub sla: Option<Duration>,
+}
+
+fn sla<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let sla = String::deserialize(deserializer)?;
+    duration::parse(&sla).map(Some).map_err(de::Error::custom)
+}
+
+/// Marks the merge requests opened with an SLA, for `check-sla` to find.
+pub const SLA_LABEL: &str = "emergency";
+
+/// Marks the merge requests opened during a deploy freeze.
+const FREEZE_LABEL: &str = "deploy-freeze";
+
+fn branches<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let branches = Vec::<String>::deserialize(deserializer)?;
+    for branch in &branches {
+        config::check_branch_name(branch).map_err(de::Error::custom)?;
+    }
+    Ok(branches)
+}
+
+/// What to patch and where the fix has to land.
+#[derive(Debug, Clone)]
+pub struct Patch {
+    /// The release line to patch; the latest one if unset.
+    pub base: Option<semver::Version>,
+    pub targets: Vec<String>,
+    pub assignee: u64,
+    /// Replaces the built-in merge request description; see `template preview`.
+    pub description_template: Option<String>,
+    /// Append to each description what the target has that the release does not.
+    pub compare_summary: bool,
+    /// Record the patch in an incident of PagerDuty or Opsgenie.
+    pub incident: Option<incident::Update>,
+    /// Commits to cherry-pick onto the branch, oldest first.
+    pub picks: Vec<String>,
+    /// Labels the merge requests and notes when they are due.
+    pub sla: Option<Duration>,
+}
+
+impl Patch {
+    pub fn new(assignee: u64) -> Self {
+        Patch {
+            base: None,
+            targets: vec!["master".to_owned(), "dev".to_owned()],
+            assignee,
+            description_template: None,
+            compare_summary: false,
+            incident: None,
+            picks: Vec::new(),
+            sla: None,
+        }
+    }
+}
+
+pub fn run(ctx: &Context, project: &str, patch: &Patch) -> anyhow::Result<String> {
+    let client = ctx.client;
+    // Once the patch branch exists it is the latest release, so a resumed run
+    // has to reuse the versions picked the first time.
+    let plan = ctx.journaled(project, "find the latest release", || {
+        let latest_release = match &patch.base {
+            Some(base) => base.clone(),
+            None => latest_release(client, project)?,
+        };
+        let emergency_patch = semver::Version::new(
+            latest_release.major,
+            latest_release.minor,
+            latest_release.patch + 1,
+        );
+        let vars = Vars::from([
+            (
+                "latest_release".to_owned(),
+                format!("release/{latest_release}"),
+            ),
+            (
+                "emergency_patch".to_owned(),
+                format!("release/{emergency_patch}"),
+            ),
+        ]);
+        Ok((None, vars))
+    })?;
+    let (Some(latest_release), Some(emergency_patch)) = (
+        plan.vars.get("latest_release").cloned(),
+        plan.vars.get("emergency_patch").cloned(),
+    ) else {
+        anyhow::bail!("the journal does not record which release to patch");
+    };
+    tracing::info!(
+        latest_release,
+        emergency_patch,
+        "creating a new patch from latest release..."
+    );
+    let (targets, assignee) = (&patch.targets, patch.assignee);
+    // Only there to help, so it never holds up the patch.
+    let context = snapshot::render(client, project, &latest_release).unwrap_or_else(|err| {
+        tracing::warn!("leaving the context out of the description: {err:#}");
+        String::new()
+    });
+    // Looked up front so a mistyped SHA stops the run before any change.
+    let picks = patch
+        .picks
+        .iter()
+        .map(|sha| {
+            let commit: Commit = repository::commits::Commit::builder()
+                .project(project)
+                .commit(sha.as_str())
+                .build()?
+                .query(client)?;
+            Ok(commit)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let picked = if picks.is_empty() {
+        String::new()
+    } else {
+        let mut picked = format!("{}\n", i18n::messages().picked_heading);
+        for commit in &picks {
+            picked.push_str(&format!("\n- `{}` {}", commit.short_id, commit.title));
+        }
+        picked
+    };
+    // Rendered up front so an undefined placeholder stops the run before any change.
+    let description = match &patch.description_template {
+        Some(template) => {
+            let version = latest_release.trim_start_matches("release/");
+            let next_patch = emergency_patch.trim_start_matches("release/");
+            let vars = Vars::from([
+                ("project".to_owned(), project.to_owned()),
+                ("latest_release".to_owned(), version.to_owned()),
+                ("latest_release_branch".to_owned(), latest_release.clone()),
+                ("next_patch".to_owned(), next_patch.to_owned()),
+                ("branch".to_owned(), emergency_patch.clone()),
+                ("context".to_owned(), context),
+                ("picks".to_owned(), picked),
+            ]);
+            template::render(template, &vars)?
+        }
+        None => [description(&emergency_patch), picked, context]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    };
+    let descriptions = targets
+        .iter()
+        .map(|target| {
+            if !patch.compare_summary {
+                return Ok(description.clone());
+            }
+            let summary = compare::summary(client, project, &latest_release, target)?;
+            Ok(format!("{description}\n\n{summary}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // A freeze does not stop an emergency patch, but its reviewers should know.
+    let freeze = freeze::active(client, project).unwrap_or_else(|err| {
+        tracing::warn!("could not tell whether a deploy freeze is on: {err:#}");
+        None
+    });
+    let descriptions: Vec<_> = match &freeze {
+        Some(freeze) => {
+            tracing::warn!(
+                project,
+                "{freeze}; the merge requests need release-manager approval"
+            );
+            let note = i18n::format(i18n::messages().deploy_freeze, &[("freeze", freeze)]);
+            descriptions
+                .into_iter()
+                .map(|description| format!("{note}\n\n{description}"))
+                .collect()
+        }
+        None => descriptions,
+    };
+    let due = patch.sla.map(|sla| {
+        let due = chrono::Utc::now() + sla;
+        i18n::format(
+            i18n::messages().sla_due,
+            &[
+                ("due", &due.format("%Y-%m-%d %H:%M UTC").to_string()),
+                ("sla", &duration::format(sla)),
+            ],
+        )
+    });
+
+    let mut run =
+        Run::new(
+            ctx,
+            project,
+            std::iter::once(format!("create branch {emergency_patch}"))
+                .chain(picks.iter().map(|commit| {
+                    format!("cherry-pick {} onto {emergency_patch}", commit.short_id)
+                }))
+                .chain(
+                    targets.iter().map(|target| {
+                        format!("open a merge request {emergency_patch} -> {target}")
+                    }),
+                ),
+        );
+
+    let mut plan = run.pending();
+    if plan
+        .iter()
+        .any(|step| step.starts_with("open a merge request"))
+    {
+        plan.push(format!("assign the merge requests to user {assignee}"));
+    }
+    ctx.confirm(project, &plan)?;
+
+    let create_branch = repository::branches::CreateBranch::builder()
+        .project(project)
+        .branch(&emergency_patch)
+        .ref_(&latest_release)
+        .build()?;
+    let event = Event::BranchCreated {
+        project,
+        branch: &emergency_patch,
+        ref_: &latest_release,
+    };
+    let repo = ctx.git_for(project)?;
+    run.step(0, &event, || {
+        match repo {
+            Some(repo) => repo
+                .create_branch(&emergency_patch, &latest_release)
+                .map_err(git::step_error)?,
+            None => api::ignore(create_branch).query(client)?,
+        }
+        Ok(Resource::Branch {
+            name: emergency_patch.clone(),
+        })
+    })?;
+
+    for (index, commit) in picks.iter().enumerate() {
+        let event = Event::CommitPicked {
+            project,
+            sha: &commit.id,
+            branch: &emergency_patch,
+        };
+        run.step(1 + index, &event, || {
+            let sha = match repo {
+                Some(repo) => repo
+                    .cherry_pick(&commit.id, &emergency_patch)
+                    .map_err(git::step_error)?,
+                None => {
+                    let picked: Commit = CherryPickCommit {
+                        project: project.into(),
+                        sha: &commit.id,
+                        branch: &emergency_patch,
+                    }
+                    .query(client)?;
+                    picked.id
+                }
+            };
+            Ok(Resource::Commit { sha })
+        })?;
+    }
+
+    let title = format!("EMERGENCY PRODUCTION PATCH ({})", latest_release);
+    for (index, target) in targets.iter().enumerate() {
+        let mut mr = CreateMergeRequest::builder();
+        mr.project(project)
+            .source_branch(&emergency_patch)
+            .target_branch(target)
+            .title(&title)
+            .assignee(assignee);
+        let mut labels = Vec::new();
+        match &due {
+            Some(due) => {
+                mr.description(origin::sign(&format!("{}\n\n{due}", descriptions[index])));
+                labels.push(SLA_LABEL);
+            }
+            None => {
+                mr.description(origin::sign(&descriptions[index]));
+            }
+        }
+        if freeze.is_some() {
+            labels.push(FREEZE_LABEL);
+        }
+        if !labels.is_empty() {
+            mr.labels(labels.into_iter());
+        }
+        let mr = mr.build()?;
+        let event = Event::MrCreated {
+            project,
+            source_branch: &emergency_patch,
+            target_branch: target,
+            title: &title,
+        };
+        // Each target's merge request stands alone, so one that fails leaves
+        // the others be.
+        run.best_effort(1 + picks.len() + index, &event, || {
+            let mr: Created = mr.query(client)?;
+            origin::note(client, project, mr.iid);
+            Ok(Resource::MergeRequest {
+                iid: mr.iid,
+                web_url: mr.web_url,
+            })
+        })?;
+    }
+
+    if let Some(update) = &patch.incident {
+        let mut note =
+            format!("Emergency patch {emergency_patch} cut from {latest_release} in {project}.");
+        let urls: Vec<_> = run
+            .created()
+            .into_iter()
+            .filter_map(|resource| match resource {
+                Resource::MergeRequest { web_url, .. } => Some(web_url),
+                _ => None,
+            })
+            .collect();
+        if let Some((project_url, _)) = urls.first().and_then(|url| url.split_once("/-/")) {
+            note.push_str(&format!("\nBranch: {project_url}/-/tree/{emergency_patch}"));
+        }
+        for url in &urls {
+            note.push_str(&format!("\nMerge request: {url}"));
+        }
+        // The incident is bookkeeping; failing it would hide that the patch is out.
+        if let Err(err) = incident::record(client, update, &title, &note) {
+            tracing::warn!("{err:#}");
+        }
+    }
+
+    run.finish()?;
+    Ok(format!("{emergency_patch} from {latest_release}"))
+}
@@ -0,0 +1,737 @@
+//! REST endpoints that the `gitlab` crate does not provide yet, written the
+//! same way as its own so they work with `Query`, `api::ignore` and friends.
+
+use chrono::NaiveDate;
+use gitlab::api::common::{path_escaped, NameOrId};
+use gitlab::api::endpoint_prelude::*;
+use serde_json::json;
+
+/// `POST /projects/:id/repository/commits/:sha/cherry_pick`
+pub struct CherryPickCommit<'a> {
+    pub project: NameOrId<'a>,
+    pub sha: &'a str,
+    pub branch: &'a str,
+}
+
+impl Endpoint for CherryPickCommit<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/repository/commits/{}/cherry_pick",
+            self.project,
+            path_escaped(self.sha),
+        )
+        .into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params.push("branch", self.branch);
+        params.into_body()
+    }
+}
+
+/// `POST /projects/:id/repository/commits/:sha/revert`
+pub struct RevertCommit<'a> {
+    pub project: NameOrId<'a>,
+    pub sha: &'a str,
+    pub branch: &'a str,
+}
+
+impl Endpoint for RevertCommit<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/repository/commits/{}/revert",
+            self.project,
+            path_escaped(self.sha),
+        )
+        .into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params.push("branch", self.branch);
+        params.into_body()
+    }
+}
+
+/// `POST /projects/:id/ci/lint`
+pub struct LintCiConfig<'a> {
+    pub project: NameOrId<'a>,
+    pub content: &'a str,
+    /// Simulate creating a pipeline, which also checks `rules` and `needs`.
+    pub dry_run: bool,
+    pub ref_: Option<&'a str>,
+    pub include_jobs: bool,
+}
+
+impl Endpoint for LintCiConfig<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/ci/lint", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("content", self.content)
+            .push("dry_run", self.dry_run)
+            .push_opt("ref", self.ref_)
+            .push("include_jobs", self.include_jobs);
+        params.into_body()
+    }
+}
+
+/// `GET /projects/:id/variables`
+pub struct ProjectVariables<'a> {
+    pub project: NameOrId<'a>,
+}
+
+impl Endpoint for ProjectVariables<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/variables", self.project).into()
+    }
+}
+
+impl Pageable for ProjectVariables<'_> {}
+
+/// `GET /groups/:id/variables`
+pub struct GroupVariables<'a> {
+    pub group: NameOrId<'a>,
+}
+
+impl Endpoint for GroupVariables<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("groups/{}/variables", self.group).into()
+    }
+}
+
+impl Pageable for GroupVariables<'_> {}
+
+/// `GET /groups/:id/access_tokens`
+pub struct GroupAccessTokens<'a> {
+    pub group: NameOrId<'a>,
+}
+
+impl Endpoint for GroupAccessTokens<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("groups/{}/access_tokens", self.group).into()
+    }
+}
+
+impl Pageable for GroupAccessTokens<'_> {}
+
+/// `POST /projects/:id/approval_rules`
+pub struct CreateApprovalRule<'a> {
+    pub project: NameOrId<'a>,
+    pub name: &'a str,
+    pub approvals_required: u64,
+}
+
+impl Endpoint for CreateApprovalRule<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/approval_rules", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("name", self.name)
+            .push("approvals_required", self.approvals_required);
+        params.into_body()
+    }
+}
+
+/// `PUT /projects/:id/approval_rules/:approval_rule_id`
+pub struct EditApprovalRule<'a> {
+    pub project: NameOrId<'a>,
+    pub id: u64,
+    pub approvals_required: u64,
+}
+
+impl Endpoint for EditApprovalRule<'_> {
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/approval_rules/{}", self.project, self.id).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params.push("approvals_required", self.approvals_required);
+        params.into_body()
+    }
+}
+
+/// An entry of `allowed_to_push` or `allowed_to_merge`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessChange {
+    /// Allow everyone with at least this access level.
+    Add { access_level: u64 },
+    /// Drop the existing entry with this id.
+    Remove { id: u64 },
+}
+
+impl AccessChange {
+    fn as_json(self) -> serde_json::Value {
+        match self {
+            AccessChange::Add { access_level } => json!({ "access_level": access_level }),
+            AccessChange::Remove { id } => json!({ "id": id, "_destroy": true }),
+        }
+    }
+}
+
+/// `PATCH /projects/:id/protected_branches/:name`, which changes a protection
+/// in place; entries not mentioned, such as those of single users, stay.
+pub struct EditProtectedBranch<'a> {
+    pub project: NameOrId<'a>,
+    pub name: &'a str,
+    pub allowed_to_push: Vec<AccessChange>,
+    pub allowed_to_merge: Vec<AccessChange>,
+    pub allow_force_push: bool,
+    pub code_owner_approval_required: Option<bool>,
+}
+
+impl Endpoint for EditProtectedBranch<'_> {
+    fn method(&self) -> Method {
+        Method::PATCH
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/protected_branches/{}",
+            self.project,
+            path_escaped(self.name),
+        )
+        .into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let changes = |changes: &[AccessChange]| -> Vec<_> {
+            changes.iter().map(|change| change.as_json()).collect()
+        };
+        JsonParams::into_body(&JsonParams::clean(json!({
+            "allowed_to_push": changes(&self.allowed_to_push),
+            "allowed_to_merge": changes(&self.allowed_to_merge),
+            "allow_force_push": self.allow_force_push,
+            "code_owner_approval_required": self.code_owner_approval_required,
+        })))
+    }
+}
+
+/// `GET /projects/:id/badges`
+pub struct ProjectBadges<'a> {
+    pub project: NameOrId<'a>,
+}
+
+impl Endpoint for ProjectBadges<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/badges", self.project).into()
+    }
+}
+
+impl Pageable for ProjectBadges<'_> {}
+
+/// `POST /projects/:id/badges`
+pub struct CreateProjectBadge<'a> {
+    pub project: NameOrId<'a>,
+    pub name: &'a str,
+    pub link_url: &'a str,
+    pub image_url: &'a str,
+}
+
+impl Endpoint for CreateProjectBadge<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/badges", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("name", self.name)
+            .push("link_url", self.link_url)
+            .push("image_url", self.image_url);
+        params.into_body()
+    }
+}
+
+/// `PUT /projects/:id/labels/:label_id`
+pub struct EditLabel<'a> {
+    pub project: NameOrId<'a>,
+    pub id: u64,
+    pub new_name: Option<&'a str>,
+    pub color: &'a str,
+    pub description: Option<&'a str>,
+    pub priority: Option<u64>,
+}
+
+impl Endpoint for EditLabel<'_> {
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/labels/{}", self.project, self.id).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push_opt("new_name", self.new_name)
+            .push("color", self.color)
+            .push_opt("description", self.description)
+            .push_opt("priority", self.priority);
+        params.into_body()
+    }
+}
+
+/// `PUT /projects/:id/badges/:badge_id`
+pub struct EditProjectBadge<'a> {
+    pub project: NameOrId<'a>,
+    pub id: u64,
+    pub link_url: &'a str,
+    pub image_url: &'a str,
+}
+
+impl Endpoint for EditProjectBadge<'_> {
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/badges/{}", self.project, self.id).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("link_url", self.link_url)
+            .push("image_url", self.image_url);
+        params.into_body()
+    }
+}
+
+/// `POST /projects/:id/deploy_tokens`
+pub struct CreateDeployToken<'a> {
+    pub project: NameOrId<'a>,
+    pub name: &'a str,
+    pub scopes: &'a [&'a str],
+    pub username: Option<&'a str>,
+    pub expires_at: Option<NaiveDate>,
+}
+
+impl Endpoint for CreateDeployToken<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/deploy_tokens", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("name", self.name)
+            .extend(self.scopes.iter().map(|&scope| ("scopes[]", scope)))
+            .push_opt("username", self.username)
+            .push_opt("expires_at", self.expires_at);
+        params.into_body()
+    }
+}
+
+/// `POST /projects/:id/deploy_keys`, which unlike the `gitlab` crate's
+/// `CreateDeployKey` can set an expiry.
+pub struct AddDeployKey<'a> {
+    pub project: NameOrId<'a>,
+    pub title: &'a str,
+    pub key: &'a str,
+    pub can_push: bool,
+    pub expires_at: Option<NaiveDate>,
+}
+
+impl Endpoint for AddDeployKey<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/deploy_keys", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("title", self.title)
+            .push("key", self.key)
+            .push("can_push", self.can_push)
+            .push_opt("expires_at", self.expires_at);
+        params.into_body()
+    }
+}
+
+/// `GET /projects/:id/job_token_scope/allowlist`
+pub struct JobTokenAllowlist<'a> {
+    pub project: NameOrId<'a>,
+}
+
+impl Endpoint for JobTokenAllowlist<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/job_token_scope/allowlist", self.project).into()
+    }
+}
+
+impl Pageable for JobTokenAllowlist<'_> {}
+
+/// `POST /projects/:id/job_token_scope/allowlist`
+pub struct AllowJobToken<'a> {
+    pub project: NameOrId<'a>,
+    pub target_project_id: u64,
+}
+
+impl Endpoint for AllowJobToken<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/job_token_scope/allowlist", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params.push("target_project_id", self.target_project_id);
+        params.into_body()
+    }
+}
+
+/// `DELETE /projects/:id/job_token_scope/allowlist/:target_project_id`
+pub struct DisallowJobToken<'a> {
+    pub project: NameOrId<'a>,
+    pub target_project_id: u64,
+}
+
+impl Endpoint for DisallowJobToken<'_> {
+    fn method(&self) -> Method {
+        Method::DELETE
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/job_token_scope/allowlist/{}",
+            self.project, self.target_project_id
+        )
+        .into()
+    }
+}
+
+/// `POST /projects/:id/wikis`
+pub struct CreateWikiPage<'a> {
+    pub project: NameOrId<'a>,
+    pub title: &'a str,
+    pub content: &'a str,
+}
+
+impl Endpoint for CreateWikiPage<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/wikis", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("title", self.title)
+            .push("content", self.content);
+        params.into_body()
+    }
+}
+
+/// `PUT /projects/:id/wikis/:slug`
+pub struct EditWikiPage<'a> {
+    pub project: NameOrId<'a>,
+    pub slug: &'a str,
+    pub content: &'a str,
+}
+
+impl Endpoint for EditWikiPage<'_> {
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/wikis/{}",
+            self.project,
+            path_escaped(self.slug)
+        )
+        .into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params.push("content", self.content);
+        params.into_body()
+    }
+}
+
+/// `GET /groups/:id/epics/:epic_iid`
+pub struct Epic<'a> {
+    pub group: NameOrId<'a>,
+    pub iid: u64,
+}
+
+impl Endpoint for Epic<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("groups/{}/epics/{}", self.group, self.iid).into()
+    }
+}
+
+/// `GET /groups/:id/epics/:epic_iid/issues`
+pub struct EpicIssues<'a> {
+    pub group: NameOrId<'a>,
+    pub iid: u64,
+}
+
+impl Endpoint for EpicIssues<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("groups/{}/epics/{}/issues", self.group, self.iid).into()
+    }
+}
+
+impl Pageable for EpicIssues<'_> {}
+
+/// `GET /groups/:id/iterations?state=current`
+pub struct CurrentIterations<'a> {
+    pub group: NameOrId<'a>,
+}
+
+impl Endpoint for CurrentIterations<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("groups/{}/iterations", self.group).into()
+    }
+
+    fn parameters(&self) -> QueryParams<'_> {
+        let mut params = QueryParams::default();
+        params.push("state", "current");
+        params
+    }
+}
+
+impl Pageable for CurrentIterations<'_> {}
+
+/// `GET /projects/:id/boards`
+pub struct ProjectBoards<'a> {
+    pub project: NameOrId<'a>,
+}
+
+impl Endpoint for ProjectBoards<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/boards", self.project).into()
+    }
+}
+
+impl Pageable for ProjectBoards<'_> {}
+
+/// `GET /projects/:id/feature_flags/:feature_flag_name`
+pub struct FeatureFlag<'a> {
+    pub project: NameOrId<'a>,
+    pub name: &'a str,
+}
+
+impl Endpoint for FeatureFlag<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/feature_flags/{}",
+            self.project,
+            path_escaped(self.name),
+        )
+        .into()
+    }
+}
+
+/// A change to the strategies of a feature flag.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrategyChange {
+    /// Roll the flag out to all users of this environment.
+    Add { environment_scope: String },
+    /// Drop the strategy with this id.
+    Remove { id: u64 },
+    /// Drop a scope of the strategy with this id.
+    RemoveScope { id: u64, scope_id: u64 },
+}
+
+impl StrategyChange {
+    fn as_json(&self) -> serde_json::Value {
+        match self {
+            StrategyChange::Add { environment_scope } => json!({
+                "name": "default",
+                "parameters": {},
+                "scopes": [{ "environment_scope": environment_scope }],
+            }),
+            StrategyChange::Remove { id } => json!({ "id": id, "_destroy": true }),
+            StrategyChange::RemoveScope { id, scope_id } => json!({
+                "id": id,
+                "scopes": [{ "id": scope_id, "_destroy": true }],
+            }),
+        }
+    }
+}
+
+/// `PUT /projects/:id/feature_flags/:feature_flag_name`
+pub struct EditFeatureFlag<'a> {
+    pub project: NameOrId<'a>,
+    pub name: &'a str,
+    pub active: bool,
+    pub strategies: Vec<StrategyChange>,
+}
+
+impl Endpoint for EditFeatureFlag<'_> {
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!(
+            "projects/{}/feature_flags/{}",
+            self.project,
+            path_escaped(self.name),
+        )
+        .into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let strategies: Vec<_> = self
+            .strategies
+            .iter()
+            .map(StrategyChange::as_json)
+            .collect();
+        JsonParams::into_body(&JsonParams::clean(json!({
+            "active": self.active,
+            "strategies": strategies,
+        })))
+    }
+}
+
+/// `GET /projects/:id/freeze_periods`
+pub struct FreezePeriods<'a> {
+    pub project: NameOrId<'a>,
+}
+
+impl Endpoint for FreezePeriods<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/freeze_periods", self.project).into()
+    }
+}
+
+impl Pageable for FreezePeriods<'_> {}
+
+/// `POST /projects/:id/freeze_periods`
+pub struct CreateFreezePeriod<'a> {
+    pub project: NameOrId<'a>,
+    pub freeze_start: &'a str,
+    pub freeze_end: &'a str,
+    pub cron_timezone: &'a str,
+}
+
+impl Endpoint for CreateFreezePeriod<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/freeze_periods", self.project).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let mut params = FormParams::default();
+        params
+            .push("freeze_start", self.freeze_start)
+            .push("freeze_end", self.freeze_end)
+            .push("cron_timezone", self.cron_timezone);
+        params.into_body()
+    }
+}
+
+/// `DELETE /projects/:id/freeze_periods/:freeze_period_id`
+pub struct DeleteFreezePeriod<'a> {
+    pub project: NameOrId<'a>,
+    pub id: u64,
+}
+
+impl Endpoint for DeleteFreezePeriod<'_> {
+    fn method(&self) -> Method {
+        Method::DELETE
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/freeze_periods/{}", self.project, self.id).into()
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use gitlab::api::projects::environments::{Environment, EnvironmentState, Environments};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::table::{self, Format};
+
+#[derive(Debug, Deserialize)]
+struct Listed {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Details {
+    name: String,
+    #[serde(default)]
+    external_url: Option<String>,
+    #[serde(default)]
+    last_deployment: Option<Deployment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Deployment {
+    #[serde(rename = "ref")]
+    ref_: String,
+    sha: String,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    finished_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    user: Option<User>,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    username: String,
+}
+
+/// Whether `url` answers with a success status, for people.
+fn health(client: &Client, url: Option<&str>) -> String {
+    let Some(url) = url.filter(|url| !url.is_empty()) else {
+        return "-".to_owned();
+    };
+    if client.is_replaying() {
+        return "not checked".to_owned();
+    }
+    match client
+        .external(http::Method::GET, url)
+        .and_then(|request| Ok(request.send()?))
+    {
+        Ok(rsp) if rsp.status().is_success() => format!("up ({})", rsp.status().as_u16()),
+        Ok(rsp) => format!("down ({})", rsp.status().as_u16()),
+        Err(err) => {
+            tracing::debug!("failed to reach {url}: {err:#}");
+            "unreachable".to_owned()
+        }
+    }
+}
+
+/// Prints the available environments of `projects` with what was last
+/// deployed to each, when and by whom, and whether its external URL is up.
+pub fn status(client: &Client, projects: &[String], format: Format) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    for project in projects {
+        let endpoint = Environments::builder()
+            .project(project.as_str())
+            .states(EnvironmentState::Available)
+            .build()?;
+        let listed: Vec<Listed> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        for environment in listed {
+            // Only a single environment comes with its last deployment.
+            let endpoint = Environment::builder()
+                .project(project.as_str())
+                .environment(environment.id)
+                .build()?;
+            let details: Details = endpoint.query(client)?;
+            let health = health(client, details.external_url.as_deref());
+            let (ref_, at, by) = match details.last_deployment {
+                Some(deployment) => (
+                    format!(
+                        "{} ({})",
+                        deployment.ref_,
+                        &deployment.sha[..deployment.sha.len().min(8)]
+                    ),
+                    deployment
+                        .finished_at
+                        .unwrap_or(deployment.created_at)
+                        .format("%Y-%m-%d %H:%M UTC")
+                        .to_string(),
+                    deployment
+                        .user
+                        .map_or_else(|| "-".to_owned(), |user| format!("@{}", user.username)),
+                ),
+                None => ("never deployed".to_owned(), "-".to_owned(), "-".to_owned()),
+            };
+            rows.push([project.clone(), details.name, ref_, at, by, health]);
+        }
+    }
+    println!(
+        "{}",
+        table::render_as(
+            format,
+            ["PROJECT", "ENVIRONMENT", "REF", "DEPLOYED", "BY", "HEALTH"],
+            &rows
+        )
+    );
+    Ok(())
+}
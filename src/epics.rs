@@ -0,0 +1,200 @@
+use clap::Args;
+use gitlab::api::issues::{IssueMilestone, ProjectIssues};
+use gitlab::api::projects::issues::EditIssue;
+use gitlab::api::projects::merge_requests::notes::{CreateMergeRequestNote, MergeRequestNotes};
+use gitlab::api::projects::merge_requests::MergeRequests;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::endpoints::{self, EpicIssues};
+use crate::table::{self, Format};
+use crate::workflow::Context;
+
+#[derive(Debug, Clone, Args)]
+pub struct Link {
+    /// The IID of the group epic.
+    #[arg(long)]
+    pub epic: u64,
+    /// The release milestone whose issues and merge requests join the epic, e.g. `v1.4.0`.
+    #[arg(long)]
+    pub milestone: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Epic {
+    id: u64,
+    iid: u64,
+    title: String,
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueEpic {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    iid: u64,
+    title: String,
+    state: String,
+    #[serde(default)]
+    epic: Option<IssueEpic>,
+    #[serde(default)]
+    references: References,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct References {
+    #[serde(default)]
+    full: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mr {
+    iid: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+    body: String,
+}
+
+fn epic(client: &Client, group: &str, iid: u64) -> anyhow::Result<Epic> {
+    Ok(endpoints::Epic {
+        group: group.into(),
+        iid,
+    }
+    .query(client)?)
+}
+
+/// Puts the issues of `link.milestone` in `project` under the epic, and
+/// mentions the epic on its merge requests, which epics cannot hold, so the
+/// epic's activity lists them. Issues and MRs already linked are left alone.
+pub fn link(ctx: &Context, group: &str, project: &str, link: &Link) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let epic = epic(client, group, link.epic)?;
+    let issues: Vec<Issue> = api::paged(
+        ProjectIssues::builder()
+            .project(project)
+            .milestone_id(IssueMilestone::Named(link.milestone.as_str().into()))
+            .build()?,
+        api::Pagination::All,
+    )
+    .query(client)?;
+    let issues: Vec<_> = issues
+        .into_iter()
+        .filter(|issue| {
+            issue
+                .epic
+                .as_ref()
+                .is_none_or(|linked| linked.id != epic.id)
+        })
+        .collect();
+    let mrs: Vec<Mr> = api::paged(
+        MergeRequests::builder()
+            .project(project)
+            .milestone(link.milestone.as_str())
+            .build()?,
+        api::Pagination::All,
+    )
+    .query(client)?;
+    let mut unmentioned = Vec::new();
+    for mr in mrs {
+        let notes: Vec<Note> = api::paged(
+            MergeRequestNotes::builder()
+                .project(project)
+                .merge_request(mr.iid)
+                .build()?,
+            api::Pagination::All,
+        )
+        .query(client)?;
+        if !notes.iter().any(|note| note.body.contains(&epic.web_url)) {
+            unmentioned.push(mr.iid);
+        }
+    }
+    if issues.is_empty() && unmentioned.is_empty() {
+        return Ok(format!(
+            "{} is already linked to &{}",
+            link.milestone, epic.iid
+        ));
+    }
+
+    let mut plan: Vec<_> = issues
+        .iter()
+        .map(|issue| format!("add #{} to epic &{}", issue.iid, epic.iid))
+        .collect();
+    plan.extend(
+        unmentioned
+            .iter()
+            .map(|iid| format!("mention epic &{} on !{iid}", epic.iid)),
+    );
+    ctx.confirm(project, &plan)?;
+    for issue in &issues {
+        let endpoint = EditIssue::builder()
+            .project(project)
+            .issue(issue.iid)
+            .epic_id(epic.id)
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    for &iid in &unmentioned {
+        let endpoint = CreateMergeRequestNote::builder()
+            .project(project)
+            .merge_request(iid)
+            .body(format!(
+                "Part of epic {} for {}",
+                epic.web_url, link.milestone
+            ))
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    Ok(format!(
+        "linked {} issue(s) and {} merge request(s) of {} to &{}",
+        issues.len(),
+        unmentioned.len(),
+        link.milestone,
+        epic.iid
+    ))
+}
+
+/// Prints the issues of epic `iid` of `group` and how many of them are closed.
+pub fn status(client: &Client, group: &str, iid: u64, format: Format) -> anyhow::Result<()> {
+    let epic = epic(client, group, iid)?;
+    let endpoint = EpicIssues {
+        group: group.into(),
+        iid,
+    };
+    let issues: Vec<Issue> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    let closed = issues
+        .iter()
+        .filter(|issue| issue.state == "closed")
+        .count();
+    let rows: Vec<_> = issues
+        .iter()
+        .map(|issue| {
+            [
+                issue.references.full.clone(),
+                issue.title.clone(),
+                issue.state.clone(),
+            ]
+        })
+        .collect();
+    println!(
+        "{}",
+        table::render_as(format, ["ISSUE", "TITLE", "STATE"], &rows)
+    );
+    let percent = if issues.is_empty() {
+        0
+    } else {
+        closed * 100 / issues.len()
+    };
+    tracing::info!(
+        "&{} {}: {closed} of {} issue(s) closed ({percent}%)",
+        epic.iid,
+        epic.title,
+        issues.len()
+    );
+    Ok(())
+}
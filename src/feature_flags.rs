@@ -0,0 +1,291 @@
+use clap::Args;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::{self, Client};
+use crate::endpoints::{EditFeatureFlag, FeatureFlag, StrategyChange};
+use crate::hooks::Event;
+use crate::journal::Resource;
+use crate::notify;
+use crate::template::Vars;
+use crate::workflow::Context;
+
+#[derive(Debug, Clone, Args)]
+pub struct Toggle {
+    /// The flag, e.g. `kill_switch_x`.
+    #[arg(long)]
+    pub name: String,
+    /// Only in this environment, e.g. `production`; everywhere if unset.
+    #[arg(long = "env")]
+    pub environment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Flag {
+    active: bool,
+    #[serde(default)]
+    strategies: Vec<Strategy>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Strategy {
+    id: u64,
+    #[serde(default)]
+    scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Scope {
+    id: u64,
+    environment_scope: String,
+}
+
+/// What turning a flag on or off takes: whether it is active afterwards
+/// and what happens to its strategies.
+#[derive(Debug, PartialEq)]
+struct Edit {
+    active: bool,
+    strategies: Vec<StrategyChange>,
+}
+
+/// The edit that turns `flag` on or off in `environment`, or everywhere,
+/// or `None` if it already is.
+fn edit(flag: &Flag, environment: Option<&str>, active: bool) -> anyhow::Result<Option<Edit>> {
+    let Some(environment) = environment else {
+        return Ok((flag.active != active).then(|| Edit {
+            active,
+            strategies: Vec::new(),
+        }));
+    };
+    let scopes = || flag.strategies.iter().flat_map(|strategy| &strategy.scopes);
+    if active {
+        let covered = scopes()
+            .any(|scope| scope.environment_scope == environment || scope.environment_scope == "*");
+        if flag.active && covered {
+            return Ok(None);
+        }
+        let strategies = if covered {
+            Vec::new()
+        } else {
+            vec![StrategyChange::Add {
+                environment_scope: environment.to_owned(),
+            }]
+        };
+        return Ok(Some(Edit {
+            active: true,
+            strategies,
+        }));
+    }
+
+    if !flag.active {
+        return Ok(None);
+    }
+    anyhow::ensure!(
+        !scopes().any(|scope| scope.environment_scope == "*"),
+        "the flag is on in every environment (`*`); turn it off everywhere by leaving out \
+         --env, or give its strategies narrower environments"
+    );
+    let strategies = flag
+        .strategies
+        .iter()
+        .flat_map(|strategy| {
+            let matching: Vec<_> = strategy
+                .scopes
+                .iter()
+                .filter(|scope| scope.environment_scope == environment)
+                .collect();
+            if !matching.is_empty() && matching.len() == strategy.scopes.len() {
+                vec![StrategyChange::Remove { id: strategy.id }]
+            } else {
+                matching
+                    .into_iter()
+                    .map(|scope| StrategyChange::RemoveScope {
+                        id: strategy.id,
+                        scope_id: scope.id,
+                    })
+                    .collect()
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok((!strategies.is_empty()).then_some(Edit {
+        active: true,
+        strategies,
+    }))
+}
+
+fn fetch(client: &Client, project: &str, name: &str) -> anyhow::Result<Flag> {
+    let endpoint = FeatureFlag {
+        project: project.into(),
+        name,
+    };
+    match endpoint.query(client) {
+        Ok(flag) => Ok(flag),
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => {
+            anyhow::bail!("{project} has no feature flag {name}")
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn apply(client: &Client, project: &str, name: &str, edit: Edit) -> anyhow::Result<()> {
+    let endpoint = EditFeatureFlag {
+        project: project.into(),
+        name,
+        active: edit.active,
+        strategies: edit.strategies,
+    };
+    api::ignore(endpoint).query(client)?;
+    Ok(())
+}
+
+fn describe(name: &str, environment: Option<&str>, active: bool) -> String {
+    let state = if active { "on" } else { "off" };
+    match environment {
+        Some(environment) => format!("turn feature flag {name} {state} in {environment}"),
+        None => format!("turn feature flag {name} {state}"),
+    }
+}
+
+/// Turns feature flag `name` on or off again, e.g. to roll back a toggle;
+/// `false` if it already was.
+pub fn set(
+    client: &Client,
+    project: &str,
+    name: &str,
+    environment: Option<&str>,
+    active: bool,
+) -> anyhow::Result<bool> {
+    let flag = fetch(client, project, name)?;
+    let Some(edit) = edit(&flag, environment, active)? else {
+        return Ok(false);
+    };
+    apply(client, project, name, edit)?;
+    Ok(true)
+}
+
+/// Turns a GitLab feature flag on or off, recording it in the journal and
+/// announcing it on the notification webhook if there is one.
+pub fn toggle(
+    ctx: &Context,
+    project: &str,
+    toggle: &Toggle,
+    active: bool,
+) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let (name, environment) = (toggle.name.as_str(), toggle.environment.as_deref());
+    let step = describe(name, environment, active);
+    let flag = fetch(client, project, name)?;
+    let Some(edit) = edit(&flag, environment, active)? else {
+        return Ok(format!("feature flag {name} already is as asked"));
+    };
+    if edit.active && !flag.active && environment.is_some() {
+        tracing::warn!(
+            project,
+            "{name} was off everywhere; it is on again in every environment its strategies name"
+        );
+    }
+    ctx.confirm(project, std::slice::from_ref(&step))?;
+    ctx.journaled(project, &step, || {
+        apply(client, project, name, edit)?;
+        let resource = Resource::FeatureFlag {
+            name: name.to_owned(),
+            environment: environment.map(str::to_owned),
+            active,
+        };
+        Ok((Some(resource), Vars::new()))
+    })?;
+
+    if let Some(webhook_url) = ctx.notify.webhook_url() {
+        let by = std::env::var("GITLAB_USER_LOGIN")
+            .map(|login| format!(" by @{login}"))
+            .unwrap_or_default();
+        let message = format!(
+            "Feature flag `{name}` of {project} turned {}{}{by}",
+            if active { "on" } else { "off" },
+            environment.map_or_else(String::new, |environment| format!(" in {environment}"))
+        );
+        let event = Event::NotificationSent {
+            project,
+            message: &message,
+        };
+        ctx.hooked(&event, || notify::send(client, &webhook_url, &message))?;
+    }
+    Ok(format!("done: {step}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(active: bool, strategies: &[(u64, &[(u64, &str)])]) -> Flag {
+        Flag {
+            active,
+            strategies: strategies
+                .iter()
+                .map(|(id, scopes)| Strategy {
+                    id: *id,
+                    scopes: scopes
+                        .iter()
+                        .map(|(id, environment_scope)| Scope {
+                            id: *id,
+                            environment_scope: (*environment_scope).to_owned(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn turning_off_in_an_environment_drops_its_scopes() {
+        let flag = flag(
+            true,
+            &[
+                (1, &[(10, "production"), (11, "staging")]),
+                (2, &[(20, "production")]),
+            ],
+        );
+        assert_eq!(
+            edit(&flag, Some("production"), false).unwrap(),
+            Some(Edit {
+                active: true,
+                strategies: vec![
+                    StrategyChange::RemoveScope {
+                        id: 1,
+                        scope_id: 10
+                    },
+                    StrategyChange::Remove { id: 2 },
+                ],
+            })
+        );
+        assert_eq!(edit(&flag, Some("review"), false).unwrap(), None);
+    }
+
+    #[test]
+    fn turning_on_in_an_environment_adds_a_strategy_unless_one_covers_it() {
+        let on_in_staging = flag(true, &[(1, &[(10, "staging")])]);
+        assert_eq!(
+            edit(&on_in_staging, Some("production"), true).unwrap(),
+            Some(Edit {
+                active: true,
+                strategies: vec![StrategyChange::Add {
+                    environment_scope: "production".to_owned(),
+                }],
+            })
+        );
+        assert_eq!(edit(&on_in_staging, Some("staging"), true).unwrap(), None);
+    }
+
+    #[test]
+    fn a_flag_on_everywhere_cannot_be_turned_off_in_one_environment() {
+        let everywhere = flag(true, &[(1, &[(10, "*")])]);
+        assert!(edit(&everywhere, Some("production"), false).is_err());
+        assert_eq!(
+            edit(&everywhere, None, false).unwrap(),
+            Some(Edit {
+                active: false,
+                strategies: Vec::new(),
+            })
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::Args;
+use gitlab::api::projects::repository::branches::Branch;
+use gitlab::api::projects::repository::commits::{CommitAction, CommitActionType, CreateCommit};
+use gitlab::api::projects::repository::files::{Encoding, FileRaw};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::authorship::CommitConfig;
+use crate::client::{self, Client};
+use crate::title;
+use crate::workflow::Context;
+
+/// One file of a commit.
+#[derive(Debug, Clone)]
+pub enum Change {
+    Write { path: String, content: Vec<u8> },
+    Delete { path: String },
+}
+
+impl Change {
+    fn path(&self) -> &str {
+        match self {
+            Change::Write { path, .. } | Change::Delete { path } => path,
+        }
+    }
+}
+
+/// Changes to several files that land as one commit.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    pub branch: String,
+    /// The branch to create `branch` from if it does not exist yet.
+    pub start_branch: Option<String>,
+    pub message: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct Put {
+    /// `REPO_PATH=LOCAL_PATH`, or just `PATH` for the same path on both sides.
+    #[arg(required_unless_present = "delete")]
+    pub files: Vec<String>,
+    /// A file to delete in the same commit.
+    #[arg(long, value_name = "REPO_PATH")]
+    pub delete: Vec<String>,
+    /// The branch to commit to.
+    #[arg(long)]
+    pub branch: String,
+    /// The branch to create `--branch` from if it does not exist yet.
+    #[arg(long)]
+    pub start_branch: Option<String>,
+    #[arg(long, short)]
+    pub message: String,
+}
+
+impl Put {
+    pub fn batch(&self) -> anyhow::Result<Batch> {
+        let mut changes = Vec::new();
+        for file in &self.files {
+            let (path, local) = file.split_once('=').unwrap_or((file, file));
+            let local = PathBuf::from(local);
+            let content = std::fs::read(&local)
+                .with_context(|| format!("failed to read {}", local.display()))?;
+            changes.push(Change::Write {
+                path: path.to_owned(),
+                content,
+            });
+        }
+        changes.extend(
+            self.delete
+                .iter()
+                .map(|path| Change::Delete { path: path.clone() }),
+        );
+        Ok(Batch {
+            branch: self.branch.clone(),
+            start_branch: self.start_branch.clone(),
+            message: self.message.clone(),
+            changes,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    id: String,
+}
+
+/// The contents of `path` at `ref_`, or `None` if there is no such file.
+pub fn get(
+    client: &Client,
+    project: &str,
+    path: &str,
+    ref_: &str,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let endpoint = FileRaw::builder()
+        .project(project)
+        .file_path(path)
+        .ref_(ref_)
+        .build()?;
+    match api::raw(endpoint).query(client) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn branch_exists(client: &Client, project: &str, branch: &str) -> anyhow::Result<bool> {
+    let endpoint = Branch::builder().project(project).branch(branch).build()?;
+    match api::ignore(endpoint).query(client) {
+        Ok(()) => Ok(true),
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Commits `batch`, leaving out the files that would not change, and
+/// returns the new commit's SHA, or `None` if nothing changed at all.
+pub fn commit(
+    client: &Client,
+    commits: &CommitConfig,
+    project: &str,
+    batch: &Batch,
+) -> anyhow::Result<Option<String>> {
+    let base = batch.start_branch.as_deref().unwrap_or(&batch.branch);
+    let branch_exists =
+        batch.start_branch.is_none() || branch_exists(client, project, &batch.branch)?;
+    let ref_ = if branch_exists {
+        batch.branch.as_str()
+    } else {
+        base
+    };
+
+    let mut actions = Vec::new();
+    for change in &batch.changes {
+        let current = get(client, project, change.path(), ref_)?;
+        let action = match (change, current) {
+            (Change::Write { content, .. }, Some(current)) if &current == content => continue,
+            (Change::Delete { .. }, None) => continue,
+            (Change::Write { path, content }, current) => {
+                let mut action = CommitAction::builder();
+                action
+                    .action(if current.is_some() {
+                        CommitActionType::Update
+                    } else {
+                        CommitActionType::Create
+                    })
+                    .file_path(path.as_str())
+                    .content(content.as_slice());
+                if std::str::from_utf8(content).is_err() {
+                    action.encoding(Encoding::Base64);
+                }
+                action.build()?
+            }
+            (Change::Delete { path }, Some(_)) => CommitAction::builder()
+                .action(CommitActionType::Delete)
+                .file_path(path.as_str())
+                .build()?,
+        };
+        actions.push(action);
+    }
+    if actions.is_empty() {
+        return Ok(None);
+    }
+
+    let jira_id = title::jira_id_of_branch(&batch.branch);
+    let mut endpoint = CreateCommit::builder();
+    endpoint
+        .project(project)
+        .branch(batch.branch.as_str())
+        .commit_message(commits.message(&batch.message, jira_id.as_deref()))
+        .actions(actions);
+    if !branch_exists {
+        endpoint.start_branch(base);
+    }
+    if let Some((name, email)) = commits.identity() {
+        endpoint.author_name(name).author_email(email);
+    }
+    let commit: Commit = endpoint.build()?.query(client)?;
+    Ok(Some(commit.id))
+}
+
+/// Prints the contents of `path` at `ref_` as they are.
+pub fn print(client: &Client, project: &str, path: &str, ref_: &str) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    let Some(content) = get(client, project, path, ref_)? else {
+        anyhow::bail!("{project} has no {path} at {ref_}");
+    };
+    std::io::stdout().write_all(&content)?;
+    Ok(())
+}
+
+pub fn put(ctx: &Context, project: &str, batch: &Batch) -> anyhow::Result<String> {
+    let plan: Vec<_> = batch
+        .changes
+        .iter()
+        .map(|change| match change {
+            Change::Write { path, .. } => format!("write {path} on {}", batch.branch),
+            Change::Delete { path } => format!("delete {path} on {}", batch.branch),
+        })
+        .collect();
+    ctx.confirm(project, &plan)?;
+    Ok(match commit(ctx.client, ctx.commits, project, batch)? {
+        Some(sha) => format!("committed {sha} to {}", batch.branch),
+        None => format!("{} is already up to date", batch.branch),
+    })
+}
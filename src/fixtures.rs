@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::client::RestError;
+use crate::redact;
+
+/// Response headers worth keeping: the ones offset pagination depends on.
+const KEPT_HEADERS: &[&str] = &[
+    "x-next-page",
+    "x-page",
+    "x-per-page",
+    "x-prev-page",
+    "x-total",
+    "x-total-pages",
+];
+
+/// One recorded API call, stored as `<dir>/<file_name>`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Fixture {
+    pub method: String,
+    /// Relative to `/api/v4/` and still percent-encoded, e.g. `projects/42/repository/branches`.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub query: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    pub body: serde_json::Value,
+}
+
+impl Fixture {
+    /// A stable, filesystem-safe name derived from the request alone.
+    pub fn file_name(method: &str, path: &str, query: &str) -> String {
+        let mut name = format!("{method}_{path}");
+        if !query.is_empty() {
+            name = format!("{name}__{query}");
+        }
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{name}.json")
+    }
+}
+
+/// Writes every REST response the client receives to a fixture directory.
+pub struct Recorder {
+    dir: PathBuf,
+    base: Url,
+}
+
+impl Recorder {
+    pub fn new(dir: &Path, base: Url) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create fixture directory {}", dir.display()))?;
+        tracing::info!("recording API fixtures to {}", dir.display());
+        Ok(Recorder {
+            dir: dir.to_owned(),
+            base,
+        })
+    }
+
+    pub fn record(&self, method: &http::Method, url: &Url, rsp: &http::Response<Bytes>) {
+        let Some(path) = url.path().strip_prefix(self.base.path()) else {
+            return;
+        };
+        let body = match std::str::from_utf8(rsp.body()) {
+            Ok("") => serde_json::Value::Null,
+            Ok(text) => serde_json::from_str(text)
+                .unwrap_or_else(|_| serde_json::Value::String(text.to_owned())),
+            Err(_) => return,
+        };
+        let fixture = Fixture {
+            method: method.to_string(),
+            path: path.to_owned(),
+            query: url.query().unwrap_or_default().to_owned(),
+            status: rsp.status().as_u16(),
+            headers: rsp
+                .headers()
+                .iter()
+                .filter(|(key, _)| KEPT_HEADERS.contains(&key.as_str()))
+                .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_owned())))
+                .collect(),
+            body,
+        };
+        let name = Fixture::file_name(&fixture.method, &fixture.path, &fixture.query);
+        let write = || -> anyhow::Result<()> {
+            let json = serde_json::to_string_pretty(&fixture)?;
+            std::fs::write(self.dir.join(&name), redact::redact(&json).as_bytes())?;
+            Ok(())
+        };
+        if let Err(err) = write() {
+            tracing::warn!("failed to record fixture {name}: {err:#}");
+        }
+    }
+}
+
+/// Answers REST requests from a fixture directory without any network access.
+pub struct Replay {
+    dir: PathBuf,
+    base: Url,
+}
+
+impl Replay {
+    pub fn new(dir: &Path, base: Url) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            dir.is_dir(),
+            "fixture directory {} does not exist",
+            dir.display()
+        );
+        tracing::warn!(
+            "replaying API fixtures from {}; nothing is sent to GitLab",
+            dir.display()
+        );
+        Ok(Replay {
+            dir: dir.to_owned(),
+            base,
+        })
+    }
+
+    fn load(&self, name: &str) -> Result<Option<Fixture>, RestError> {
+        let contents = match std::fs::read(self.dir.join(name)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(RestError::Replay(format!("failed to read {name}: {err}"))),
+        };
+        serde_json::from_slice(&contents)
+            .map(Some)
+            .map_err(|err| RestError::Replay(format!("invalid fixture {name}: {err}")))
+    }
+
+    /// What GitLab would plausibly answer to a change nobody recorded: the
+    /// submitted fields plus the ids and URL callers tend to read back.
+    fn stub(&self, method: &http::Method, path: &str, body: &[u8]) -> Fixture {
+        let mut object: serde_json::Map<_, _> = url::form_urlencoded::parse(body)
+            .map(|(key, value)| (key.into_owned(), value.into_owned().into()))
+            .collect();
+        object.insert("id".to_owned(), 0.into());
+        object.insert("iid".to_owned(), 0.into());
+        object.insert("web_url".to_owned(), format!("{}{path}", self.base).into());
+        let (status, body) = if method == http::Method::DELETE {
+            (204, serde_json::Value::Null)
+        } else {
+            (201, object.into())
+        };
+        Fixture {
+            method: method.to_string(),
+            path: path.to_owned(),
+            query: String::new(),
+            status,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    pub fn respond(
+        &self,
+        request: &http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Bytes>, RestError> {
+        let url = Url::parse(&request.uri().to_string())
+            .map_err(|err| RestError::Replay(format!("invalid request URL: {err}")))?;
+        let path = url
+            .path()
+            .strip_prefix(self.base.path())
+            .unwrap_or(url.path());
+        let query = url.query().unwrap_or_default();
+        let method = request.method();
+        let name = Fixture::file_name(method.as_str(), path, query);
+        let fixture = match self.load(&name)? {
+            Some(fixture) => fixture,
+            None if method == http::Method::GET => {
+                return Err(RestError::Replay(format!(
+                    "no fixture recorded for GET {path} (expected {})",
+                    self.dir.join(&name).display()
+                )))
+            }
+            None => self.stub(method, path, request.body()),
+        };
+        if method != http::Method::GET {
+            tracing::info!("replay: not sending {method} {path}");
+        }
+
+        let mut rsp = http::Response::builder().status(fixture.status);
+        for (key, value) in &fixture.headers {
+            rsp = rsp.header(key, value);
+        }
+        let body = match fixture.body {
+            serde_json::Value::Null => Vec::new(),
+            body => serde_json::to_vec(&body).unwrap(),
+        };
+        Ok(rsp
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(Bytes::from(body))?)
+    }
+}
@@ -0,0 +1,144 @@
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use gitlab::api::{self, groups, Query};
+use serde::Deserialize;
+
+use crate::cancel;
+use crate::client::Client;
+use crate::progress::Progress;
+use crate::{redact, reporting, table};
+
+#[derive(Debug, thiserror::Error)]
+#[error("{failed} of {total} {kind}s failed")]
+pub struct Failed {
+    kind: &'static str,
+    failed: usize,
+    total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    path_with_namespace: String,
+}
+
+/// Every non-archived project in `group`, including those in its subgroups.
+pub fn group_projects(client: &Client, group: &str) -> anyhow::Result<Vec<String>> {
+    let endpoint = groups::projects::GroupProjects::builder()
+        .group(group)
+        .include_subgroups(true)
+        .archived(false)
+        .simple(true)
+        .build()?;
+    let projects: Vec<Project> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    anyhow::ensure!(!projects.is_empty(), "group {group} has no projects");
+    tracing::info!(group, count = projects.len(), "projects found in group");
+    Ok(projects
+        .into_iter()
+        .map(|project| project.path_with_namespace)
+        .collect())
+}
+
+/// Runs `task` for every project on at most `jobs` threads at once and reports
+/// the outcome of each in a table; fails if any project failed, but only after
+/// all have finished.
+pub fn run<F>(projects: &[String], jobs: NonZeroUsize, task: F) -> anyhow::Result<()>
+where
+    F: Fn(&str) -> anyhow::Result<String> + Sync,
+{
+    if let [project] = projects {
+        let result = task(project)?;
+        tracing::info!(project, "{result}");
+        return Ok(());
+    }
+    each("project", projects, jobs, task)
+}
+
+/// Like `run`, for any kind of item, such as the branches of one project.
+pub fn each<F>(
+    kind: &'static str,
+    items: &[String],
+    jobs: NonZeroUsize,
+    task: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&str) -> anyhow::Result<String> + Sync,
+{
+    if let [item] = items {
+        let result = task(item)?;
+        tracing::info!(kind, item, "{result}");
+        return Ok(());
+    }
+
+    // Workers take the next item off a shared counter until none are left.
+    let next = AtomicUsize::new(0);
+    let progress = Progress::bar(&format!("{kind}s"), items.len());
+    let parent = tracing::Span::current();
+    let mut results: Vec<_> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.get().min(items.len()))
+            .map(|_| {
+                let (task, next, parent, progress) = (&task, &next, &parent, &progress);
+                scope.spawn(move || {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break done;
+                        };
+                        let span = if kind == "project" {
+                            tracing::info_span!(parent: parent, "project", project = item)
+                        } else {
+                            tracing::info_span!(parent: parent, "item", kind, item)
+                        };
+                        let _span = span.entered();
+                        // Items not started yet are skipped once a signal asked to stop.
+                        let result = match cancel::check() {
+                            Ok(()) => panic::catch_unwind(AssertUnwindSafe(|| task(item)))
+                                .unwrap_or_else(|_| Err(anyhow::anyhow!("panicked"))),
+                            Err(cancelled) => Err(cancelled.into()),
+                        };
+                        done.push((index, result));
+                        progress.inc();
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+    drop(progress);
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<_> = results.into_iter().map(|(_, result)| result).collect();
+
+    let rows: Vec<_> = items
+        .iter()
+        .zip(&results)
+        .map(|(item, result)| match result {
+            Ok(summary) => [item.clone(), "ok".to_owned(), summary.clone()],
+            Err(err) => {
+                reporting::failure((kind == "project").then_some(item.as_str()), err);
+                let details = redact::redact(&format!("{err:#}")).into_owned();
+                [item.clone(), "failed".to_owned(), details]
+            }
+        })
+        .collect();
+    let header = kind.to_uppercase();
+    println!(
+        "{}",
+        table::render([header.as_str(), "RESULT", "DETAILS"], &rows)
+    );
+
+    let failed = results.iter().filter(|result| result.is_err()).count();
+    if failed > 0 {
+        return Err(Failed {
+            kind,
+            failed,
+            total: items.len(),
+        }
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use clap::Args;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::endpoints::{CreateFreezePeriod, DeleteFreezePeriod, FreezePeriods};
+use crate::table::{self, Format};
+use crate::workflow::Context;
+
+/// The time zones `active` can evaluate a freeze period in by itself.
+const UTC_ZONES: &[&str] = &["UTC", "Etc/UTC", "GMT", "Etc/GMT"];
+
+#[derive(Debug, Clone, Args)]
+pub struct Period {
+    /// When the freeze starts, as a cron expression, e.g. `"0 23 * * 5"`.
+    #[arg(long)]
+    pub start: String,
+    /// When it ends, e.g. `"0 7 * * 1"`.
+    #[arg(long)]
+    pub end: String,
+    /// The IANA time zone the expressions are in, e.g. `Europe/Budapest`.
+    #[arg(long, default_value = "UTC")]
+    pub timezone: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FreezePeriod {
+    id: u64,
+    freeze_start: String,
+    freeze_end: String,
+    cron_timezone: String,
+}
+
+/// A day-of-week field counted from Sunday as 0 (or 7), as cron has it, counted
+/// from Sunday as 1, as the `cron` crate does.
+fn days_of_week(field: &str) -> String {
+    let day = |day: &str| match day.parse::<u8>() {
+        Ok(day @ 0..=6) => (day + 1).to_string(),
+        Ok(7) => "1".to_owned(),
+        _ => day.to_owned(),
+    };
+    field
+        .split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            let range = match range.split_once('-') {
+                // Up to Sunday, which is first now rather than last.
+                Some((from, "7")) => format!("{}-7,1", day(from)),
+                Some((from, to)) => format!("{}-{}", day(from), day(to)),
+                None => day(range),
+            };
+            match step {
+                Some(step) => format!("{range}/{step}"),
+                None => range,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// GitLab's five-field cron expression, as the `cron` crate reads it.
+fn schedule(expression: &str) -> anyhow::Result<cron::Schedule> {
+    let fields: Vec<_> = expression.split_whitespace().collect();
+    let [minute, hour, day, month, weekday] = fields[..] else {
+        anyhow::bail!("{expression:?} is not a cron expression like \"0 23 * * 5\"");
+    };
+    let weekday = days_of_week(weekday);
+    cron::Schedule::from_str(&format!("0 {minute} {hour} {day} {month} {weekday}"))
+        .map_err(|err| anyhow::anyhow!("{expression:?} is not a valid cron expression: {err}"))
+}
+
+/// When the freeze that is on at `now` ends, or `None` if it is not on: it
+/// is if it started more recently than it last ended.
+fn ends(start: &str, end: &str, now: DateTime<Utc>) -> anyhow::Result<Option<DateTime<Utc>>> {
+    let (start, end) = (schedule(start)?, schedule(end)?);
+    // Looking back from just past `now`, so that what fires at `now` counts.
+    let past = now + chrono::Duration::seconds(1);
+    let Some(started) = start.after(&past).next_back() else {
+        return Ok(None);
+    };
+    let ended = end.after(&past).next_back();
+    if ended.is_some_and(|ended| ended >= started) {
+        return Ok(None);
+    }
+    Ok(end.after(&now).next())
+}
+
+impl FreezePeriod {
+    fn ends(&self, now: DateTime<Utc>) -> anyhow::Result<Option<DateTime<Utc>>> {
+        if !UTC_ZONES.contains(&self.cron_timezone.as_str()) {
+            tracing::warn!(
+                "freeze period {} is in {}, which is only told apart from UTC in the project's own CI jobs",
+                self.id,
+                self.cron_timezone
+            );
+        }
+        ends(&self.freeze_start, &self.freeze_end, now)
+    }
+}
+
+fn periods(client: &Client, project: &str) -> anyhow::Result<Vec<FreezePeriod>> {
+    let endpoint = FreezePeriods {
+        project: project.into(),
+    };
+    Ok(api::paged(endpoint, api::Pagination::All).query(client)?)
+}
+
+/// Whether this runs in a CI job of `project`, given by ID or path.
+fn in_own_pipeline(project: &str) -> bool {
+    std::env::var_os("GITLAB_CI").is_some()
+        && ["CI_PROJECT_ID", "CI_PROJECT_PATH"]
+            .into_iter()
+            .any(|var| std::env::var(var).is_ok_and(|value| value.eq_ignore_ascii_case(project)))
+}
+
+/// The deploy freeze `project` is in, if any, described for people. In a CI
+/// job of `project` GitLab says so itself with `CI_DEPLOY_FREEZE`, time zones
+/// and all; that variable says nothing about other projects.
+pub fn active(client: &Client, project: &str) -> anyhow::Result<Option<String>> {
+    if in_own_pipeline(project) {
+        return Ok(
+            std::env::var_os("CI_DEPLOY_FREEZE").map(|_| "a deploy freeze period is on".to_owned())
+        );
+    }
+    let now = Utc::now();
+    for period in periods(client, project)? {
+        if let Some(ends) = period.ends(now)? {
+            return Ok(Some(format!(
+                "freeze period {} is on until {}",
+                period.id,
+                ends.format("%Y-%m-%d %H:%M UTC")
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Adds a deploy freeze period, during which `$CI_DEPLOY_FREEZE` is set.
+pub fn create(ctx: &Context, project: &str, period: &Period) -> anyhow::Result<String> {
+    schedule(&period.start)?;
+    schedule(&period.end)?;
+    let what = format!(
+        "freeze deploys from `{}` to `{}` ({})",
+        period.start, period.end, period.timezone
+    );
+    ctx.confirm(project, std::slice::from_ref(&what))?;
+    let endpoint = CreateFreezePeriod {
+        project: project.into(),
+        freeze_start: &period.start,
+        freeze_end: &period.end,
+        cron_timezone: &period.timezone,
+    };
+    api::ignore(endpoint).query(ctx.client)?;
+    Ok(format!("added: {what}"))
+}
+
+pub fn delete(ctx: &Context, project: &str, id: u64) -> anyhow::Result<String> {
+    ctx.confirm(project, &[format!("delete freeze period {id}")])?;
+    let endpoint = DeleteFreezePeriod {
+        project: project.into(),
+        id,
+    };
+    api::ignore(endpoint).query(ctx.client)?;
+    Ok(format!("deleted freeze period {id}"))
+}
+
+/// Prints the freeze periods of `projects` and which of them are on now.
+pub fn list(client: &Client, projects: &[String], format: Format) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let mut rows = Vec::new();
+    for project in projects {
+        for period in periods(client, project)? {
+            let on = match period.ends(now)? {
+                Some(ends) => format!("until {}", ends.format("%Y-%m-%d %H:%M UTC")),
+                None => "no".to_owned(),
+            };
+            rows.push([
+                project.clone(),
+                period.id.to_string(),
+                period.freeze_start,
+                period.freeze_end,
+                period.cron_timezone,
+                on,
+            ]);
+        }
+    }
+    println!(
+        "{}",
+        table::render_as(
+            format,
+            ["PROJECT", "ID", "START", "END", "TIMEZONE", "ON"],
+            &rows
+        )
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().into()
+    }
+
+    #[test]
+    fn a_weekend_freeze_is_on_from_friday_night_to_monday_morning() {
+        let (start, end) = ("0 23 * * 5", "0 7 * * 1");
+        // 2026-10-16 is a Friday.
+        assert_eq!(ends(start, end, at("2026-10-16T22:59:00Z")).unwrap(), None);
+        assert_eq!(
+            ends(start, end, at("2026-10-17T12:00:00Z")).unwrap(),
+            Some(at("2026-10-19T07:00:00Z"))
+        );
+        assert_eq!(ends(start, end, at("2026-10-19T07:00:00Z")).unwrap(), None);
+    }
+
+    #[test]
+    fn days_of_week_are_counted_from_sunday_as_one() {
+        assert_eq!(days_of_week("5"), "6");
+        assert_eq!(days_of_week("0,6"), "1,7");
+        assert_eq!(days_of_week("1-5"), "2-6");
+        assert_eq!(days_of_week("5-7"), "6-7,1");
+        assert_eq!(days_of_week("*/2"), "*/2");
+        assert_eq!(days_of_week("FRI"), "FRI");
+    }
+
+    #[test]
+    fn only_five_field_expressions_are_taken() {
+        assert!(schedule("0 23 * * 5").is_ok());
+        assert!(schedule("0 0 23 * * 5").is_err());
+        assert!(schedule("61 * * * *").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use gitlab::api::projects::merge_requests::approvals::MergeRequestApprovals;
+use gitlab::api::projects::merge_requests::MergeRequest;
+use gitlab::api::Query;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::release_notes::{self, Author, MergeRequestNode, PipelineNode, Users};
+use crate::table::{self, Format};
+use crate::title;
+
+#[derive(Debug, Deserialize)]
+struct Approvals {
+    approved_by: Vec<Approval>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Approval {
+    user: Author,
+}
+
+#[derive(Debug, Deserialize)]
+struct Details {
+    head_pipeline: Option<Pipeline>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+}
+
+/// Fills in the approvers and pipeline the REST API's list leaves out, one
+/// merge request at a time.
+fn complete(client: &Client, project: &str, mr: &mut MergeRequestNode) -> anyhow::Result<()> {
+    let iid: u64 = mr.iid.parse()?;
+    if mr.approved_by.is_none() {
+        let approvals: Approvals = MergeRequestApprovals::builder()
+            .project(project)
+            .merge_request(iid)
+            .build()?
+            .query(client)?;
+        mr.approved_by = Some(Users {
+            nodes: approvals
+                .approved_by
+                .into_iter()
+                .map(|approval| approval.user)
+                .collect(),
+        });
+    }
+    if mr.head_pipeline.is_none() {
+        let details: Details = MergeRequest::builder()
+            .project(project)
+            .merge_request(iid)
+            .build()?
+            .query(client)?;
+        mr.head_pipeline = details.head_pipeline.map(|pipeline| PipelineNode {
+            id: pipeline.id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+const HEADERS: [&str; 11] = [
+    "MR",
+    "MERGED AT",
+    "KIND",
+    "JIRA ID",
+    "TITLE",
+    "AUTHOR",
+    "APPROVERS",
+    "MERGE SHA",
+    "PIPELINE",
+    "URL",
+    "PROJECT",
+];
+
+/// Prints a record of every merge request merged between the `since` and
+/// `until` tags of each project, for change-management audits.
+pub fn export(
+    client: &Client,
+    projects: &[String],
+    target: Option<&str>,
+    since: &str,
+    until: &str,
+    format: Format,
+) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    for project in projects {
+        let merge_requests = release_notes::merged_between(client, project, target, since, until)?;
+        for mut mr in merge_requests {
+            complete(client, project, &mut mr)?;
+            let (kind, jira_id, summary) = match title::parse_merge_request(&mut mr.title.as_str())
+            {
+                Ok(parsed) => (
+                    parsed.kind.to_string(),
+                    parsed.jira_id.to_owned(),
+                    parsed.title.to_owned(),
+                ),
+                Err(_) => (String::new(), String::new(), mr.title.clone()),
+            };
+            let approvers: Vec<_> = mr
+                .approved_by
+                .iter()
+                .flat_map(|users| &users.nodes)
+                .map(|user| user.username.as_str())
+                .collect();
+            rows.push([
+                format!("!{}", mr.iid),
+                mr.merged_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+                kind,
+                jira_id,
+                summary,
+                mr.author.map(|author| author.username).unwrap_or_default(),
+                approvers.join(" "),
+                mr.squash_commit_sha
+                    .or(mr.merge_commit_sha)
+                    .unwrap_or_default(),
+                mr.head_pipeline
+                    .as_ref()
+                    .map(|pipeline| pipeline.number().to_owned())
+                    .unwrap_or_default(),
+                mr.web_url,
+                project.clone(),
+            ]);
+        }
+    }
+    println!("{}", table::render_as(format, HEADERS, &rows));
+    Ok(())
+}
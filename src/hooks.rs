@@ -0,0 +1,209 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::{config, duration};
+
+/// A workflow step that hooks can be attached to, serialized as the JSON
+/// document hook commands receive on stdin.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    BranchCreated {
+        project: &'a str,
+        branch: &'a str,
+        #[serde(rename = "ref")]
+        ref_: &'a str,
+    },
+    MrCreated {
+        project: &'a str,
+        source_branch: &'a str,
+        target_branch: &'a str,
+        title: &'a str,
+    },
+    NotificationSent {
+        project: &'a str,
+        message: &'a str,
+    },
+    CommitPicked {
+        project: &'a str,
+        sha: &'a str,
+        branch: &'a str,
+    },
+    CommitReverted {
+        project: &'a str,
+        sha: &'a str,
+        branch: &'a str,
+    },
+}
+
+/// Every name `Event::name` returns.
+const EVENTS: &[&str] = &[
+    "branch_created",
+    "mr_created",
+    "notification_sent",
+    "commit_picked",
+    "commit_reverted",
+];
+
+impl Event<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::BranchCreated { .. } => "branch_created",
+            Event::MrCreated { .. } => "mr_created",
+            Event::NotificationSent { .. } => "notification_sent",
+            Event::CommitPicked { .. } => "commit_picked",
+            Event::CommitReverted { .. } => "commit_reverted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Runs before the step; a failing pre hook stops the workflow.
+    Pre,
+    /// Runs after the step succeeded; failures are only logged.
+    Post,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    /// The step to run around, e.g. `branch_created` or `mr_created`.
+    #[serde(deserialize_with = "event")]
+    pub event: String,
+    #[serde(default = "default_phase")]
+    pub phase: Phase,
+    /// The program and its arguments; not run through a shell.
+    #[serde(deserialize_with = "command")]
+    pub command: Vec<String>,
+    /// How long the hook may run before it is killed, e.g. `30s`.
+    #[serde(default = "default_timeout", deserialize_with = "timeout")]
+    pub timeout: Duration,
+}
+
+fn event<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    config::checked(deserializer, |event| {
+        if EVENTS.contains(&event) {
+            Ok(())
+        } else {
+            Err(format!(
+                "unknown event {event:?}, expected one of {}",
+                EVENTS.join(", ")
+            ))
+        }
+    })
+}
+
+pub fn command<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let command = Vec::<String>::deserialize(deserializer)?;
+    if command.first().is_none_or(|program| program.is_empty()) {
+        return Err(de::Error::custom("the command needs at least a program"));
+    }
+    Ok(command)
+}
+
+pub fn timeout<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let timeout = String::deserialize(deserializer)?;
+    duration::parse(&timeout).map_err(de::Error::custom)
+}
+
+fn default_phase() -> Phase {
+    Phase::Post
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Hooks(Vec<Hook>);
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    phase: Phase,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+impl Hooks {
+    /// Runs every hook registered for `event` in `phase`, in configuration
+    /// order; none of them may run past `deadline`.
+    pub fn run(
+        &self,
+        phase: Phase,
+        event: &Event,
+        deadline: Option<Instant>,
+    ) -> anyhow::Result<()> {
+        let hooks = self
+            .0
+            .iter()
+            .filter(|hook| hook.phase == phase && hook.event == event.name());
+        for hook in hooks {
+            let result = run_hook(hook, &Payload { phase, event }, deadline);
+            match (phase, result) {
+                (_, Ok(())) => {}
+                (Phase::Pre, Err(err)) => return Err(err),
+                (Phase::Post, Err(err)) => tracing::warn!("{err:#}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn run_hook(hook: &Hook, payload: &Payload, deadline: Option<Instant>) -> anyhow::Result<()> {
+    let Some((program, args)) = hook.command.split_first() else {
+        anyhow::bail!("{} hook has an empty command", hook.event);
+    };
+    let describe = || format!("{:?} {} hook `{program}`", hook.phase, hook.event);
+    tracing::debug!("running {}", describe());
+    let timeout = match deadline {
+        Some(deadline) => hook
+            .timeout
+            .min(deadline.saturating_duration_since(Instant::now())),
+        None => hook.timeout,
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start the {}", describe()))?;
+    // Our stdout is for release notes and `--output json`, so the hook's goes
+    // to the log. The reader is not joined: whatever the hook left running in
+    // the background may keep the pipe open.
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    let hook_name = program.clone();
+    std::thread::spawn(move || {
+        for line in stdout.lines().map_while(Result::ok) {
+            tracing::info!(hook = hook_name, "{line}");
+        }
+    });
+    let input = serde_json::to_vec(payload)?;
+    // A hook that does not read its input closes the pipe early; that is its call.
+    let _ = child.stdin.take().unwrap().write_all(&input);
+
+    let started = Instant::now();
+    let status = loop {
+        let status = child
+            .try_wait()
+            .with_context(|| format!("failed to wait for the {}", describe()))?;
+        if let Some(status) = status {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("killed the {} after {timeout:?}", describe());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    anyhow::ensure!(status.success(), "the {} failed with {status}", describe());
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::sync::RwLock;
+
+use serde::Deserialize;
+
+/// The language of the text we write into merge requests and notifications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    #[default]
+    En,
+    Hu,
+}
+
+/// Everything generated in a locale. `{{ name }}` placeholders are filled
+/// in with [`format`].
+pub struct Messages {
+    pub emergency_intro: &'static str,
+    pub switch_branch: &'static str,
+    pub start_fix: &'static str,
+    pub checklist: &'static str,
+    pub why_heading: &'static str,
+    pub what_heading: &'static str,
+    pub test_heading: &'static str,
+    pub picked_heading: &'static str,
+    pub features: &'static str,
+    pub fixes: &'static str,
+    pub other_changes: &'static str,
+    pub sla_due: &'static str,
+    pub deploy_freeze: &'static str,
+    pub sla_missed: &'static str,
+    pub tokens_expiring: &'static str,
+}
+
+const EN: Messages = Messages {
+    emergency_intro: "## This is an auto-generated emergency patch aimed at PRODUCTION.",
+    switch_branch: "To start working, switch to this branch:",
+    start_fix: "Or start a fix branch off it with:",
+    checklist: "Please fill out the following checklist:",
+    why_heading: "### Why this change is necessary?",
+    what_heading: "### What does this change do?",
+    test_heading: "### How to test this change?",
+    picked_heading: "### Cherry-picked commits",
+    features: "Features",
+    fixes: "Fixes",
+    other_changes: "Other changes",
+    sla_due: "**Due:** merge by {{ due }} ({{ sla }} SLA)",
+    deploy_freeze: "> **Created during a deploy freeze** ({{ freeze }}); merging requires release-manager approval.",
+    sla_missed: "{{ count }} emergency merge request(s) missed the {{ sla }} SLA:",
+    tokens_expiring: "{{ count }} access token(s) expire within {{ days }} days:",
+};
+
+const HU: Messages = Messages {
+    emergency_intro: "## Ez egy automatikusan létrehozott, PRODUCTION-re szánt sürgősségi javítás.",
+    switch_branch: "A munka megkezdéséhez válts erre a branchre:",
+    start_fix: "Vagy indíts róla egy javító branchet:",
+    checklist: "Kérlek, töltsd ki az alábbi ellenőrzőlistát:",
+    why_heading: "### Miért szükséges ez a változtatás?",
+    what_heading: "### Mit csinál ez a változtatás?",
+    test_heading: "### Hogyan tesztelhető ez a változtatás?",
+    picked_heading: "### Átvett (cherry-pick) commitok",
+    features: "Új funkciók",
+    fixes: "Javítások",
+    other_changes: "Egyéb változtatások",
+    sla_due: "**Határidő:** egyesítés eddig: {{ due }} ({{ sla }} SLA)",
+    deploy_freeze: "> **Deploy freeze idején készült** ({{ freeze }}); az egyesítéshez release manager jóváhagyása kell.",
+    sla_missed: "{{ count }} sürgősségi merge request lépte túl a(z) {{ sla }} SLA-t:",
+    tokens_expiring: "{{ count }} hozzáférési token jár le {{ days }} napon belül:",
+};
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::En);
+
+/// Sets the locale for the rest of the run, from the config's `locale`.
+pub fn init(locale: Locale) {
+    *LOCALE.write().unwrap() = locale;
+}
+
+pub fn messages() -> &'static Messages {
+    match *LOCALE.read().unwrap() {
+        Locale::En => &EN,
+        Locale::Hu => &HU,
+    }
+}
+
+/// `message` with each `{{ name }}` replaced by its value in `args`.
+pub fn format(message: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(message.to_owned(), |message, (name, value)| {
+            message.replace(&format!("{{{{ {name} }}}}"), value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_filled() {
+        assert_eq!(
+            format(HU.tokens_expiring, &[("count", "2"), ("days", "7")]),
+            "2 hozzáférési token jár le 7 napon belül:"
+        );
+    }
+
+    #[test]
+    fn every_locale_has_the_same_placeholders() {
+        let placeholders = |messages: &Messages| {
+            [
+                messages.sla_due,
+                messages.sla_missed,
+                messages.tokens_expiring,
+            ]
+            .map(|message| message.matches("{{").count())
+        };
+        assert_eq!(placeholders(&EN), placeholders(&HU));
+    }
+}
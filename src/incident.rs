@@ -0,0 +1,115 @@
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::oncall::{api_key, opsgenie_url, pagerduty_url};
+
+/// The `[incident]` section: where emergency patches are recorded.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case", deny_unknown_fields)]
+pub enum IncidentConfig {
+    Pagerduty {
+        /// The service new incidents are opened on.
+        service: Option<String>,
+        /// The email of the PagerDuty user the changes are made as.
+        from: String,
+        #[serde(default = "pagerduty_url")]
+        api_url: String,
+    },
+    Opsgenie {
+        #[serde(default = "opsgenie_url")]
+        api_url: String,
+    },
+}
+
+/// What an emergency patch does with the incident it remedies.
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub config: IncidentConfig,
+    /// The incident to add a note to; a new one is opened if unset.
+    pub id: Option<String>,
+}
+
+fn send(request: reqwest::blocking::RequestBuilder, what: &str) -> anyhow::Result<()> {
+    request
+        .send()
+        .and_then(|rsp| rsp.error_for_status())
+        .with_context(|| format!("failed to {what}"))?;
+    Ok(())
+}
+
+/// Opens an incident titled `title` with `note` as its details, or adds
+/// `note` to the incident of `update`.
+pub fn record(client: &Client, update: &Update, title: &str, note: &str) -> anyhow::Result<()> {
+    if client.is_replaying() {
+        tracing::info!("replay: not recording {title:?} in the incident");
+        return Ok(());
+    }
+    match &update.config {
+        IncidentConfig::Pagerduty {
+            service,
+            from,
+            api_url,
+        } => {
+            let key = api_key("PAGERDUTY_API_TOKEN")?;
+            let (url, body, what) = match &update.id {
+                Some(id) => (
+                    format!("{api_url}/incidents/{id}/notes"),
+                    serde_json::json!({ "note": { "content": note } }),
+                    format!("add a note to PagerDuty incident {id}"),
+                ),
+                None => {
+                    let Some(service) = service else {
+                        anyhow::bail!("set [incident] service to open PagerDuty incidents");
+                    };
+                    let body = serde_json::json!({ "incident": {
+                        "type": "incident",
+                        "title": title,
+                        "service": { "id": service, "type": "service_reference" },
+                        "body": { "type": "incident_body", "details": note },
+                    } });
+                    (
+                        format!("{api_url}/incidents"),
+                        body,
+                        "open a PagerDuty incident".to_owned(),
+                    )
+                }
+            };
+            let request = client
+                .external(http::Method::POST, &url)?
+                .header("Authorization", format!("Token token={key}"))
+                .header("From", from)
+                .header("Accept", "application/vnd.pagerduty+json;version=2")
+                .json(&body);
+            send(request, &what)
+        }
+        IncidentConfig::Opsgenie { api_url } => {
+            let key = api_key("OPSGENIE_API_KEY")?;
+            let (request, what) = match &update.id {
+                Some(id) => (
+                    client
+                        .external(
+                            http::Method::POST,
+                            &format!("{api_url}/v1/incidents/{id}/notes"),
+                        )?
+                        .query(&[("identifierType", "id")])
+                        .json(&serde_json::json!({ "note": note })),
+                    format!("add a note to Opsgenie incident {id}"),
+                ),
+                None => (
+                    client
+                        .external(
+                            http::Method::POST,
+                            &format!("{api_url}/v1/incidents/create"),
+                        )?
+                        .json(&serde_json::json!({ "message": title, "description": note })),
+                    "open an Opsgenie incident".to_owned(),
+                ),
+            };
+            send(
+                request.header("Authorization", format!("GenieKey {key}")),
+                &what,
+            )
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::{Args, ValueEnum};
+use gitlab::api::issues::IssueType;
+use gitlab::api::projects::issues::CreateIssue;
+use gitlab::api::Query;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::journal::{Journal, Resource};
+use crate::origin;
+use crate::template::{self, Vars};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "UPPER")]
+pub enum Severity {
+    S1,
+    S2,
+    S3,
+    S4,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::S1 => "S1",
+            Severity::S2 => "S2",
+            Severity::S3 => "S3",
+            Severity::S4 => "S4",
+        }
+    }
+}
+
+const DEFAULT_DESCRIPTION: &str =
+    "**Severity:** {{ severity }}\n\n{{ summary }}\n\n### Merge requests\n\n{{ merge_requests }}";
+
+#[derive(Debug, Clone, Args)]
+pub struct IncidentIssue {
+    #[arg(long, value_enum)]
+    pub severity: Severity,
+    /// What is broken, for the title and description.
+    #[arg(long)]
+    pub summary: String,
+    /// A merge request that remedies the incident; repeat for several.
+    #[arg(long = "mr", value_name = "IID")]
+    pub mrs: Vec<u64>,
+    /// Also link the merge requests a journaled run, such as an emergency patch, opened.
+    #[arg(long, value_name = "JOURNAL")]
+    pub from_journal: Option<PathBuf>,
+    /// A template for the description, with `{{ severity }}`, `{{ summary }}` and `{{ merge_requests }}`.
+    #[arg(long, value_name = "PATH")]
+    pub template: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    iid: u64,
+    web_url: String,
+}
+
+/// Opens an incident issue in `project` labelled with its severity that
+/// mentions the merge requests fixing it, so GitLab links them both ways,
+/// and returns its IID.
+pub fn create(client: &Client, project: &str, incident: &IncidentIssue) -> anyhow::Result<u64> {
+    let mut mrs = incident.mrs.clone();
+    if let Some(path) = &incident.from_journal {
+        mrs.extend(
+            Journal::open(path)?
+                .entries()
+                .into_iter()
+                .filter(|entry| entry.project == project)
+                .filter_map(|entry| match entry.created {
+                    Some(Resource::MergeRequest { iid, .. }) => Some(iid),
+                    _ => None,
+                }),
+        );
+    }
+    mrs.sort_unstable();
+    mrs.dedup();
+    let merge_requests = if mrs.is_empty() {
+        "None yet.".to_owned()
+    } else {
+        mrs.iter()
+            .map(|iid| format!("- !{iid}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let vars = Vars::from([
+        ("severity".to_owned(), incident.severity.name().to_owned()),
+        ("summary".to_owned(), incident.summary.clone()),
+        ("merge_requests".to_owned(), merge_requests),
+    ]);
+    let description = match &incident.template {
+        Some(path) => template::render(&template::load(path)?, &vars)
+            .with_context(|| format!("{} does not render", path.display()))?,
+        None => template::render(DEFAULT_DESCRIPTION, &vars)?,
+    };
+
+    let severity = format!("severity::{}", incident.severity.name());
+    let issue: Issue = CreateIssue::builder()
+        .project(project)
+        .title(format!(
+            "[{}] {}",
+            incident.severity.name(),
+            incident.summary
+        ))
+        .description(origin::sign(&description))
+        .issue_type(IssueType::Incident)
+        .labels(["incident", severity.as_str()])
+        .build()?
+        .query(client)?;
+    tracing::info!(project, "opened incident {}", issue.web_url);
+    Ok(issue.iid)
+}
@@ -0,0 +1,142 @@
+use chrono::NaiveDate;
+use clap::Args;
+use gitlab::api::projects::issues::notes::CreateIssueNote;
+use gitlab::api::projects::merge_requests::IssuesClosedBy;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::endpoints::CurrentIterations;
+use crate::workflow::Context;
+
+#[derive(Debug, Clone, Args)]
+pub struct AssignIteration {
+    #[arg(long = "mr", env = "CI_MERGE_REQUEST_IID")]
+    pub iid: u64,
+    /// The ID of the iteration to use when several cadences have one running.
+    #[arg(long)]
+    pub iteration: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Iteration {
+    id: u64,
+    #[serde(default)]
+    title: Option<String>,
+    start_date: NaiveDate,
+    due_date: NaiveDate,
+}
+
+impl Iteration {
+    fn describe(&self) -> String {
+        let dates = format!("{} - {}", self.start_date, self.due_date);
+        match &self.title {
+            Some(title) => format!("{title} ({dates})"),
+            None => dates,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueIteration {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    iid: u64,
+    project_id: u64,
+    #[serde(default)]
+    iteration: Option<IssueIteration>,
+}
+
+/// The iteration of the group's cadence that is running today.
+fn current(client: &Client, group: &str, id: Option<u64>) -> anyhow::Result<Iteration> {
+    let endpoint = CurrentIterations {
+        group: group.into(),
+    };
+    let mut iterations: Vec<Iteration> =
+        api::paged(endpoint, api::Pagination::All).query(client)?;
+    if let Some(id) = id {
+        let position = iterations.iter().position(|iteration| iteration.id == id);
+        return match position {
+            Some(position) => Ok(iterations.swap_remove(position)),
+            None => anyhow::bail!("iteration {id} of {group} is not running"),
+        };
+    }
+    match iterations.len() {
+        0 => anyhow::bail!("{group} has no iteration running; is an iteration cadence set up?"),
+        1 => Ok(iterations.remove(0)),
+        _ => {
+            let running: Vec<_> = iterations
+                .iter()
+                .map(|iteration| format!("{} ({})", iteration.id, iteration.describe()))
+                .collect();
+            anyhow::bail!(
+                "several iterations of {group} are running: {}; pick one with --iteration",
+                running.join(", ")
+            )
+        }
+    }
+}
+
+/// Puts the issues merge request `assign.iid` closes into the running
+/// iteration. Merge requests themselves cannot be in one; boards show them
+/// through their issues.
+pub fn assign(
+    ctx: &Context,
+    group: &str,
+    project: &str,
+    assign: &AssignIteration,
+) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let iteration = current(client, group, assign.iteration)?;
+    let endpoint = IssuesClosedBy::builder()
+        .project(project)
+        .merge_request(assign.iid)
+        .build()?;
+    let issues: Vec<Issue> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    if issues.is_empty() {
+        return Ok(format!(
+            "!{} closes no issues to put in an iteration",
+            assign.iid
+        ));
+    }
+    let issues: Vec<_> = issues
+        .into_iter()
+        .filter(|issue| {
+            issue
+                .iteration
+                .as_ref()
+                .is_none_or(|assigned| assigned.id != iteration.id)
+        })
+        .collect();
+    if issues.is_empty() {
+        return Ok(format!(
+            "the issues of !{} are already in {}",
+            assign.iid,
+            iteration.describe()
+        ));
+    }
+
+    let plan: Vec<_> = issues
+        .iter()
+        .map(|issue| format!("put #{} in {}", issue.iid, iteration.describe()))
+        .collect();
+    ctx.confirm(project, &plan)?;
+    // The REST API has no field for it, so the quick action does it.
+    for issue in &issues {
+        let endpoint = CreateIssueNote::builder()
+            .project(issue.project_id)
+            .issue(issue.iid)
+            .body(format!("/iteration *iteration:{}", iteration.id))
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    Ok(format!(
+        "put {} issue(s) of !{} in {}",
+        issues.len(),
+        assign.iid,
+        iteration.describe()
+    ))
+}
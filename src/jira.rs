@@ -0,0 +1,307 @@
+use std::collections::BTreeSet;
+
+use anyhow::Context as _;
+use clap::Args;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::oncall::api_key;
+use crate::release_notes;
+use crate::table::{self, Format};
+use crate::title;
+
+/// The `[jira]` section: where the tickets of merge requests are tracked.
+/// Cloud sites authenticate with `JIRA_USER` and `JIRA_API_TOKEN`, Data
+/// Center with a personal access token in `JIRA_API_TOKEN` alone.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JiraConfig {
+    /// The site, e.g. `https://example.atlassian.net`.
+    pub url: String,
+    /// The key of the Jira project the versions belong to, e.g. `PROJ`;
+    /// versions of every project of that name are looked at if unset.
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct Report {
+    /// The Jira fix version being released, e.g. `1.4.0`.
+    #[arg(long)]
+    pub version: String,
+    /// The previous release tag.
+    #[arg(long)]
+    pub from: String,
+    /// The tag being released; `v<VERSION>` if unset.
+    #[arg(long)]
+    pub to: Option<String>,
+    /// The branch the release was cut from; the default branch if unset.
+    #[arg(long)]
+    pub target_branch: Option<String>,
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    key: String,
+    fields: Fields,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Fields {
+    status: Status,
+    #[serde(default)]
+    fix_versions: Vec<FixVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    name: String,
+    status_category: StatusCategory,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusCategory {
+    key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixVersion {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Search {
+    issues: Vec<Issue>,
+    total: usize,
+}
+
+impl Issue {
+    fn is_done(&self) -> bool {
+        self.fields.status.status_category.key == "done"
+    }
+
+    fn fix_versions(&self) -> String {
+        self.fields
+            .fix_versions
+            .iter()
+            .map(|version| version.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// What is wrong with the ticket of a merge request that shipped in `version`.
+    fn problem(&self, version: &str) -> Option<String> {
+        let mut problems = Vec::new();
+        if !self.is_done() {
+            problems.push(format!("shipped but {}", self.fields.status.name));
+        }
+        if !self
+            .fields
+            .fix_versions
+            .iter()
+            .any(|fix| fix.name == version)
+        {
+            problems.push(format!("shipped but its fix version is not {version}"));
+        }
+        (!problems.is_empty()).then(|| problems.join("; "))
+    }
+}
+
+fn request(
+    client: &Client,
+    url: &str,
+    query: &[(&str, &str)],
+) -> anyhow::Result<reqwest::blocking::RequestBuilder> {
+    let token = api_key("JIRA_API_TOKEN")?;
+    let request = client.external(http::Method::GET, url)?.query(query);
+    Ok(match std::env::var("JIRA_USER") {
+        Ok(user) => request.basic_auth(user, Some(token)),
+        Err(_) => request.bearer_auth(token),
+    })
+}
+
+fn issue(client: &Client, config: &JiraConfig, key: &str) -> anyhow::Result<Option<Issue>> {
+    let url = format!(
+        "{}/rest/api/2/issue/{key}",
+        config.url.trim_end_matches('/')
+    );
+    let what = || format!("failed to look up {key} in Jira");
+    let rsp = request(client, &url, &[("fields", "status,fixVersions")])?
+        .send()
+        .with_context(what)?;
+    if rsp.status() == http::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let issue = rsp
+        .error_for_status()
+        .and_then(|rsp| rsp.json())
+        .with_context(what)?;
+    Ok(Some(issue))
+}
+
+/// The tickets of `version` that Jira says are done.
+fn done(client: &Client, config: &JiraConfig, version: &str) -> anyhow::Result<Vec<Issue>> {
+    let mut jql = format!("fixVersion = \"{version}\" AND statusCategory = Done");
+    if let Some(project) = &config.project {
+        jql = format!("project = \"{project}\" AND {jql}");
+    }
+    let url = format!("{}/rest/api/2/search", config.url.trim_end_matches('/'));
+    let mut issues = Vec::new();
+    loop {
+        let start = issues.len().to_string();
+        let page: Search = request(
+            client,
+            &url,
+            &[
+                ("jql", jql.as_str()),
+                ("fields", "status,fixVersions"),
+                ("startAt", start.as_str()),
+                ("maxResults", "100"),
+            ],
+        )?
+        .send()
+        .and_then(|rsp| rsp.error_for_status())
+        .and_then(|rsp| rsp.json())
+        .with_context(|| format!("failed to search Jira for the tickets of {version}"))?;
+        let last = page.issues.is_empty();
+        issues.extend(page.issues);
+        if last || issues.len() >= page.total {
+            return Ok(issues);
+        }
+    }
+}
+
+/// Prints the Jira tickets of the merge requests merged between `from` and
+/// the tag of `version`, and of the tickets Jira says are done in `version`,
+/// and fails if their status or fix version disagrees with what shipped.
+pub fn report(
+    client: &Client,
+    config: &JiraConfig,
+    project: &str,
+    report: &Report,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !client.is_replaying(),
+        "jira-report asks Jira, which --replay cannot stand in for"
+    );
+    let version = report.version.as_str();
+    let to = report.to.clone().unwrap_or_else(|| format!("v{version}"));
+    let merge_requests = release_notes::merged_between(
+        client,
+        project,
+        report.target_branch.as_deref(),
+        &report.from,
+        &to,
+    )?;
+
+    let mut rows = Vec::new();
+    let mut shipped = BTreeSet::new();
+    let mut problems = 0;
+    for mr in &merge_requests {
+        let mr_ref = format!("!{}", mr.iid);
+        let Ok(parsed) = title::parse_merge_request(&mut mr.title.as_str()) else {
+            // Shown, but without a ticket there is nothing to disagree with.
+            rows.push([
+                "-".to_owned(),
+                mr_ref,
+                "-".to_owned(),
+                "-".to_owned(),
+                "no Jira ID in its title".to_owned(),
+            ]);
+            continue;
+        };
+        let key = parsed.jira_id.to_ascii_uppercase();
+        if !shipped.insert(key.clone()) {
+            // Several merge requests of one ticket are reported once.
+            continue;
+        }
+        let Some(issue) = issue(client, config, &key)? else {
+            problems += 1;
+            rows.push([
+                key,
+                mr_ref,
+                "-".to_owned(),
+                "-".to_owned(),
+                "not found in Jira".to_owned(),
+            ]);
+            continue;
+        };
+        let problem = issue.problem(version);
+        problems += usize::from(problem.is_some());
+        rows.push([
+            issue.key.clone(),
+            mr_ref,
+            issue.fields.status.name.clone(),
+            issue.fix_versions(),
+            problem.unwrap_or_else(|| "-".to_owned()),
+        ]);
+    }
+    for issue in done(client, config, version)? {
+        if shipped.contains(&issue.key) {
+            continue;
+        }
+        problems += 1;
+        rows.push([
+            issue.key.clone(),
+            "-".to_owned(),
+            issue.fields.status.name.clone(),
+            issue.fix_versions(),
+            format!("done in {version} but not in the release"),
+        ]);
+    }
+
+    println!(
+        "{}",
+        table::render_as(
+            report.format,
+            ["ISSUE", "MR", "STATUS", "FIX VERSIONS", "PROBLEM"],
+            &rows
+        )
+    );
+    anyhow::ensure!(
+        problems == 0,
+        "{problems} ticket(s) of {version} disagree with what {to} ships"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(status: &str, category: &str, fix_versions: &[&str]) -> Issue {
+        Issue {
+            key: "PROJ-1".to_owned(),
+            fields: Fields {
+                status: Status {
+                    name: status.to_owned(),
+                    status_category: StatusCategory {
+                        key: category.to_owned(),
+                    },
+                },
+                fix_versions: fix_versions
+                    .iter()
+                    .map(|name| FixVersion {
+                        name: (*name).to_owned(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn shipped_tickets_must_be_done_in_the_version() {
+        assert_eq!(ticket("Done", "done", &["1.4.0"]).problem("1.4.0"), None);
+        assert_eq!(
+            ticket("In Progress", "indeterminate", &["1.4.0"]).problem("1.4.0"),
+            Some("shipped but In Progress".to_owned())
+        );
+        assert_eq!(
+            ticket("Closed", "done", &["1.3.0"]).problem("1.4.0"),
+            Some("shipped but its fix version is not 1.4.0".to_owned())
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use gitlab::api::projects::jobs::{JobScope, JobTrace, Jobs};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::progress::Progress;
+use crate::table::{self, Format};
+
+#[derive(Debug, Deserialize)]
+struct Job {
+    id: u64,
+    name: String,
+    status: String,
+    started_at: Option<DateTime<Utc>>,
+    duration: Option<f64>,
+    #[serde(default)]
+    artifacts: Vec<Artifact>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    #[serde(default)]
+    size: u64,
+}
+
+/// How long each collapsible section of a job trace took, in seconds, e.g.
+/// `restore_cache` or `archive_cache`.
+fn sections(trace: &str) -> HashMap<String, i64> {
+    let mut started = HashMap::new();
+    let mut took = HashMap::new();
+    for marker in trace.split(['\r', '\n', '\x1b']) {
+        let marker = marker.trim_start_matches("[0K");
+        let Some((kind, rest)) = marker.split_once(':') else {
+            continue;
+        };
+        let Some((time, name)) = rest.split_once(':') else {
+            continue;
+        };
+        let Ok(time) = time.parse::<i64>() else {
+            continue;
+        };
+        // Options such as `[collapsed=true]` follow the name.
+        let name = name.split('[').next().unwrap_or(name).to_owned();
+        match kind {
+            "section_start" => {
+                started.insert(name, time);
+            }
+            "section_end" => {
+                if let Some(start) = started.remove(&name) {
+                    *took.entry(name).or_default() += time - start;
+                }
+            }
+            _ => {}
+        }
+    }
+    took
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// `older -> newer`, comparing the older and the newer half of `values`,
+/// which are newest first.
+fn trend(values: &[f64]) -> Option<String> {
+    let (newer, older) = values.split_at(values.len() / 2);
+    let (older, newer) = (mean(older)?, mean(newer)?);
+    let change = if older > 0.0 {
+        format!(" ({:+.0}%)", (newer - older) / older * 100.0)
+    } else {
+        String::new()
+    };
+    Some(format!("{older:.0}s -> {newer:.0}s{change}"))
+}
+
+fn seconds(value: Option<i64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Prints the duration, artifact size and cache times of the last `last`
+/// finished runs of `job`, then how they trend, so a caching change can be
+/// judged by numbers.
+pub fn run(
+    client: &Client,
+    projects: &[String],
+    job: &str,
+    last: usize,
+    format: Format,
+) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    for project in projects {
+        let endpoint = Jobs::builder()
+            .project(project.as_str())
+            .scopes([JobScope::Success, JobScope::Failed].into_iter())
+            .build()?;
+        let paged = api::paged(endpoint, api::Pagination::All);
+        let mut jobs = Vec::new();
+        let scanning = Progress::spinner(&format!("looking for runs of {job} in {project}"));
+        for (scanned, found) in paged.iter(client).enumerate() {
+            let found: Job = found?;
+            if found.name == job {
+                jobs.push(found);
+                if jobs.len() == last {
+                    break;
+                }
+            }
+            scanning.set_message(format!("{} found in {} jobs", jobs.len(), scanned + 1));
+        }
+        drop(scanning);
+        let (mut durations, mut restores, mut archives) = (Vec::new(), Vec::new(), Vec::new());
+        let reading = Progress::bar("job logs", jobs.len());
+        for found in &jobs {
+            reading.inc();
+            let endpoint = JobTrace::builder()
+                .project(project.as_str())
+                .job(found.id)
+                .build()?;
+            let trace = api::raw(endpoint).query(client)?;
+            let sections = sections(&String::from_utf8_lossy(&trace));
+            let restore = sections.get("restore_cache").copied();
+            let archive = sections
+                .iter()
+                .filter(|(name, _)| name.starts_with("archive_cache"))
+                .map(|(_, took)| *took)
+                .reduce(|a, b| a + b);
+            durations.extend(found.duration);
+            restores.extend(restore.map(|took| took as f64));
+            archives.extend(archive.map(|took| took as f64));
+            let size: u64 = found.artifacts.iter().map(|artifact| artifact.size).sum();
+            rows.push([
+                project.clone(),
+                found.id.to_string(),
+                found
+                    .started_at
+                    .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+                found.status.clone(),
+                found
+                    .duration
+                    .map(|duration| format!("{duration:.0}"))
+                    .unwrap_or_default(),
+                (size / 1024).to_string(),
+                seconds(restore),
+                seconds(archive),
+            ]);
+        }
+        let trends = [
+            ("duration", trend(&durations)),
+            ("cache restore", trend(&restores)),
+            ("cache archive", trend(&archives)),
+        ];
+        for (what, trend) in trends {
+            if let Some(trend) = trend {
+                tracing::info!(project, "{job} {what}: {trend} over {} runs", jobs.len());
+            }
+        }
+    }
+    println!(
+        "{}",
+        table::render_as(
+            format,
+            [
+                "PROJECT",
+                "JOB",
+                "STARTED",
+                "STATUS",
+                "DURATION (S)",
+                "ARTIFACTS (KB)",
+                "CACHE RESTORE (S)",
+                "CACHE ARCHIVE (S)",
+            ],
+            &rows
+        )
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_the_sections_of_a_trace() {
+        let trace = "\x1b[0Ksection_start:1714557600:restore_cache[collapsed=true]\r\x1b[0KRestoring cache\n\
+            Successfully extracted cache\n\
+            \x1b[0Ksection_end:1714557642:restore_cache\r\x1b[0K\n\
+            \x1b[0Ksection_start:1714557700:archive_cache\r\x1b[0KSaving cache\n\
+            \x1b[0Ksection_end:1714557709:archive_cache\r\x1b[0K\n";
+
+        let sections = sections(trace);
+
+        assert_eq!(sections["restore_cache"], 42);
+        assert_eq!(sections["archive_cache"], 9);
+    }
+
+    #[test]
+    fn compares_the_older_half_with_the_newer_one() {
+        assert_eq!(
+            trend(&[50.0, 50.0, 100.0, 100.0]).as_deref(),
+            Some("100s -> 50s (-50%)")
+        );
+        assert_eq!(trend(&[]), None);
+    }
+}
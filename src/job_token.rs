@@ -0,0 +1,96 @@
+use gitlab::api::{self, projects, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::endpoints::{AllowJobToken, DisallowJobToken, JobTokenAllowlist};
+use crate::table;
+use crate::workflow::Context;
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    id: u64,
+    path_with_namespace: String,
+}
+
+fn project(client: &Client, project: &str) -> anyhow::Result<Project> {
+    Ok(projects::Project::builder()
+        .project(project)
+        .build()?
+        .query(client)?)
+}
+
+fn allowlist(client: &Client, project: &str) -> anyhow::Result<Vec<Project>> {
+    let endpoint = JobTokenAllowlist {
+        project: project.into(),
+    };
+    Ok(api::paged(endpoint, api::Pagination::All).query(client)?)
+}
+
+/// Lets the `CI_JOB_TOKEN` of jobs in `targets` access `project`. Projects
+/// already on its allowlist are skipped.
+pub fn add(ctx: &Context, project: &str, targets: &[String]) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let current = allowlist(client, project)?;
+    let mut missing = Vec::new();
+    for target in targets {
+        let target = self::project(client, target)?;
+        if !current.iter().any(|allowed| allowed.id == target.id) {
+            missing.push(target);
+        }
+    }
+    if missing.is_empty() {
+        return Ok("already allowed".to_owned());
+    }
+    let plan: Vec<_> = missing
+        .iter()
+        .map(|target| format!("allow job tokens of {}", target.path_with_namespace))
+        .collect();
+    ctx.confirm(project, &plan)?;
+    for target in &missing {
+        api::ignore(AllowJobToken {
+            project: project.into(),
+            target_project_id: target.id,
+        })
+        .query(client)?;
+    }
+    Ok(format!("allowed {} project(s)", missing.len()))
+}
+
+/// Takes `targets` off the job token allowlist of `project`.
+pub fn remove(ctx: &Context, project: &str, targets: &[String]) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let current = allowlist(client, project)?;
+    let mut allowed = Vec::new();
+    for target in targets {
+        let target = self::project(client, target)?;
+        if current.iter().any(|allowed| allowed.id == target.id) {
+            allowed.push(target);
+        }
+    }
+    if allowed.is_empty() {
+        return Ok("none of them are allowed".to_owned());
+    }
+    let plan: Vec<_> = allowed
+        .iter()
+        .map(|target| format!("disallow job tokens of {}", target.path_with_namespace))
+        .collect();
+    ctx.confirm(project, &plan)?;
+    for target in &allowed {
+        api::ignore(DisallowJobToken {
+            project: project.into(),
+            target_project_id: target.id,
+        })
+        .query(client)?;
+    }
+    Ok(format!("disallowed {} project(s)", allowed.len()))
+}
+
+/// Prints the projects whose job tokens may access `project`.
+pub fn list(client: &Client, project: &str) -> anyhow::Result<()> {
+    let rows: Vec<_> = allowlist(client, project)?
+        .into_iter()
+        .map(|allowed| [allowed.id.to_string(), allowed.path_with_namespace])
+        .collect();
+    println!("{}", table::render(["ID", "PROJECT"], &rows));
+    Ok(())
+}
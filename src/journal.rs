@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::redact;
+use crate::template::Vars;
+
+/// Something a step created on GitLab.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Resource {
+    Branch {
+        name: String,
+    },
+    MergeRequest {
+        iid: u64,
+        web_url: String,
+    },
+    Pipeline {
+        id: u64,
+        web_url: String,
+    },
+    Commit {
+        sha: String,
+    },
+    /// A feature flag turned on or off, in `environment` if set.
+    FeatureFlag {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        environment: Option<String>,
+        active: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub project: String,
+    /// What was done, e.g. `create branch release/1.2.4`.
+    pub step: String,
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<Resource>,
+    /// Variables known after the step, restored when resuming.
+    #[serde(default, skip_serializing_if = "Vars::is_empty")]
+    pub vars: Vars,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    command: String,
+    entries: Vec<Entry>,
+}
+
+/// The steps a command completed, rewritten to disk after every one of them
+/// so that a failed run can be resumed and audited.
+#[derive(Debug, Default)]
+pub struct Journal {
+    path: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+impl Journal {
+    /// A journal that is kept in memory only.
+    pub fn disabled() -> Self {
+        Journal::default()
+    }
+
+    /// Starts a new journal at `path`; an existing one is kept, since it may
+    /// be the only record of what a failed run left behind.
+    pub fn create(path: &Path, command: &str) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !path.exists(),
+            "the journal {0} already exists; continue it with --resume {0}, undo it with \
+             `rollback {0}` or pass another --journal path",
+            path.display()
+        );
+        let journal = Journal {
+            path: Some(path.to_path_buf()),
+            state: Mutex::new(State {
+                command: command.to_owned(),
+                entries: Vec::new(),
+            }),
+        };
+        journal.save(&journal.state.lock().unwrap())?;
+        Ok(journal)
+    }
+
+    /// Opens an existing journal to append to.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let state: State = std::fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| Ok(serde_json::from_slice(&contents)?))
+            .with_context(|| format!("failed to read the journal {}", path.display()))?;
+        Ok(Journal {
+            path: Some(path.to_path_buf()),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Continues the journal at `path`, which must have been written by `command`.
+    pub fn resume(path: &Path, command: &str) -> anyhow::Result<Self> {
+        let journal = Journal::open(path)?;
+        let state = journal.state.lock().unwrap();
+        anyhow::ensure!(
+            state.command == command,
+            "{} is a journal of `{}`, not `{command}`",
+            path.display(),
+            state.command
+        );
+        tracing::info!(
+            journal = %path.display(),
+            "resuming after {} recorded steps",
+            state.entries.len()
+        );
+        drop(state);
+        Ok(journal)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn entries(&self) -> Vec<Entry> {
+        self.state.lock().unwrap().entries.clone()
+    }
+
+    pub fn find(&self, project: &str, step: &str) -> Option<Entry> {
+        let state = self.state.lock().unwrap();
+        state
+            .entries
+            .iter()
+            .find(|entry| entry.project == project && entry.step == step)
+            .cloned()
+    }
+
+    pub fn record(
+        &self,
+        project: &str,
+        step: &str,
+        created: Option<Resource>,
+        vars: Vars,
+    ) -> anyhow::Result<Entry> {
+        let entry = Entry {
+            project: project.to_owned(),
+            step: step.to_owned(),
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            created,
+            vars,
+        };
+        let mut state = self.state.lock().unwrap();
+        state.entries.push(entry.clone());
+        self.save(&state)?;
+        Ok(entry)
+    }
+
+    fn save(&self, state: &State) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Write next to the journal and rename so a crash never leaves half a file.
+        let tmp = path.with_extension("json.tmp");
+        let contents = serde_json::to_string_pretty(state)?;
+        std::fs::write(&tmp, redact::redact(&contents).as_bytes())
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .with_context(|| format!("failed to write the journal {}", path.display()))
+    }
+}
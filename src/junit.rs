@@ -0,0 +1,81 @@
+use crate::checks::Case;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Not even escaped are these allowed in XML 1.0.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `cases` as a JUnit report, for the pipeline's Test tab.
+pub fn render(suite: &str, cases: &[Case]) -> String {
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n  <testsuite name=\"{0}\" tests=\"{1}\" failures=\"{failures}\">\n",
+        escape(suite),
+        cases.len()
+    );
+    for case in cases {
+        let mut attributes = format!(
+            "classname=\"{}\" name=\"{}\"",
+            escape(suite),
+            escape(&case.name)
+        );
+        if let Some(location) = &case.location {
+            attributes.push_str(&format!(" file=\"{}\"", escape(&location.path)));
+        }
+        match &case.failure {
+            None => xml.push_str(&format!("    <testcase {attributes}/>\n")),
+            Some(failure) => {
+                let message = failure.lines().next().unwrap_or_default();
+                xml.push_str(&format!(
+                    "    <testcase {attributes}>\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                    escape(message),
+                    escape(failure)
+                ));
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_are_counted_and_escaped() {
+        let xml = render(
+            "check-mr",
+            &[
+                Case::passed("title"),
+                Case::failed(
+                    "migrations",
+                    "needs the ~db label\nand <a changelog> & more",
+                ),
+            ],
+        );
+
+        assert!(xml.contains("<testsuite name=\"check-mr\" tests=\"2\" failures=\"1\">"));
+        assert!(xml.contains("<testcase classname=\"check-mr\" name=\"title\"/>"));
+        assert!(xml.contains(
+            "<failure message=\"needs the ~db label\">needs the ~db label\nand &lt;a changelog&gt; &amp; more</failure>"
+        ));
+    }
+
+    #[test]
+    fn control_characters_are_dropped() {
+        assert_eq!(escape("a\u{1b}[31mb\"c"), "a[31mb&quot;c");
+    }
+}
@@ -0,0 +1,242 @@
+use gitlab::api::projects::labels;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::endpoints::EditLabel;
+use crate::workflow::Context;
+
+/// A label every project should have. The description and priority are left
+/// alone when unset.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Label {
+    pub name: String,
+    /// A `#rrggbb` color or one of the CSS color names.
+    pub color: String,
+    pub description: Option<String>,
+    /// Lower numbers sort first.
+    pub priority: Option<u64>,
+    /// Older names of the label; a label found under one of them is renamed,
+    /// keeping it on the issues and merge requests it is on.
+    #[serde(default)]
+    pub renamed_from: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentLabel {
+    id: u64,
+    name: String,
+    color: String,
+    description: Option<String>,
+    priority: Option<u64>,
+    /// False for labels inherited from a group, which only the group can change.
+    #[serde(default = "yes")]
+    is_project_label: bool,
+}
+
+fn yes() -> bool {
+    true
+}
+
+impl CurrentLabel {
+    /// How this label differs from `desired`, apart from its name.
+    fn differences(&self, desired: &Label) -> Vec<String> {
+        let mut differences = Vec::new();
+        if !self.color.eq_ignore_ascii_case(&desired.color) {
+            differences.push(format!("color {} -> {}", self.color, desired.color));
+        }
+        if let Some(description) = &desired.description {
+            if self.description.as_deref().unwrap_or_default() != description {
+                differences.push("description".to_owned());
+            }
+        }
+        if let Some(priority) = desired.priority {
+            if self.priority != Some(priority) {
+                differences.push(format!("priority {priority}"));
+            }
+        }
+        differences
+    }
+}
+
+enum Change<'a> {
+    Create(&'a Label),
+    Edit {
+        desired: &'a Label,
+        current: &'a CurrentLabel,
+        step: String,
+    },
+}
+
+fn changes<'a>(project: &str, current: &'a [CurrentLabel], labels: &'a [Label]) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    for desired in labels {
+        let existing = current
+            .iter()
+            .find(|current| current.name == desired.name)
+            .or_else(|| {
+                current.iter().find(|current| {
+                    current.is_project_label && desired.renamed_from.contains(&current.name)
+                })
+            });
+        let Some(existing) = existing else {
+            changes.push(Change::Create(desired));
+            continue;
+        };
+        let mut differences = existing.differences(desired);
+        if existing.name != desired.name {
+            differences.insert(0, format!("rename {} -> {}", existing.name, desired.name));
+        }
+        if differences.is_empty() {
+            continue;
+        }
+        if !existing.is_project_label {
+            tracing::warn!(
+                project,
+                "label {} is inherited from a group; change it there ({})",
+                desired.name,
+                differences.join(", ")
+            );
+            continue;
+        }
+        changes.push(Change::Edit {
+            desired,
+            current: existing,
+            step: format!("update label {}: {}", desired.name, differences.join(", ")),
+        });
+    }
+    changes
+}
+
+/// Creates, renames and recolors the labels of `project` to match `labels`.
+/// Labels that are not listed are left alone.
+pub fn sync(ctx: &Context, project: &str, labels: &[Label]) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let endpoint = labels::Labels::builder()
+        .project(project)
+        .include_ancestor_groups(true)
+        .build()?;
+    let current: Vec<CurrentLabel> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    let changes = changes(project, &current, labels);
+    if changes.is_empty() {
+        return Ok("labels in sync".to_owned());
+    }
+    let plan: Vec<_> = changes
+        .iter()
+        .map(|change| match change {
+            Change::Create(label) => format!("create label {} ({})", label.name, label.color),
+            Change::Edit { step, .. } => step.clone(),
+        })
+        .collect();
+    ctx.confirm(project, &plan)?;
+    for change in &changes {
+        match change {
+            Change::Create(label) => {
+                let mut create = labels::CreateLabel::builder();
+                create
+                    .project(project)
+                    .name(label.name.as_str())
+                    .color(label.color.as_str());
+                if let Some(description) = &label.description {
+                    create.description(description.as_str());
+                }
+                if let Some(priority) = label.priority {
+                    create.priority(priority);
+                }
+                api::ignore(create.build()?).query(client)?;
+            }
+            Change::Edit {
+                desired, current, ..
+            } => {
+                api::ignore(EditLabel {
+                    project: project.into(),
+                    id: current.id,
+                    new_name: (current.name != desired.name).then_some(desired.name.as_str()),
+                    color: &desired.color,
+                    description: desired.description.as_deref(),
+                    priority: desired.priority,
+                })
+                .query(client)?;
+            }
+        }
+    }
+    Ok(format!("synced {} label(s)", changes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(name: &str, color: &str) -> Label {
+        Label {
+            name: name.to_owned(),
+            color: color.to_owned(),
+            description: None,
+            priority: None,
+            renamed_from: Vec::new(),
+        }
+    }
+
+    fn current(id: u64, name: &str, color: &str, is_project_label: bool) -> CurrentLabel {
+        CurrentLabel {
+            id,
+            name: name.to_owned(),
+            color: color.to_owned(),
+            description: None,
+            priority: None,
+            is_project_label,
+        }
+    }
+
+    fn steps(changes: &[Change]) -> Vec<String> {
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Create(label) => format!("create {}", label.name),
+                Change::Edit { step, .. } => step.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn colors_differ_only_by_case_is_in_sync() {
+        let current = [current(1, "type::fix", "#D9534F", true)];
+        let labels = [label("type::fix", "#d9534f")];
+        assert!(changes("p", &current, &labels).is_empty());
+    }
+
+    #[test]
+    fn renames_a_label_found_under_an_old_name() {
+        let current = [current(1, "bug", "#ff0000", true)];
+        let mut fix = label("type::fix", "#d9534f");
+        fix.renamed_from = vec!["bug".to_owned()];
+        assert_eq!(
+            steps(&changes("p", &current, &[fix])),
+            ["update label type::fix: rename bug -> type::fix, color #ff0000 -> #d9534f"]
+        );
+    }
+
+    #[test]
+    fn the_new_name_wins_over_an_old_one() {
+        let current = [
+            current(1, "bug", "#ff0000", true),
+            current(2, "type::fix", "#d9534f", true),
+        ];
+        let mut fix = label("type::fix", "#d9534f");
+        fix.renamed_from = vec!["bug".to_owned()];
+        assert!(changes("p", &current, &[fix]).is_empty());
+    }
+
+    #[test]
+    fn group_labels_are_not_edited_or_duplicated() {
+        let current = [current(1, "type::fix", "#000000", false)];
+        let labels = [
+            label("type::fix", "#d9534f"),
+            label("type::feature", "#5cb85c"),
+        ];
+        assert_eq!(
+            steps(&changes("p", &current, &labels)),
+            ["create type::feature"]
+        );
+    }
+}
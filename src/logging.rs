@@ -0,0 +1,127 @@
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use clap::{ArgAction, Args, ValueEnum};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+use crate::progress;
+use crate::redact::Redacted;
+use crate::style::{self, Stream};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct LogOptions {
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t,
+        env = "GITLAB_HELPER_LOG_FORMAT"
+    )]
+    pub log_format: LogFormat,
+    /// Also append logs to this file, with timestamps.
+    #[arg(long, global = true, env = "GITLAB_HELPER_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+    /// Log more of what this tool does; repeat for trace-level detail.
+    /// RUST_LOG directives are applied on top.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+    /// Filter directives such as `info,gitlab_helper::client=trace`; takes
+    /// precedence over `-v` and RUST_LOG.
+    #[arg(long, global = true)]
+    pub log_filter: Option<String>,
+}
+
+/// Flushes the spans that are still waiting to be exported when dropped.
+pub struct Guard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("failed to export traces: {err}");
+            }
+        }
+    }
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn layer<W>(format: LogFormat, writer: W, console: bool) -> BoxedLayer
+where
+    W: for<'w> fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+    let writer = Redacted(writer);
+    match (format, console) {
+        (LogFormat::Json, _) => fmt::layer().json().with_writer(writer).boxed(),
+        (LogFormat::Text, true) => fmt::layer()
+            .with_writer(writer)
+            .with_ansi(style::colored(Stream::Stderr))
+            .without_time()
+            .with_target(false)
+            .boxed(),
+        (LogFormat::Text, false) => fmt::layer().with_writer(writer).with_ansi(false).boxed(),
+    }
+}
+
+pub fn init(options: &LogOptions) -> anyhow::Result<Guard> {
+    let filter = match (&options.log_filter, options.verbose) {
+        (Some(directives), _) => EnvFilter::try_new(directives)
+            .with_context(|| format!("invalid --log-filter {directives:?}"))?,
+        (None, verbose) => {
+            let levels = match verbose {
+                0 => "info",
+                1 => "info,gitlab_helper=debug",
+                _ => "debug,gitlab_helper=trace",
+            };
+            // Later directives for the same target win, so RUST_LOG refines `-v`.
+            match std::env::var("RUST_LOG") {
+                Ok(env) if !env.trim().is_empty() => EnvFilter::try_new(format!("{levels},{env}"))
+                    .with_context(|| format!("invalid RUST_LOG {env:?}"))?,
+                _ => EnvFilter::new(levels),
+            }
+        }
+    };
+
+    let console = || {
+        progress::clear();
+        std::io::stderr()
+    };
+    let mut layers = vec![layer(options.log_format, console, true)];
+    if let Some(path) = &options.log_file {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open the log file {}", path.display()))?;
+        layers.push(layer(options.log_format, Arc::new(file), false));
+    }
+    let registry = tracing_subscriber::registry().with(layers.with_filter(filter));
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "otel")] {
+            let provider = crate::telemetry::provider()?;
+            registry
+                .with(provider.as_ref().map(crate::telemetry::layer))
+                .init();
+            Ok(Guard { provider })
+        } else {
+            registry.init();
+            Ok(Guard {})
+        }
+    }
+}
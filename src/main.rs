@@ -1,226 +1,1815 @@
-use clap::{Parser as ArgParser, Subcommand};
-use gitlab::api::{
-    projects::{merge_requests::CreateMergeRequest, repository},
-    Query,
-};
-use serde::Deserialize;
-use tracing::Level;
-use tracing_subscriber::{
-    fmt::writer::MakeWriterExt, layer::SubscriberExt, util::SubscriberInitExt,
-};
-use winnow::{
-    ascii::{space0, Caseless},
-    combinator::{alt, delimited, terminated},
-    error::{ContextError, ParseError, StrContext, StrContextValue},
-    prelude::*,
-    token::{literal, take_while},
-};
+mod auth;
+mod authorship;
+mod badges;
+mod bisect;
+mod boards;
+mod bootstrap;
+mod cache;
+mod cancel;
+mod chatops;
+mod checks;
+mod ci;
+mod client;
+mod codequality;
+mod commit_status;
+mod compare;
+mod compat;
+mod components;
+mod config;
+mod confluence;
+mod deploy;
+mod diff_check;
+mod discussions;
+mod doctor;
+mod draft_description;
+mod duration;
+mod emergency;
+mod endpoints;
+mod environments;
+mod epics;
+mod feature_flags;
+mod files;
+mod fixtures;
+mod fleet;
+mod freeze;
+mod git;
+mod history;
+mod hooks;
+mod i18n;
+mod incident;
+mod incident_issue;
+mod iterations;
+mod jira;
+mod job_stats;
+mod job_token;
+mod journal;
+mod junit;
+mod labels;
+mod logging;
+mod members;
+mod merge;
+mod metrics;
+mod mr_rules;
+mod notify;
+mod oncall;
+mod origin;
+mod picker;
+mod platform;
+mod policy;
+mod postmortem;
+mod progress;
+mod promote;
+mod prompt;
+mod propagate;
+mod protect;
+mod provenance;
+mod redact;
+mod release;
+mod release_notes;
+mod reporting;
+mod revert;
+mod rollback;
+mod rollback_deploy;
+mod runners;
+mod serve;
+mod settings;
+mod signatures;
+mod sla;
+mod snapshot;
+mod start_fix;
+mod status_page;
+mod style;
+mod table;
+#[cfg(feature = "otel")]
+mod telemetry;
+mod template;
+mod title;
+mod tokens;
+mod usage;
+mod variables;
+mod webhooks;
+mod workflow;
+mod workflow_file;
+
+use std::io::Write;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use clap::{CommandFactory, FromArgMatches, Parser as ArgParser, Subcommand};
 
 #[derive(ArgParser)]
 struct Cli {
+    /// Configuration file; defaults to `.gitlab-ci-helper.toml` if present.
+    #[arg(long, global = true)]
+    config: Option<std::path::PathBuf>,
+    /// The GitLab instance to talk to.
+    #[arg(long, global = true, env = "GITLAB_HOST")]
+    host: Option<String>,
+    /// Apply this `[profile.NAME]` section of the configuration, e.g. `staging`.
+    #[arg(long, global = true, env = "GLCH_PROFILE")]
+    profile: Option<String>,
+    /// Project ID or path to run against; repeat for several projects.
+    #[arg(long = "project", global = true, value_delimiter = ',')]
+    projects: Vec<String>,
+    /// Run against every project in this group, including subgroups.
+    #[arg(long, global = true, conflicts_with = "projects")]
+    group: Option<String>,
+    /// GitLab token; takes precedence over every environment variable.
+    #[arg(long, global = true)]
+    token: Option<String>,
+    /// How to send the token; defaults to the kind implied by its source.
+    #[arg(long, global = true, value_enum)]
+    token_kind: Option<auth::TokenKind>,
+    /// Record every step in this JSON file, e.g. to keep it as a CI artifact.
+    #[arg(long, global = true, env = "GITLAB_HELPER_JOURNAL")]
+    journal: Option<std::path::PathBuf>,
+    /// Continue a failed run from its journal, skipping the steps it completed.
+    #[arg(
+        long,
+        global = true,
+        value_name = "JOURNAL",
+        conflicts_with = "journal"
+    )]
+    resume: Option<std::path::PathBuf>,
+    /// Count runs, failures and durations of each command in this JSON file;
+    /// next to the journal if unset.
+    #[arg(long, global = true, env = "GITLAB_HELPER_USAGE_FILE")]
+    usage_file: Option<std::path::PathBuf>,
+    /// Run `emergency-patch`, `run`, `revert`, `rollback-deploy`, `promote-rc` and `feature-flag`
+    /// only if this pipeline variable has this value, e.g. as set by playing a manual job.
+    #[arg(
+        long,
+        global = true,
+        value_name = "VAR=VALUE",
+        value_parser = parse_var,
+        env = "GITLAB_HELPER_REQUIRE_APPROVAL",
+        hide_env_values = true
+    )]
+    require_approval: Option<(String, String)>,
+    /// Do not ask for confirmation before changing anything.
+    #[arg(long, short, global = true)]
+    yes: bool,
+    /// How many projects, or branches of one, to work on at once.
+    #[arg(
+        long,
+        short,
+        visible_alias = "parallel",
+        global = true,
+        default_value = "4"
+    )]
+    jobs: std::num::NonZeroUsize,
+    /// Create branches and cherry-pick in the clone in the working directory
+    /// and push them, rather than through the API, if it is in one.
+    #[arg(long, global = true, env = "GITLAB_HELPER_LOCAL_GIT")]
+    local_git: bool,
+    /// Print no colors or hyperlinks; neither is used in CI or when
+    /// NO_COLOR is set either.
+    #[arg(long, global = true)]
+    no_color: bool,
+    #[command(flatten)]
+    logging: logging::LogOptions,
+    #[command(flatten)]
+    network: client::NetworkOptions,
+    #[command(flatten)]
+    cache: cache::CacheOptions,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    EmergencyPatch,
-    GenerateReleaseNotes,
+    EmergencyPatch {
+        /// Choose the release, targets and assignee interactively; commits to
+        /// pick onto the branch go to `--cherry-pick`.
+        #[arg(long)]
+        pick: bool,
+        /// A template file for the merge request descriptions, instead of the built-in one.
+        #[arg(long, value_name = "PATH")]
+        description_template: Option<std::path::PathBuf>,
+        /// Summarize in each description what the target has that the release does not.
+        #[arg(long)]
+        compare_summary: bool,
+        /// Add the branch and MRs to this incident, as set up in `[incident]`.
+        #[arg(long, value_name = "ID")]
+        incident: Option<String>,
+        /// Open a new incident for the patch instead.
+        #[arg(long, conflicts_with = "incident")]
+        open_incident: bool,
+        /// Commits to cherry-pick onto the new branch, such as a fix already on dev.
+        /// It is not `--pick <SHA>...`, as `--pick` already chooses the release.
+        #[arg(long = "cherry-pick", value_name = "SHA", num_args = 1..)]
+        picks: Vec<String>,
+    },
+    /// Promote a release candidate branch to its release, tag, pipeline and announcement.
+    PromoteRc {
+        /// The release candidate's branch, e.g. `release/1.4.0-rc.2`.
+        #[arg(long)]
+        rc: String,
+        /// The branch the announcement merge request goes into.
+        #[arg(long, default_value = "master")]
+        announce_into: String,
+        /// A variable for the production pipeline, e.g. `--var DEPLOY=production`.
+        #[arg(long = "var", value_parser = parse_var)]
+        variables: Vec<(String, String)>,
+    },
+    /// Merge a fix along the maintained branches, oldest to newest, one merge request at a time.
+    PropagateFix {
+        /// The branch the fix landed on, e.g. `release/1.2.5`.
+        #[arg(long)]
+        from: String,
+        /// The branches to merge it into, in order; `propagate.chain` if unset.
+        #[arg(long, value_delimiter = ',')]
+        through: Vec<String>,
+        /// How often to check each merge request at first.
+        #[arg(long, value_parser = duration::parse, default_value = "15s")]
+        poll: std::time::Duration,
+        /// Give up on a merge request after this long.
+        #[arg(long, value_parser = duration::parse, default_value = "2h")]
+        wait: std::time::Duration,
+    },
+    /// Create a `fix/JIRA-ID-slug` work branch off the emergency patch branch.
+    StartFix(start_fix::StartFix),
+    /// Report how a job's duration, artifacts and cache times trend.
+    JobStats {
+        #[arg(long)]
+        job: String,
+        /// How many of its latest finished runs to look at.
+        #[arg(long, default_value_t = 100)]
+        last: usize,
+        #[arg(long, value_enum, default_value_t = table::Format::Table)]
+        format: table::Format,
+    },
+    /// Show what is running in each environment, e.g. before cutting a patch.
+    Envs {
+        #[command(subcommand)]
+        command: EnvsCommand,
+    },
+    /// Check on the runners that pick up the projects' jobs.
+    Runners {
+        #[command(subcommand)]
+        command: RunnersCommand,
+    },
+    /// Find the commit that broke a job by running it on midpoints.
+    PipelineBisect {
+        /// A commit the job passes on.
+        #[arg(long)]
+        good: String,
+        /// A later commit the job fails on.
+        #[arg(long)]
+        bad: String,
+        #[arg(long)]
+        job: String,
+        #[arg(long, value_parser = duration::parse, default_value = "30s")]
+        poll: std::time::Duration,
+        /// How long to wait for the job on each commit.
+        #[arg(long, value_parser = duration::parse, default_value = "1h")]
+        wait: std::time::Duration,
+    },
+    /// Open merge requests that undo a merged merge request or a commit.
+    Revert(revert::Revert),
+    /// Roll an environment back to its last good deployment, e.g. `--env production`.
+    RollbackDeploy(rollback_deploy::RollbackDeploy),
+    /// Print the commits one ref has that another does not.
+    Compare {
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        /// Print a Markdown summary of the authors, files and MRs instead.
+        #[arg(long)]
+        summary: bool,
+    },
+    /// Print release notes for the MRs merged between two tags, e.g. as semantic-release would.
+    GenerateReleaseNotes {
+        /// The previous release tag.
+        #[arg(long)]
+        from: String,
+        /// The tag being released.
+        #[arg(long)]
+        to: String,
+        /// The branch the release was cut from; the default branch if unset.
+        #[arg(long)]
+        target_branch: Option<String>,
+        #[arg(long, value_enum, default_value_t = release_notes::NotesFormat::Markdown)]
+        format: release_notes::NotesFormat,
+        /// Publish them to the `[confluence]` space too.
+        #[arg(long)]
+        confluence: bool,
+    },
+    /// Publish the release of a tag with its dependency and SBOM reports attached.
+    PublishRelease {
+        /// The tag being released.
+        #[arg(long)]
+        tag: String,
+        /// The previous release tag, to generate the release notes from; they
+        /// go to the `[confluence]` space too if there is one.
+        #[arg(long)]
+        from: Option<String>,
+        /// The branch the release was cut from; the default branch if unset.
+        #[arg(long)]
+        target_branch: Option<String>,
+    },
+    /// Attach a signed record of the pipeline, runner and artifact checksums to a release.
+    Provenance(provenance::Provenance),
+    /// Print the canonical MR title for a branch named like `fix/JIRA-12-short-slug`.
+    SuggestTitle {
+        #[arg(long, env = "CI_COMMIT_REF_NAME")]
+        branch: String,
+    },
+    /// Check a merge request title against the `kind (JIRA-ID): title` convention.
+    LintTitle {
+        #[arg(long, env = "CI_MERGE_REQUEST_TITLE")]
+        title: String,
+        #[command(flatten)]
+        reports: checks::Reports,
+    },
+    /// Summarize the licenses in CycloneDX SBOMs, failing on denied ones.
+    LicensesReport {
+        #[arg(required = true)]
+        sboms: Vec<std::path::PathBuf>,
+        /// A license no component may be under, e.g. `--deny GPL-3.0-only`.
+        #[arg(long = "deny")]
+        denied: Vec<String>,
+        #[arg(long, value_enum, default_value_t = table::Format::Table)]
+        format: table::Format,
+    },
+    /// Check the Jira tickets of a release against the MRs merged into it.
+    JiraReport(jira::Report),
+    /// Export every MR merged between two tags, for change-management audits.
+    ExportHistory {
+        /// The tag the history starts after.
+        #[arg(long)]
+        since: String,
+        /// The tag the history ends at.
+        #[arg(long)]
+        until: String,
+        /// The branch the MRs were merged into; the default branch if unset.
+        #[arg(long)]
+        target_branch: Option<String>,
+        #[arg(long, value_enum, default_value_t = table::Format::Csv)]
+        format: table::Format,
+    },
+    /// Track releases in group epics.
+    Epic {
+        #[command(subcommand)]
+        command: EpicCommand,
+    },
+    /// Print the issues of a group epic and how many are closed.
+    EpicStatus {
+        #[arg(long)]
+        epic: u64,
+        #[arg(long, value_enum, default_value_t = table::Format::Table)]
+        format: table::Format,
+    },
+    /// Print the columns of an issue board with their issue counts and ages.
+    BoardReport {
+        /// The board's name or ID.
+        #[arg(long)]
+        board: String,
+        #[arg(long, value_enum, default_value_t = boards::BoardFormat::Markdown)]
+        format: boards::BoardFormat,
+    },
+    /// Put the issues a merge request closes into the group's running iteration.
+    AssignIteration(iterations::AssignIteration),
+    /// Fill the "What does this change do?" section of an MR from its commits.
+    DraftDescription {
+        #[arg(long = "mr", env = "CI_MERGE_REQUEST_IID")]
+        iid: u64,
+    },
+    /// Manage project CI/CD variables.
+    Variables {
+        #[command(subcommand)]
+        command: VariablesCommand,
+    },
+    /// Run a workflow composed of built-in steps in a workflow file.
+    Run {
+        /// The workflow's name in the file.
+        name: String,
+        #[arg(long, default_value = workflow_file::DEFAULT_PATH)]
+        file: std::path::PathBuf,
+        /// Set a template variable, e.g. `--var target=master`.
+        #[arg(long = "var", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+    },
+    /// Lint a `.gitlab-ci.yml` with GitLab, resolving includes, and print the merged config.
+    CiLint {
+        #[arg(default_value = ".gitlab-ci.yml")]
+        path: std::path::PathBuf,
+        /// Simulate creating a pipeline, which also checks `rules` and `needs`.
+        #[arg(long)]
+        dry_run: bool,
+        /// Branch or tag to resolve includes and simulate the pipeline for.
+        #[arg(long = "ref", requires = "dry_run")]
+        ref_: Option<String>,
+    },
+    /// Print the stages, jobs, needs and includes of the merged CI config as a graph.
+    CiGraph {
+        /// A local CI file instead of the project's own `.gitlab-ci.yml`.
+        #[arg(long)]
+        file: Option<std::path::PathBuf>,
+        /// Branch or tag to read the config and resolve includes at.
+        #[arg(long = "ref")]
+        ref_: Option<String>,
+        #[arg(long, value_enum, default_value_t = ci::GraphFormat::Dot)]
+        format: ci::GraphFormat,
+    },
+    /// List the variables CI jobs use that nothing defines.
+    CiCheckVars {
+        #[arg(default_value = ".gitlab-ci.yml")]
+        path: std::path::PathBuf,
+        #[command(flatten)]
+        reports: checks::Reports,
+    },
+    /// Print the commit message an MR is merged with: its title, Jira IDs, approvers and URL.
+    BuildMergeMessage {
+        #[arg(long = "mr", env = "CI_MERGE_REQUEST_IID")]
+        iid: u64,
+    },
+    /// Merge a merge request once it is approved, resolved and green.
+    MergeWhenReady {
+        #[arg(long = "mr")]
+        iid: u64,
+        /// Delete the source branch, whatever `[merge]` says.
+        #[arg(long)]
+        remove_source_branch: bool,
+        /// How often to check at first; it backs off from there.
+        #[arg(long, value_parser = duration::parse, default_value = "15s")]
+        poll: std::time::Duration,
+        /// Give up after this long.
+        #[arg(long, value_parser = duration::parse, default_value = "2h")]
+        wait: std::time::Duration,
+    },
+    /// Fail if an emergency merge request is open past its SLA, escalating newly late ones.
+    CheckSla {
+        /// Overrides `emergency_patch.sla`.
+        #[arg(long, value_parser = duration::parse)]
+        sla: Option<std::time::Duration>,
+    },
+    /// Publish the result of an external check on a commit.
+    SetStatus(commit_status::SetStatus),
+    /// Open an incident issue and print its IID.
+    CreateIncidentIssue(incident_issue::IncidentIssue),
+    /// Draft the post-mortem of an incident.
+    Postmortem {
+        #[command(subcommand)]
+        command: PostmortemCommand,
+    },
+    /// Keep stakeholders posted on an incident.
+    StatusPage {
+        #[command(subcommand)]
+        command: StatusPageCommand,
+    },
+    /// Read repository files, or write several in one commit.
+    File {
+        #[command(subcommand)]
+        command: FileCommand,
+    },
+    /// Print the configured components a merge request changes.
+    ChangedComponents {
+        #[arg(long = "mr", env = "CI_MERGE_REQUEST_IID")]
+        iid: u64,
+        /// Also write them as a dotenv report for later jobs.
+        #[arg(long, value_name = "PATH")]
+        dotenv: Option<std::path::PathBuf>,
+    },
+    /// Write a child pipeline with jobs only for the components a merge request changes.
+    GenerateChildPipeline {
+        #[arg(long = "mr", env = "CI_MERGE_REQUEST_IID")]
+        iid: u64,
+        /// The jobs of one component, with `{{ component }}` and `{{ component_path }}`.
+        #[arg(long)]
+        template: std::path::PathBuf,
+        /// YAML put once before the jobs, such as `stages`.
+        #[arg(long)]
+        header: Option<std::path::PathBuf>,
+        #[arg(long, default_value = "child-pipeline.yml")]
+        out: std::path::PathBuf,
+    },
+    /// Check a merge request's title and the `mr_rules` for the paths it changes.
+    CheckMr {
+        #[arg(long = "mr")]
+        iid: u64,
+        #[command(flatten)]
+        reports: checks::Reports,
+    },
+    /// Fail if a merge request has unresolved discussions.
+    CheckDiscussions {
+        #[arg(long = "mr")]
+        iid: u64,
+        #[command(flatten)]
+        reports: checks::Reports,
+    },
+    /// Flag large or binary files and secrets added by a merge request.
+    CheckDiff {
+        #[arg(long = "mr")]
+        iid: u64,
+        /// Only print the findings instead of also posting them on the MR.
+        #[arg(long)]
+        no_comment: bool,
+        #[command(flatten)]
+        reports: checks::Reports,
+    },
+    /// Fail if `--head` breaks the API of `--base`, as `check_compat.command` tells.
+    CheckCompat {
+        /// The release to stay compatible with, e.g. `release/1.3.0`.
+        #[arg(long)]
+        base: String,
+        #[arg(long, env = "CI_COMMIT_REF_NAME")]
+        head: String,
+        /// The merge request to post the result on.
+        #[arg(long = "mr", env = "CI_MERGE_REQUEST_IID")]
+        iid: Option<u64>,
+        #[command(flatten)]
+        reports: checks::Reports,
+    },
+    /// Fail if a commit of a merge request is not signed and verified.
+    CheckSignatures {
+        #[arg(long = "mr")]
+        iid: u64,
+        /// An author, by name or email, whose commits need no signature, e.g. a bot.
+        #[arg(long = "allow-author")]
+        allowed_authors: Vec<String>,
+        #[command(flatten)]
+        reports: checks::Reports,
+    },
+    /// Turn GitLab feature flags on and off.
+    FeatureFlag {
+        #[command(subcommand)]
+        command: FeatureFlagCommand,
+    },
+    /// Manage the deploy freeze periods during which `$CI_DEPLOY_FREEZE` is set.
+    Freeze {
+        #[command(subcommand)]
+        command: FreezeCommand,
+    },
+    /// Create deploy tokens for pulling from a project.
+    DeployToken {
+        #[command(subcommand)]
+        command: DeployTokenCommand,
+    },
+    /// Add deploy keys for cloning a project over SSH.
+    DeployKey {
+        #[command(subcommand)]
+        command: DeployKeyCommand,
+    },
+    /// Manage the projects' webhooks.
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommand,
+    },
+    /// Manage which projects' CI_JOB_TOKEN may access the projects.
+    JobTokenScope {
+        #[command(subcommand)]
+        command: JobTokenScopeCommand,
+    },
+    /// Manage project labels.
+    Labels {
+        #[command(subcommand)]
+        command: LabelsCommand,
+    },
+    /// Manage protected branches and tags.
+    Protect {
+        #[command(subcommand)]
+        command: ProtectCommand,
+    },
+    /// Compare project settings against a desired-state file and print the drift.
+    AuditSettings {
+        #[arg(long, default_value = settings::DEFAULT_PATH)]
+        file: std::path::PathBuf,
+        /// Change the projects to match the file.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Manage project badges.
+    Badges {
+        #[command(subcommand)]
+        command: BadgesCommand,
+    },
+    /// Apply the standard setup of a template file to new projects.
+    Bootstrap {
+        #[arg(long, default_value = bootstrap::DEFAULT_PATH)]
+        file: std::path::PathBuf,
+    },
+    /// List the projects' members and flag unapproved maintainers and stale accounts.
+    AuditMembers {
+        /// A group whose members may be direct maintainers; repeat for several.
+        #[arg(long = "approved-group")]
+        approved_groups: Vec<String>,
+        /// Flag accounts inactive for longer than this many days.
+        #[arg(long, default_value = "90")]
+        stale_after: u32,
+        #[arg(long, value_enum, default_value_t = table::Format::Table)]
+        format: table::Format,
+    },
+    /// List the access tokens of the projects and their group, and fail if any expires soon.
+    AuditTokens {
+        /// How many days ahead counts as expiring soon.
+        #[arg(long, default_value = "30")]
+        within: u32,
+        /// Post the expiring tokens to the notification webhook.
+        #[arg(long)]
+        notify: bool,
+    },
+    /// Close the merge requests, delete the branches and turn back the feature flags of a journaled run.
+    Rollback { journal: std::path::PathBuf },
+    /// Listen for GitLab webhooks and run the configured workflows.
+    Serve {
+        /// Address to listen on; overrides `serve.listen` in the config.
+        #[arg(long)]
+        listen: Option<String>,
+        /// Secret token GitLab sends with every webhook.
+        #[arg(long, env = "GITLAB_WEBHOOK_SECRET", hide_env_values = true)]
+        secret: Option<String>,
+    },
+    /// Check the token, host, projects and branches the other commands rely on.
+    Doctor {
+        /// Also check the webhook listener and the projects' webhooks.
+        #[arg(long)]
+        serve: bool,
+    },
+    /// Check templates before a workflow uses them.
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommand,
+    },
+    /// Work with the configuration file.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Print a shell completion script, e.g. `gitlab-helper completions zsh > _gitlab-helper`.
+    Completions { shell: clap_complete::Shell },
+    /// Manage the personal access token stored in the OS keyring.
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
 }
 
-#[derive(Debug, PartialEq)]
-pub enum Kind {
-    Feature,
-    Fix,
+#[derive(Subcommand)]
+enum VariablesCommand {
+    /// Create a variable, or update it if it already exists.
+    Set(variables::SetVariable),
 }
 
-#[derive(Debug, PartialEq)]
-pub struct MergeRequest<'a> {
-    kind: Kind,
-    jira_id: &'a str,
-    title: &'a str,
+#[derive(Subcommand)]
+enum BadgesCommand {
+    /// Add or update badges to match the `[[badges]]` of the config.
+    Apply,
 }
 
-const GITLAB_PROJECT_ID: &str = "823";
+#[derive(Subcommand)]
+enum FeatureFlagCommand {
+    /// Turn a feature flag on, everywhere or in one environment.
+    Enable(feature_flags::Toggle),
+    /// Turn a feature flag off, e.g. a kill switch during an incident.
+    Disable(feature_flags::Toggle),
+}
 
-fn is_jira_id(c: char) -> bool {
-    c.is_ascii_alphanumeric() || c == '-'
-}
-
-pub fn parse_kind(input: &mut &str) -> PResult<Kind> {
-    alt((
-        literal(Caseless("fix")).map(|_| Kind::Fix),
-        literal(Caseless("feat")).map(|_| Kind::Feature),
-        literal(Caseless("feature")).map(|_| Kind::Feature),
-    ))
-    .context(StrContext::Label("kind"))
-    .context(StrContext::Expected(StrContextValue::Description(
-        "fix or feat",
-    )))
-    .parse_next(input)
-}
-
-pub fn parse_jira_id<'a>(input: &'_ mut &'a str) -> PResult<&'a str> {
-    (
-        space0,
-        delimited(
-            literal("("),
-            delimited(space0, take_while(1.., is_jira_id), space0),
-            literal(")"),
-        ),
-    )
-        .context(StrContext::Label("jira id"))
-        .context(StrContext::Expected(StrContextValue::Description(
-            "a valid jira id",
-        )))
-        .map(|(_, jira_id)| jira_id)
-        .parse_next(input)
-}
-
-pub fn parse_title<'a>(input: &'_ mut &'a str) -> PResult<&'a str> {
-    (
-        space0,
-        literal(':'),
-        delimited(space0, take_while(1.., |c: char| c.is_ascii()), space0),
-    )
-        .context(StrContext::Label("title"))
-        .context(StrContext::Expected(StrContextValue::Description(
-            "any valid title",
-        )))
-        .map(|(_, _, title)| title)
-        .parse_next(input)
-}
-
-pub fn parse_merge_request<'a>(
-    input: &'_ mut &'a str,
-) -> Result<MergeRequest<'a>, ParseError<&'a str, ContextError>> {
-    terminated(
-        (parse_kind, parse_jira_id, parse_title).map(|(kind, jira_id, title)| MergeRequest {
-            kind,
-            jira_id,
-            title,
-        }),
-        space0,
-    )
-    .parse(input)
-}
-
-#[derive(Debug, Deserialize)]
-struct Branch {
-    name: String,
-}
-
-fn main() -> anyhow::Result<()> {
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
-        )
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_writer(std::io::stderr.with_max_level(Level::INFO))
-                .without_time()
-                .with_target(false),
-        )
-        .init();
-    dotenvy::dotenv().ok();
-    let client = if std::env::var("CI").is_ok() {
-        gitlab::Gitlab::new_job_token("gitlab.zengo.eu", std::env::var("CI_JOB_TOKEN")?)?
-    } else {
-        gitlab::Gitlab::new("gitlab.zengo.eu", std::env::var("ACCESS_TOKEN")?)?
-    };
+#[derive(Subcommand)]
+enum FreezeCommand {
+    /// Add a deploy freeze period, e.g. `--start "0 23 * * 5" --end "0 7 * * 1"` for weekends.
+    Create(freeze::Period),
+    /// List the freeze periods and whether they are on now.
+    List {
+        #[arg(long, value_enum, default_value_t = table::Format::Table)]
+        format: table::Format,
+    },
+    /// Delete a freeze period by ID.
+    Delete { id: u64 },
+}
 
-    let args = Cli::parse();
-    match args.command {
-        Some(Commands::EmergencyPatch) => {
-            let branches = repository::branches::Branches::builder()
-                .project(GITLAB_PROJECT_ID)
-                .regex(r"release/\d+\.\d+\.\d+")
-                .build()?;
-            let branches: Vec<Branch> = branches.query(&client)?;
-            let Some(latest_release) = branches
-                .iter()
-                .map(|branch| {
-                    semver::Version::parse(branch.name.split('/').last().unwrap()).unwrap()
-                })
-                .max()
-            else {
-                anyhow::bail!("No branches found based on the release/x.x.x pattern")
-            };
-            let emergency_patch = semver::Version::new(
-                latest_release.major,
-                latest_release.minor,
-                latest_release.patch + 1,
-            );
+#[derive(Subcommand)]
+enum DeployTokenCommand {
+    /// Create a deploy token and print it or store it in another project.
+    Create(deploy::CreateToken),
+}
 
-            let latest_release = format!("release/{latest_release}");
-            let emergency_patch = format!("release/{}", emergency_patch);
-            tracing::info!(
-                latest_release,
-                emergency_patch,
-                "creating a new patch from latest release..."
-            );
-            let create_branch = repository::branches::CreateBranch::builder()
-                .project(GITLAB_PROJECT_ID)
-                .branch(&emergency_patch)
-                .ref_(&latest_release)
-                .build()?;
-            let _: Result<serde_json::Value, _> = create_branch.query(&client);
-            let gitlab_user_id = std::env::var("GITLAB_USER_ID")?.parse::<u64>()?;
+#[derive(Subcommand)]
+enum DeployKeyCommand {
+    /// Add a deploy key, generating the key pair unless one is given.
+    Add(deploy::AddKey),
+}
+
+#[derive(Subcommand)]
+enum HooksCommand {
+    /// Add a webhook, or update the one with the same URL.
+    Add(webhooks::AddHook),
+    /// List the webhooks and the events they are sent.
+    List,
+    /// Remove a webhook by ID or URL.
+    Remove { hook: String },
+}
 
-            let mr = CreateMergeRequest::builder()
-                .project(GITLAB_PROJECT_ID)
-                .source_branch(&emergency_patch)
-                .target_branch("master")
-                .title(format!("EMERGENCY PRODUCTION PATCH ({})", latest_release))
-                .description(format!(
-                    "## This is an auto-generated emergency patch aimed at PRODUCTION.
+#[derive(Subcommand)]
+enum JobTokenScopeCommand {
+    /// Let jobs in these projects use their job token on the projects.
+    Add {
+        #[arg(required = true)]
+        targets: Vec<String>,
+    },
+    /// Take these projects off the allowlist.
+    Remove {
+        #[arg(required = true)]
+        targets: Vec<String>,
+    },
+    /// List the projects whose job tokens are allowed.
+    List,
+}
 
-To start working, switch to this branch:
-```bash
-git pull origin {emergency_patch} && git checkout {emergency_patch}
-```
+#[derive(Subcommand)]
+enum LabelsCommand {
+    /// Create, rename and recolor labels to match the `[[labels]]` of the config.
+    Sync,
+}
+
+#[derive(Subcommand)]
+enum EnvsCommand {
+    /// List the environments with their last deployment and whether they are up.
+    Status {
+        #[arg(long, value_enum, default_value_t = table::Format::Table)]
+        format: table::Format,
+    },
+}
+
+#[derive(Subcommand)]
+enum RunnersCommand {
+    /// List the runners and the pending jobs none of them can pick up.
+    Status,
+}
+
+#[derive(Subcommand)]
+enum StatusPageCommand {
+    /// Open the incident on the `[status_page]`, or post an update to it.
+    Update(status_page::Update),
+}
+
+#[derive(Subcommand)]
+enum EpicCommand {
+    /// Add the issues of a milestone to an epic and mention it on the milestone's MRs.
+    Link(epics::Link),
+}
+
+#[derive(Subcommand)]
+enum PostmortemCommand {
+    /// Write the timeline of the incident's branch, MRs, pipelines and deployments under empty sections.
+    Scaffold(postmortem::Scaffold),
+}
+
+#[derive(Subcommand)]
+enum FileCommand {
+    /// Print a file of the repository.
+    Get {
+        path: String,
+        #[arg(long = "ref", default_value = "HEAD")]
+        ref_: String,
+    },
+    /// Write local files to the repository and delete others, all in one commit.
+    Put(files::Put),
+}
+
+#[derive(Subcommand)]
+enum ProtectCommand {
+    /// Protect branches and tags as the `[protect]` section of the config describes.
+    Apply,
+}
+
+#[derive(Subcommand)]
+enum TemplateCommand {
+    /// Render a `description_template` file and report any undefined placeholders.
+    Preview {
+        path: std::path::PathBuf,
+        /// Set a template variable, e.g. `--var version=1.2.4`.
+        #[arg(long = "var", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+        /// Only use the `--var` values, not the sample values of step outputs.
+        #[arg(long)]
+        no_samples: bool,
+    },
+}
 
-Please fill out the following checklist:
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check the configuration file and report the line and column of any error.
+    Validate,
+}
 
-### Why this change is necessary?
+#[derive(Subcommand)]
+enum AuthCommand {
+    /// Verify a personal access token and store it in the OS keyring.
+    Login,
+    /// Remove the stored token from the OS keyring.
+    Logout,
+}
 
-### What does this change do?
+const GITLAB_HOST: &str = "gitlab.zengo.eu";
+const GITLAB_PROJECT_ID: &str = "823";
 
-### How to test this change?"
-                ))
-                .assignee(gitlab_user_id)
-                .build()?;
+fn parse_var(input: &str) -> Result<(String, String), String> {
+    input
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_owned(), value.to_owned()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got {input:?}"))
+}
 
-            let _: Result<serde_json::Value, _> = mr.query(&client);
+fn run_auth(
+    host: &str,
+    command: AuthCommand,
+    token: Option<String>,
+    network: &client::NetworkOptions,
+) -> anyhow::Result<()> {
+    match command {
+        AuthCommand::Login => {
+            let (token, source) = match token {
+                Some(token) => (token, auth::TokenSource::Flag),
+                None => (
+                    rpassword::prompt_password(format!("Access token for {host}: "))?,
+                    auth::TokenSource::Prompt,
+                ),
+            };
+            let token = token.trim();
+            anyhow::ensure!(!token.is_empty(), "No token provided");
+            redact::register(token);
+            let credentials = auth::Credentials {
+                token: token.to_owned(),
+                kind: auth::TokenKind::Personal,
+                source,
+            };
+            client::Client::new(host, credentials, network)?;
+            auth::store(host, token)?;
+            tracing::info!("token for {host} stored in the OS keyring");
+        }
+        AuthCommand::Logout => {
+            if auth::forget(host)? {
+                tracing::info!("token for {host} removed from the OS keyring");
+            } else {
+                tracing::info!("no token stored for {host}");
+            }
+        }
+    }
+    Ok(())
+}
 
-            let mr2 = CreateMergeRequest::builder()
-                .project(GITLAB_PROJECT_ID)
-                .source_branch(&emergency_patch)
-                .target_branch("dev")
-                .title(format!("EMERGENCY PRODUCTION PATCH ({})", latest_release))
-                .description(format!(
-                    "## This is an auto-generated emergency patch aimed at PRODUCTION. 
+fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    redact::register_env();
+    let matches = Cli::command().get_matches();
+    let args = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let command = matches.subcommand_name().unwrap_or_default();
+    style::init(args.no_color);
+    let usage_file = usage::path(
+        args.usage_file.as_deref(),
+        args.journal.as_deref().or(args.resume.as_deref()),
+    );
+    let result = logging::init(&args.logging).and_then(|_logging| {
+        let _reporting = reporting::init(command);
+        cancel::install();
+        let started = std::time::Instant::now();
+        let result = tracing::info_span!("command", name = command).in_scope(|| run(args));
+        if let Err(err) = &result {
+            // Multi-project runs have already reported each failing project.
+            if !err.is::<fleet::Failed>() {
+                reporting::failure(None, err);
+            }
+        }
+        if let Some(path) = &usage_file {
+            if let Err(err) = usage::record(path, command, result.is_err(), started.elapsed()) {
+                tracing::warn!("{err:#}");
+            }
+        }
+        result
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            // API error bodies can echo request headers back.
+            eprintln!(
+                "{} {}",
+                style::paint(style::Stream::Stderr, style::Color::Red, "Error:"),
+                redact::redact(&format!("{err:?}"))
+            );
+            if let Some(hint) = client::explain(&err) {
+                eprintln!(
+                    "\n{} {hint}",
+                    style::paint(style::Stream::Stderr, style::Color::Yellow, "hint:")
+                );
+            }
+            match cancel::check() {
+                Err(cancelled) => ExitCode::from(cancelled.exit_code()),
+                Ok(()) => ExitCode::FAILURE,
+            }
+        }
+    }
+}
 
-To start working, switch to this branch:
-```bash
-git pull origin {emergency_patch} && git checkout {emergency_patch}
-```
+fn run(args: Cli) -> anyhow::Result<()> {
+    if let Some(Commands::Completions { shell }) = args.command {
+        let mut command = Cli::command();
+        let name = command.get_name().to_owned();
+        let mut script = Vec::new();
+        clap_complete::generate(shell, &mut command, name, &mut script);
+        // `completions bash | head` closing the pipe early is not an error.
+        return match std::io::stdout().write_all(&script) {
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            result => Ok(result?),
+        };
+    }
+    if let Some(Commands::Template {
+        command:
+            TemplateCommand::Preview {
+                path,
+                vars,
+                no_samples,
+            },
+    }) = args.command
+    {
+        let template = template::load(&path)?;
+        let samples = if no_samples {
+            &[][..]
+        } else {
+            template::SAMPLE_VARS
+        };
+        let vars: template::Vars = samples
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .chain(vars)
+            .collect();
+        let rendered = template::render(&template, &vars)
+            .with_context(|| format!("{} does not render", path.display()))?;
+        print!("{rendered}");
+        return Ok(());
+    }
+    if let Some(Commands::SuggestTitle { branch }) = &args.command {
+        let title = title::from_branch(branch)
+            .with_context(|| format!("{branch} is not named like `fix/JIRA-12-short-slug`"))?;
+        println!("{title}");
+        return Ok(());
+    }
+    if let Some(Commands::LintTitle { title, reports }) = &args.command {
+        return mr_rules::lint_title(title, reports);
+    }
+    if let Some(Commands::LicensesReport {
+        sboms,
+        denied,
+        format,
+    }) = &args.command
+    {
+        return release::licenses_report(sboms, denied, *format);
+    }
+    if let Some(Commands::Config {
+        command: ConfigCommand::Validate,
+    }) = args.command
+    {
+        let path = args.config.unwrap_or_else(|| config::DEFAULT_PATH.into());
+        let config = config::Config::load(Some(&path), None)?;
+        for name in config.profile.keys() {
+            config::Config::load(Some(&path), Some(name))?;
+        }
+        println!("{} is valid", path.display());
+        return Ok(());
+    }
+    let config = config::Config::load(args.config.as_deref(), args.profile.as_deref())?;
+    i18n::init(config.locale);
+    let host = args
+        .host
+        .or_else(|| config.host.clone())
+        .unwrap_or_else(|| GITLAB_HOST.to_owned());
+    if let Some(Commands::Auth { command }) = args.command {
+        return run_auth(&host, command, args.token, &args.network);
+    }
+    if let Some(Commands::Doctor { serve }) = args.command {
+        let options = doctor::Options {
+            host: &host,
+            token: args.token,
+            token_kind: args.token_kind,
+            network: &args.network,
+            config: &config,
+            serve,
+        };
+        return doctor::run(options, |client| {
+            resolve_projects(client, args.projects, args.group.as_deref(), &config)
+        });
+    }
+    let credentials = match auth::resolve(&host, args.token, args.token_kind) {
+        Err(_) if args.network.replay.is_some() => auth::Credentials {
+            token: String::new(),
+            kind: auth::TokenKind::Personal,
+            source: auth::TokenSource::Replay,
+        },
+        credentials => credentials?,
+    };
+    redact::register(&credentials.token);
+    if let Some(webhook_url) = config.notify.webhook_url() {
+        redact::register(&webhook_url);
+    }
+    let mut client = client::Client::new(&host, credentials, &args.network)?;
+    match args.command {
+        Some(Commands::Serve { listen, secret }) => {
+            if let Some(secret) = &secret {
+                redact::register(secret);
+            }
+            let mut config = config;
+            if let Some(listen) = listen {
+                config.serve.listen = listen;
+            }
+            return serve::run(client, config, secret);
+        }
+        Some(Commands::Rollback { journal }) => {
+            return rollback::run(&client, &journal::Journal::open(&journal)?, args.yes);
+        }
+        Some(
+            Commands::GenerateReleaseNotes { .. }
+            | Commands::ExportHistory { .. }
+            | Commands::JiraReport(_),
+        ) => {
+            client = client.with_cache(cache::Cache::new(&args.cache));
+        }
+        _ => {}
+    }
+    // The group the projects come from, if any, for commands that also look at the group itself.
+    let group = args.group.clone().or_else(|| {
+        (args.projects.is_empty() && config.projects.is_empty())
+            .then(|| config.group.clone())
+            .flatten()
+    });
+    let projects = resolve_projects(&client, args.projects, args.group.as_deref(), &config)?;
 
-Please fill out the following checklist:
+    let journaled = match &args.command {
+        Some(Commands::EmergencyPatch { .. }) => Some("emergency-patch".to_owned()),
+        Some(Commands::Run { name, .. }) => Some(format!("run {name}")),
+        Some(Commands::FeatureFlag { .. }) => Some("feature-flag".to_owned()),
+        Some(Commands::RollbackDeploy(_)) => Some("rollback-deploy".to_owned()),
+        _ => None,
+    };
+    let guarded = match &args.command {
+        Some(Commands::EmergencyPatch { .. }) => Some("emergency-patch"),
+        Some(Commands::Run { .. }) => Some("run"),
+        Some(Commands::Revert(_)) => Some("revert"),
+        Some(Commands::RollbackDeploy(_)) => Some("rollback-deploy"),
+        Some(Commands::PromoteRc { .. }) => Some("promote-rc"),
+        Some(Commands::FeatureFlag { .. }) => Some("feature-flag"),
+        _ => None,
+    };
+    if let Some(command) = guarded {
+        policy::check(&config.policy, command)?;
+        if let Some((name, value)) = &args.require_approval {
+            redact::register(value);
+            policy::check_approval(name, value, command)?;
+        }
+    }
+    let journal = match (&journaled, &args.journal, &args.resume) {
+        (Some(command), _, Some(path)) => journal::Journal::resume(path, command)?,
+        (Some(command), Some(path), None) => journal::Journal::create(path, command)?,
+        (None, _, Some(_)) => {
+            anyhow::bail!(
+                "only emergency-patch, run, feature-flag and rollback-deploy can be resumed"
+            )
+        }
+        _ => journal::Journal::disabled(),
+    };
+    // Replays stub every change, which a push would not be.
+    let repo = match args.local_git && !client.is_replaying() {
+        true => {
+            let repo = std::env::current_dir()
+                .ok()
+                .and_then(|dir| git::Repo::open(&dir, config.commits.identity()));
+            if repo.is_none() {
+                tracing::warn!("not in a git clone; creating branches through the API");
+            }
+            repo
+        }
+        false => None,
+    };
+    let ctx = workflow::Context {
+        client: &client,
+        hooks: &config.hooks,
+        notify: &config.notify,
+        commits: &config.commits,
+        journal: &journal,
+        git: repo.as_ref(),
+        confirm: !args.yes && prompt::interactive(),
+    };
 
-### Why this change is necessary?
+    let result = dispatch(
+        args.command,
+        &ctx,
+        &config,
+        group.as_deref(),
+        &projects,
+        args.jobs,
+    );
+    if result.is_err() && cancel::requested() {
+        for entry in journal.entries() {
+            tracing::info!(
+                project = entry.project,
+                "done before stopping: {}",
+                entry.step
+            );
+        }
+        match journal.path() {
+            Some(path) => tracing::info!(
+                "continue with --resume {0} or undo with `gitlab-helper rollback {0}`",
+                path.display()
+            ),
+            None => tracing::info!("pass --journal to be able to resume or roll back a run"),
+        }
+    } else if let (Err(_), Some(path)) = (&result, journal.path()) {
+        tracing::info!(
+            "steps completed so far are in {0}; retry with --resume {0}",
+            path.display()
+        );
+    }
+    result
+}
 
-### What does this change do?
+fn resolve_projects(
+    client: &client::Client,
+    projects: Vec<String>,
+    group: Option<&str>,
+    config: &config::Config,
+) -> anyhow::Result<Vec<String>> {
+    Ok(if !projects.is_empty() {
+        projects
+    } else if let Some(group) = group {
+        fleet::group_projects(client, group)?
+    } else if !config.projects.is_empty() {
+        config.projects.clone()
+    } else if let Some(group) = &config.group {
+        fleet::group_projects(client, group)?
+    } else {
+        vec![GITLAB_PROJECT_ID.to_owned()]
+    })
+}
 
-### How to test this change?"
-                ))
-                .assignee(gitlab_user_id)
-                .build()?;
+fn incident_update(
+    config: &config::Config,
+    id: Option<String>,
+    open: bool,
+) -> anyhow::Result<Option<incident::Update>> {
+    if id.is_none() && !open {
+        return Ok(None);
+    }
+    let Some(config) = &config.incident else {
+        anyhow::bail!("set up [incident] in the config to record patches in incidents");
+    };
+    Ok(Some(incident::Update {
+        config: config.clone(),
+        id,
+    }))
+}
 
-            let _: Result<serde_json::Value, _> = mr2.query(&client);
+fn dispatch(
+    command: Option<Commands>,
+    ctx: &workflow::Context,
+    config: &config::Config,
+    group: Option<&str>,
+    projects: &[String],
+    jobs: std::num::NonZeroUsize,
+) -> anyhow::Result<()> {
+    let client = ctx.client;
+    match command {
+        Some(Commands::EmergencyPatch {
+            pick: true,
+            description_template,
+            compare_summary,
+            incident,
+            open_incident,
+            picks,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("--pick works on a single project");
+            };
+            let assignee = match &config.oncall {
+                Some(oncall) => Some(oncall::assignee(client, oncall)?),
+                None => std::env::var("GITLAB_USER_ID")
+                    .ok()
+                    .and_then(|id| id.parse().ok()),
+            };
+            let mut patch = picker::pick(client, project, assignee)?;
+            patch.description_template = description_template
+                .as_deref()
+                .or(config.emergency_patch.description_template.as_deref())
+                .map(template::load)
+                .transpose()?;
+            patch.compare_summary = compare_summary;
+            patch.incident = incident_update(config, incident, open_incident)?;
+            patch.picks = picks;
+            patch.sla = config.emergency_patch.sla;
+            let summary = emergency::run(ctx, project, &patch)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::EmergencyPatch {
+            pick: false,
+            description_template,
+            compare_summary,
+            incident,
+            open_incident,
+            picks,
+        }) => {
+            let assignee = match &config.oncall {
+                Some(oncall) => oncall::assignee(client, oncall)?,
+                None => std::env::var("GITLAB_USER_ID")?.parse::<u64>()?,
+            };
+            let mut patch = emergency::Patch::new(assignee);
+            if !config.emergency_patch.targets.is_empty() {
+                patch.targets = config.emergency_patch.targets.clone();
+            }
+            patch.description_template = description_template
+                .as_deref()
+                .or(config.emergency_patch.description_template.as_deref())
+                .map(template::load)
+                .transpose()?;
+            patch.compare_summary = compare_summary;
+            patch.incident = incident_update(config, incident, open_incident)?;
+            patch.picks = picks;
+            patch.sla = config.emergency_patch.sla;
+            fleet::run(projects, jobs, |project| {
+                emergency::run(ctx, project, &patch)
+            })?;
+        }
+        Some(Commands::CheckSla { sla }) => {
+            let sla = sla
+                .or(config.emergency_patch.sla)
+                .context("pass --sla or set emergency_patch.sla in the config")?;
+            sla::check(
+                client,
+                projects,
+                sla,
+                config.notify.webhook_url().as_deref(),
+            )?;
+        }
+        Some(Commands::Compare { from, to, summary }) => {
+            for project in projects {
+                compare::run(client, project, &from, &to, summary)?;
+            }
+        }
+        Some(Commands::GenerateReleaseNotes {
+            from,
+            to,
+            target_branch,
+            format,
+            confluence,
+        }) => {
+            anyhow::ensure!(
+                !confluence || format != release_notes::NotesFormat::ConventionalJson,
+                "--confluence publishes Markdown; pick another --format"
+            );
+            let confluence = match (confluence, &config.confluence) {
+                (true, None) => anyhow::bail!("--confluence needs a [confluence] section"),
+                (true, Some(confluence)) => Some(confluence),
+                (false, _) => None,
+            };
+            for project in projects {
+                release_notes::run(
+                    client,
+                    project,
+                    target_branch.as_deref(),
+                    &from,
+                    &to,
+                    format,
+                    confluence,
+                )?;
+            }
+        }
+        Some(Commands::PublishRelease {
+            tag,
+            from,
+            target_branch,
+        }) => {
+            fleet::run(projects, jobs, |project| {
+                release::publish(
+                    ctx,
+                    project,
+                    &tag,
+                    from.as_deref(),
+                    target_branch.as_deref(),
+                    config.confluence.as_ref(),
+                )
+            })?;
+        }
+        Some(Commands::Provenance(provenance)) => {
+            let [project] = projects else {
+                anyhow::bail!("provenance works on a single project");
+            };
+            let summary = provenance::record(ctx, &config.provenance, project, &provenance)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::JiraReport(report)) => {
+            let Some(jira) = &config.jira else {
+                anyhow::bail!("jira-report needs a [jira] section");
+            };
+            for project in projects {
+                jira::report(client, jira, project, &report)?;
+            }
+        }
+        Some(Commands::ExportHistory {
+            since,
+            until,
+            target_branch,
+            format,
+        }) => {
+            history::export(
+                client,
+                projects,
+                target_branch.as_deref(),
+                &since,
+                &until,
+                format,
+            )?;
+        }
+        Some(Commands::Epic {
+            command: EpicCommand::Link(link),
+        }) => {
+            let group = group
+                .or(config.group.as_deref())
+                .context("epic link needs --group or `group` in the config")?;
+            fleet::run(projects, jobs, |project| {
+                epics::link(ctx, group, project, &link)
+            })?;
+        }
+        Some(Commands::EpicStatus { epic, format }) => {
+            let group = group
+                .or(config.group.as_deref())
+                .context("epic-status needs --group or `group` in the config")?;
+            epics::status(client, group, epic, format)?;
+        }
+        Some(Commands::BoardReport { board, format }) => {
+            let [project] = projects else {
+                anyhow::bail!("board-report works on a single project");
+            };
+            boards::report(client, project, &board, format)?;
+        }
+        Some(Commands::AssignIteration(assign)) => {
+            let group = group
+                .or(config.group.as_deref())
+                .context("assign-iteration needs --group or `group` in the config")?;
+            let [project] = projects else {
+                anyhow::bail!("assign-iteration works on a single project");
+            };
+            let summary = iterations::assign(ctx, group, project, &assign)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::PromoteRc {
+            rc,
+            announce_into,
+            variables,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("promote-rc works on a single project");
+            };
+            let promotion = promote::Promotion {
+                rc,
+                announce_into,
+                variables,
+            };
+            let summary = promote::run(ctx, project, &promotion)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::PropagateFix {
+            from,
+            through,
+            poll,
+            wait,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("propagate-fix works on a single project");
+            };
+            let through = if through.is_empty() {
+                &config.propagate.chain
+            } else {
+                &through
+            };
+            let waiting = propagate::Waiting { poll, wait };
+            let summary = propagate::run(ctx, project, &from, through, waiting)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::StartFix(start)) => {
+            let [project] = projects else {
+                anyhow::bail!("start-fix works on a single project");
+            };
+            let summary = start_fix::start(ctx, project, &start)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::DraftDescription { iid }) => {
+            let [project] = projects else {
+                anyhow::bail!("draft-description works on a single project");
+            };
+            let summary = draft_description::draft(ctx, project, iid)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::Variables {
+            command: VariablesCommand::Set(variable),
+        }) => {
+            fleet::run(projects, jobs, |project| {
+                ctx.confirm(project, &[format!("set the {} variable", variable.key)])?;
+                variables::set(client, project, &variable)
+            })?;
+        }
+        Some(Commands::Run { name, file, vars }) => {
+            let workflows = workflow_file::WorkflowFile::load(&file)?;
+            let workflow = workflows.get(&name)?;
+            let vars: template::Vars = vars.into_iter().collect();
+            fleet::run(projects, jobs, |project| {
+                workflow_file::run(ctx, &name, workflow, project, vars.clone())
+            })?;
+        }
+        Some(Commands::CiLint {
+            path,
+            dry_run,
+            ref_,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("ci-lint works on a single project");
+            };
+            ci::lint(client, project, &path, dry_run, ref_.as_deref())?;
+        }
+        Some(Commands::CiGraph { file, ref_, format }) => {
+            let [project] = projects else {
+                anyhow::bail!("ci-graph works on a single project");
+            };
+            ci::graph(client, project, file.as_deref(), ref_.as_deref(), format)?;
+        }
+        Some(Commands::JobStats { job, last, format }) => {
+            job_stats::run(client, projects, &job, last, format)?
+        }
+        Some(Commands::Envs {
+            command: EnvsCommand::Status { format },
+        }) => environments::status(client, projects, format)?,
+        Some(Commands::Runners {
+            command: RunnersCommand::Status,
+        }) => runners::status(client, group, projects)?,
+        Some(Commands::PipelineBisect {
+            good,
+            bad,
+            job,
+            poll,
+            wait,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("pipeline-bisect works on a single project");
+            };
+            bisect::run(
+                ctx,
+                project,
+                &good,
+                &bad,
+                &job,
+                &bisect::Polling { poll, wait },
+            )?;
+        }
+        Some(Commands::Revert(revert)) => {
+            let [project] = projects else {
+                anyhow::bail!("revert works on a single project");
+            };
+            let summary = revert::run(ctx, project, &revert, jobs)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::RollbackDeploy(rollback)) => {
+            let [project] = projects else {
+                anyhow::bail!("rollback-deploy works on a single project");
+            };
+            let summary = rollback_deploy::run(ctx, project, &rollback)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::BuildMergeMessage { iid }) => {
+            let [project] = projects else {
+                anyhow::bail!("build-merge-message works on a single project");
+            };
+            println!("{}", merge::build_message(client, project, iid)?);
+        }
+        Some(Commands::MergeWhenReady {
+            iid,
+            remove_source_branch,
+            poll,
+            wait,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("merge-when-ready works on a single project");
+            };
+            let summary = merge::run(
+                client,
+                project,
+                iid,
+                &config.merge,
+                remove_source_branch,
+                poll,
+                wait,
+            )?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::SetStatus(status)) => {
+            let [project] = projects else {
+                anyhow::bail!("set-status works on a single project");
+            };
+            commit_status::set(client, project, &status)?;
+            tracing::info!(
+                project,
+                "{} is {:?} on {}",
+                status.name,
+                status.state,
+                status.sha
+            );
+        }
+        Some(Commands::CreateIncidentIssue(incident)) => {
+            let [project] = projects else {
+                anyhow::bail!("create-incident-issue works on a single project");
+            };
+            println!("{}", incident_issue::create(client, project, &incident)?);
+        }
+        Some(Commands::Postmortem {
+            command: PostmortemCommand::Scaffold(scaffold),
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("postmortem scaffold works on a single project");
+            };
+            let summary = postmortem::scaffold(ctx, project, &scaffold)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::StatusPage {
+            command: StatusPageCommand::Update(update),
+        }) => {
+            let Some(status_page) = &config.status_page else {
+                anyhow::bail!("set up [status_page] in the config first");
+            };
+            let [project] = projects else {
+                anyhow::bail!("status-page update works on a single project");
+            };
+            let summary = status_page::update(client, project, status_page, &update)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::File { command }) => match command {
+            FileCommand::Get { path, ref_ } => {
+                let [project] = projects else {
+                    anyhow::bail!("file get works on a single project");
+                };
+                files::print(client, project, &path, &ref_)?;
+            }
+            FileCommand::Put(put) => {
+                let batch = put.batch()?;
+                fleet::run(projects, jobs, |project| files::put(ctx, project, &batch))?;
+            }
+        },
+        Some(Commands::ChangedComponents { iid, dotenv }) => {
+            let [project] = projects else {
+                anyhow::bail!("changed-components works on a single project");
+            };
+            components::run(client, project, iid, &config.components, dotenv.as_deref())?;
+        }
+        Some(Commands::GenerateChildPipeline {
+            iid,
+            template,
+            header,
+            out,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("generate-child-pipeline works on a single project");
+            };
+            let header = header.as_deref().map(template::load).transpose()?;
+            let jobs = template::load(&template)?;
+            let changed = components::changed(client, project, iid, &config.components)?;
+            let pipeline = components::child_pipeline(header.as_deref(), &jobs, &changed)?;
+            std::fs::write(&out, pipeline)
+                .with_context(|| format!("failed to write {}", out.display()))?;
+            tracing::info!(
+                project,
+                components = changed.len(),
+                "wrote {}",
+                out.display()
+            );
+        }
+        Some(Commands::CheckMr { iid, reports }) => {
+            let [project] = projects else {
+                anyhow::bail!("check-mr works on a single project");
+            };
+            mr_rules::check(client, project, iid, &config.mr_rules, &reports)?;
+        }
+        Some(Commands::CheckDiscussions { iid, reports }) => {
+            let [project] = projects else {
+                anyhow::bail!("check-discussions works on a single project");
+            };
+            discussions::check(client, project, iid, &reports)?;
+        }
+        Some(Commands::CheckDiff {
+            iid,
+            no_comment,
+            reports,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("check-diff works on a single project");
+            };
+            diff_check::check(
+                client,
+                project,
+                iid,
+                &config.check_diff,
+                !no_comment,
+                &reports,
+            )?;
+        }
+        Some(Commands::CheckCompat {
+            base,
+            head,
+            iid,
+            reports,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("check-compat works on a single project");
+            };
+            compat::check(
+                client,
+                project,
+                &base,
+                &head,
+                iid,
+                &config.check_compat,
+                &reports,
+            )?;
+        }
+        Some(Commands::CheckSignatures {
+            iid,
+            allowed_authors,
+            reports,
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("check-signatures works on a single project");
+            };
+            signatures::check(client, project, iid, &allowed_authors, &reports)?;
+        }
+        Some(Commands::FeatureFlag { command }) => {
+            let [project] = projects else {
+                anyhow::bail!("feature-flag works on a single project");
+            };
+            let summary = match command {
+                FeatureFlagCommand::Enable(toggle) => {
+                    feature_flags::toggle(ctx, project, &toggle, true)?
+                }
+                FeatureFlagCommand::Disable(toggle) => {
+                    feature_flags::toggle(ctx, project, &toggle, false)?
+                }
+            };
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::Freeze { command }) => match command {
+            FreezeCommand::Create(period) => {
+                fleet::run(projects, jobs, |project| {
+                    freeze::create(ctx, project, &period)
+                })?;
+            }
+            FreezeCommand::List { format } => freeze::list(client, projects, format)?,
+            FreezeCommand::Delete { id } => {
+                let [project] = projects else {
+                    anyhow::bail!("freeze delete works on a single project");
+                };
+                let summary = freeze::delete(ctx, project, id)?;
+                tracing::info!(project, "{summary}");
+            }
+        },
+        Some(Commands::DeployToken {
+            command: DeployTokenCommand::Create(token),
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("deploy-token create works on a single project");
+            };
+            let summary = deploy::create_token(ctx, project, &token)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::DeployKey {
+            command: DeployKeyCommand::Add(key),
+        }) => {
+            let [project] = projects else {
+                anyhow::bail!("deploy-key add works on a single project");
+            };
+            let summary = deploy::add_key(ctx, project, &key)?;
+            tracing::info!(project, "{summary}");
+        }
+        Some(Commands::Hooks { command }) => match command {
+            HooksCommand::Add(hook) => {
+                if let Some(secret) = &hook.secret {
+                    redact::register(secret);
+                }
+                fleet::run(projects, jobs, |project| webhooks::add(ctx, project, &hook))?;
+            }
+            HooksCommand::List => {
+                for project in projects {
+                    webhooks::list(client, project)?;
+                }
+            }
+            HooksCommand::Remove { hook } => {
+                fleet::run(projects, jobs, |project| {
+                    webhooks::remove(ctx, project, &hook)
+                })?;
+            }
+        },
+        Some(Commands::JobTokenScope { command }) => match command {
+            JobTokenScopeCommand::Add { targets } => {
+                fleet::run(projects, jobs, |project| {
+                    job_token::add(ctx, project, &targets)
+                })?;
+            }
+            JobTokenScopeCommand::Remove { targets } => {
+                fleet::run(projects, jobs, |project| {
+                    job_token::remove(ctx, project, &targets)
+                })?;
+            }
+            JobTokenScopeCommand::List => {
+                for project in projects {
+                    job_token::list(client, project)?;
+                }
+            }
+        },
+        Some(Commands::Labels {
+            command: LabelsCommand::Sync,
+        }) => {
+            anyhow::ensure!(!config.labels.is_empty(), "the config has no [[labels]]");
+            fleet::run(projects, jobs, |project| {
+                labels::sync(ctx, project, &config.labels)
+            })?;
+        }
+        Some(Commands::Protect {
+            command: ProtectCommand::Apply,
+        }) => {
+            let protect = &config.protect;
+            anyhow::ensure!(
+                !protect.branches.is_empty() || !protect.tags.is_empty(),
+                "the config has no [[protect.branches]] or [[protect.tags]]"
+            );
+            fleet::run(projects, jobs, |project| {
+                protect::apply(ctx, project, protect)
+            })?;
+        }
+        Some(Commands::AuditSettings { file, fix }) => {
+            let desired = settings::Desired::load(&file)?;
+            fleet::run(projects, jobs, |project| {
+                settings::audit(ctx, project, &desired, &file, fix)
+            })?;
+        }
+        Some(Commands::AuditMembers {
+            approved_groups,
+            stale_after,
+            format,
+        }) => {
+            let policy = members::Policy {
+                approved_groups: &approved_groups,
+                stale_after_days: stale_after,
+            };
+            members::audit(client, projects, &policy, format)?;
+        }
+        Some(Commands::AuditTokens { within, notify }) => {
+            let notify_url = if notify {
+                Some(
+                    config
+                        .notify
+                        .webhook_url()
+                        .context("--notify needs notify.webhook_url or NOTIFY_WEBHOOK_URL")?,
+                )
+            } else {
+                None
+            };
+            tokens::audit(client, group, projects, within, notify_url.as_deref())?;
+        }
+        Some(Commands::Badges {
+            command: BadgesCommand::Apply,
+        }) => {
+            anyhow::ensure!(!config.badges.is_empty(), "the config has no [[badges]]");
+            fleet::run(projects, jobs, |project| {
+                badges::apply(ctx, project, &config.badges)
+            })?;
+        }
+        Some(Commands::Bootstrap { file }) => {
+            let setup = bootstrap::Setup::load(&file)?;
+            for variable in setup.variables.iter().filter(|variable| variable.masked) {
+                redact::register(&variable.value);
+            }
+            for secret in setup
+                .webhooks
+                .iter()
+                .filter_map(|hook| hook.secret.as_ref())
+            {
+                redact::register(secret);
+            }
+            fleet::run(projects, jobs, |project| {
+                bootstrap::run(ctx, project, &setup, &file)
+            })?;
+        }
+        Some(Commands::CiCheckVars { path, reports }) => {
+            let [project] = projects else {
+                anyhow::bail!("ci-check-vars works on a single project");
+            };
+            ci::check_vars(client, project, &path, &reports)?;
         }
-        Some(Commands::GenerateReleaseNotes) => {
-            todo!();
+        Some(
+            Commands::Serve { .. }
+            | Commands::Rollback { .. }
+            | Commands::Completions { .. }
+            | Commands::Auth { .. }
+            | Commands::Doctor { .. }
+            | Commands::Config { .. }
+            | Commands::Template { .. }
+            | Commands::LicensesReport { .. }
+            | Commands::SuggestTitle { .. }
+            | Commands::LintTitle { .. },
+        ) => {
+            unreachable!("handled before resolving projects")
         }
         None => {
             anyhow::bail!("No command provided");
@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use gitlab::api::groups::members::GroupMembers;
+use gitlab::api::projects::members::{AllProjectMembers, ProjectMembers};
+use gitlab::api::{self, users, Query};
+use serde::Deserialize;
+
+use crate::client::{self, Client};
+use crate::table::{self, Format};
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    id: u64,
+    username: String,
+    name: String,
+    #[serde(default)]
+    state: String,
+    access_level: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    last_activity_on: Option<NaiveDate>,
+}
+
+fn role(access_level: u64) -> String {
+    match access_level {
+        5 => "minimal".to_owned(),
+        10 => "guest".to_owned(),
+        15 => "planner".to_owned(),
+        20 => "reporter".to_owned(),
+        30 => "developer".to_owned(),
+        40 => "maintainer".to_owned(),
+        50 => "owner".to_owned(),
+        level => format!("level {level}"),
+    }
+}
+
+/// What the audit looks for besides listing everyone.
+pub struct Policy<'a> {
+    /// Groups whose members may be direct maintainers; none means anyone may.
+    pub approved_groups: &'a [String],
+    pub stale_after_days: u32,
+}
+
+/// When the user was last active, as far as the token may see; only
+/// administrators see it for other users.
+fn last_activity(
+    client: &Client,
+    cache: &mut HashMap<u64, Option<NaiveDate>>,
+    user: u64,
+) -> anyhow::Result<Option<NaiveDate>> {
+    if let Some(known) = cache.get(&user) {
+        return Ok(*known);
+    }
+    let endpoint = users::User::builder().user(user).build()?;
+    let activity = match endpoint.query(client) {
+        Ok(User { last_activity_on }) => last_activity_on,
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => None,
+        Err(err) => return Err(err.into()),
+    };
+    cache.insert(user, activity);
+    Ok(activity)
+}
+
+/// Prints the members of `projects`, direct or inherited, in `format`, and
+/// fails if a direct maintainer is outside the approved groups or an account
+/// is stale.
+pub fn audit(
+    client: &Client,
+    projects: &[String],
+    policy: &Policy,
+    format: Format,
+) -> anyhow::Result<()> {
+    let mut approved = HashSet::new();
+    for group in policy.approved_groups {
+        let endpoint = GroupMembers::builder().group(group.as_str()).build()?;
+        let members: Vec<Member> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        approved.extend(members.into_iter().map(|member| member.id));
+    }
+
+    let today = chrono::Utc::now().date_naive();
+    let mut activity = HashMap::new();
+    let mut rows = Vec::new();
+    let mut flagged = 0;
+    for project in projects {
+        let endpoint = ProjectMembers::builder()
+            .project(project.as_str())
+            .build()?;
+        let direct: Vec<Member> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        let endpoint = AllProjectMembers::builder()
+            .project(project.as_str())
+            .build()?;
+        let all: Vec<Member> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        for member in all {
+            let is_direct = direct.iter().any(|direct| direct.id == member.id);
+            let last_active = last_activity(client, &mut activity, member.id)?;
+            let mut flags = Vec::new();
+            if is_direct
+                && member.access_level >= 40
+                && !policy.approved_groups.is_empty()
+                && !approved.contains(&member.id)
+            {
+                flags.push("maintainer outside the approved groups".to_owned());
+            }
+            if let Some(last_active) = last_active {
+                let idle = (today - last_active).num_days();
+                if idle > policy.stale_after_days.into() {
+                    flags.push(format!("inactive for {idle} days"));
+                }
+            }
+            if !member.state.is_empty() && member.state != "active" {
+                flags.push(member.state.clone());
+            }
+            flagged += usize::from(!flags.is_empty());
+            rows.push([
+                project.clone(),
+                member.username,
+                member.name,
+                role(member.access_level),
+                if is_direct { "direct" } else { "inherited" }.to_owned(),
+                last_active.map_or("unknown".to_owned(), |date| date.to_string()),
+                flags.join("; "),
+            ]);
+        }
+    }
+    println!(
+        "{}",
+        table::render_as(
+            format,
+            [
+                "PROJECT",
+                "USERNAME",
+                "NAME",
+                "ACCESS",
+                "MEMBERSHIP",
+                "LAST ACTIVITY",
+                "FLAGS"
+            ],
+            &rows
+        )
+    );
+    anyhow::ensure!(flagged == 0, "{flagged} membership(s) flagged");
+    Ok(())
+}
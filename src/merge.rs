@@ -0,0 +1,234 @@
+use std::time::{Duration, Instant};
+
+use gitlab::api::projects::merge_requests::approvals::MergeRequestApprovals;
+use gitlab::api::projects::merge_requests::{MergeMergeRequest, MergeRequest};
+use gitlab::api::Query;
+use serde::Deserialize;
+
+use crate::cancel;
+use crate::client::Client;
+use crate::progress::Progress;
+use crate::template::{self, Vars};
+use crate::title;
+
+/// The `[merge]` section: how `merge-when-ready` merges.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MergeConfig {
+    /// Squash the commits; the project's default if unset.
+    pub squash: Option<bool>,
+    /// A template for the merge or squash commit's message, with
+    /// `{{ title }}`, `{{ iid }}`, `{{ source_branch }}` and `{{ url }}`;
+    /// `build-merge-message`'s if unset and the title follows the convention.
+    pub message: Option<String>,
+    #[serde(default)]
+    pub remove_source_branch: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mr {
+    title: String,
+    state: String,
+    sha: String,
+    source_branch: String,
+    web_url: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    blocking_discussions_resolved: bool,
+    head_pipeline: Option<Pipeline>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Approvals {
+    approved: bool,
+    #[serde(default)]
+    approvals_left: u64,
+    #[serde(default)]
+    approved_by: Vec<Approval>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Approval {
+    user: User,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    name: String,
+    username: String,
+}
+
+/// The title's Jira ID first, then those of the same Jira project that the
+/// source branch and description mention.
+fn jira_ids(jira_id: &str, mr: &Mr) -> Vec<String> {
+    let mut ids = vec![jira_id.to_ascii_uppercase()];
+    let key = jira_id.split('-').next().unwrap_or_default();
+    let mentioned = std::iter::once(mr.source_branch.as_str())
+        .chain(mr.description.as_deref())
+        .flat_map(|text| text.split(|c: char| !c.is_ascii_alphanumeric() && c != '-'))
+        .filter_map(|word| {
+            let (prefix, number) = word.split_once('-')?;
+            let number = number.split('-').next()?;
+            let is_id = prefix.eq_ignore_ascii_case(key)
+                && !number.is_empty()
+                && number.chars().all(|c| c.is_ascii_digit());
+            is_id.then(|| format!("{}-{number}", prefix.to_ascii_uppercase()))
+        });
+    for id in mentioned {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// The merge commit message in our format: the canonical title, then the
+/// Jira IDs, approvers and merge request as trailers.
+fn message(mr: &Mr, iid: u64, approvals: &Approvals) -> anyhow::Result<String> {
+    let parsed = title::parse_merge_request(&mut mr.title.as_str()).map_err(|err| {
+        anyhow::anyhow!("the title of !{iid} does not follow the convention:\n{err}")
+    })?;
+    let mut message = format!(
+        "{parsed}\n\nJira: {}",
+        jira_ids(parsed.jira_id, mr).join(", ")
+    );
+    for approval in &approvals.approved_by {
+        message.push_str(&format!(
+            "\nApproved-by: {} (@{})",
+            approval.user.name, approval.user.username
+        ));
+    }
+    message.push_str(&format!("\nMerge-request: !{iid} {}", mr.web_url));
+    Ok(message)
+}
+
+fn fetch(client: &Client, project: &str, iid: u64) -> anyhow::Result<(Mr, Approvals)> {
+    let mr: Mr = MergeRequest::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?
+        .query(client)?;
+    let approvals: Approvals = MergeRequestApprovals::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?
+        .query(client)?;
+    Ok((mr, approvals))
+}
+
+/// The commit message merge request `iid` is merged with unless
+/// `[merge] message` says otherwise.
+pub fn build_message(client: &Client, project: &str, iid: u64) -> anyhow::Result<String> {
+    let (mr, approvals) = fetch(client, project, iid)?;
+    message(&mr, iid, &approvals)
+}
+
+/// What `mr` still waits for; empty once it can be merged.
+fn waiting_for(mr: &Mr, approvals: &Approvals) -> anyhow::Result<Vec<String>> {
+    let mut waiting = Vec::new();
+    if !approvals.approved {
+        waiting.push(format!("{} approval(s)", approvals.approvals_left.max(1)));
+    }
+    if !mr.blocking_discussions_resolved {
+        waiting.push("unresolved discussions".to_owned());
+    }
+    match mr
+        .head_pipeline
+        .as_ref()
+        .map(|pipeline| pipeline.status.as_str())
+    {
+        Some("success") => {}
+        Some(status @ ("failed" | "canceled")) => {
+            anyhow::bail!("the pipeline of {} {status}", mr.web_url)
+        }
+        Some(status) => waiting.push(format!("the pipeline ({status})")),
+        None => waiting.push("a pipeline".to_owned()),
+    }
+    Ok(waiting)
+}
+
+/// Waits until merge request `iid` is approved, has no unresolved
+/// discussions and a green pipeline, polling at `poll` and backing off to
+/// eight times that, then merges it as `config` says.
+pub fn run(
+    client: &Client,
+    project: &str,
+    iid: u64,
+    config: &MergeConfig,
+    remove_source_branch: bool,
+    poll: Duration,
+    wait: Duration,
+) -> anyhow::Result<String> {
+    let started = Instant::now();
+    let mut interval = poll;
+    let progress = Progress::spinner(&format!("waiting on !{iid}"));
+    let (mr, approvals) = loop {
+        let (mr, approvals) = fetch(client, project, iid)?;
+        match mr.state.as_str() {
+            "opened" => {}
+            "merged" => return Ok(format!("{} is already merged", mr.web_url)),
+            state => anyhow::bail!("{} is {state}", mr.web_url),
+        }
+        let waiting = waiting_for(&mr, &approvals)?;
+        if waiting.is_empty() {
+            break (mr, approvals);
+        }
+        anyhow::ensure!(
+            started.elapsed() + interval <= wait,
+            "gave up on {} after {:?}; still waiting for {}",
+            mr.web_url,
+            started.elapsed(),
+            waiting.join(", ")
+        );
+        tracing::info!(project, "waiting for {}", waiting.join(", "));
+        progress.set_message(waiting.join(", "));
+        progress.wait(interval);
+        cancel::check()?;
+        interval = (interval * 2).min(poll * 8);
+    };
+    drop(progress);
+
+    let message = match config.message.as_deref() {
+        Some(message) => {
+            let vars = Vars::from([
+                ("title".to_owned(), mr.title.clone()),
+                ("iid".to_owned(), iid.to_string()),
+                ("source_branch".to_owned(), mr.source_branch.clone()),
+                ("url".to_owned(), mr.web_url.clone()),
+            ]);
+            Some(template::render(message, &vars)?)
+        }
+        None => match message(&mr, iid, &approvals) {
+            Ok(message) => Some(message),
+            Err(err) => {
+                tracing::warn!("merging with GitLab's message: {err:#}");
+                None
+            }
+        },
+    };
+    let mut merge = MergeMergeRequest::builder();
+    merge
+        .project(project)
+        .merge_request(iid)
+        // Whatever was approved and tested is what gets merged.
+        .sha(mr.sha.as_str())
+        .should_remove_source_branch(remove_source_branch || config.remove_source_branch);
+    if let Some(squash) = config.squash {
+        merge.squash(squash);
+    }
+    if let Some(message) = &message {
+        if config.squash == Some(true) {
+            merge.squash_commit_message(message.as_str());
+        } else {
+            merge.merge_commit_message(message.as_str());
+        }
+    }
+    gitlab::api::ignore(merge.build()?).query(client)?;
+    Ok(format!("merged {}", mr.web_url))
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Every metric we export, with its type and help text.
+const METRICS: &[(&str, &str, &str)] = &[
+    (
+        "gitlab_helper_webhooks_total",
+        "counter",
+        "Webhooks received, by event kind and result.",
+    ),
+    (
+        "gitlab_helper_workflows_total",
+        "counter",
+        "Comment commands executed, by command and result.",
+    ),
+    (
+        "gitlab_helper_api_errors_total",
+        "counter",
+        "GitLab API requests that failed or returned an error status.",
+    ),
+    (
+        "gitlab_helper_api_request_duration_seconds",
+        "histogram",
+        "Latency of GitLab API requests.",
+    ),
+];
+
+const BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Keyed by name and the rendered label set.
+    counters: BTreeMap<(&'static str, String), u64>,
+    histograms: BTreeMap<&'static str, Histogram>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    counters: BTreeMap::new(),
+    histograms: BTreeMap::new(),
+});
+
+fn labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<_> = labels
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{key}=\"{}\"",
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+pub fn increment(name: &'static str, label_values: &[(&str, &str)]) {
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry
+        .counters
+        .entry((name, labels(label_values)))
+        .or_default() += 1;
+}
+
+pub fn observe(name: &'static str, elapsed: Duration) {
+    let seconds = elapsed.as_secs_f64();
+    let mut registry = REGISTRY.lock().unwrap();
+    let histogram = registry.histograms.entry(name).or_default();
+    for (bucket, bound) in histogram.buckets.iter_mut().zip(BUCKETS) {
+        if seconds <= bound {
+            *bucket += 1;
+        }
+    }
+    histogram.count += 1;
+    histogram.sum += seconds;
+}
+
+/// The Prometheus text exposition of everything recorded so far.
+pub fn render() -> String {
+    let registry = REGISTRY.lock().unwrap();
+    let mut out = String::new();
+    for (name, kind, help) in METRICS {
+        writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}").unwrap();
+        for ((_, labels), value) in registry.counters.iter().filter(|((n, _), _)| n == name) {
+            writeln!(out, "{name}{labels} {value}").unwrap();
+        }
+        if let Some(histogram) = registry.histograms.get(name) {
+            for (count, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {count}").unwrap();
+            }
+            writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", histogram.count).unwrap();
+            writeln!(out, "{name}_sum {}", histogram.sum).unwrap();
+            writeln!(out, "{name}_count {}", histogram.count).unwrap();
+        }
+    }
+    out
+}
@@ -0,0 +1,233 @@
+use anyhow::Context as _;
+use gitlab::api::projects::merge_requests::{MergeRequest, MergeRequestDiffs};
+use gitlab::api::{self, Query};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::checks::{Case, Reports};
+use crate::client::Client;
+use crate::title;
+
+/// A `[[mr_rules]]` entry: what a merge request touching `paths` must also
+/// contain.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathRule {
+    pub name: String,
+    /// Globs like `migrations/` or `services/*/openapi.yaml`; `**` crosses directories.
+    pub paths: Vec<String>,
+    /// Each of these globs must match a path the merge request changes too.
+    #[serde(default)]
+    pub require_changes: Vec<String>,
+    #[serde(default)]
+    pub require_labels: Vec<String>,
+}
+
+/// A glob as a regex over the whole path. A trailing `/` matches everything
+/// below the directory.
+pub fn glob(pattern: &str) -> anyhow::Result<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if pattern.ends_with('/') {
+        regex.push_str(".*");
+    }
+    regex.push('$');
+    Regex::new(&regex).with_context(|| format!("invalid path pattern {pattern:?}"))
+}
+
+pub fn any_matches(patterns: &[String], paths: &[String]) -> anyhow::Result<bool> {
+    Ok(first_match(patterns, paths)?.is_some())
+}
+
+/// The first of `paths` one of `patterns` matches.
+fn first_match<'a>(patterns: &[String], paths: &'a [String]) -> anyhow::Result<Option<&'a str>> {
+    for pattern in patterns {
+        let regex = glob(pattern)?;
+        if let Some(path) = paths.iter().find(|path| regex.is_match(path)) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// What `rule` finds missing from a merge request that changes `paths` and
+/// has `labels`.
+fn violations(rule: &PathRule, paths: &[String], labels: &[String]) -> anyhow::Result<Vec<String>> {
+    if !any_matches(&rule.paths, paths)? {
+        return Ok(Vec::new());
+    }
+    let mut missing = Vec::new();
+    for required in &rule.require_changes {
+        if !any_matches(std::slice::from_ref(required), paths)? {
+            missing.push(format!("{}: needs a change to {required}", rule.name));
+        }
+    }
+    for label in &rule.require_labels {
+        if !labels.contains(label) {
+            missing.push(format!("{}: needs the ~{label} label", rule.name));
+        }
+    }
+    Ok(missing)
+}
+
+#[derive(Debug, Deserialize)]
+struct Details {
+    title: String,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diff {
+    old_path: String,
+    new_path: String,
+}
+
+/// Every path merge request `iid` changes; a rename changes both.
+pub fn changed_paths(client: &Client, project: &str, iid: u64) -> anyhow::Result<Vec<String>> {
+    let endpoint = MergeRequestDiffs::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?;
+    let diffs: Vec<Diff> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    let mut paths: Vec<_> = diffs
+        .into_iter()
+        .flat_map(|diff| [diff.old_path, diff.new_path])
+        .collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Checks `title` against the naming convention, and fails saying what is
+/// wrong with it after writing the check to `reports`.
+pub fn lint_title(title: &str, reports: &Reports) -> anyhow::Result<()> {
+    let result = title::lint(title);
+    let case = match &result {
+        Ok(()) => Case::passed("title"),
+        Err(err) => Case::failed("title", err.as_str()),
+    };
+    reports.write("lint-title", &[case])?;
+    result.map_err(|err| anyhow::anyhow!("the title breaks the naming convention:\n{err}"))
+}
+
+/// Checks merge request `iid` against the title convention and `rules`,
+/// and fails listing whatever it breaks, after writing the checks to `reports`.
+pub fn check(
+    client: &Client,
+    project: &str,
+    iid: u64,
+    rules: &[PathRule],
+    reports: &Reports,
+) -> anyhow::Result<()> {
+    let details: Details = MergeRequest::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?
+        .query(client)?;
+    let paths = changed_paths(client, project, iid)?;
+
+    let mut problems = Vec::new();
+    let mut cases = Vec::new();
+    match title::lint(&details.title) {
+        Ok(()) => cases.push(Case::passed("title")),
+        Err(err) => {
+            cases.push(Case::failed("title", err.as_str()));
+            problems.push(format!("the title breaks the naming convention:\n{err}"));
+        }
+    }
+    for rule in rules {
+        let missing = violations(rule, &paths, &details.labels)?;
+        cases.push(if missing.is_empty() {
+            Case::passed(&rule.name)
+        } else {
+            let case = Case::failed(&rule.name, missing.join("\n"));
+            match first_match(&rule.paths, &paths)? {
+                Some(path) => case.at(path, 1),
+                None => case,
+            }
+        });
+        problems.extend(missing);
+    }
+    reports.write("check-mr", &cases)?;
+    for problem in &problems {
+        tracing::error!("{problem}");
+    }
+    anyhow::ensure!(
+        problems.is_empty(),
+        "!{iid} breaks {} rule(s)",
+        problems.len()
+    );
+    tracing::info!(project, iid, "the merge request passes every check");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        for (pattern, path, matches) in [
+            ("migrations/", "migrations/0001_init.sql", true),
+            ("migrations/", "app/migrations/0001_init.sql", false),
+            ("openapi.yaml", "openapi.yaml", true),
+            ("openapi.yaml", "docs/openapi.yaml", false),
+            (
+                "services/*/openapi.yaml",
+                "services/billing/openapi.yaml",
+                true,
+            ),
+            (
+                "services/*/openapi.yaml",
+                "services/a/b/openapi.yaml",
+                false,
+            ),
+            ("**/openapi.yaml", "services/a/b/openapi.yaml", true),
+            ("clients/**", "clients/ts/index.ts", true),
+            ("CHANGELOG.md", "CHANGELOG-md", false),
+        ] {
+            assert_eq!(
+                glob(pattern).unwrap().is_match(path),
+                matches,
+                "{pattern} ~ {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn rules_only_apply_to_merge_requests_touching_their_paths() {
+        let rule = PathRule {
+            name: "migrations".to_owned(),
+            paths: vec!["migrations/".to_owned()],
+            require_changes: vec!["CHANGELOG.md".to_owned()],
+            require_labels: vec!["db".to_owned()],
+        };
+        let untouched = violations(&rule, &["src/main.rs".to_owned()], &[]).unwrap();
+        assert!(untouched.is_empty());
+
+        let paths = ["migrations/0002.sql".to_owned()];
+        assert_eq!(
+            violations(&rule, &paths, &[]).unwrap(),
+            [
+                "migrations: needs a change to CHANGELOG.md",
+                "migrations: needs the ~db label"
+            ]
+        );
+        let complete = [paths[0].clone(), "CHANGELOG.md".to_owned()];
+        assert!(violations(&rule, &complete, &["db".to_owned()])
+            .unwrap()
+            .is_empty());
+    }
+}
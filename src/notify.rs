@@ -0,0 +1,43 @@
+use anyhow::Context;
+use serde::{Deserialize, Deserializer};
+
+use crate::client::Client;
+use crate::config;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Incoming webhook (Slack, Mattermost, Teams, ...) that receives `{"text": ...}`.
+    #[serde(deserialize_with = "webhook_url")]
+    pub webhook_url: Option<String>,
+}
+
+fn webhook_url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    config::checked(deserializer, |url| match url::Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        _ => Err("not an http(s) URL".to_owned()),
+    })
+    .map(Some)
+}
+
+impl NotifyConfig {
+    pub fn webhook_url(&self) -> Option<String> {
+        std::env::var("NOTIFY_WEBHOOK_URL")
+            .ok()
+            .or_else(|| self.webhook_url.clone())
+    }
+}
+
+pub fn send(client: &Client, webhook_url: &str, message: &str) -> anyhow::Result<()> {
+    if client.is_replaying() {
+        tracing::info!("replay: not sending the notification {message:?}");
+        return Ok(());
+    }
+    client
+        .external(http::Method::POST, webhook_url)?
+        .json(&serde_json::json!({ "text": message }))
+        .send()
+        .and_then(|rsp| rsp.error_for_status())
+        .context("failed to send the notification")?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+use chrono::NaiveDate;
+use gitlab::api::users::Users;
+use gitlab::api::Query;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::redact;
+
+/// The `[oncall]` section: who emergency patches are assigned to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case", deny_unknown_fields)]
+pub enum OnCall {
+    /// A weekly rota of GitLab usernames, the first one on call the week of `start`.
+    Rota {
+        start: NaiveDate,
+        engineers: Vec<String>,
+    },
+    /// The first on-call of a PagerDuty schedule; the key is `PAGERDUTY_API_TOKEN`.
+    Pagerduty {
+        schedule: String,
+        #[serde(default = "pagerduty_url")]
+        api_url: String,
+        /// GitLab usernames by email, for people whose email GitLab does not show.
+        #[serde(default)]
+        users: BTreeMap<String, String>,
+    },
+    /// The first on-call of an Opsgenie schedule, by name; the key is `OPSGENIE_API_KEY`.
+    Opsgenie {
+        schedule: String,
+        #[serde(default = "opsgenie_url")]
+        api_url: String,
+        #[serde(default)]
+        users: BTreeMap<String, String>,
+    },
+}
+
+pub fn pagerduty_url() -> String {
+    "https://api.pagerduty.com".to_owned()
+}
+
+pub fn opsgenie_url() -> String {
+    "https://api.opsgenie.com".to_owned()
+}
+
+/// Who is on call, as a GitLab username or an email address.
+enum Engineer {
+    Username(String),
+    Email(String),
+}
+
+pub fn api_key(name: &str) -> anyhow::Result<String> {
+    let key = std::env::var(name).with_context(|| format!("${name} is not set"))?;
+    redact::register(&key);
+    Ok(key)
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerdutyOncalls {
+    oncalls: Vec<PagerdutyOncall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerdutyOncall {
+    user: PagerdutyUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerdutyUser {
+    email: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpsgenieResponse {
+    data: OpsgenieOncalls,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpsgenieOncalls {
+    on_call_recipients: Vec<String>,
+}
+
+fn on_rota(start: NaiveDate, engineers: &[String], today: NaiveDate) -> Option<&String> {
+    let weeks = (today - start).num_days().div_euclid(7);
+    let index = weeks.rem_euclid(engineers.len().max(1) as i64);
+    engineers.get(index as usize)
+}
+
+fn current(client: &Client, oncall: &OnCall) -> anyhow::Result<Engineer> {
+    match oncall {
+        OnCall::Rota { start, engineers } => {
+            let today = chrono::Utc::now().date_naive();
+            on_rota(*start, engineers, today)
+                .map(|username| Engineer::Username(username.clone()))
+                .context("the on-call rota has no engineers")
+        }
+        OnCall::Pagerduty {
+            schedule,
+            api_url,
+            users,
+        } => {
+            let key = api_key("PAGERDUTY_API_TOKEN")?;
+            let found: PagerdutyOncalls = client
+                .external(http::Method::GET, &format!("{api_url}/oncalls"))?
+                .header("Authorization", format!("Token token={key}"))
+                .query(&[
+                    ("schedule_ids[]", schedule.as_str()),
+                    ("earliest", "true"),
+                    ("include[]", "users"),
+                ])
+                .send()
+                .and_then(|rsp| rsp.error_for_status())
+                .and_then(|rsp| rsp.json())
+                .context("failed to ask PagerDuty who is on call")?;
+            let email = found
+                .oncalls
+                .into_iter()
+                .next()
+                .map(|oncall| oncall.user.email)
+                .with_context(|| {
+                    format!("nobody is on call in the PagerDuty schedule {schedule}")
+                })?;
+            Ok(mapped(users, email))
+        }
+        OnCall::Opsgenie {
+            schedule,
+            api_url,
+            users,
+        } => {
+            let key = api_key("OPSGENIE_API_KEY")?;
+            let found: OpsgenieResponse = client
+                .external(
+                    http::Method::GET,
+                    &format!("{api_url}/v2/schedules/{schedule}/on-calls"),
+                )?
+                .header("Authorization", format!("GenieKey {key}"))
+                .query(&[("scheduleIdentifierType", "name"), ("flat", "true")])
+                .send()
+                .and_then(|rsp| rsp.error_for_status())
+                .and_then(|rsp| rsp.json())
+                .context("failed to ask Opsgenie who is on call")?;
+            let email = found
+                .data
+                .on_call_recipients
+                .into_iter()
+                .next()
+                .with_context(|| {
+                    format!("nobody is on call in the Opsgenie schedule {schedule}")
+                })?;
+            Ok(mapped(users, email))
+        }
+    }
+}
+
+fn mapped(users: &BTreeMap<String, String>, email: String) -> Engineer {
+    match users.get(&email) {
+        Some(username) => Engineer::Username(username.clone()),
+        None => Engineer::Email(email),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: u64,
+    username: String,
+}
+
+/// The GitLab user ID of whoever is on call now.
+pub fn assignee(client: &Client, oncall: &OnCall) -> anyhow::Result<u64> {
+    let engineer = current(client, oncall)?;
+    let mut endpoint = Users::builder();
+    let who = match &engineer {
+        Engineer::Username(username) => {
+            endpoint.username(username.as_str());
+            username
+        }
+        Engineer::Email(email) => {
+            endpoint.search(email.as_str());
+            email
+        }
+    };
+    let users: Vec<User> = endpoint.build()?.query(client)?;
+    let [user] = users.as_slice() else {
+        anyhow::bail!(
+            "{who} is on call, but {} GitLab users match; map them in [oncall.users]",
+            users.len()
+        );
+    };
+    tracing::info!(username = user.username, "assigning the on-call engineer");
+    Ok(user.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_rota_turns_over_every_week() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let engineers = ["alice", "bob", "carol"].map(str::to_owned);
+        for (day, expected) in [
+            ((2024, 1, 1), "alice"),
+            ((2024, 1, 7), "alice"),
+            ((2024, 1, 8), "bob"),
+            ((2024, 1, 22), "alice"),
+            ((2023, 12, 31), "carol"),
+        ] {
+            let today = NaiveDate::from_ymd_opt(day.0, day.1, day.2).unwrap();
+            assert_eq!(
+                on_rota(start, &engineers, today).map(String::as_str),
+                Some(expected),
+                "{today}"
+            );
+        }
+    }
+}
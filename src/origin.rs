@@ -0,0 +1,46 @@
+use gitlab::api::projects::merge_requests::notes::CreateMergeRequestNote;
+use gitlab::api::{self, Query};
+
+use crate::client::Client;
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Which CI job this run is part of and who started it, as in "Created by
+/// job <url> triggered by @user"; nothing outside of CI.
+pub fn footer() -> Option<String> {
+    let job = env("CI_JOB_URL")?;
+    Some(match env("GITLAB_USER_LOGIN") {
+        Some(user) => format!("Created by job {job} triggered by @{user}"),
+        None => format!("Created by job {job}"),
+    })
+}
+
+/// `description` with the footer below a rule, when there is one.
+pub fn sign(description: &str) -> String {
+    match footer() {
+        Some(footer) if description.is_empty() => footer,
+        Some(footer) => format!("{description}\n\n---\n\n{footer}"),
+        None => description.to_owned(),
+    }
+}
+
+/// Leaves the footer as a note on merge request `iid` too, so the activity
+/// shows it even after the description is edited. The merge request is there
+/// either way, so failing is only logged.
+pub fn note(client: &Client, project: &str, iid: u64) {
+    let Some(footer) = footer() else {
+        return;
+    };
+    let result = CreateMergeRequestNote::builder()
+        .project(project)
+        .merge_request(iid)
+        .body(footer)
+        .build()
+        .map_err(anyhow::Error::from)
+        .and_then(|note| Ok(api::ignore(note).query(client)?));
+    if let Err(err) = result {
+        tracing::warn!("failed to note the job on !{iid}: {err:#}");
+    }
+}
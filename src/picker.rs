@@ -0,0 +1,145 @@
+use dialoguer::{theme::ColorfulTheme, MultiSelect, Select};
+use gitlab::api::{
+    self,
+    common::SortOrder,
+    projects::{
+        members::AllProjectMembers,
+        pipelines::{PipelineOrderBy, Pipelines},
+        protected_branches::ProtectedBranches,
+    },
+    Pagination, Query,
+};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::emergency::{self, Patch};
+use crate::prompt;
+
+/// Looking up pipelines is a request per branch, so only the newest lines are offered.
+const RELEASES_SHOWN: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProtectedBranch {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    id: u64,
+    username: String,
+    name: String,
+}
+
+fn pipeline_status(client: &Client, project: &str, branch: &str) -> anyhow::Result<String> {
+    let endpoint = Pipelines::builder()
+        .project(project)
+        .ref_(branch)
+        .order_by(PipelineOrderBy::Id)
+        .sort(SortOrder::Descending)
+        .build()?;
+    let pipelines: Vec<Pipeline> = api::paged(endpoint, Pagination::Limit(1)).query(client)?;
+    Ok(pipelines
+        .into_iter()
+        .next()
+        .map_or_else(|| "no pipeline".to_owned(), |pipeline| pipeline.status))
+}
+
+fn selected<T>(choice: Option<T>) -> anyhow::Result<T> {
+    choice.ok_or_else(|| anyhow::anyhow!("cancelled; nothing was changed"))
+}
+
+/// Lets the operator choose the release line, targets and assignee of an
+/// emergency patch with the arrow keys.
+pub fn pick(client: &Client, project: &str, assignee: Option<u64>) -> anyhow::Result<Patch> {
+    anyhow::ensure!(prompt::interactive(), "--pick needs a terminal");
+    let theme = ColorfulTheme::default();
+
+    // Only the newest patch of each line can be patched: anything older would
+    // compute a patch branch that already exists.
+    let mut releases = emergency::releases(client, project)?;
+    releases.dedup_by_key(|version| (version.major, version.minor));
+    releases.truncate(RELEASES_SHOWN);
+    anyhow::ensure!(
+        !releases.is_empty(),
+        "{project} has no release/x.y.z branches"
+    );
+    let items = releases
+        .iter()
+        .map(|version| {
+            let branch = format!("release/{version}");
+            let status = pipeline_status(client, project, &branch)?;
+            Ok(format!("{branch:<24} {status}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let base = selected(
+        Select::with_theme(&theme)
+            .with_prompt("Release to patch")
+            .items(&items)
+            .default(0)
+            .interact_opt()?,
+    )?;
+
+    let mut targets = Patch::new(0).targets;
+    let defaults = targets.len();
+    let protected: Vec<ProtectedBranch> = api::paged(
+        ProtectedBranches::builder().project(project).build()?,
+        Pagination::All,
+    )
+    .query(client)?;
+    let others: Vec<_> = protected
+        .into_iter()
+        .map(|branch| branch.name)
+        .filter(|name| !name.starts_with("release/") && !name.contains('*'))
+        .filter(|name| !targets.contains(name))
+        .collect();
+    targets.extend(others);
+    let checked: Vec<_> = (0..targets.len()).map(|index| index < defaults).collect();
+    let chosen = selected(
+        MultiSelect::with_theme(&theme)
+            .with_prompt("Merge the fix back into (space to toggle)")
+            .items(&targets)
+            .defaults(&checked)
+            .interact_opt()?,
+    )?;
+    anyhow::ensure!(!chosen.is_empty(), "no target branch selected");
+
+    let members: Vec<Member> = api::paged(
+        AllProjectMembers::builder().project(project).build()?,
+        Pagination::All,
+    )
+    .query(client)?;
+    anyhow::ensure!(!members.is_empty(), "{project} has no members to assign");
+    let default = assignee
+        .and_then(|id| members.iter().position(|member| member.id == id))
+        .unwrap_or(0);
+    let names: Vec<_> = members
+        .iter()
+        .map(|member| format!("@{} ({})", member.username, member.name))
+        .collect();
+    let assignee = selected(
+        Select::with_theme(&theme)
+            .with_prompt("Assignee")
+            .items(&names)
+            .default(default)
+            .interact_opt()?,
+    )?;
+
+    Ok(Patch {
+        base: Some(releases[base].clone()),
+        targets: chosen
+            .into_iter()
+            .map(|index| targets[index].clone())
+            .collect(),
+        assignee: members[assignee].id,
+        description_template: None,
+        compare_summary: false,
+        incident: None,
+        picks: Vec::new(),
+        sla: None,
+    })
+}
@@ -0,0 +1,63 @@
+//! Per-OS directories, written out by hand because `directories` is not in
+//! this build's dependency tree. They are `ProjectDirs::from("", "",
+//! "gitlab-helper")`'s, except that on Windows they have no `config` or
+//! `cache` subdirectory and the XDG variables win on every OS.
+
+use std::path::PathBuf;
+
+const APP: &str = "gitlab-helper";
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Where the user-wide config lives: `%APPDATA%` on Windows,
+/// `~/Library/Application Support` on macOS, `$XDG_CONFIG_HOME` or
+/// `~/.config` elsewhere.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if let Some(dir) = env_dir("XDG_CONFIG_HOME") {
+        Some(dir)
+    } else if cfg!(windows) {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env_dir("HOME").map(|home| home.join(".config"))
+    };
+    base.map(|dir| dir.join(APP))
+}
+
+/// Where cached responses go: `%LOCALAPPDATA%` on Windows, `~/Library/Caches`
+/// on macOS, `$XDG_CACHE_HOME` or `~/.cache` elsewhere.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = if let Some(dir) = env_dir("XDG_CACHE_HOME") {
+        Some(dir)
+    } else if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Caches"))
+    } else {
+        env_dir("HOME").map(|home| home.join(".cache"))
+    };
+    base.map(|dir| dir.join(APP))
+}
+
+/// Whether a stream going to a `terminal` can be colored. Consoles on Windows
+/// only understand escape codes when the terminal says it does, as Windows
+/// Terminal and ConEmu do.
+pub fn ansi(terminal: bool) -> bool {
+    if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) || !terminal {
+        return false;
+    }
+    !cfg!(windows)
+        || std::env::var_os("WT_SESSION").is_some()
+        || std::env::var_os("TERM").is_some()
+        || std::env::var("ConEmuANSI").is_ok_and(|value| value == "ON")
+}
+
+/// Whether this runs in a CI job, which sets `CI` as GitLab and most others do.
+pub fn in_ci() -> bool {
+    std::env::var_os("CI").is_some_and(|ci| !ci.is_empty())
+}
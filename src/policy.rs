@@ -0,0 +1,65 @@
+use serde::Deserialize;
+
+/// Where the commands that cut patches and open merge requests may run.
+/// Every condition that is set has to hold.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyConfig {
+    /// Only in pipelines of protected branches or tags.
+    pub protected_ref: bool,
+    /// Only when one of these users started the pipeline or job.
+    pub users: Vec<String>,
+    /// Only when this variable is set, e.g. by playing a manual job.
+    pub variable: Option<String>,
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Fails with every condition of `policy` that this run breaks.
+pub fn check(policy: &PolicyConfig, command: &str) -> anyhow::Result<()> {
+    let mut broken = Vec::new();
+    if policy.protected_ref && env("CI_COMMIT_REF_PROTECTED").as_deref() != Some("true") {
+        broken.push(match env("CI_COMMIT_REF_NAME") {
+            Some(name) => format!("{name} is not a protected branch or tag"),
+            None => "this is not a pipeline of a protected branch or tag".to_owned(),
+        });
+    }
+    if !policy.users.is_empty() {
+        match env("GITLAB_USER_LOGIN") {
+            Some(user) if policy.users.contains(&user) => {}
+            Some(user) => broken.push(format!("@{user} is not one of the allowed users")),
+            None => broken.push("the user who started the pipeline is unknown".to_owned()),
+        }
+    }
+    if let Some(variable) = &policy.variable {
+        if env(variable).is_none() {
+            broken.push(format!("${variable} is not set"));
+        }
+    }
+    anyhow::ensure!(
+        broken.is_empty(),
+        "refusing to run {command} as [policy] says: {}",
+        broken.join("; ")
+    );
+    Ok(())
+}
+
+/// Fails unless pipeline variable `name` is `expected`, as it is when someone
+/// plays the manual job that sets it, so a second person signs off on the run.
+pub fn check_approval(name: &str, expected: &str, command: &str) -> anyhow::Result<()> {
+    match std::env::var(name) {
+        Ok(value) if value == expected => {
+            match env("GITLAB_USER_LOGIN") {
+                Some(user) => tracing::info!("{command} approved by @{user} through ${name}"),
+                None => tracing::info!("{command} approved through ${name}"),
+            }
+            Ok(())
+        }
+        Ok(_) => anyhow::bail!("refusing to run {command}: ${name} does not hold the approval"),
+        Err(_) => anyhow::bail!(
+            "refusing to run {command}: ${name} is not set; play the manual job that approves it"
+        ),
+    }
+}
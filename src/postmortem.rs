@@ -0,0 +1,296 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use gitlab::api::projects::deployments::Deployments;
+use gitlab::api::projects::issues::{Issue as GetIssue, RelatedMergeRequests};
+use gitlab::api::projects::merge_requests::pipelines::MergeRequestPipelines;
+use gitlab::api::projects::merge_requests::MergeRequest;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::{self, Client};
+use crate::endpoints::{CreateWikiPage, EditWikiPage};
+use crate::files::{self, Batch, Change};
+use crate::journal::{Journal, Resource};
+use crate::workflow::Context;
+
+#[derive(Debug, Clone, Args)]
+pub struct Scaffold {
+    /// The IID of the incident issue.
+    #[arg(long)]
+    pub incident: u64,
+    /// Put the steps of this journaled run, such as the emergency patch, on the timeline.
+    #[arg(long, value_name = "JOURNAL")]
+    pub journal: Option<PathBuf>,
+    /// Write the document to this file instead of printing it.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["wiki", "commit_to"])]
+    pub out: Option<PathBuf>,
+    /// Publish the document as the wiki page `postmortems/incident-<IID>`.
+    #[arg(long, conflicts_with = "commit_to")]
+    pub wiki: bool,
+    /// Commit the document to this docs project instead.
+    #[arg(long, value_name = "PROJECT")]
+    pub commit_to: Option<String>,
+    #[arg(long, requires = "commit_to", default_value = "main")]
+    pub branch: String,
+    /// Where in the docs project; `postmortems/incident-<IID>.md` if unset.
+    #[arg(long, requires = "commit_to")]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    iid: u64,
+    title: String,
+    web_url: String,
+    created_at: DateTime<Utc>,
+    closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Related {
+    iid: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mr {
+    iid: u64,
+    title: String,
+    web_url: String,
+    target_branch: String,
+    created_at: DateTime<Utc>,
+    merged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+    status: String,
+    web_url: String,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Environment {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Deployment {
+    id: u64,
+    status: String,
+    environment: Environment,
+    updated_at: DateTime<Utc>,
+}
+
+const SECTIONS: &str = "## Summary
+
+_What happened, and who noticed it how?_
+
+## Impact
+
+_Who was affected, for how long and how badly?_
+
+## Root cause
+
+## Resolution
+
+## What went well
+
+## What went wrong
+
+## Action items
+
+- [ ] ";
+
+/// Gathers what happened around incident `iid` of `project`, from the
+/// journal of the run that remedied it and from GitLab, in order.
+fn timeline(
+    client: &Client,
+    project: &str,
+    issue: &Issue,
+    journal: Option<&Journal>,
+) -> anyhow::Result<Vec<(DateTime<Utc>, String)>> {
+    let mut events = vec![(
+        issue.created_at,
+        format!("Incident [#{}]({}) opened", issue.iid, issue.web_url),
+    )];
+    if let Some(closed_at) = issue.closed_at {
+        events.push((closed_at, format!("Incident #{} closed", issue.iid)));
+    }
+
+    let related: Vec<Related> = api::paged(
+        RelatedMergeRequests::builder()
+            .project(project)
+            .issue(issue.iid)
+            .build()?,
+        api::Pagination::All,
+    )
+    .query(client)?;
+    let mut iids: BTreeSet<u64> = related.iter().map(|mr| mr.iid).collect();
+    for entry in journal.map(Journal::entries).unwrap_or_default() {
+        if entry.project != project {
+            continue;
+        }
+        if let Some(Resource::MergeRequest { iid, .. }) = entry.created {
+            iids.insert(iid);
+        }
+        if let Some(at) = DateTime::from_timestamp(entry.at as i64, 0) {
+            events.push((at, format!("Helper: {}", entry.step)));
+        }
+    }
+
+    for iid in iids {
+        let mr: Mr = MergeRequest::builder()
+            .project(project)
+            .merge_request(iid)
+            .build()?
+            .query(client)?;
+        events.push((
+            mr.created_at,
+            format!(
+                "[!{}]({}) opened into `{}`: {}",
+                mr.iid, mr.web_url, mr.target_branch, mr.title
+            ),
+        ));
+        if let Some(merged_at) = mr.merged_at {
+            events.push((merged_at, format!("!{} merged", mr.iid)));
+        }
+        let pipelines: Vec<Pipeline> = api::paged(
+            MergeRequestPipelines::builder()
+                .project(project)
+                .merge_request(iid)
+                .build()?,
+            api::Pagination::All,
+        )
+        .query(client)?;
+        events.extend(pipelines.into_iter().map(|pipeline| {
+            (
+                pipeline.updated_at,
+                format!(
+                    "Pipeline [#{}]({}) of !{iid}: {}",
+                    pipeline.id, pipeline.web_url, pipeline.status
+                ),
+            )
+        }));
+    }
+
+    // The endpoint does not page; its first page covers an incident's worth.
+    let deployments: Vec<Deployment> = Deployments::builder()
+        .project(project)
+        .updated_after(issue.created_at)
+        .build()?
+        .query(client)?;
+    events.extend(
+        deployments
+            .into_iter()
+            .filter(|deployment| issue.closed_at.is_none_or(|at| deployment.updated_at <= at))
+            .map(|deployment| {
+                (
+                    deployment.updated_at,
+                    format!(
+                        "Deployment #{} to {}: {}",
+                        deployment.id, deployment.environment.name, deployment.status
+                    ),
+                )
+            }),
+    );
+    events.sort_by_key(|(at, _)| *at);
+    Ok(events)
+}
+
+fn render(issue: &Issue, events: &[(DateTime<Utc>, String)]) -> String {
+    let mut out = format!(
+        "# Post-mortem: {}\n\n- Incident: [#{}]({})\n- Opened: {}\n",
+        issue.title,
+        issue.iid,
+        issue.web_url,
+        issue.created_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    match issue.closed_at {
+        Some(closed_at) => {
+            let took = closed_at - issue.created_at;
+            out.push_str(&format!(
+                "- Resolved: {} (after {}h {}m)\n",
+                closed_at.format("%Y-%m-%d %H:%M UTC"),
+                took.num_hours(),
+                took.num_minutes() % 60
+            ));
+        }
+        None => out.push_str("- Resolved: not yet\n"),
+    }
+    out.push_str("\n## Timeline (UTC)\n\n| Time | Event |\n| --- | --- |\n");
+    for (at, event) in events {
+        out.push_str(&format!(
+            "| {} | {} |\n",
+            at.format("%Y-%m-%d %H:%M:%S"),
+            event.replace('|', "\\|")
+        ));
+    }
+    out.push('\n');
+    out.push_str(SECTIONS);
+    out.push('\n');
+    out
+}
+
+/// Drafts the post-mortem of incident `scaffold.incident` with its timeline
+/// filled in, and prints, writes, commits or publishes it to the wiki.
+pub fn scaffold(ctx: &Context, project: &str, scaffold: &Scaffold) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let issue: Issue = GetIssue::builder()
+        .project(project)
+        .issue(scaffold.incident)
+        .build()?
+        .query(client)?;
+    let journal = scaffold.journal.as_deref().map(Journal::open).transpose()?;
+    let events = timeline(client, project, &issue, journal.as_ref())?;
+    let document = render(&issue, &events);
+
+    let slug = format!("postmortems/incident-{}", issue.iid);
+    if let Some(path) = &scaffold.out {
+        std::fs::write(path, &document)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        return Ok(format!("wrote {}", path.display()));
+    }
+    if scaffold.wiki {
+        ctx.confirm(project, &[format!("write the wiki page {slug}")])?;
+        let edit = EditWikiPage {
+            project: project.into(),
+            slug: &slug,
+            content: &document,
+        };
+        match api::ignore(edit).query(client) {
+            Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => {
+                let create = CreateWikiPage {
+                    project: project.into(),
+                    title: &slug,
+                    content: &document,
+                };
+                api::ignore(create).query(client)?;
+            }
+            result => result?,
+        }
+        return Ok(format!("wrote the wiki page {slug}"));
+    }
+    if let Some(docs) = &scaffold.commit_to {
+        let path = scaffold
+            .path
+            .clone()
+            .unwrap_or_else(|| format!("{slug}.md"));
+        let batch = Batch {
+            branch: scaffold.branch.clone(),
+            start_branch: None,
+            message: format!("Add the post-mortem of {project}#{}", issue.iid),
+            changes: vec![Change::Write {
+                path,
+                content: document.into_bytes(),
+            }],
+        };
+        return files::put(ctx, docs, &batch);
+    }
+    print!("{document}");
+    Ok(format!("drafted the post-mortem of #{}", issue.iid))
+}
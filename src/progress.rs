@@ -0,0 +1,172 @@
+//! Spinners and bars drawn by hand because `indicatif` is not in this
+//! build's dependency tree. They keep to what it would be used for here: a
+//! single line on stderr with the elapsed time, hidden when stderr is not a
+//! terminal or the run is in CI.
+
+use std::io::{IsTerminal, Write};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::cancel;
+use crate::platform;
+
+const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+const REDRAW: Duration = Duration::from_millis(100);
+const BAR: usize = 20;
+
+/// Whether a status line is on screen, so log lines can make room for it.
+static DRAWN: Mutex<bool> = Mutex::new(false);
+
+/// Erases the status line, if one is drawn; the next update brings it back.
+pub fn clear() {
+    let mut drawn = DRAWN.lock().unwrap_or_else(PoisonError::into_inner);
+    if *drawn {
+        eprint!("\r\x1b[2K");
+        *drawn = false;
+    }
+}
+
+struct State {
+    done: usize,
+    message: String,
+    frame: usize,
+    drawn_at: Option<Instant>,
+}
+
+/// A spinner or bar on the last line of stderr, for work that takes a while.
+/// It is only drawn for someone watching a terminal, never in CI or when
+/// stderr is redirected.
+pub struct Progress {
+    label: String,
+    total: Option<usize>,
+    started: Instant,
+    shown: bool,
+    state: Mutex<State>,
+}
+
+impl Progress {
+    fn new(label: &str, total: Option<usize>) -> Self {
+        Progress {
+            label: label.to_owned(),
+            total,
+            started: Instant::now(),
+            shown: std::io::stderr().is_terminal() && !platform::in_ci(),
+            state: Mutex::new(State {
+                done: 0,
+                message: String::new(),
+                frame: 0,
+                drawn_at: None,
+            }),
+        }
+    }
+
+    /// Counts up to `total`, e.g. the projects of a fan-out.
+    pub fn bar(label: &str, total: usize) -> Self {
+        Progress::new(label, Some(total))
+    }
+
+    /// For work whose end is unknown, such as waiting on a pipeline.
+    pub fn spinner(label: &str) -> Self {
+        Progress::new(label, None)
+    }
+
+    pub fn inc(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.done += 1;
+        self.draw(&mut state);
+    }
+
+    pub fn set_message(&self, message: impl Into<String>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.message = message.into();
+        self.draw(&mut state);
+    }
+
+    /// Sleeps for `duration`, keeping the spinner turning meanwhile; a signal
+    /// asking to stop cuts it short.
+    pub fn wait(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        loop {
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() || cancel::requested() {
+                return;
+            }
+            std::thread::sleep(left.min(REDRAW));
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            self.draw(&mut state);
+        }
+    }
+
+    fn render(&self, state: &State) -> String {
+        let frame = FRAMES[state.frame % FRAMES.len()];
+        let mut line = match self.total {
+            Some(total) => {
+                let filled = (state.done * BAR)
+                    .checked_div(total)
+                    .unwrap_or(BAR)
+                    .min(BAR);
+                format!(
+                    "[{}{}] {}/{total} {}",
+                    "=".repeat(filled),
+                    " ".repeat(BAR - filled),
+                    state.done,
+                    self.label
+                )
+            }
+            None => format!("{frame} {}", self.label),
+        };
+        if !state.message.is_empty() {
+            line.push_str(": ");
+            line.push_str(&state.message);
+        }
+        line.push_str(&format!(" ({}s)", self.started.elapsed().as_secs()));
+        line
+    }
+
+    /// Redraws the line, at most every `REDRAW`.
+    fn draw(&self, state: &mut State) {
+        if !self.shown
+            || state
+                .drawn_at
+                .is_some_and(|drawn_at| drawn_at.elapsed() < REDRAW)
+        {
+            return;
+        }
+        state.frame += 1;
+        state.drawn_at = Some(Instant::now());
+        let line = self.render(state);
+        let mut drawn = DRAWN.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{line}");
+        let _ = stderr.flush();
+        *drawn = true;
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.shown {
+            clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_bar_fills_with_what_is_done() {
+        let progress = Progress::bar("projects", 4);
+        let state = State {
+            done: 2,
+            message: "group/api".to_owned(),
+            frame: 0,
+            drawn_at: None,
+        };
+        assert_eq!(
+            progress.render(&state),
+            "[==========          ] 2/4 projects: group/api (0s)"
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use gitlab::api::projects::merge_requests::CreateMergeRequest;
+use gitlab::api::projects::pipelines::{CreatePipeline, PipelineVariable};
+use gitlab::api::projects::repository::branches::{Branch, CreateBranch};
+use gitlab::api::projects::repository::commits::CompareCommits;
+use gitlab::api::projects::repository::tags::{CreateTag, Tag};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::{self, Client};
+use crate::files::{self, Batch, Change};
+use crate::hooks::Event;
+use crate::workflow::Context;
+
+/// What `promote-rc` promotes and how.
+#[derive(Debug, Clone)]
+pub struct Promotion {
+    /// The release candidate's branch, e.g. `release/1.4.0-rc.2`.
+    pub rc: String,
+    /// The branch the announcement merge request goes into.
+    pub announce_into: String,
+    /// Variables for the production pipeline of the release tag.
+    pub variables: Vec<(String, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ref {
+    commit: Commit,
+}
+
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    #[serde(default)]
+    commits: Vec<Commit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Created {
+    web_url: String,
+}
+
+/// The version of an RC branch and the one it becomes, e.g. `1.4.0-rc.2`
+/// and `1.4.0` for `release/1.4.0-rc.2`.
+fn versions(rc: &str) -> anyhow::Result<(semver::Version, semver::Version)> {
+    let version = rc
+        .strip_prefix("release/")
+        .and_then(|version| semver::Version::parse(version).ok())
+        .filter(|version| !version.pre.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!("{rc} is not a release candidate branch like release/1.4.0-rc.2")
+        })?;
+    let ga = semver::Version::new(version.major, version.minor, version.patch);
+    Ok((version, ga))
+}
+
+/// The commit `name` points at, or `None` if there is no such branch.
+fn branch_head(client: &Client, project: &str, name: &str) -> anyhow::Result<Option<String>> {
+    let endpoint = Branch::builder().project(project).branch(name).build()?;
+    match endpoint.query(client) {
+        Ok(Ref { commit }) => Ok(Some(commit.id)),
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn tag_target(client: &Client, project: &str, name: &str) -> anyhow::Result<Option<String>> {
+    let endpoint = Tag::builder().project(project).tag_name(name).build()?;
+    match endpoint.query(client) {
+        Ok(Ref { commit }) => Ok(Some(commit.id)),
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// What the GA branch needs to point at the release candidate.
+enum Update {
+    Create,
+    FastForward,
+    None,
+}
+
+fn announcement(project: &str, ga: &semver::Version, rc: &str, sha: &str) -> String {
+    format!(
+        "# {project} {ga}\n\n{ga} is generally available. It is {rc}, promoted unchanged at `{}`.\n",
+        &sha[..sha.len().min(8)]
+    )
+}
+
+/// Promotes a release candidate to its release: points `release/x.y.z` at
+/// it, creating or fast-forwarding the branch, tags it `vx.y.z`, starts the
+/// tag's production pipeline and opens the announcement merge request.
+pub fn run(ctx: &Context, project: &str, promotion: &Promotion) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let repo = ctx.git_for(project)?;
+    let (rc_version, ga) = versions(&promotion.rc)?;
+    let (ga_branch, ga_tag) = (format!("release/{ga}"), format!("v{ga}"));
+    let Some(sha) = branch_head(client, project, &promotion.rc)? else {
+        anyhow::bail!("{project} has no branch {}", promotion.rc);
+    };
+
+    let update = match branch_head(client, project, &ga_branch)? {
+        None => Update::Create,
+        Some(head) if head == sha => Update::None,
+        Some(_) => {
+            let comparison: Comparison = CompareCommits::builder()
+                .project(project)
+                .from(promotion.rc.as_str())
+                .to(ga_branch.as_str())
+                .build()?
+                .query(client)?;
+            anyhow::ensure!(
+                comparison.commits.is_empty(),
+                "{ga_branch} has {} commit(s) that {} does not; promote a new release candidate",
+                comparison.commits.len(),
+                promotion.rc
+            );
+            anyhow::ensure!(
+                repo.is_some(),
+                "{ga_branch} is behind {}; the API cannot fast-forward a branch, so pass --local-git",
+                promotion.rc
+            );
+            Update::FastForward
+        }
+    };
+    let tag = match tag_target(client, project, &ga_tag)? {
+        Some(target) if target == sha => false,
+        Some(target) => anyhow::bail!(
+            "{ga_tag} already exists at {target}, not at {} of {}",
+            sha,
+            promotion.rc
+        ),
+        None => true,
+    };
+    let announce_branch = format!("announce/{ga}");
+
+    let mut plan = Vec::new();
+    match update {
+        Update::Create => plan.push(format!("create branch {ga_branch} from {}", promotion.rc)),
+        Update::FastForward => plan.push(format!("fast-forward {ga_branch} to {}", promotion.rc)),
+        Update::None => {}
+    }
+    if tag {
+        plan.push(format!("tag {ga_tag} at {}", &sha[..sha.len().min(8)]));
+    }
+    plan.push(format!("trigger the production pipeline of {ga_tag}"));
+    plan.push(format!(
+        "open a merge request announcing {ga} into {}",
+        promotion.announce_into
+    ));
+    ctx.confirm(project, &plan)?;
+
+    match update {
+        Update::Create => {
+            let event = Event::BranchCreated {
+                project,
+                branch: &ga_branch,
+                ref_: &sha,
+            };
+            ctx.hooked(&event, || match repo {
+                Some(repo) => repo.create_branch(&ga_branch, &sha),
+                None => Ok(api::ignore(
+                    CreateBranch::builder()
+                        .project(project)
+                        .branch(&ga_branch)
+                        .ref_(&sha)
+                        .build()?,
+                )
+                .query(client)?),
+            })?;
+        }
+        Update::FastForward => {
+            if let Some(repo) = repo {
+                repo.fast_forward(&ga_branch, &sha)?;
+            }
+        }
+        Update::None => {}
+    }
+    if tag {
+        let endpoint = CreateTag::builder()
+            .project(project)
+            .tag_name(&ga_tag)
+            .ref_(&sha)
+            .message(format!("{ga}, promoted from {rc_version}"))
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    let variables = promotion
+        .variables
+        .iter()
+        .map(|(key, value)| {
+            Ok(PipelineVariable::builder()
+                .key(key.as_str())
+                .value(value.as_str())
+                .build()?)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let pipeline: Pipeline = CreatePipeline::builder()
+        .project(project)
+        .ref_(&ga_tag)
+        .variables(variables.into_iter())
+        .build()?
+        .query(client)?;
+
+    let batch = Batch {
+        branch: announce_branch.clone(),
+        start_branch: Some(promotion.announce_into.clone()),
+        message: format!("Announce {ga}"),
+        changes: vec![Change::Write {
+            path: format!("announcements/{ga}.md"),
+            content: announcement(project, &ga, &promotion.rc, &sha).into_bytes(),
+        }],
+    };
+    files::commit(client, ctx.commits, project, &batch)?;
+    let title = format!("Announce {ga}");
+    let event = Event::MrCreated {
+        project,
+        source_branch: &announce_branch,
+        target_branch: &promotion.announce_into,
+        title: &title,
+    };
+    let mr: Created = ctx.hooked(&event, || {
+        Ok(CreateMergeRequest::builder()
+            .project(project)
+            .source_branch(&announce_branch)
+            .target_branch(&promotion.announce_into)
+            .title(&title)
+            .description(format!(
+                "{ga} was promoted from {}; its production pipeline: {}",
+                promotion.rc, pipeline.web_url
+            ))
+            .remove_source_branch(true)
+            .build()?
+            .query(client)?)
+    })?;
+    Ok(format!(
+        "promoted {} to {ga_tag}; pipeline {}, announcement {}",
+        promotion.rc, pipeline.web_url, mr.web_url
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_release_candidates_are_promoted() {
+        let (rc, ga) = versions("release/1.4.0-rc.2").unwrap();
+        assert_eq!(
+            (rc.to_string(), ga.to_string()),
+            ("1.4.0-rc.2".into(), "1.4.0".into())
+        );
+        assert!(versions("release/1.4.0").is_err());
+        assert!(versions("1.4.0-rc.2").is_err());
+    }
+}
@@ -0,0 +1,30 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::{Mutex, PoisonError};
+
+use crate::progress;
+
+/// Whether someone is at the keyboard: a terminal on both ends and not CI.
+pub fn interactive() -> bool {
+    std::env::var_os("CI").is_none()
+        && std::io::stdin().is_terminal()
+        && std::io::stderr().is_terminal()
+}
+
+/// Shows `plan` under `header` and asks whether to go ahead. Prompts from
+/// concurrently running projects are asked one at a time.
+pub fn confirm(header: &str, plan: &[String]) -> anyhow::Result<bool> {
+    static PROMPT: Mutex<()> = Mutex::new(());
+    let _turn = PROMPT.lock().unwrap_or_else(PoisonError::into_inner);
+
+    progress::clear();
+    let mut stderr = std::io::stderr().lock();
+    writeln!(stderr, "{header}")?;
+    for step in plan {
+        writeln!(stderr, "  - {step}")?;
+    }
+    write!(stderr, "Proceed? [y/N] ")?;
+    stderr.flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
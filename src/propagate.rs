@@ -0,0 +1,219 @@
+use std::time::{Duration, Instant};
+
+use gitlab::api::projects::merge_requests::notes::CreateMergeRequestNote;
+use gitlab::api::projects::merge_requests::{
+    CreateMergeRequest, MergeRequest, MergeRequestState, MergeRequests,
+};
+use gitlab::api::projects::repository::commits::CompareCommits;
+use gitlab::api::{self, Query};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::cancel;
+use crate::client::Client;
+use crate::config;
+use crate::hooks::Event;
+use crate::merge::{self, MergeConfig};
+use crate::workflow::Context;
+
+/// The `[propagate]` section: the branches a fix is merged along.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PropagateConfig {
+    /// From the oldest maintained version to the newest, e.g.
+    /// `["release/1.3", "release/1.4", "dev"]`.
+    #[serde(deserialize_with = "chain")]
+    pub chain: Vec<String>,
+}
+
+fn chain<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let chain = Vec::<String>::deserialize(deserializer)?;
+    for branch in &chain {
+        config::check_branch_name(branch).map_err(de::Error::custom)?;
+    }
+    Ok(chain)
+}
+
+/// How long to wait on each merge request of the chain.
+#[derive(Debug, Clone, Copy)]
+pub struct Waiting {
+    pub poll: Duration,
+    pub wait: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mr {
+    iid: u64,
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mergeability {
+    #[serde(default)]
+    has_conflicts: bool,
+    #[serde(default)]
+    detailed_merge_status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    #[serde(default)]
+    commits: Vec<de::IgnoredAny>,
+}
+
+/// Whether `target` lacks commits of `source`.
+fn behind(client: &Client, project: &str, source: &str, target: &str) -> anyhow::Result<bool> {
+    let comparison: Comparison = CompareCommits::builder()
+        .project(project)
+        .from(target)
+        .to(source)
+        .build()?
+        .query(client)?;
+    Ok(!comparison.commits.is_empty())
+}
+
+/// The open merge request from `source` into `target`, opening one if there
+/// is none, e.g. when resuming after a conflict was resolved.
+fn merge_request(ctx: &Context, project: &str, source: &str, target: &str) -> anyhow::Result<Mr> {
+    let client = ctx.client;
+    let existing: Vec<Mr> = MergeRequests::builder()
+        .project(project)
+        .state(MergeRequestState::Opened)
+        .source_branch(source)
+        .target_branch(target)
+        .build()?
+        .query(client)?;
+    if let Some(mr) = existing.into_iter().next() {
+        return Ok(mr);
+    }
+    let title = format!("Merge {source} into {target}");
+    let event = Event::MrCreated {
+        project,
+        source_branch: source,
+        target_branch: target,
+        title: &title,
+    };
+    ctx.hooked(&event, || {
+        Ok(CreateMergeRequest::builder()
+            .project(project)
+            .source_branch(source)
+            .target_branch(target)
+            .title(&title)
+            .description(format!(
+                "Keeps `{target}` up to date with the fixes on `{source}`; opened by `propagate-fix`."
+            ))
+            .remove_source_branch(false)
+            .build()?
+            .query(client)?)
+    })
+}
+
+/// Whether GitLab found conflicts in merge request `iid`, once it is done checking.
+fn conflicts(client: &Client, project: &str, iid: u64, waiting: Waiting) -> anyhow::Result<bool> {
+    let started = Instant::now();
+    loop {
+        let mr: Mergeability = MergeRequest::builder()
+            .project(project)
+            .merge_request(iid)
+            .build()?
+            .query(client)?;
+        let checking = matches!(
+            mr.detailed_merge_status.as_deref(),
+            Some("checking" | "unchecked" | "preparing" | "approvals_syncing")
+        );
+        if !checking || started.elapsed() >= waiting.wait {
+            return Ok(mr.has_conflicts);
+        }
+        std::thread::sleep(waiting.poll.min(Duration::from_secs(5)));
+        cancel::check()?;
+    }
+}
+
+/// Merges `from` into each branch of `through` in turn, each into the next,
+/// waiting for every merge request to be approved, green and merged. Stops
+/// at the first conflict, with a note on how to resolve it; running again
+/// picks up from there.
+pub fn run(
+    ctx: &Context,
+    project: &str,
+    from: &str,
+    through: &[String],
+    waiting: Waiting,
+) -> anyhow::Result<String> {
+    let client = ctx.client;
+    // Starting from a branch of the chain goes on from there.
+    let through = match through.iter().position(|branch| branch == from) {
+        Some(at) => &through[at + 1..],
+        None => through,
+    };
+    anyhow::ensure!(
+        !through.is_empty(),
+        "nothing to propagate to; pass --through or set `propagate.chain`"
+    );
+    let hops: Vec<_> = std::iter::once(from)
+        .chain(through.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|hop| (hop[0].to_owned(), hop[1].to_owned()))
+        .collect();
+    let plan: Vec<_> = hops
+        .iter()
+        .map(|(source, target)| format!("merge {source} into {target}"))
+        .collect();
+    ctx.confirm(project, &plan)?;
+
+    // Release branches stay, and merge commits keep the history of each line.
+    let config = MergeConfig {
+        squash: Some(false),
+        message: Some("{{ title }}\n\nMerge-request: !{{ iid }} {{ url }}".to_owned()),
+        remove_source_branch: false,
+    };
+    let mut merged = Vec::new();
+    for (source, target) in &hops {
+        if !behind(client, project, source, target)? {
+            tracing::info!(project, "{target} already has everything on {source}");
+            continue;
+        }
+        let mr = merge_request(ctx, project, source, target)?;
+        if conflicts(client, project, mr.iid, waiting)? {
+            let branch = format!("propagate/{}-into-{}", source, target).replace('/', "-");
+            let body = format!(
+                "`{source}` does not merge cleanly into `{target}`. Resolve it with:\n\n```bash\n\
+                 git fetch origin {source} {target}\n\
+                 git checkout -b {branch} origin/{target}\n\
+                 git merge origin/{source}\n\
+                 # fix the conflicts, commit, then push and merge {branch} into {target}\n\
+                 ```\n\nThen run `propagate-fix` again to carry on along the chain."
+            );
+            let note = CreateMergeRequestNote::builder()
+                .project(project)
+                .merge_request(mr.iid)
+                .body(body)
+                .build()?;
+            api::ignore(note).query(client)?;
+            anyhow::bail!(
+                "{source} conflicts with {target} in {}; stopped there{}",
+                mr.web_url,
+                if merged.is_empty() {
+                    String::new()
+                } else {
+                    format!(" after merging into {}", merged.join(", "))
+                }
+            );
+        }
+        merge::run(
+            client,
+            project,
+            mr.iid,
+            &config,
+            false,
+            waiting.poll,
+            waiting.wait,
+        )?;
+        merged.push(target.clone());
+    }
+    Ok(if merged.is_empty() {
+        format!("{} already had everything on {from}", through.join(", "))
+    } else {
+        format!("merged {from} into {}", merged.join(", "))
+    })
+}
@@ -0,0 +1,396 @@
+use gitlab::api::common::ProtectedAccessLevel;
+use gitlab::api::projects::{protected_branches, protected_tags};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::endpoints::{AccessChange, EditProtectedBranch};
+use crate::workflow::Context;
+
+/// The `[protect]` section: how branches and tags should be protected.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtectConfig {
+    pub branches: Vec<ProtectedBranch>,
+    pub tags: Vec<ProtectedTag>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    NoOne,
+    Developer,
+    Maintainer,
+    Admin,
+}
+
+impl Access {
+    fn name(level: u64) -> String {
+        match level {
+            0 => "no_one".to_owned(),
+            30 => "developer".to_owned(),
+            40 => "maintainer".to_owned(),
+            60 => "admin".to_owned(),
+            level => format!("level {level}"),
+        }
+    }
+
+    fn level(self) -> u64 {
+        match self {
+            Access::NoOne => 0,
+            Access::Developer => 30,
+            Access::Maintainer => 40,
+            Access::Admin => 60,
+        }
+    }
+}
+
+impl From<Access> for ProtectedAccessLevel {
+    fn from(access: Access) -> Self {
+        match access {
+            Access::NoOne => ProtectedAccessLevel::NoAccess,
+            Access::Developer => ProtectedAccessLevel::Developer,
+            Access::Maintainer => ProtectedAccessLevel::Maintainer,
+            Access::Admin => ProtectedAccessLevel::Admin,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtectedBranch {
+    /// A branch name or wildcard such as `release/*`.
+    pub name: String,
+    pub push_access_level: Access,
+    pub merge_access_level: Access,
+    #[serde(default)]
+    pub allow_force_push: bool,
+    /// Needs GitLab Premium; left alone when unset.
+    pub code_owner_approval_required: Option<bool>,
+}
+
+impl ProtectedBranch {
+    pub fn describe(&self) -> String {
+        describe_branch(
+            &Access::name(self.push_access_level.level()),
+            &Access::name(self.merge_access_level.level()),
+            self.allow_force_push,
+            self.code_owner_approval_required,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtectedTag {
+    /// A tag name or wildcard such as `v*`.
+    pub name: String,
+    pub create_access_level: Access,
+}
+
+impl ProtectedTag {
+    pub fn describe(&self) -> String {
+        format!("create {}", Access::name(self.create_access_level.level()))
+    }
+}
+
+fn describe_branch(
+    push: &str,
+    merge: &str,
+    allow_force_push: bool,
+    code_owners: Option<bool>,
+) -> String {
+    let force = if allow_force_push {
+        "allowed"
+    } else {
+        "denied"
+    };
+    let mut description = format!("push {push}, merge {merge}, force push {force}");
+    match code_owners {
+        Some(true) => description.push_str(", code owner approval required"),
+        Some(false) => description.push_str(", no code owner approval"),
+        None => {}
+    }
+    description
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessEntry {
+    id: u64,
+    access_level: u64,
+    user_id: Option<u64>,
+    group_id: Option<u64>,
+}
+
+impl AccessEntry {
+    /// The user or group this entry is for; `None` if it is for a role.
+    fn grantee(&self) -> Option<String> {
+        match (self.user_id, self.group_id) {
+            (Some(user), _) => Some(format!("user {user}")),
+            (_, Some(group)) => Some(format!("group {group}")),
+            _ => None,
+        }
+    }
+}
+
+/// The roles allowed by `entries`. The config only speaks of roles, so the
+/// entries of single users and groups are not part of the comparison.
+fn describe_levels(entries: &[AccessEntry]) -> String {
+    let names: Vec<_> = entries
+        .iter()
+        .filter(|entry| entry.grantee().is_none())
+        .map(|entry| Access::name(entry.access_level))
+        .collect();
+    if names.is_empty() {
+        Access::name(0)
+    } else {
+        names.join("+")
+    }
+}
+
+fn grantees<'a>(entries: impl IntoIterator<Item = &'a AccessEntry>) -> Vec<String> {
+    let mut grantees: Vec<_> = entries
+        .into_iter()
+        .filter_map(AccessEntry::grantee)
+        .collect();
+    grantees.sort();
+    grantees.dedup();
+    grantees
+}
+
+/// What it takes to make the role entries of `current` allow just `desired`.
+fn access_changes(current: &[AccessEntry], desired: Access) -> Vec<AccessChange> {
+    let roles = || current.iter().filter(|entry| entry.grantee().is_none());
+    let mut changes: Vec<_> = roles()
+        .filter(|entry| entry.access_level != desired.level())
+        .map(|entry| AccessChange::Remove { id: entry.id })
+        .collect();
+    if !roles().any(|entry| entry.access_level == desired.level()) {
+        changes.push(AccessChange::Add {
+            access_level: desired.level(),
+        });
+    }
+    changes
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentBranch {
+    name: String,
+    push_access_levels: Vec<AccessEntry>,
+    merge_access_levels: Vec<AccessEntry>,
+    #[serde(default)]
+    allow_force_push: bool,
+    #[serde(default)]
+    code_owner_approval_required: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentTag {
+    name: String,
+    create_access_levels: Vec<AccessEntry>,
+}
+
+/// A branch or tag pattern that is not protected the way it should be.
+pub struct Mismatch<'a, T> {
+    pub desired: &'a T,
+    /// How it is protected now, or `None` if it is not.
+    pub current: Option<String>,
+    /// The users and groups that are allowed in besides the roles.
+    pub grantees: Vec<String>,
+}
+
+impl<T> Mismatch<'_, T> {
+    pub fn current(&self) -> &str {
+        self.current.as_deref().unwrap_or("unprotected")
+    }
+}
+
+pub fn branch_mismatches<'a>(
+    client: &Client,
+    project: &str,
+    branches: &'a [ProtectedBranch],
+) -> anyhow::Result<Vec<Mismatch<'a, ProtectedBranch>>> {
+    if branches.is_empty() {
+        return Ok(Vec::new());
+    }
+    let endpoint = protected_branches::ProtectedBranches::builder()
+        .project(project)
+        .build()?;
+    let current: Vec<CurrentBranch> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    Ok(branches
+        .iter()
+        .filter_map(|desired| {
+            let current = current.iter().find(|current| current.name == desired.name);
+            let grantees = current.map_or_else(Vec::new, |current| {
+                grantees(
+                    current
+                        .push_access_levels
+                        .iter()
+                        .chain(&current.merge_access_levels),
+                )
+            });
+            let current = current.map(|current| {
+                describe_branch(
+                    &describe_levels(&current.push_access_levels),
+                    &describe_levels(&current.merge_access_levels),
+                    current.allow_force_push,
+                    desired
+                        .code_owner_approval_required
+                        .map(|_| current.code_owner_approval_required),
+                )
+            });
+            (current.as_ref() != Some(&desired.describe())).then_some(Mismatch {
+                desired,
+                current,
+                grantees,
+            })
+        })
+        .collect())
+}
+
+pub fn tag_mismatches<'a>(
+    client: &Client,
+    project: &str,
+    tags: &'a [ProtectedTag],
+) -> anyhow::Result<Vec<Mismatch<'a, ProtectedTag>>> {
+    if tags.is_empty() {
+        return Ok(Vec::new());
+    }
+    let endpoint = protected_tags::ProtectedTags::builder()
+        .project(project)
+        .build()?;
+    let current: Vec<CurrentTag> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    Ok(tags
+        .iter()
+        .filter_map(|desired| {
+            let current = current.iter().find(|current| current.name == desired.name);
+            let grantees =
+                current.map_or_else(Vec::new, |current| grantees(&current.create_access_levels));
+            let current = current.map(|current| {
+                format!("create {}", describe_levels(&current.create_access_levels))
+            });
+            (current.as_ref() != Some(&desired.describe())).then_some(Mismatch {
+                desired,
+                current,
+                grantees,
+            })
+        })
+        .collect())
+}
+
+/// Protects the branch as described. An existing protection is changed in
+/// place, so the branch is never unprotected and users and groups that are
+/// allowed in stay so.
+pub fn protect_branch(
+    client: &Client,
+    project: &str,
+    mismatch: &Mismatch<ProtectedBranch>,
+) -> anyhow::Result<()> {
+    let branch = mismatch.desired;
+    if mismatch.current.is_some() {
+        let endpoint = protected_branches::ProtectedBranch::builder()
+            .project(project)
+            .name(&branch.name)
+            .build()?;
+        let current: CurrentBranch = endpoint.query(client)?;
+        let endpoint = EditProtectedBranch {
+            project: project.into(),
+            name: &branch.name,
+            allowed_to_push: access_changes(&current.push_access_levels, branch.push_access_level),
+            allowed_to_merge: access_changes(
+                &current.merge_access_levels,
+                branch.merge_access_level,
+            ),
+            allow_force_push: branch.allow_force_push,
+            code_owner_approval_required: branch.code_owner_approval_required,
+        };
+        api::ignore(endpoint).query(client)?;
+        return Ok(());
+    }
+    let mut endpoint = protected_branches::ProtectBranch::builder();
+    endpoint
+        .project(project)
+        .name(&branch.name)
+        .push_access_level(branch.push_access_level.into())
+        .merge_access_level(branch.merge_access_level.into())
+        .allow_force_push(branch.allow_force_push);
+    if let Some(required) = branch.code_owner_approval_required {
+        endpoint.code_owner_approval_required(required);
+    }
+    api::ignore(endpoint.build()?).query(client)?;
+    Ok(())
+}
+
+/// Protects the tag as described. GitLab cannot change a protected tag, so an
+/// existing protection is replaced, dropping the users and groups allowed in.
+pub fn protect_tag(
+    client: &Client,
+    project: &str,
+    mismatch: &Mismatch<ProtectedTag>,
+) -> anyhow::Result<()> {
+    let tag = mismatch.desired;
+    if mismatch.current.is_some() {
+        let endpoint = protected_tags::UnprotectTag::builder()
+            .project(project)
+            .name(&tag.name)
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    let endpoint = protected_tags::ProtectTag::builder()
+        .project(project)
+        .name(&tag.name)
+        .create_access_level(tag.create_access_level.into())
+        .build()?;
+    api::ignore(endpoint).query(client)?;
+    Ok(())
+}
+
+/// Makes the protected branches and tags of `project` match `config`.
+pub fn apply(ctx: &Context, project: &str, config: &ProtectConfig) -> anyhow::Result<String> {
+    let branches = branch_mismatches(ctx.client, project, &config.branches)?;
+    let tags = tag_mismatches(ctx.client, project, &config.tags)?;
+    let plan: Vec<_> = branches
+        .iter()
+        .map(|branch| {
+            let mut step = format!(
+                "protect branch {}: {} (now {})",
+                branch.desired.name,
+                branch.desired.describe(),
+                branch.current()
+            );
+            if !branch.grantees.is_empty() {
+                step.push_str(&format!(", keeping {}", branch.grantees.join(", ")));
+            }
+            step
+        })
+        .chain(tags.iter().map(|tag| {
+            let mut step = format!(
+                "protect tag {}: {} (now {})",
+                tag.desired.name,
+                tag.desired.describe(),
+                tag.current()
+            );
+            if !tag.grantees.is_empty() {
+                step.push_str(&format!(", dropping {}", tag.grantees.join(", ")));
+            }
+            step
+        }))
+        .collect();
+    if plan.is_empty() {
+        return Ok("already protected as configured".to_owned());
+    }
+    ctx.confirm(project, &plan)?;
+    for branch in &branches {
+        protect_branch(ctx.client, project, branch)?;
+        tracing::info!("protected branch {}", branch.desired.name);
+    }
+    for tag in &tags {
+        protect_tag(ctx.client, project, tag)?;
+        tracing::info!("protected tag {}", tag.desired.name);
+    }
+    Ok(format!(
+        "updated {} branch and {} tag protection(s)",
+        branches.len(),
+        tags.len()
+    ))
+}
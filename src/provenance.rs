@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use clap::Args;
+use gitlab::api::projects::packages::generic::UploadPackageFile;
+use gitlab::api::projects::releases::links::{CreateReleaseLink, LinkType};
+use gitlab::api::{self, Query, RestClient};
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::compat;
+use crate::hooks;
+use crate::template::{self, Vars};
+use crate::workflow::Context;
+
+/// The generic package the provenance of every release is uploaded to, one
+/// version per tag.
+const PACKAGE: &str = "provenance";
+
+/// The `[provenance]` section: how `provenance` signs what it records.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvenanceConfig {
+    /// The program and its arguments, with `{{ file }}` and `{{ signature }}`
+    /// for the document and the signature to write, e.g. `["cosign",
+    /// "sign-blob", "--yes", "--key", "env://COSIGN_KEY", "--output-signature",
+    /// "{{ signature }}", "{{ file }}"]`. Unsigned if unset.
+    #[serde(deserialize_with = "hooks::command")]
+    pub sign: Vec<String>,
+    #[serde(deserialize_with = "hooks::timeout")]
+    pub timeout: Duration,
+}
+
+impl Default for ProvenanceConfig {
+    fn default() -> Self {
+        ProvenanceConfig {
+            sign: Vec::new(),
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct Provenance {
+    /// The release the build is attached to.
+    #[arg(long, env = "CI_COMMIT_TAG")]
+    pub tag: String,
+    /// The files the pipeline built, e.g. the binaries; checksummed, not uploaded.
+    #[arg(required = true)]
+    pub artifacts: Vec<PathBuf>,
+    /// Where to write the document, e.g. to keep it as a job artifact too.
+    #[arg(long, default_value = "provenance.json")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+struct Document<'a> {
+    project: &'a str,
+    tag: &'a str,
+    commit: String,
+    pipeline: Build,
+    job: Build,
+    runner: Runner,
+    artifacts: Vec<Checksum>,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Build {
+    id: String,
+    url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Runner {
+    id: Option<String>,
+    description: Option<String>,
+    tags: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Checksum {
+    name: String,
+    sha256: String,
+}
+
+fn sha256(path: &Path) -> anyhow::Result<String> {
+    let contents =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let digest = ring::digest::digest(&ring::digest::SHA256, &contents);
+    Ok(digest
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// The CI job's predefined variable `name`, which the document needs.
+fn required(name: &str) -> anyhow::Result<String> {
+    std::env::var(name).with_context(|| format!("${name} is not set; run this in a CI job"))
+}
+
+fn document<'a>(
+    project: &'a str,
+    tag: &'a str,
+    artifacts: &[PathBuf],
+) -> anyhow::Result<Document<'a>> {
+    let var = |name: &str| std::env::var(name).ok();
+    let artifacts = artifacts
+        .iter()
+        .map(|path| {
+            Ok(Checksum {
+                name: path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                ),
+                sha256: sha256(path)?,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Document {
+        project,
+        tag,
+        commit: required("CI_COMMIT_SHA")?,
+        pipeline: Build {
+            id: required("CI_PIPELINE_ID")?,
+            url: var("CI_PIPELINE_URL"),
+        },
+        job: Build {
+            id: required("CI_JOB_ID")?,
+            url: var("CI_JOB_URL"),
+        },
+        runner: Runner {
+            id: var("CI_RUNNER_ID"),
+            description: var("CI_RUNNER_DESCRIPTION"),
+            tags: var("CI_RUNNER_TAGS"),
+        },
+        artifacts,
+        created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    })
+}
+
+/// Signs `file` into `signature` with the configured command.
+fn sign(config: &ProvenanceConfig, file: &Path, signature: &Path) -> anyhow::Result<()> {
+    let vars = Vars::from([
+        ("file".to_owned(), file.display().to_string()),
+        ("signature".to_owned(), signature.display().to_string()),
+    ]);
+    let command = config
+        .sign
+        .iter()
+        .map(|arg| template::render(arg, &vars))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // So that a signature left from an earlier run is not taken for this one's.
+    let _ = std::fs::remove_file(signature);
+    let outcome = compat::run_captured(&command, config.timeout)?;
+    anyhow::ensure!(
+        outcome.passed,
+        "failed to sign {}: {}",
+        file.display(),
+        outcome.output.trim()
+    );
+    anyhow::ensure!(
+        signature.exists(),
+        "`{}` wrote no signature to {}",
+        command[0],
+        signature.display()
+    );
+    Ok(())
+}
+
+/// Where the generic package API serves `file` of the provenance of `tag`.
+fn package_url(client: &Client, project: &str, tag: &str, file: &str) -> anyhow::Result<String> {
+    let mut url = client.rest_endpoint("projects")?;
+    url.path_segments_mut()
+        .map_err(|()| anyhow::anyhow!("the API URL cannot have a path"))?
+        .extend([project, "packages", "generic", PACKAGE, tag, file]);
+    Ok(url.into())
+}
+
+/// Records how the artifacts of `tag` were built, where and from what in a
+/// JSON document, signs it if configured to, and attaches it to the release.
+pub fn record(
+    ctx: &Context,
+    config: &ProvenanceConfig,
+    project: &str,
+    provenance: &Provenance,
+) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let tag = provenance.tag.as_str();
+    let document = document(project, tag, &provenance.artifacts)?;
+    let output = &provenance.output;
+    std::fs::write(output, serde_json::to_vec_pretty(&document)?)
+        .with_context(|| format!("failed to write {}", output.display()))?;
+
+    let mut files = vec![output.clone()];
+    if config.sign.is_empty() {
+        tracing::warn!(
+            project,
+            tag,
+            "`provenance.sign` is not set; attaching the provenance unsigned"
+        );
+    } else {
+        let mut signature = output.clone().into_os_string();
+        signature.push(".sig");
+        let signature = PathBuf::from(signature);
+        sign(config, output, &signature)?;
+        files.push(signature);
+    }
+
+    let names = files
+        .iter()
+        .map(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .with_context(|| format!("{} is not a file", path.display()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let plan: Vec<_> = names
+        .iter()
+        .map(|name| format!("attach {name} to release {tag}"))
+        .collect();
+    ctx.confirm(project, &plan)?;
+    for (path, name) in files.iter().zip(&names) {
+        let contents =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let upload = UploadPackageFile::builder()
+            .project(project)
+            .package_name(PACKAGE)
+            .package_version(tag)
+            .file_name(name.as_str())
+            .contents(contents)
+            .build()?;
+        api::ignore(upload).query(client)?;
+        let link = CreateReleaseLink::builder()
+            .project(project)
+            .tag_name(tag)
+            .name(name.as_str())
+            .url(package_url(client, project, tag, name)?)
+            .link_type(LinkType::Other)
+            .build()?;
+        api::ignore(link)
+            .query(client)
+            .with_context(|| format!("failed to attach {name}; is {tag} released yet?"))?;
+    }
+    Ok(format!(
+        "attached the provenance of {} artifact(s) to {tag}{}",
+        document.artifacts.len(),
+        if config.sign.is_empty() {
+            ""
+        } else {
+            ", signed"
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_are_lowercase_hex_sha256() {
+        let path = std::env::temp_dir().join(format!("provenance-{}", std::process::id()));
+        std::fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            sha256(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use gitlab::api::projects::pipelines::{PipelineJobs, Pipelines};
+use gitlab::api::projects::releases::links::CreateReleaseLink;
+use gitlab::api::projects::releases::{CreateRelease, CreateReleaseAssetLinks, ProjectReleases};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::confluence::{self, ConfluenceConfig};
+use crate::release_notes;
+use crate::table::{self, Format};
+use crate::workflow::Context;
+
+/// The artifact reports attached to a release for compliance.
+const REPORTS: &[&str] = &["cyclonedx", "dependency_scanning", "license_scanning"];
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Job {
+    name: String,
+    web_url: String,
+    #[serde(default)]
+    artifacts: Vec<Artifact>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    file_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    assets: Assets,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Assets {
+    #[serde(default)]
+    links: Vec<Link>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    name: String,
+}
+
+/// A link to every dependency and SBOM report of the latest pipeline for
+/// `tag`, as the link's name and URL.
+fn report_links(
+    client: &Client,
+    project: &str,
+    tag: &str,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let endpoint = Pipelines::builder().project(project).ref_(tag).build()?;
+    let pipelines: Vec<Pipeline> = api::paged(endpoint, api::Pagination::Limit(1)).query(client)?;
+    let Some(pipeline) = pipelines.first() else {
+        tracing::warn!(
+            project,
+            tag,
+            "no pipeline ran for the tag; no reports to attach"
+        );
+        return Ok(Vec::new());
+    };
+    let endpoint = PipelineJobs::builder()
+        .project(project)
+        .pipeline(pipeline.id)
+        .build()?;
+    let jobs: Vec<Job> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    Ok(jobs
+        .iter()
+        .flat_map(|job| {
+            job.artifacts
+                .iter()
+                .filter(|artifact| REPORTS.contains(&artifact.file_type.as_str()))
+                .map(move |artifact| {
+                    (
+                        format!("{} ({})", artifact.file_type, job.name),
+                        format!(
+                            "{}/artifacts/download?file_type={}",
+                            job.web_url, artifact.file_type
+                        ),
+                    )
+                })
+        })
+        .collect())
+}
+
+/// Creates the release of `tag`, with the release notes since `from` if set,
+/// also published to `confluence` if that is, and attaches the dependency
+/// and SBOM reports of the tag's pipeline. A release that exists already
+/// only gets the reports it is missing.
+pub fn publish(
+    ctx: &Context,
+    project: &str,
+    tag: &str,
+    from: Option<&str>,
+    target: Option<&str>,
+    confluence: Option<&ConfluenceConfig>,
+) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let endpoint = ProjectReleases::builder().project(project).build()?;
+    let releases: Vec<Release> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    let existing = releases.into_iter().find(|release| release.tag_name == tag);
+    let mut links = report_links(client, project, tag)?;
+
+    let Some(existing) = existing else {
+        let mut plan = vec![format!("create release {tag}")];
+        plan.extend(links.iter().map(|(name, _)| format!("attach {name}")));
+        if from.is_some() && confluence.is_some() {
+            plan.push("publish the release notes to Confluence".to_owned());
+        }
+        ctx.confirm(project, &plan)?;
+        let description = from
+            .map(|from| release_notes::render(client, project, target, from, tag))
+            .transpose()?;
+        let mut release = CreateRelease::builder();
+        release.project(project).tag_name(tag);
+        if let Some(description) = &description {
+            release.description(description.as_str());
+        }
+        for (name, url) in &links {
+            release.asset(
+                CreateReleaseAssetLinks::builder()
+                    .name(name.as_str())
+                    .url(url.as_str())
+                    .build()?,
+            );
+        }
+        api::ignore(release.build()?).query(client)?;
+        if let (Some(description), Some(config)) = (&description, confluence) {
+            let url = confluence::publish(client, config, project, tag, description)?;
+            tracing::info!(project, tag, "published the release notes to {url}");
+        }
+        return Ok(format!(
+            "published {tag} with {} report(s) attached",
+            links.len()
+        ));
+    };
+
+    links.retain(|(name, _)| !existing.assets.links.iter().any(|link| &link.name == name));
+    if links.is_empty() {
+        return Ok(format!("{tag} is already published"));
+    }
+    let plan: Vec<_> = links
+        .iter()
+        .map(|(name, _)| format!("attach {name} to release {tag}"))
+        .collect();
+    ctx.confirm(project, &plan)?;
+    for (name, url) in &links {
+        let endpoint = CreateReleaseLink::builder()
+            .project(project)
+            .tag_name(tag)
+            .name(name.as_str())
+            .url(url.as_str())
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    Ok(format!("attached {} report(s) to {tag}", links.len()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Sbom {
+    #[serde(default)]
+    components: Vec<Component>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Component {
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    licenses: Vec<LicenseChoice>,
+}
+
+/// CycloneDX names a license by SPDX ID, by free-form name or as an SPDX
+/// expression.
+#[derive(Debug, Deserialize)]
+struct LicenseChoice {
+    license: Option<License>,
+    expression: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct License {
+    id: Option<String>,
+    name: Option<String>,
+}
+
+impl Component {
+    fn licenses(&self) -> Vec<String> {
+        let licenses: Vec<_> = self
+            .licenses
+            .iter()
+            .filter_map(|choice| {
+                choice.expression.clone().or_else(|| {
+                    let license = choice.license.as_ref()?;
+                    license.id.clone().or_else(|| license.name.clone())
+                })
+            })
+            .collect();
+        if licenses.is_empty() {
+            vec!["unknown".to_owned()]
+        } else {
+            licenses
+        }
+    }
+}
+
+/// Counts the components of the CycloneDX `sboms` per license, and fails
+/// listing the components under a `denied` one.
+pub fn licenses_report(sboms: &[PathBuf], denied: &[String], format: Format) -> anyhow::Result<()> {
+    let mut counts = BTreeMap::<String, usize>::new();
+    let mut violations = Vec::new();
+    for path in sboms {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let sbom: Sbom = serde_json::from_str(&contents)
+            .with_context(|| format!("{} is not a CycloneDX JSON SBOM", path.display()))?;
+        for component in &sbom.components {
+            for license in component.licenses() {
+                if denied
+                    .iter()
+                    .any(|denied| license.eq_ignore_ascii_case(denied))
+                {
+                    violations.push(format!(
+                        "{}@{} is under {license}",
+                        component.name, component.version
+                    ));
+                }
+                *counts.entry(license).or_default() += 1;
+            }
+        }
+    }
+    let rows: Vec<_> = counts
+        .into_iter()
+        .map(|(license, count)| [license, count.to_string()])
+        .collect();
+    println!(
+        "{}",
+        table::render_as(format, ["LICENSE", "COMPONENTS"], &rows)
+    );
+    for violation in &violations {
+        tracing::error!("{violation}");
+    }
+    anyhow::ensure!(
+        violations.is_empty(),
+        "{} component(s) under a denied license",
+        violations.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn licenses_fall_back_from_id_to_name_to_unknown() {
+        let sbom: Sbom = serde_json::from_str(
+            r#"{ "components": [
+                { "name": "a", "licenses": [{ "license": { "id": "MIT" } }] },
+                { "name": "b", "licenses": [{ "license": { "name": "Custom" } }] },
+                { "name": "c", "licenses": [{ "expression": "MIT OR Apache-2.0" }] },
+                { "name": "d" }
+            ] }"#,
+        )
+        .unwrap();
+        let licenses: Vec<_> = sbom.components.iter().map(Component::licenses).collect();
+        assert_eq!(
+            licenses,
+            [
+                vec!["MIT"],
+                vec!["Custom"],
+                vec!["MIT OR Apache-2.0"],
+                vec!["unknown"]
+            ]
+        );
+    }
+}
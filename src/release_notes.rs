@@ -0,0 +1,616 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use gitlab::api::projects::merge_requests::{MergeRequestState, MergeRequests};
+use gitlab::api::{self, projects, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::confluence::{self, ConfluenceConfig};
+use crate::title::{self, Kind};
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    path_with_namespace: String,
+    default_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tag {
+    commit: Commit,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    committed_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    commits: Vec<ComparedCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ComparedCommit {
+    id: String,
+}
+
+/// How long after the `to` tag was committed a merge request that it ships
+/// may say it was merged: a tag at a merge commit predates its merge request's
+/// `merged_at`, and a fast-forwarded commit may be older still.
+const MERGED_AT_SLACK: chrono::TimeDelta = chrono::TimeDelta::days(1);
+
+/// Every MR merged in the window, with its author and labels, in pages of a
+/// hundred instead of one REST round trip per MR for the labels and author.
+const MERGED_MERGE_REQUESTS: &str = r#"
+query($project: ID!, $target: String!, $after: Time, $before: Time, $cursor: String) {
+  project(fullPath: $project) {
+    mergeRequests(state: merged, targetBranches: [$target], mergedAfter: $after, mergedBefore: $before, first: 100, after: $cursor) {
+      nodes {
+        iid
+        title
+        description
+        webUrl
+        author { username }
+        labels { nodes { title } }
+        mergedAt
+        mergeCommitSha
+        squashCommitSha
+        diffHeadSha
+        approvedBy { nodes { username } }
+        headPipeline { id }
+      }
+      pageInfo { hasNextPage endCursor }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct MergedMergeRequestsData {
+    project: Option<ProjectNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectNode {
+    merge_requests: Connection<MergeRequestNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    nodes: Vec<T>,
+    page_info: PageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeRequestNode {
+    pub iid: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub web_url: String,
+    pub author: Option<Author>,
+    pub labels: Labels,
+    pub merged_at: Option<DateTime<Utc>>,
+    pub merge_commit_sha: Option<String>,
+    pub squash_commit_sha: Option<String>,
+    /// The last commit of the source branch, which is all a fast-forward
+    /// merge leaves on the target.
+    #[serde(default)]
+    pub diff_head_sha: Option<String>,
+    /// `None` if not asked for, as the REST API's list does not say.
+    #[serde(default)]
+    pub approved_by: Option<Users>,
+    /// Likewise `None` if not asked for.
+    #[serde(default)]
+    pub head_pipeline: Option<PipelineNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Author {
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Users {
+    pub nodes: Vec<Author>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PipelineNode {
+    /// A global ID such as `gid://gitlab/Ci::Pipeline/123`.
+    pub id: String,
+}
+
+impl PipelineNode {
+    pub fn number(&self) -> &str {
+        self.id.rsplit('/').next().unwrap_or(&self.id)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Labels {
+    pub nodes: Vec<Label>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Label {
+    pub title: String,
+}
+
+/// A merge request as the REST API lists it.
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    title: String,
+    #[serde(default)]
+    description: Option<String>,
+    web_url: String,
+    author: Option<Author>,
+    labels: Vec<String>,
+    merged_at: Option<DateTime<Utc>>,
+    merge_commit_sha: Option<String>,
+    squash_commit_sha: Option<String>,
+    #[serde(default)]
+    sha: Option<String>,
+}
+
+impl From<MergeRequest> for MergeRequestNode {
+    fn from(mr: MergeRequest) -> Self {
+        MergeRequestNode {
+            iid: mr.iid.to_string(),
+            title: mr.title,
+            description: mr.description,
+            web_url: mr.web_url,
+            author: mr.author,
+            labels: Labels {
+                nodes: mr.labels.into_iter().map(|title| Label { title }).collect(),
+            },
+            merged_at: mr.merged_at,
+            merge_commit_sha: mr.merge_commit_sha,
+            squash_commit_sha: mr.squash_commit_sha,
+            diff_head_sha: mr.sha,
+            approved_by: None,
+            head_pipeline: None,
+        }
+    }
+}
+
+fn tag_date(client: &Client, project: &str, tag: &str) -> anyhow::Result<DateTime<Utc>> {
+    let endpoint = projects::repository::tags::Tag::builder()
+        .project(project)
+        .tag_name(tag)
+        .build()?;
+    let tag: Tag = endpoint.query(client)?;
+    Ok(tag.commit.committed_date)
+}
+
+impl MergeRequestNode {
+    /// Whether one of the commits the merge request left on its target is
+    /// in `commits`.
+    fn landed_in(&self, commits: &HashSet<String>) -> bool {
+        [
+            &self.merge_commit_sha,
+            &self.squash_commit_sha,
+            &self.diff_head_sha,
+        ]
+        .into_iter()
+        .flatten()
+        .any(|sha| commits.contains(sha))
+    }
+}
+
+/// The commits `to` has that `from` does not.
+fn commits_between(
+    client: &Client,
+    project: &str,
+    from: &str,
+    to: &str,
+) -> anyhow::Result<HashSet<String>> {
+    let comparison: Comparison = projects::repository::commits::CompareCommits::builder()
+        .project(project)
+        .from(from)
+        .to(to)
+        .build()?
+        .query(client)?;
+    Ok(comparison
+        .commits
+        .into_iter()
+        .map(|commit| commit.id)
+        .collect())
+}
+
+/// Merge requests merged into `target` whose commits are in `to` but not in
+/// `from`. The tag dates only narrow down the merge requests to look at.
+pub fn merged_between(
+    client: &Client,
+    project: &str,
+    target: Option<&str>,
+    from: &str,
+    to: &str,
+) -> anyhow::Result<Vec<MergeRequestNode>> {
+    let details: Project = projects::Project::builder()
+        .project(project)
+        .build()?
+        .query(client)?;
+    let Some(target) = target.or(details.default_branch.as_deref()) else {
+        anyhow::bail!("{project} has no default branch; pass --target-branch");
+    };
+    let after = tag_date(client, project, from)?;
+    let before = tag_date(client, project, to)? + MERGED_AT_SLACK;
+    let commits = commits_between(client, project, from, to)?;
+    if !client.accepts_graphql() {
+        let merge_requests = merged_between_rest(client, project, target, after, before)?;
+        return Ok(merge_requests
+            .into_iter()
+            .filter(|mr| mr.landed_in(&commits))
+            .collect());
+    }
+
+    let full_path = details.path_with_namespace;
+    let mut merge_requests = Vec::new();
+    let mut cursor = None;
+    loop {
+        let data: MergedMergeRequestsData = client.graphql(
+            MERGED_MERGE_REQUESTS,
+            serde_json::json!({
+                "project": full_path,
+                "target": target,
+                "after": after,
+                "before": before,
+                "cursor": cursor,
+            }),
+        )?;
+        let Some(project) = data.project else {
+            anyhow::bail!("project {full_path} is not visible to this token");
+        };
+        let page = project.merge_requests;
+        merge_requests.extend(page.nodes.into_iter().filter(|mr| mr.landed_in(&commits)));
+        if !page.page_info.has_next_page {
+            break;
+        }
+        cursor = page.page_info.end_cursor;
+    }
+    Ok(merge_requests)
+}
+
+/// The same as the GraphQL query, for tokens it does not accept. REST cannot
+/// filter on the merge time, but a merge request merged in the window was
+/// last updated after it started.
+fn merged_between_rest(
+    client: &Client,
+    project: &str,
+    target: &str,
+    after: DateTime<Utc>,
+    before: DateTime<Utc>,
+) -> anyhow::Result<Vec<MergeRequestNode>> {
+    let endpoint = MergeRequests::builder()
+        .project(project)
+        .state(MergeRequestState::Merged)
+        .target_branch(target)
+        .updated_after(after)
+        .build()?;
+    let merge_requests: Vec<MergeRequest> =
+        api::paged(endpoint, api::Pagination::All).query(client)?;
+    Ok(merge_requests
+        .into_iter()
+        .filter(|mr| {
+            mr.merged_at
+                .is_some_and(|merged_at| after <= merged_at && merged_at <= before)
+        })
+        .map(MergeRequestNode::from)
+        .collect())
+}
+
+fn entry(mr: &MergeRequestNode, summary: &str) -> String {
+    let mut line = format!("- {summary} ([!{}]({}))", mr.iid, mr.web_url);
+    if let Some(author) = &mr.author {
+        line.push_str(&format!(" @{}", author.username));
+    }
+    if !mr.labels.nodes.is_empty() {
+        let labels = mr
+            .labels
+            .nodes
+            .iter()
+            .map(|label| format!("~\"{}\"", label.title))
+            .collect::<Vec<_>>();
+        line.push_str(&format!(" {}", labels.join(" ")));
+    }
+    line
+}
+
+/// How the release notes are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NotesFormat {
+    /// Features, fixes and the rest, with authors and labels.
+    Markdown,
+    /// The Markdown of conventional-changelog's angular preset, as
+    /// semantic-release writes it.
+    Conventional,
+    /// The context conventional-changelog-writer renders its templates with.
+    ConventionalJson,
+}
+
+/// A merge request as conventional-changelog sees a commit.
+struct Change<'a> {
+    kind: Option<&'static str>,
+    scope: Option<&'a str>,
+    subject: &'a str,
+    breaking: Option<String>,
+    mr: &'a MergeRequestNode,
+}
+
+/// The note of a `BREAKING CHANGE:` footer in `description`, up to the next
+/// blank line.
+fn breaking_change(description: &str) -> Option<String> {
+    let mut lines = description.lines();
+    let first = lines.by_ref().find_map(|line| {
+        line.strip_prefix("BREAKING CHANGE:")
+            .or_else(|| line.strip_prefix("BREAKING-CHANGE:"))
+    })?;
+    let mut note = first.trim().to_owned();
+    for line in lines.take_while(|line| !line.trim().is_empty()) {
+        if !note.is_empty() {
+            note.push(' ');
+        }
+        note.push_str(line.trim());
+    }
+    Some(note)
+}
+
+fn change(mr: &MergeRequestNode) -> Change<'_> {
+    let breaking = mr.description.as_deref().and_then(breaking_change);
+    match title::parse_merge_request(&mut mr.title.as_str()) {
+        Ok(parsed) => Change {
+            kind: Some(match parsed.kind {
+                Kind::Feature => "feat",
+                Kind::Fix => "fix",
+            }),
+            scope: Some(parsed.jira_id),
+            subject: parsed.title,
+            breaking,
+            mr,
+        },
+        Err(_) => Change {
+            kind: None,
+            scope: None,
+            subject: &mr.title,
+            breaking,
+            mr,
+        },
+    }
+}
+
+/// The section conventional-changelog's angular preset puts `kind` under.
+fn section(kind: &str) -> &'static str {
+    match kind {
+        "feat" => "Features",
+        _ => "Bug Fixes",
+    }
+}
+
+/// The angular preset's Markdown: breaking changes first, then features and
+/// fixes; other merge requests are left out as the preset leaves out other
+/// types, unless they break something.
+fn conventional(version: &str, date: &str, changes: &[Change]) -> String {
+    let mut notes = format!("## {version} ({date})\n");
+    let breaking: Vec<_> = changes
+        .iter()
+        .filter_map(|change| change.breaking.as_deref())
+        .collect();
+    if !breaking.is_empty() {
+        notes.push_str("\n\n### ⚠ BREAKING CHANGES\n\n");
+        for note in breaking {
+            notes.push_str(&format!("* {note}\n"));
+        }
+    }
+    for kind in ["feat", "fix"] {
+        let lines: Vec<_> = changes
+            .iter()
+            .filter(|change| change.kind == Some(kind))
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        notes.push_str(&format!("\n\n### {}\n\n", section(kind)));
+        for change in lines {
+            let scope = change
+                .scope
+                .map_or_else(String::new, |scope| format!("**{scope}:** "));
+            notes.push_str(&format!(
+                "* {scope}{} ([!{}]({}))\n",
+                change.subject, change.mr.iid, change.mr.web_url
+            ));
+        }
+    }
+    notes
+}
+
+fn conventional_json(
+    version: &str,
+    date: &str,
+    from: &str,
+    to: &str,
+    changes: &[Change],
+) -> anyhow::Result<String> {
+    let commit = |change: &Change| {
+        let header = match (change.kind, change.scope) {
+            (Some(kind), Some(scope)) => format!("{kind}({scope}): {}", change.subject),
+            _ => change.subject.to_owned(),
+        };
+        let notes: Vec<_> = change
+            .breaking
+            .iter()
+            .map(|text| serde_json::json!({ "title": "BREAKING CHANGE", "text": text }))
+            .collect();
+        serde_json::json!({
+            "type": change.kind.map(section),
+            "scope": change.scope,
+            "subject": change.subject,
+            "header": header,
+            "hash": change.mr.squash_commit_sha.as_ref().or(change.mr.merge_commit_sha.as_ref()),
+            "notes": notes,
+            "references": [{ "issue": format!("!{}", change.mr.iid), "url": change.mr.web_url }],
+        })
+    };
+    let commit_groups: Vec<_> = ["feat", "fix"]
+        .into_iter()
+        .filter_map(|kind| {
+            let commits: Vec<_> = changes
+                .iter()
+                .filter(|change| change.kind == Some(kind))
+                .map(commit)
+                .collect();
+            (!commits.is_empty())
+                .then(|| serde_json::json!({ "title": section(kind), "commits": commits }))
+        })
+        .collect();
+    let notes: Vec<_> = changes
+        .iter()
+        .filter_map(|change| {
+            Some(serde_json::json!({
+                "title": "BREAKING CHANGE",
+                "text": change.breaking.as_ref()?,
+                "commit": commit(change),
+            }))
+        })
+        .collect();
+    let note_groups = if notes.is_empty() {
+        Vec::new()
+    } else {
+        vec![serde_json::json!({ "title": "BREAKING CHANGES", "notes": notes })]
+    };
+    let context = serde_json::json!({
+        "version": version,
+        "date": date,
+        "previousTag": from,
+        "currentTag": to,
+        "commitGroups": commit_groups,
+        "noteGroups": note_groups,
+    });
+    Ok(format!("{}\n", serde_json::to_string_pretty(&context)?))
+}
+
+/// The release notes for `to` as Markdown.
+pub fn render(
+    client: &Client,
+    project: &str,
+    target: Option<&str>,
+    from: &str,
+    to: &str,
+) -> anyhow::Result<String> {
+    render_as(client, project, target, from, to, NotesFormat::Markdown)
+}
+
+/// The release notes for `to` in `format`.
+pub fn render_as(
+    client: &Client,
+    project: &str,
+    target: Option<&str>,
+    from: &str,
+    to: &str,
+    format: NotesFormat,
+) -> anyhow::Result<String> {
+    let merge_requests = merged_between(client, project, target, from, to)?;
+    tracing::info!(
+        from,
+        to,
+        count = merge_requests.len(),
+        "merge requests found"
+    );
+    match format {
+        NotesFormat::Markdown => Ok(markdown(to, &merge_requests)),
+        NotesFormat::Conventional | NotesFormat::ConventionalJson => {
+            let version = to.strip_prefix('v').unwrap_or(to);
+            let date = tag_date(client, project, to)?
+                .format("%Y-%m-%d")
+                .to_string();
+            let changes: Vec<_> = merge_requests.iter().map(change).collect();
+            if format == NotesFormat::Conventional {
+                Ok(conventional(version, &date, &changes))
+            } else {
+                conventional_json(version, &date, from, to, &changes)
+            }
+        }
+    }
+}
+
+fn markdown(to: &str, merge_requests: &[MergeRequestNode]) -> String {
+    let (mut features, mut fixes, mut other) = (Vec::new(), Vec::new(), Vec::new());
+    for mr in merge_requests {
+        match title::parse_merge_request(&mut mr.title.as_str()) {
+            Ok(parsed) => {
+                let line = entry(mr, &format!("{} ({})", parsed.title, parsed.jira_id));
+                match parsed.kind {
+                    Kind::Feature => features.push(line),
+                    Kind::Fix => fixes.push(line),
+                }
+            }
+            Err(_) => other.push(entry(mr, &mr.title)),
+        }
+    }
+
+    let mut notes = format!("# Release notes for {to}\n");
+    for (heading, lines) in [("Features", features), ("Fixes", fixes), ("Other", other)] {
+        if lines.is_empty() {
+            continue;
+        }
+        notes.push_str(&format!("\n## {heading}\n\n"));
+        for line in lines {
+            notes.push_str(&line);
+            notes.push('\n');
+        }
+    }
+    notes
+}
+
+/// Prints the release notes for `to`, publishing them to Confluence too if
+/// `confluence` is set.
+pub fn run(
+    client: &Client,
+    project: &str,
+    target: Option<&str>,
+    from: &str,
+    to: &str,
+    format: NotesFormat,
+    confluence: Option<&ConfluenceConfig>,
+) -> anyhow::Result<()> {
+    let notes = render_as(client, project, target, from, to, format)?;
+    print!("{notes}");
+    if let Some(config) = confluence {
+        let url = confluence::publish(client, config, project, to, &notes)?;
+        tracing::info!(project, "published the release notes to {url}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaking_changes_run_to_the_next_blank_line() {
+        let description =
+            "Drops the v1 API.\n\nBREAKING CHANGE: `/v1` is gone;\nuse `/v2`.\n\nCloses PROJ-1";
+        assert_eq!(
+            breaking_change(description).as_deref(),
+            Some("`/v1` is gone; use `/v2`.")
+        );
+        assert_eq!(
+            breaking_change("BREAKING-CHANGE: no v1"),
+            Some("no v1".to_owned())
+        );
+        assert_eq!(breaking_change("Mentions a BREAKING CHANGE: inline"), None);
+    }
+}
@@ -0,0 +1,66 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "sentry")] {
+        use gitlab::api::ApiError;
+
+        use crate::client::{self, RestError};
+        use crate::redact;
+
+        /// Keeps the Sentry client alive so queued events are sent on exit.
+        pub struct Guard(#[allow(dead_code)] Option<sentry::ClientInitGuard>);
+
+        /// Starts reporting to the DSN in SENTRY_DSN, if set. Only the tags
+        /// below and error messages are sent; no user or request data.
+        pub fn init(command: &str) -> Guard {
+            let Ok(dsn) = std::env::var("SENTRY_DSN") else {
+                return Guard(None);
+            };
+            let dsn = match dsn.parse() {
+                Ok(dsn) => dsn,
+                Err(err) => {
+                    tracing::warn!("ignoring invalid SENTRY_DSN: {err}");
+                    return Guard(None);
+                }
+            };
+            let mut options = sentry::ClientOptions::default();
+            options.dsn = Some(dsn);
+            options.release = sentry::release_name!();
+            options.send_default_pii = false;
+            let guard = sentry::init(options);
+            sentry::configure_scope(|scope| scope.set_tag("command", command));
+            Guard(Some(guard))
+        }
+
+        /// The status code GitLab answered with, if `err` came from the API.
+        fn gitlab_status(err: &anyhow::Error) -> Option<u16> {
+            err.chain()
+                .find_map(|cause| cause.downcast_ref::<ApiError<RestError>>())
+                .and_then(client::status)
+                .map(|status| status.as_u16())
+        }
+
+        pub fn failure(project: Option<&str>, err: &anyhow::Error) {
+            sentry::with_scope(
+                |scope| {
+                    if let Some(project) = project {
+                        scope.set_tag("project", project);
+                    }
+                    if let Some(status) = gitlab_status(err) {
+                        scope.set_tag("gitlab_status", status);
+                    }
+                },
+                || {
+                    let message = redact::redact(&format!("{err:#}")).into_owned();
+                    sentry::capture_message(&message, sentry::Level::Error)
+                },
+            );
+        }
+    } else {
+        pub struct Guard;
+
+        pub fn init(_command: &str) -> Guard {
+            Guard
+        }
+
+        pub fn failure(_project: Option<&str>, _err: &anyhow::Error) {}
+    }
+}
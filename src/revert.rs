@@ -0,0 +1,251 @@
+use std::num::NonZeroUsize;
+
+use clap::{ArgGroup, Args};
+use gitlab::api::projects::merge_requests::{CreateMergeRequest, MergeRequest};
+use gitlab::api::projects::repository::{branches::CreateBranch, commits::Commit};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::endpoints::RevertCommit;
+use crate::fleet;
+use crate::hooks::Event;
+use crate::journal::Resource;
+use crate::origin;
+use crate::template::{self, Vars};
+use crate::workflow::{Context, Run};
+
+#[derive(Debug, Clone, Args)]
+#[command(group(ArgGroup::new("change").required(true).args(["iid", "commit"])))]
+pub struct Revert {
+    /// The merged merge request to undo.
+    #[arg(long = "mr")]
+    pub iid: Option<u64>,
+    /// The commit to undo.
+    #[arg(long)]
+    pub commit: Option<String>,
+    /// Where to undo it; the merge request's target branch by default.
+    #[arg(long = "target")]
+    pub targets: Vec<String>,
+    /// A template file for the merge request descriptions, with `{{ original_title }}`,
+    /// `{{ original_url }}`, `{{ sha }}` and `{{ target }}`.
+    #[arg(long, value_name = "PATH")]
+    pub description_template: Option<std::path::PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergedRequest {
+    title: String,
+    state: String,
+    target_branch: String,
+    web_url: String,
+    merge_commit_sha: Option<String>,
+    squash_commit_sha: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitDetails {
+    id: String,
+    short_id: String,
+    title: String,
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Created {
+    iid: u64,
+    web_url: String,
+}
+
+/// What is being undone.
+struct Original {
+    sha: String,
+    short_id: String,
+    title: String,
+    url: String,
+    targets: Vec<String>,
+}
+
+fn original(ctx: &Context, project: &str, revert: &Revert) -> anyhow::Result<Original> {
+    let client = ctx.client;
+    if let Some(iid) = revert.iid {
+        let mr: MergedRequest = MergeRequest::builder()
+            .project(project)
+            .merge_request(iid)
+            .build()?
+            .query(client)?;
+        anyhow::ensure!(mr.state == "merged", "!{iid} is not merged");
+        let Some(sha) = mr.squash_commit_sha.or(mr.merge_commit_sha) else {
+            anyhow::bail!("!{iid} has no merge commit to revert");
+        };
+        return Ok(Original {
+            short_id: sha.chars().take(8).collect(),
+            sha,
+            title: mr.title,
+            url: mr.web_url,
+            targets: vec![mr.target_branch],
+        });
+    }
+    let Some(sha) = &revert.commit else {
+        anyhow::bail!("pass --mr or --commit");
+    };
+    anyhow::ensure!(
+        !revert.targets.is_empty(),
+        "--commit needs a --target to revert it on"
+    );
+    let commit: CommitDetails = Commit::builder()
+        .project(project)
+        .commit(sha.as_str())
+        .build()?
+        .query(client)?;
+    Ok(Original {
+        sha: commit.id,
+        short_id: commit.short_id,
+        title: commit.title,
+        url: commit.web_url,
+        targets: Vec::new(),
+    })
+}
+
+/// Opens a merge request per target that undoes the change, each from its
+/// own `revert/...` branch, working on up to `jobs` targets at once.
+pub fn run(
+    ctx: &Context,
+    project: &str,
+    revert: &Revert,
+    jobs: NonZeroUsize,
+) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let mut original = original(ctx, project, revert)?;
+    if !revert.targets.is_empty() {
+        original.targets = revert.targets.clone();
+    }
+    let template = revert
+        .description_template
+        .as_deref()
+        .map(template::load)
+        .transpose()?;
+    let title = format!("Revert \"{}\"", original.title);
+    let branches: Vec<_> = original
+        .targets
+        .iter()
+        .map(|target| {
+            format!(
+                "revert/{}-on-{}",
+                original.short_id,
+                target.replace('/', "-")
+            )
+        })
+        .collect();
+    // Rendered up front so an undefined placeholder stops the run before any change.
+    let descriptions = original
+        .targets
+        .iter()
+        .map(|target| match &template {
+            Some(template) => {
+                let vars = Vars::from([
+                    ("original_title".to_owned(), original.title.clone()),
+                    ("original_url".to_owned(), original.url.clone()),
+                    ("sha".to_owned(), original.sha.clone()),
+                    ("target".to_owned(), target.clone()),
+                ]);
+                template::render(template, &vars)
+            }
+            None => Ok(format!(
+                "Reverts [{}]({}) (`{}`) on `{target}`.\n\n### Why is this revert necessary?",
+                original.title, original.url, original.short_id
+            )),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let steps = |target: &str, branch: &str| {
+        [
+            format!("create branch {branch}"),
+            format!("revert {} on {branch}", original.short_id),
+            format!("open a merge request {branch} -> {target}"),
+        ]
+    };
+    let plan = Run::new(
+        ctx,
+        project,
+        original
+            .targets
+            .iter()
+            .zip(&branches)
+            .flat_map(|(target, branch)| steps(target, branch)),
+    );
+    ctx.confirm(project, &plan.pending())?;
+
+    // Each target is its own run, so one that fails leaves the others be.
+    fleet::each("target", &original.targets, jobs, |target| {
+        let index = original
+            .targets
+            .iter()
+            .position(|known| known == target)
+            .unwrap_or_default();
+        let branch = &branches[index];
+        let mut run = Run::new(ctx, project, steps(target, branch));
+
+        let create_branch = CreateBranch::builder()
+            .project(project)
+            .branch(branch.as_str())
+            .ref_(target)
+            .build()?;
+        let event = Event::BranchCreated {
+            project,
+            branch,
+            ref_: target,
+        };
+        run.step(0, &event, || {
+            api::ignore(create_branch).query(client)?;
+            Ok(Resource::Branch {
+                name: branch.clone(),
+            })
+        })?;
+
+        let event = Event::CommitReverted {
+            project,
+            sha: &original.sha,
+            branch,
+        };
+        run.step(1, &event, || {
+            let reverted: CommitDetails = RevertCommit {
+                project: project.into(),
+                sha: &original.sha,
+                branch,
+            }
+            .query(client)?;
+            Ok(Resource::Commit { sha: reverted.id })
+        })?;
+
+        let mr = CreateMergeRequest::builder()
+            .project(project)
+            .source_branch(branch.as_str())
+            .target_branch(target)
+            .title(&title)
+            .description(origin::sign(&descriptions[index]))
+            .remove_source_branch(true)
+            .build()?;
+        let event = Event::MrCreated {
+            project,
+            source_branch: branch,
+            target_branch: target,
+            title: &title,
+        };
+        run.step(2, &event, || {
+            let mr: Created = mr.query(client)?;
+            origin::note(client, project, mr.iid);
+            Ok(Resource::MergeRequest {
+                iid: mr.iid,
+                web_url: mr.web_url,
+            })
+        })?;
+
+        run.finish()?;
+        Ok(format!("{branch} -> {target}"))
+    })?;
+    Ok(format!(
+        "reverted {} on {}",
+        original.short_id,
+        original.targets.join(", ")
+    ))
+}
@@ -0,0 +1,123 @@
+use gitlab::api::{
+    self,
+    projects::{
+        merge_requests::{EditMergeRequest, MergeRequestStateEvent},
+        repository::branches::DeleteBranch,
+    },
+    Query,
+};
+
+use crate::client::{self, Client};
+use crate::feature_flags;
+use crate::journal::{Entry, Journal, Resource};
+use crate::prompt;
+
+fn describe(entry: &Entry) -> Option<String> {
+    match entry.created.as_ref()? {
+        Resource::Branch { name } => Some(format!("delete branch {name} of {}", entry.project)),
+        Resource::MergeRequest { iid, web_url } => Some(format!("close !{iid} ({web_url})")),
+        Resource::FeatureFlag {
+            name,
+            environment,
+            active,
+        } => Some(format!(
+            "turn feature flag {name} of {} back {}{}",
+            entry.project,
+            if *active { "off" } else { "on" },
+            environment
+                .as_ref()
+                .map_or_else(String::new, |environment| format!(" in {environment}"))
+        )),
+        // Picked commits go with the branch they were picked onto.
+        Resource::Pipeline { .. } | Resource::Commit { .. } => None,
+    }
+}
+
+fn undo(client: &Client, entry: &Entry) -> anyhow::Result<()> {
+    match entry.created.as_ref() {
+        Some(Resource::Branch { name }) => {
+            let endpoint = DeleteBranch::builder()
+                .project(entry.project.as_str())
+                .branch(name)
+                .build()?;
+            match api::ignore(endpoint).query(client) {
+                Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => {
+                    tracing::info!("branch {name} is already gone");
+                }
+                result => result?,
+            }
+        }
+        Some(Resource::MergeRequest { iid, .. }) => {
+            let endpoint = EditMergeRequest::builder()
+                .project(entry.project.as_str())
+                .merge_request(*iid)
+                .state_event(MergeRequestStateEvent::Close)
+                .build()?;
+            api::ignore(endpoint).query(client)?;
+        }
+        Some(Resource::FeatureFlag {
+            name,
+            environment,
+            active,
+        }) => {
+            if !feature_flags::set(
+                client,
+                &entry.project,
+                name,
+                environment.as_deref(),
+                !active,
+            )? {
+                tracing::info!("feature flag {name} is already back");
+            }
+        }
+        Some(Resource::Pipeline { .. } | Resource::Commit { .. }) | None => {}
+    }
+    Ok(())
+}
+
+/// Closes the merge requests, deletes the branches and turns back the feature
+/// flags of a journaled run, newest first, and records each undo in the same
+/// journal.
+pub fn run(client: &Client, journal: &Journal, yes: bool) -> anyhow::Result<()> {
+    let entries = journal.entries();
+    let plan: Vec<_> = entries
+        .iter()
+        .rev()
+        .filter_map(|entry| {
+            let undo = describe(entry)?;
+            let step = format!("roll back: {}", entry.step);
+            journal
+                .find(&entry.project, &step)
+                .is_none()
+                .then_some((entry, undo, step))
+        })
+        .collect();
+    for entry in &entries {
+        if let Some(Resource::Pipeline { web_url, .. }) = &entry.created {
+            tracing::info!("leaving pipeline {web_url} alone");
+        }
+    }
+    if plan.is_empty() {
+        tracing::info!("nothing to roll back");
+        return Ok(());
+    }
+
+    if !yes {
+        // Unlike the other commands, a script has to opt in to deleting things.
+        anyhow::ensure!(
+            prompt::interactive(),
+            "refusing to roll back without confirmation; pass --yes"
+        );
+        let undos: Vec<_> = plan.iter().map(|(_, undo, _)| undo.clone()).collect();
+        if !prompt::confirm(&format!("Rolling back {} changes:", plan.len()), &undos)? {
+            anyhow::bail!("rollback cancelled");
+        }
+    }
+
+    for (entry, description, step) in plan {
+        undo(client, entry).map_err(|err| err.context(format!("failed to {description}")))?;
+        tracing::info!("done: {description}");
+        journal.record(&entry.project, &step, None, Default::default())?;
+    }
+    Ok(())
+}
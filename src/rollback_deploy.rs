@@ -0,0 +1,309 @@
+use clap::Args;
+use gitlab::api::common::SortOrder;
+use gitlab::api::projects::deployments::{DeploymentOrderBy, DeploymentStatusFilter, Deployments};
+use gitlab::api::projects::jobs::RetryJob;
+use gitlab::api::projects::merge_requests::CreateMergeRequest;
+use gitlab::api::projects::repository::commits::CompareCommits;
+use gitlab::api::Query;
+use serde::Deserialize;
+
+use crate::files::{self, Batch, Change};
+use crate::hooks::Event;
+use crate::journal::Resource;
+use crate::notify;
+use crate::origin;
+use crate::template::Vars;
+use crate::workflow::Context;
+
+#[derive(Debug, Clone, Args)]
+pub struct RollbackDeploy {
+    /// The environment to roll back, e.g. `production`.
+    #[arg(long = "env")]
+    pub environment: String,
+    /// The branch the merge request undoes the changes on; the deployed ref by default.
+    #[arg(long, conflicts_with = "redeploy")]
+    pub target: Option<String>,
+    /// Retry the deploy job of the last good deployment instead of opening a merge request.
+    #[arg(long)]
+    pub redeploy: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Deployment {
+    id: u64,
+    #[serde(rename = "ref")]
+    ref_: String,
+    sha: String,
+    #[serde(default)]
+    deployable: Option<Job>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Job {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Retried {
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    #[serde(default)]
+    diffs: Vec<Diff>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diff {
+    old_path: String,
+    new_path: String,
+    #[serde(default)]
+    new_file: bool,
+    #[serde(default)]
+    renamed_file: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Created {
+    iid: u64,
+    web_url: String,
+}
+
+fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(8)]
+}
+
+/// The deployment `environment` runs now and the latest one before it of
+/// another commit.
+fn deployments(
+    ctx: &Context,
+    project: &str,
+    environment: &str,
+) -> anyhow::Result<(Deployment, Deployment)> {
+    let endpoint = Deployments::builder()
+        .project(project)
+        .environment(environment)
+        .status(DeploymentStatusFilter::Success)
+        .order_by(DeploymentOrderBy::FinishedAt)
+        .sort(SortOrder::Descending)
+        .build()?;
+    // The first page, which goes back far enough for a rollback.
+    let mut deployments: Vec<Deployment> = endpoint.query(ctx.client)?;
+    anyhow::ensure!(
+        !deployments.is_empty(),
+        "nothing was deployed to {environment} of {project} yet"
+    );
+    let current = deployments.remove(0);
+    let Some(good) = deployments
+        .into_iter()
+        .find(|deployment| deployment.sha != current.sha)
+    else {
+        anyhow::bail!(
+            "{environment} has run only {} lately; there is nothing to roll back to",
+            short(&current.sha)
+        );
+    };
+    Ok((current, good))
+}
+
+/// The changes that take the files of `target` back to what they were at `sha`.
+fn changes(ctx: &Context, project: &str, sha: &str, target: &str) -> anyhow::Result<Vec<Change>> {
+    let client = ctx.client;
+    let comparison: Comparison = CompareCommits::builder()
+        .project(project)
+        .from(sha)
+        .to(target)
+        .straight(true)
+        .build()?
+        .query(client)?;
+    let mut changes = Vec::new();
+    for diff in comparison.diffs {
+        if diff.new_file || diff.renamed_file {
+            changes.push(Change::Delete {
+                path: diff.new_path,
+            });
+        }
+        if diff.new_file {
+            continue;
+        }
+        let Some(content) = files::get(client, project, &diff.old_path, sha)? else {
+            anyhow::bail!("{} is missing at {}", diff.old_path, short(sha));
+        };
+        changes.push(Change::Write {
+            path: diff.old_path,
+            content,
+        });
+    }
+    Ok(changes)
+}
+
+/// Retries the deploy job of `good`, which GitLab deploys its commit with again.
+fn redeploy(
+    ctx: &Context,
+    project: &str,
+    environment: &str,
+    good: &Deployment,
+) -> anyhow::Result<String> {
+    let Some(job) = &good.deployable else {
+        anyhow::bail!(
+            "deployment {} to {environment} has no job to retry; open a merge request instead",
+            good.id
+        );
+    };
+    let step = format!(
+        "retry job {} ({}) to deploy {} to {environment}",
+        job.id,
+        job.name,
+        short(&good.sha)
+    );
+    ctx.confirm(project, std::slice::from_ref(&step))?;
+    let entry = ctx.journaled(project, &step, || {
+        let endpoint = RetryJob::builder().project(project).job(job.id).build()?;
+        let retried: Retried = endpoint.query(ctx.client)?;
+        Ok((None, Vars::from([("job_url".to_owned(), retried.web_url)])))
+    })?;
+    Ok(entry.vars.get("job_url").cloned().unwrap_or_default())
+}
+
+/// Opens a merge request that takes `target` back to the commit of `good`.
+fn merge_request(
+    ctx: &Context,
+    project: &str,
+    environment: &str,
+    good: &Deployment,
+    target: &str,
+) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let changes = changes(ctx, project, &good.sha, target)?;
+    anyhow::ensure!(
+        !changes.is_empty(),
+        "{target} has the same files as {}; redeploy it with --redeploy",
+        short(&good.sha)
+    );
+    let branch = format!(
+        "rollback/{}-{}",
+        environment.replace('/', "-"),
+        short(&good.sha)
+    );
+    let title = format!(
+        "Roll back {environment} to {} ({})",
+        good.ref_,
+        short(&good.sha)
+    );
+    let steps = [
+        format!(
+            "create branch {branch} with {target} as of {}",
+            short(&good.sha)
+        ),
+        format!("open a merge request {branch} -> {target}"),
+    ];
+    ctx.confirm(project, &steps)?;
+
+    let batch = Batch {
+        branch: branch.clone(),
+        start_branch: Some(target.to_owned()),
+        message: title.clone(),
+        changes,
+    };
+    let event = Event::BranchCreated {
+        project,
+        branch: &branch,
+        ref_: target,
+    };
+    ctx.journaled(project, &steps[0], || {
+        ctx.hooked(&event, || {
+            files::commit(client, ctx.commits, project, &batch)?;
+            Ok((
+                Some(Resource::Branch {
+                    name: branch.clone(),
+                }),
+                Vars::new(),
+            ))
+        })
+    })?;
+
+    let description = format!(
+        "Takes `{target}` back to `{}` ({}), which {environment} ran before its last deployment.",
+        good.ref_, good.sha
+    );
+    let event = Event::MrCreated {
+        project,
+        source_branch: &branch,
+        target_branch: target,
+        title: &title,
+    };
+    let entry = ctx.journaled(project, &steps[1], || {
+        ctx.hooked(&event, || {
+            let mr: Created = CreateMergeRequest::builder()
+                .project(project)
+                .source_branch(branch.as_str())
+                .target_branch(target)
+                .title(title.as_str())
+                .description(origin::sign(&description))
+                .remove_source_branch(true)
+                .build()?
+                .query(client)?;
+            origin::note(client, project, mr.iid);
+            Ok((
+                Some(Resource::MergeRequest {
+                    iid: mr.iid,
+                    web_url: mr.web_url,
+                }),
+                Vars::new(),
+            ))
+        })
+    })?;
+    match entry.created {
+        Some(Resource::MergeRequest { web_url, .. }) => Ok(web_url),
+        _ => Ok(branch),
+    }
+}
+
+/// Rolls `environment` back to the last successful deployment of another
+/// commit, with a merge request or by redeploying it, and announces it on
+/// the notification webhook if there is one.
+pub fn run(ctx: &Context, project: &str, rollback: &RollbackDeploy) -> anyhow::Result<String> {
+    let environment = rollback.environment.as_str();
+    let (current, good) = deployments(ctx, project, environment)?;
+    tracing::info!(
+        project,
+        "{environment} runs {} ({}); rolling back to {} ({})",
+        current.ref_,
+        short(&current.sha),
+        good.ref_,
+        short(&good.sha)
+    );
+    let url = if rollback.redeploy {
+        redeploy(ctx, project, environment, &good)?
+    } else {
+        let target = rollback.target.as_deref().unwrap_or(&current.ref_);
+        anyhow::ensure!(
+            files::branch_exists(ctx.client, project, target)?,
+            "{environment} runs {target}, which is not a branch; pass --target or --redeploy"
+        );
+        merge_request(ctx, project, environment, &good, target)?
+    };
+
+    if let Some(webhook_url) = ctx.notify.webhook_url() {
+        let by = std::env::var("GITLAB_USER_LOGIN")
+            .map(|login| format!(" by @{login}"))
+            .unwrap_or_default();
+        let message = format!(
+            "Rolling back {environment} of {project} from `{}` to `{}` ({}){by}: {url}",
+            short(&current.sha),
+            good.ref_,
+            short(&good.sha)
+        );
+        let event = Event::NotificationSent {
+            project,
+            message: &message,
+        };
+        ctx.hooked(&event, || notify::send(ctx.client, &webhook_url, &message))?;
+    }
+    Ok(format!(
+        "rolling back {environment} to {}: {url}",
+        short(&good.sha)
+    ))
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use gitlab::api::groups::runners::GroupRunners;
+use gitlab::api::projects::jobs::{JobScope, Jobs};
+use gitlab::api::projects::runners::ProjectRunners;
+use gitlab::api::runners::{Runner, RunnerJobStatus, RunnerJobs};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::table;
+
+#[derive(Debug, Deserialize)]
+struct Listed {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Details {
+    id: u64,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    runner_type: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    paused: bool,
+    #[serde(default)]
+    run_untagged: bool,
+    #[serde(default)]
+    tag_list: Vec<String>,
+}
+
+impl Details {
+    fn picks_up(&self, job: &PendingJob) -> bool {
+        self.status == "online"
+            && !self.paused
+            && if job.tag_list.is_empty() {
+                self.run_untagged
+            } else {
+                job.tag_list.iter().all(|tag| self.tag_list.contains(tag))
+            }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PendingJob {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    tag_list: Vec<String>,
+    created_at: DateTime<Utc>,
+    web_url: String,
+}
+
+fn details(client: &Client, listed: Vec<Listed>) -> anyhow::Result<Vec<Details>> {
+    listed
+        .into_iter()
+        .map(|runner| Ok(Runner::builder().runner(runner.id).build()?.query(client)?))
+        .collect()
+}
+
+/// Prints the runners of `group` and `projects` with how many jobs each is
+/// running, then the pending jobs no online runner can pick up, and fails
+/// if there are any.
+pub fn status(client: &Client, group: Option<&str>, projects: &[String]) -> anyhow::Result<()> {
+    let mut runners = BTreeMap::new();
+    let mut available = BTreeMap::new();
+    if let Some(group) = group {
+        let endpoint = GroupRunners::builder().group(group).build()?;
+        let listed = api::paged(endpoint, api::Pagination::All).query(client)?;
+        for runner in details(client, listed)? {
+            runners.insert(runner.id, runner);
+        }
+    }
+    for project in projects {
+        let endpoint = ProjectRunners::builder()
+            .project(project.as_str())
+            .build()?;
+        let listed: Vec<Listed> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        available.insert(
+            project,
+            listed.iter().map(|runner| runner.id).collect::<Vec<_>>(),
+        );
+        let missing = listed
+            .into_iter()
+            .filter(|runner| !runners.contains_key(&runner.id))
+            .collect();
+        for runner in details(client, missing)? {
+            runners.insert(runner.id, runner);
+        }
+    }
+
+    let mut rows = Vec::new();
+    for runner in runners.values() {
+        let endpoint = RunnerJobs::builder()
+            .runner(runner.id)
+            .status(RunnerJobStatus::Running)
+            .build()?;
+        let running: Vec<Listed> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        let status = if runner.paused {
+            format!("{} (paused)", runner.status)
+        } else {
+            runner.status.clone()
+        };
+        rows.push([
+            runner.id.to_string(),
+            runner.description.clone().unwrap_or_default(),
+            runner.runner_type.clone(),
+            status,
+            runner.tag_list.join(","),
+            running.len().to_string(),
+        ]);
+    }
+    println!(
+        "{}",
+        table::render(
+            ["ID", "DESCRIPTION", "TYPE", "STATUS", "TAGS", "RUNNING"],
+            &rows
+        )
+    );
+
+    let now = Utc::now();
+    let mut stuck = Vec::new();
+    for (project, ids) in &available {
+        let endpoint = Jobs::builder()
+            .project(project.as_str())
+            .scope(JobScope::Pending)
+            .build()?;
+        let pending: Vec<PendingJob> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        for job in pending {
+            if ids.iter().any(|id| runners[id].picks_up(&job)) {
+                continue;
+            }
+            let tags = if job.tag_list.is_empty() {
+                "(untagged)".to_owned()
+            } else {
+                job.tag_list.join(",")
+            };
+            stuck.push([
+                project.to_string(),
+                job.id.to_string(),
+                job.name,
+                tags,
+                format!("{}m", (now - job.created_at).num_minutes()),
+                job.web_url,
+            ]);
+        }
+    }
+    if stuck.is_empty() {
+        return Ok(());
+    }
+    println!(
+        "\n{}",
+        table::render(["PROJECT", "JOB", "NAME", "TAGS", "WAITING", "URL"], &stuck)
+    );
+    anyhow::bail!(
+        "{} pending job(s) have tags no online runner has",
+        stuck.len()
+    )
+}
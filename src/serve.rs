@@ -0,0 +1,414 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use gitlab::api::{
+    self,
+    projects::{
+        members::AllProjectMember,
+        merge_requests::{notes::CreateMergeRequestNote, CreateMergeRequest, EditMergeRequest},
+    },
+    Query,
+};
+use serde::Deserialize;
+
+use crate::authorship::CommitConfig;
+use crate::client::{self, Client};
+use crate::config::{Config, ServeConfig};
+use crate::hooks::Hooks;
+use crate::journal::Journal;
+use crate::notify::NotifyConfig;
+use crate::workflow::Context;
+use crate::{chatops, metrics, redact, reporting, title};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "object_kind", rename_all = "snake_case")]
+enum Event {
+    MergeRequest(MergeRequestEvent),
+    Note(NoteEvent),
+    Pipeline(PipelineEvent),
+    #[serde(other)]
+    Other,
+}
+
+impl Event {
+    fn kind(&self) -> &'static str {
+        match self {
+            Event::MergeRequest(_) => "merge_request",
+            Event::Note(_) => "note",
+            Event::Pipeline(_) => "pipeline",
+            Event::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: u64,
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    access_level: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequestEvent {
+    project: Project,
+    user: User,
+    object_attributes: MergeRequestAttributes,
+    #[serde(default)]
+    changes: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequestAttributes {
+    iid: u64,
+    title: String,
+    action: Option<String>,
+    source_branch: String,
+    target_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoteEvent {
+    project: Project,
+    user: User,
+    object_attributes: NoteAttributes,
+    merge_request: Option<NoteMergeRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoteAttributes {
+    note: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoteMergeRequest {
+    iid: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PipelineEvent {
+    object_attributes: PipelineAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+struct PipelineAttributes {
+    id: u64,
+    #[serde(rename = "ref")]
+    ref_: String,
+    status: String,
+}
+
+struct AppState {
+    client: Client,
+    /// The token's own user, whose notes are our replies.
+    bot_user_id: u64,
+    hooks: Hooks,
+    notify: NotifyConfig,
+    commits: CommitConfig,
+    config: ServeConfig,
+    secret: Option<String>,
+}
+
+impl AppState {
+    /// Every command gets a journal of its own, so a repeated command starts afresh.
+    fn context<'a>(&'a self, journal: &'a Journal) -> Context<'a> {
+        Context {
+            client: &self.client,
+            hooks: &self.hooks,
+            notify: &self.notify,
+            commits: &self.commits,
+            journal,
+            git: None,
+            confirm: false,
+        }
+    }
+}
+
+fn is_hotfix_branch(branch: &str) -> bool {
+    branch
+        .strip_prefix("release/")
+        .is_some_and(|version| semver::Version::parse(version).is_ok())
+}
+
+fn reply(client: &Client, project: u64, iid: u64, body: &str) -> anyhow::Result<()> {
+    let note = CreateMergeRequestNote::builder()
+        .project(project)
+        .merge_request(iid)
+        .body(redact::redact(body))
+        .build()?;
+    api::ignore(note).query(client)?;
+    Ok(())
+}
+
+/// The commenter's access level in the project, counting inherited group
+/// membership; 0 for someone who is not a member at all.
+fn access_level(client: &Client, project: u64, user: u64) -> anyhow::Result<u64> {
+    let endpoint = AllProjectMember::builder()
+        .project(project)
+        .user(user)
+        .build()?;
+    match endpoint.query(client) {
+        Ok(Member { access_level }) => Ok(access_level),
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => Ok(0),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn on_merge_request(state: &AppState, event: MergeRequestEvent) -> anyhow::Result<()> {
+    let mr = &event.object_attributes;
+    let action = mr.action.as_deref().unwrap_or_default();
+
+    let mut mr_title = mr.title.clone();
+    if state.config.suggest_titles
+        && action == "open"
+        && title::is_derived_from(&mr.title, &mr.source_branch)
+    {
+        if let Some(suggested) = title::from_branch(&mr.source_branch) {
+            tracing::info!(
+                project = event.project.id,
+                iid = mr.iid,
+                suggested,
+                "retitling from the branch name"
+            );
+            let draft = if mr.title.starts_with("Draft:") {
+                "Draft: "
+            } else {
+                ""
+            };
+            let edit = EditMergeRequest::builder()
+                .project(event.project.id)
+                .merge_request(mr.iid)
+                .title(format!("{draft}{suggested}"))
+                .build()?;
+            api::ignore(edit).query(&state.client)?;
+            mr_title = suggested;
+        }
+    }
+
+    let title_changed = action == "open" || event.changes.contains_key("title");
+    if state.config.lint_titles && title_changed {
+        if let Err(err) = title::lint(&mr_title) {
+            tracing::info!(
+                project = event.project.id,
+                iid = mr.iid,
+                "title does not lint"
+            );
+            reply(
+                &state.client,
+                event.project.id,
+                mr.iid,
+                &format!(
+                    "@{} this title does not follow the `kind (JIRA-ID): title` convention:\n\n```\n{err}\n```",
+                    event.user.username
+                ),
+            )?;
+        }
+    }
+
+    if let Some(target) = &state.config.merge_back_target {
+        if action == "merge" && mr.target_branch == "master" && is_hotfix_branch(&mr.source_branch)
+        {
+            tracing::info!(source = mr.source_branch, target, "merging hotfix back");
+            let merge_back = CreateMergeRequest::builder()
+                .project(event.project.id)
+                .source_branch(&mr.source_branch)
+                .target_branch(target)
+                .title(format!("Merge back {} into {target}", mr.source_branch))
+                .description(format!(
+                    "Auto-generated after !{} was merged into master.",
+                    mr.iid
+                ))
+                .build()?;
+            match api::ignore(merge_back).query(&state.client) {
+                Ok(()) => {}
+                Err(err) if client::status(&err) == Some(http::StatusCode::CONFLICT) => {
+                    tracing::info!("a merge request into {target} already exists");
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn on_note(state: &AppState, event: NoteEvent) -> anyhow::Result<()> {
+    let Some(mr) = event.merge_request else {
+        return Ok(());
+    };
+    if !state.config.slash_commands || event.user.id == state.bot_user_id {
+        return Ok(());
+    }
+    let commands = chatops::commands(&event.object_attributes.note);
+    if commands.is_empty() {
+        return Ok(());
+    }
+    let role = state.config.command_role;
+    let level = access_level(&state.client, event.project.id, event.user.id)?;
+    if level < role.access_level() {
+        tracing::info!(
+            user = event.user.username,
+            level,
+            "refusing comment commands from a user below {role}"
+        );
+        return reply(
+            &state.client,
+            event.project.id,
+            mr.iid,
+            &format!(
+                "@{} :no_entry: running commands here needs at least the {role} role.",
+                event.user.username
+            ),
+        );
+    }
+
+    let origin = chatops::Origin {
+        project: event.project.id,
+        iid: mr.iid,
+        user_id: event.user.id,
+    };
+    let mut body = format!("@{}", event.user.username);
+    for command in commands {
+        let result = command.and_then(|command| {
+            let patch_role = state.config.patch_role;
+            if command == chatops::Command::CutPatch && level < patch_role.access_level() {
+                return Err(format!(
+                    "/{} needs at least the {patch_role} role",
+                    command.name()
+                ));
+            }
+            tracing::info!(
+                user = event.user.username,
+                ?command,
+                "running comment command"
+            );
+            let journal = Journal::disabled();
+            let result = chatops::execute(&state.context(&journal), &origin, &command);
+            metrics::increment(
+                "gitlab_helper_workflows_total",
+                &[("command", command.name()), ("result", outcome(&result))],
+            );
+            if let Err(err) = &result {
+                reporting::failure(Some(&origin.project.to_string()), err);
+            }
+            result.map_err(|err| format!("{err:#}"))
+        });
+        match result {
+            Ok(summary) => body.push_str(&format!("\n\n:white_check_mark: {summary}")),
+            Err(err) => body.push_str(&format!("\n\n:x:\n```\n{err}\n```")),
+        }
+    }
+    reply(&state.client, event.project.id, mr.iid, &body)
+}
+
+fn outcome<T>(result: &anyhow::Result<T>) -> &'static str {
+    if result.is_ok() {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+fn handle(state: &AppState, event: Event) -> anyhow::Result<()> {
+    match event {
+        Event::MergeRequest(event) => on_merge_request(state, event),
+        Event::Note(event) => on_note(state, event),
+        Event::Pipeline(event) => {
+            let pipeline = event.object_attributes;
+            tracing::info!(
+                id = pipeline.id,
+                ref_ = pipeline.ref_,
+                status = pipeline.status,
+                "pipeline event"
+            );
+            Ok(())
+        }
+        Event::Other => Ok(()),
+    }
+}
+
+async fn webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(event): Json<Event>,
+) -> StatusCode {
+    if let Some(secret) = &state.secret {
+        let token = headers
+            .get("x-gitlab-token")
+            .and_then(|token| token.to_str().ok());
+        if token != Some(secret.as_str()) {
+            return StatusCode::UNAUTHORIZED;
+        }
+    }
+
+    // GitLab gives up on slow webhooks, so answer right away and do the work
+    // on the blocking pool where the synchronous API client lives.
+    let kind = event.kind();
+    let span = tracing::info_span!("webhook", kind);
+    tokio::task::spawn_blocking(move || {
+        let _entered = span.enter();
+        let result = handle(&state, event);
+        metrics::increment(
+            "gitlab_helper_webhooks_total",
+            &[("kind", kind), ("result", outcome(&result))],
+        );
+        if let Err(err) = result {
+            tracing::error!("webhook handling failed: {err:#}");
+            reporting::failure(None, &err);
+        }
+    });
+    StatusCode::OK
+}
+
+async fn metrics_endpoint() -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
+pub fn run(client: Client, config: Config, secret: Option<String>) -> anyhow::Result<()> {
+    if secret.is_none() {
+        tracing::warn!("GITLAB_WEBHOOK_SECRET is not set; accepting unauthenticated webhooks");
+    }
+    let listen = config.serve.listen.clone();
+    let bot: User = api::users::CurrentUser::builder().build()?.query(&client)?;
+    let state = Arc::new(AppState {
+        bot_user_id: bot.id,
+        client,
+        hooks: config.hooks,
+        notify: config.notify,
+        commits: config.commits,
+        config: config.serve,
+        secret,
+    });
+    let app = Router::new()
+        .route("/webhook", post(webhook))
+        .route("/metrics", get(metrics_endpoint))
+        .with_state(state.clone());
+
+    let result = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(async {
+            let listener = tokio::net::TcpListener::bind(&listen).await?;
+            tracing::info!("listening for GitLab webhooks on {listen}");
+            axum::serve(listener, app).await?;
+            Ok(())
+        });
+    // The blocking HTTP client must not be dropped on a runtime thread.
+    drop(state);
+    result
+}
@@ -0,0 +1,330 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use gitlab::api::common::EnableState;
+use gitlab::api::projects::{self, approval_rules, MergeMethod, SquashOption};
+use gitlab::api::{self, Query};
+use serde::{Deserialize, Serialize};
+
+use crate::client::{self, Client};
+use crate::endpoints::{CreateApprovalRule, EditApprovalRule};
+use crate::protect::{self, Mismatch, ProtectedBranch};
+use crate::workflow::Context;
+
+pub const DEFAULT_PATH: &str = "settings.toml";
+
+/// The settings every project should have; whatever is left out is not audited.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Desired {
+    #[serde(default)]
+    pub merge_requests: MergeRequests,
+    #[serde(default)]
+    pub pipelines: Pipelines,
+    #[serde(default)]
+    pub protected_branches: Vec<ProtectedBranch>,
+    #[serde(default)]
+    pub approval_rules: Vec<ApprovalRule>,
+}
+
+/// Named after the fields of GitLab's project API.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MergeRequests {
+    pub merge_method: Option<Method>,
+    pub squash_option: Option<Squash>,
+    pub only_allow_merge_if_pipeline_succeeds: Option<bool>,
+    pub allow_merge_on_skipped_pipeline: Option<bool>,
+    pub only_allow_merge_if_all_discussions_are_resolved: Option<bool>,
+    pub remove_source_branch_after_merge: Option<bool>,
+    pub resolve_outdated_diff_discussions: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pipelines {
+    pub ci_config_path: Option<String>,
+    /// In seconds.
+    pub build_timeout: Option<u64>,
+    pub auto_cancel_pending_pipelines: Option<Toggle>,
+    pub ci_default_git_depth: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    Merge,
+    RebaseMerge,
+    Ff,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Squash {
+    Never,
+    Always,
+    DefaultOn,
+    DefaultOff,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Toggle {
+    Enabled,
+    Disabled,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalRule {
+    pub name: String,
+    pub approvals_required: u64,
+}
+
+impl Desired {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))
+    }
+
+    /// The project-level settings that are set, as the API names and spells them.
+    fn project_settings(&self) -> Vec<(String, serde_json::Value)> {
+        [
+            serde_json::to_value(&self.merge_requests),
+            serde_json::to_value(&self.pipelines),
+        ]
+        .into_iter()
+        .filter_map(|value| match value {
+            Ok(serde_json::Value::Object(object)) => Some(object),
+            _ => None,
+        })
+        .flatten()
+        .filter(|(_, value)| !value.is_null())
+        .collect()
+    }
+
+    fn edit_project<'a>(&'a self, project: &'a str) -> anyhow::Result<projects::EditProject<'a>> {
+        let mut edit = projects::EditProject::builder();
+        edit.project(project);
+        let mr = &self.merge_requests;
+        if let Some(method) = mr.merge_method {
+            edit.merge_method(match method {
+                Method::Merge => MergeMethod::Merge,
+                Method::RebaseMerge => MergeMethod::RebaseMerge,
+                Method::Ff => MergeMethod::FastForward,
+            });
+        }
+        if let Some(squash) = mr.squash_option {
+            edit.squash_option(match squash {
+                Squash::Never => SquashOption::Never,
+                Squash::Always => SquashOption::Always,
+                Squash::DefaultOn => SquashOption::DefaultOn,
+                Squash::DefaultOff => SquashOption::DefaultOff,
+            });
+        }
+        if let Some(enabled) = mr.only_allow_merge_if_pipeline_succeeds {
+            edit.only_allow_merge_if_pipeline_succeeds(enabled);
+        }
+        if let Some(enabled) = mr.allow_merge_on_skipped_pipeline {
+            edit.allow_merge_on_skipped_pipeline(enabled);
+        }
+        if let Some(enabled) = mr.only_allow_merge_if_all_discussions_are_resolved {
+            edit.only_allow_merge_if_all_discussions_are_resolved(enabled);
+        }
+        if let Some(enabled) = mr.remove_source_branch_after_merge {
+            edit.remove_source_branch_after_merge(enabled);
+        }
+        if let Some(enabled) = mr.resolve_outdated_diff_discussions {
+            edit.resolve_outdated_diff_discussions(enabled);
+        }
+        let pipelines = &self.pipelines;
+        if let Some(path) = &pipelines.ci_config_path {
+            edit.ci_config_path(path.as_str());
+        }
+        if let Some(timeout) = pipelines.build_timeout {
+            edit.build_timeout(timeout);
+        }
+        if let Some(toggle) = pipelines.auto_cancel_pending_pipelines {
+            edit.auto_cancel_pending_pipelines(match toggle {
+                Toggle::Enabled => EnableState::Enabled,
+                Toggle::Disabled => EnableState::Disabled,
+            });
+        }
+        if let Some(depth) = pipelines.ci_default_git_depth {
+            edit.ci_default_git_depth(depth);
+        }
+        Ok(edit.build()?)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentRule {
+    id: u64,
+    name: String,
+    approvals_required: u64,
+}
+
+enum Change<'a> {
+    /// Settings of the project itself, all fixed by a single edit.
+    Project,
+    Protect(Mismatch<'a, ProtectedBranch>),
+    ApprovalRule {
+        rule: &'a ApprovalRule,
+        id: Option<u64>,
+    },
+}
+
+struct Drift<'a> {
+    setting: String,
+    current: String,
+    desired: String,
+    change: Change<'a>,
+}
+
+fn drift<'a>(
+    client: &Client,
+    project: &str,
+    desired: &'a Desired,
+) -> anyhow::Result<Vec<Drift<'a>>> {
+    let mut drifts = Vec::new();
+
+    let settings = desired.project_settings();
+    if !settings.is_empty() {
+        let current: serde_json::Value = projects::Project::builder()
+            .project(project)
+            .build()?
+            .query(client)?;
+        for (key, want) in settings {
+            let have = match current.get(&key) {
+                // GitLab reports an unset `ci_config_path` as null.
+                Some(serde_json::Value::Null) if want.is_string() => "".into(),
+                Some(have) => have.clone(),
+                None => serde_json::Value::Null,
+            };
+            if have != want {
+                drifts.push(Drift {
+                    setting: key,
+                    current: have.to_string(),
+                    desired: want.to_string(),
+                    change: Change::Project,
+                });
+            }
+        }
+    }
+
+    for branch in protect::branch_mismatches(client, project, &desired.protected_branches)? {
+        drifts.push(Drift {
+            setting: format!("protected branch {}", branch.desired.name),
+            current: branch.current().to_owned(),
+            desired: branch.desired.describe(),
+            change: Change::Protect(branch),
+        });
+    }
+
+    if !desired.approval_rules.is_empty() {
+        let endpoint = approval_rules::ProjectApprovalRules::builder()
+            .project(project)
+            .build()?;
+        let current: Vec<CurrentRule> =
+            match api::paged(endpoint, api::Pagination::All).query(client) {
+                Ok(current) => current,
+                // The free tier answers as if the endpoint did not exist, or refuses it.
+                Err(err)
+                    if matches!(
+                        client::status(&err),
+                        Some(http::StatusCode::FORBIDDEN | http::StatusCode::NOT_FOUND)
+                    ) =>
+                {
+                    Err(err).context("approval rules need GitLab Premium")?
+                }
+                Err(err) => Err(err)?,
+            };
+        for rule in &desired.approval_rules {
+            let have = current.iter().find(|have| have.name == rule.name);
+            if have.map(|have| have.approvals_required) != Some(rule.approvals_required) {
+                drifts.push(Drift {
+                    setting: format!("approval rule {}", rule.name),
+                    current: have.map_or("missing".to_owned(), |have| {
+                        format!("{} approval(s) required", have.approvals_required)
+                    }),
+                    desired: format!("{} approval(s) required", rule.approvals_required),
+                    change: Change::ApprovalRule {
+                        rule,
+                        id: have.map(|have| have.id),
+                    },
+                });
+            }
+        }
+    }
+
+    Ok(drifts)
+}
+
+fn fix(client: &Client, project: &str, desired: &Desired, drifts: &[Drift]) -> anyhow::Result<()> {
+    if drifts
+        .iter()
+        .any(|drift| matches!(drift.change, Change::Project))
+    {
+        api::ignore(desired.edit_project(project)?).query(client)?;
+    }
+    for drift in drifts {
+        match drift.change {
+            Change::Project => {}
+            Change::Protect(ref branch) => protect::protect_branch(client, project, branch)?,
+            Change::ApprovalRule { rule, id: None } => {
+                api::ignore(CreateApprovalRule {
+                    project: project.into(),
+                    name: &rule.name,
+                    approvals_required: rule.approvals_required,
+                })
+                .query(client)?;
+            }
+            Change::ApprovalRule { rule, id: Some(id) } => {
+                api::ignore(EditApprovalRule {
+                    project: project.into(),
+                    id,
+                    approvals_required: rule.approvals_required,
+                })
+                .query(client)?;
+            }
+        }
+        tracing::info!("fixed {}", drift.setting);
+    }
+    Ok(())
+}
+
+/// Prints how `project` differs from `desired` (read from `path`) as a diff,
+/// and with `apply` makes it match.
+pub fn audit(
+    ctx: &Context,
+    project: &str,
+    desired: &Desired,
+    path: &Path,
+    apply: bool,
+) -> anyhow::Result<String> {
+    let drifts = drift(ctx.client, project, desired)?;
+    if drifts.is_empty() {
+        return Ok("in sync".to_owned());
+    }
+
+    let mut diff = format!("--- {project}\n+++ {}\n", path.display());
+    for drift in &drifts {
+        diff.push_str(&format!(
+            "-{0} = {1}\n+{0} = {2}\n",
+            drift.setting, drift.current, drift.desired
+        ));
+    }
+    print!("{diff}");
+
+    if !apply {
+        anyhow::bail!("{} setting(s) drifted", drifts.len());
+    }
+    let plan: Vec<_> = drifts
+        .iter()
+        .map(|drift| format!("set {} to {}", drift.setting, drift.desired))
+        .collect();
+    ctx.confirm(project, &plan)?;
+    fix(ctx.client, project, desired, &drifts)?;
+    Ok(format!("fixed {} setting(s)", drifts.len()))
+}
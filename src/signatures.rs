@@ -0,0 +1,106 @@
+use gitlab::api::projects::merge_requests::MergeRequestCommits;
+use gitlab::api::projects::repository::commits::Signature;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::checks::{Case, Reports};
+use crate::client::{self, Client};
+use crate::table;
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    id: String,
+    short_id: String,
+    title: String,
+    author_name: String,
+    author_email: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitSignature {
+    signature_type: String,
+    verification_status: String,
+}
+
+/// The signature of `sha`, or `None` if the commit is not signed.
+fn signature(client: &Client, project: &str, sha: &str) -> anyhow::Result<Option<CommitSignature>> {
+    let endpoint = Signature::builder().project(project).commit(sha).build()?;
+    match endpoint.query(client) {
+        Ok(signature) => Ok(Some(signature)),
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Lists the commits of merge request `iid` with their signature, and fails
+/// if any is unsigned or unverified, unless its author's name or email is
+/// in `allowed_authors`.
+pub fn check(
+    client: &Client,
+    project: &str,
+    iid: u64,
+    allowed_authors: &[String],
+    reports: &Reports,
+) -> anyhow::Result<()> {
+    let endpoint = MergeRequestCommits::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?;
+    let commits: Vec<Commit> = api::paged(endpoint, api::Pagination::All).query(client)?;
+
+    let mut rows = Vec::new();
+    let mut cases = Vec::new();
+    let mut failing = 0;
+    for commit in &commits {
+        let allowed = allowed_authors
+            .iter()
+            .any(|author| author == &commit.author_name || author == &commit.author_email);
+        let (kind, status) = match signature(client, project, &commit.id)? {
+            Some(signature) => (signature.signature_type, signature.verification_status),
+            None => ("-".to_owned(), "unsigned".to_owned()),
+        };
+        let verdict = if status == "verified" {
+            "ok"
+        } else if allowed {
+            "allowed"
+        } else {
+            failing += 1;
+            "FAIL"
+        };
+        let name = format!("{} {}", commit.short_id, commit.title);
+        cases.push(if verdict == "FAIL" {
+            Case::failed(name, format!("{status} commit by {}", commit.author_name))
+        } else {
+            Case::passed(name)
+        });
+        rows.push([
+            commit.short_id.clone(),
+            commit.author_name.clone(),
+            kind,
+            status,
+            verdict.to_owned(),
+            commit.title.clone(),
+        ]);
+    }
+    println!(
+        "{}",
+        table::render(
+            [
+                "COMMIT",
+                "AUTHOR",
+                "SIGNATURE",
+                "STATUS",
+                "VERDICT",
+                "TITLE"
+            ],
+            &rows
+        )
+    );
+    reports.write("check-signatures", &cases)?;
+    anyhow::ensure!(
+        failing == 0,
+        "{failing} of {} commit(s) in !{iid} are not signed and verified",
+        commits.len()
+    );
+    Ok(())
+}
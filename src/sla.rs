@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use gitlab::api::projects::merge_requests::notes::{CreateMergeRequestNote, MergeRequestNotes};
+use gitlab::api::projects::merge_requests::{MergeRequestState, MergeRequests};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::duration;
+use crate::emergency::SLA_LABEL;
+use crate::i18n;
+use crate::notify;
+use crate::table;
+
+/// Starts the note left on an overdue merge request, so it is escalated once.
+const MISSED: &str = "SLA missed:";
+
+#[derive(Debug, Deserialize)]
+struct User {
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mr {
+    iid: u64,
+    web_url: String,
+    target_branch: String,
+    created_at: DateTime<Utc>,
+    assignee: Option<User>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+    body: String,
+}
+
+fn escalated(client: &Client, project: &str, iid: u64) -> anyhow::Result<bool> {
+    let endpoint = MergeRequestNotes::builder()
+        .project(project)
+        .merge_request(iid)
+        .build()?;
+    let notes: Vec<Note> = api::paged(endpoint, api::Pagination::All).query(client)?;
+    Ok(notes.iter().any(|note| note.body.starts_with(MISSED)))
+}
+
+/// Lists the open emergency merge requests of `projects` and when each is
+/// due, and fails if any is past `sla`. Those newly overdue get a note
+/// mentioning their assignee and are posted to `notify_url` if set.
+pub fn check(
+    client: &Client,
+    projects: &[String],
+    sla: Duration,
+    notify_url: Option<&str>,
+) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let mut rows = Vec::new();
+    let mut overdue = 0;
+    let mut newly_overdue = Vec::new();
+    for project in projects {
+        let endpoint = MergeRequests::builder()
+            .project(project.as_str())
+            .state(MergeRequestState::Opened)
+            .labels(std::iter::once(SLA_LABEL))
+            .build()?;
+        let mrs: Vec<Mr> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        for mr in mrs {
+            let due = mr.created_at + sla;
+            let status = if due < now {
+                overdue += 1;
+                let late = duration::format((now - due).to_std().unwrap_or_default());
+                if !escalated(client, project, mr.iid)? {
+                    let mut body = format!(
+                        "{MISSED} open for longer than {}, due {}.",
+                        duration::format(sla),
+                        due.format("%Y-%m-%d %H:%M UTC")
+                    );
+                    if let Some(assignee) = &mr.assignee {
+                        body.push_str(&format!(
+                            " @{} please merge or hand over.",
+                            assignee.username
+                        ));
+                    }
+                    let endpoint = CreateMergeRequestNote::builder()
+                        .project(project.as_str())
+                        .merge_request(mr.iid)
+                        .body(body)
+                        .build()?;
+                    api::ignore(endpoint).query(client)?;
+                    newly_overdue.push(format!("- {} ({late} overdue)", mr.web_url));
+                }
+                format!("overdue by {late}")
+            } else {
+                let left = duration::format((due - now).to_std().unwrap_or_default());
+                format!("due in {left}")
+            };
+            rows.push([
+                project.clone(),
+                format!("!{}", mr.iid),
+                mr.target_branch,
+                due.format("%Y-%m-%d %H:%M").to_string(),
+                status,
+            ]);
+        }
+    }
+    println!(
+        "{}",
+        table::render(["PROJECT", "MR", "TARGET", "DUE (UTC)", "STATUS"], &rows)
+    );
+
+    if overdue == 0 {
+        return Ok(());
+    }
+    match notify_url {
+        Some(url) if !newly_overdue.is_empty() => {
+            let heading = i18n::format(
+                i18n::messages().sla_missed,
+                &[
+                    ("count", &newly_overdue.len().to_string()),
+                    ("sla", &duration::format(sla)),
+                ],
+            );
+            let message = format!("{heading}\n{}", newly_overdue.join("\n"));
+            notify::send(client, url, &message)?;
+        }
+        Some(_) => {}
+        None => tracing::warn!("set notify.webhook_url to escalate missed SLAs beyond the note"),
+    }
+    anyhow::bail!(
+        "{overdue} emergency merge request(s) are open past the {} SLA",
+        duration::format(sla)
+    )
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use gitlab::api::common::SortOrder;
+use gitlab::api::projects::deployments::{DeploymentOrderBy, DeploymentStatusFilter, Deployments};
+use gitlab::api::projects::pipelines::Pipelines;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::compare;
+
+/// The environment whose last deployment the snapshot shows.
+const PRODUCTION: &str = "production";
+/// Longer lists of commits since the deployment are cut short.
+const MAX_COMMITS: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+    status: String,
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Deployment {
+    sha: String,
+    #[serde(rename = "ref")]
+    ref_: String,
+    finished_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    environment: Environment,
+}
+
+#[derive(Debug, Deserialize)]
+struct Environment {
+    name: String,
+}
+
+/// A Markdown block on the state of `branch` and production, for people
+/// picking up an incident: the latest pipeline of `branch`, the last
+/// successful production deployment and what `branch` has gained since.
+pub fn render(client: &Client, project: &str, branch: &str) -> anyhow::Result<String> {
+    let mut out = String::from("### Context\n\n");
+
+    let pipelines = Pipelines::builder().project(project).ref_(branch).build()?;
+    let pipelines: Vec<Pipeline> =
+        api::paged(pipelines, api::Pagination::Limit(1)).query(client)?;
+    match pipelines.into_iter().next() {
+        Some(pipeline) => out.push_str(&format!(
+            "- Latest pipeline of `{branch}`: [#{}]({}) **{}**\n",
+            pipeline.id, pipeline.web_url, pipeline.status
+        )),
+        None => out.push_str(&format!("- `{branch}` has no pipelines\n")),
+    }
+
+    let deployments = Deployments::builder()
+        .project(project)
+        .environment(PRODUCTION)
+        .status(DeploymentStatusFilter::Success)
+        .order_by(DeploymentOrderBy::FinishedAt)
+        .sort(SortOrder::Descending)
+        .build()?;
+    let deployments: Vec<Deployment> = deployments.query(client)?;
+    let Some(deployment) = deployments.into_iter().next() else {
+        out.push_str(&format!("- Nothing was deployed to {PRODUCTION} yet\n"));
+        return Ok(out);
+    };
+    let at = deployment.finished_at.unwrap_or(deployment.created_at);
+    out.push_str(&format!(
+        "- Last deployed to {}: `{}` ({}) at {}\n",
+        deployment.environment.name,
+        deployment.ref_,
+        &deployment.sha[..deployment.sha.len().min(8)],
+        at.format("%Y-%m-%d %H:%M UTC")
+    ));
+
+    let commits = compare::commit_titles(client, project, &deployment.sha, branch)?;
+    if commits.is_empty() {
+        out.push_str(&format!("- `{branch}` has nothing that is not deployed\n"));
+        return Ok(out);
+    }
+    out.push_str(&format!(
+        "- {} commit(s) on `{branch}` since that deployment:\n",
+        commits.len()
+    ));
+    for commit in commits.iter().take(MAX_COMMITS) {
+        out.push_str(&format!("  - {commit}\n"));
+    }
+    if commits.len() > MAX_COMMITS {
+        out.push_str(&format!("  - and {} more\n", commits.len() - MAX_COMMITS));
+    }
+    Ok(out)
+}
@@ -0,0 +1,88 @@
+use clap::Args;
+use gitlab::api::projects::repository::branches::CreateBranch;
+use gitlab::api::{self, Query};
+
+use crate::emergency;
+use crate::hooks::Event;
+use crate::title;
+use crate::workflow::Context;
+
+#[derive(Debug, Clone, Args)]
+pub struct StartFix {
+    /// The Jira issue the fix is for, e.g. `JIRA-55`.
+    #[arg(long)]
+    pub jira: String,
+    /// A few words on the fix, which become the branch's slug.
+    #[arg(long)]
+    pub summary: String,
+    /// The branch to start from; the latest `release/x.y.z`, which is the
+    /// emergency patch branch once one is cut, if unset.
+    #[arg(long)]
+    pub from: Option<String>,
+}
+
+/// `summary` as lowercase words joined by dashes, e.g. `handle-empty-pages`.
+fn slug(summary: &str) -> String {
+    summary
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The work branch for the fix, named so that `suggest-title` understands it.
+fn branch_name(jira: &str, summary: &str) -> anyhow::Result<String> {
+    let branch = format!("fix/{}-{}", jira.to_ascii_uppercase(), slug(summary));
+    anyhow::ensure!(
+        title::from_branch(&branch).is_some(),
+        "cannot name a branch after {jira:?} and {summary:?}; \
+         expected a Jira ID like JIRA-55 and an ASCII summary"
+    );
+    Ok(branch)
+}
+
+/// Creates the work branch for a fix off the emergency patch branch and
+/// prints how to check it out.
+pub fn start(ctx: &Context, project: &str, start: &StartFix) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let branch = branch_name(&start.jira, &start.summary)?;
+    let from = match &start.from {
+        Some(from) => from.clone(),
+        None => format!("release/{}", emergency::latest_release(client, project)?),
+    };
+
+    ctx.confirm(project, &[format!("create branch {branch} from {from}")])?;
+    let create_branch = CreateBranch::builder()
+        .project(project)
+        .branch(&branch)
+        .ref_(&from)
+        .build()?;
+    let event = Event::BranchCreated {
+        project,
+        branch: &branch,
+        ref_: &from,
+    };
+    let repo = ctx.git_for(project)?;
+    ctx.hooked(&event, || match repo {
+        Some(repo) => repo.create_branch(&branch, &from),
+        None => Ok(api::ignore(create_branch).query(client)?),
+    })?;
+    println!("git fetch origin {branch}\ngit checkout {branch}");
+    Ok(format!("created {branch} from {from}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branches_follow_the_naming_convention() {
+        assert_eq!(
+            branch_name("jira-55", "Handle empty pages!").unwrap(),
+            "fix/JIRA-55-handle-empty-pages"
+        );
+        assert!(branch_name("JIRA", "Handle empty pages").is_err());
+        assert!(branch_name("JIRA-55", "???").is_err());
+    }
+}
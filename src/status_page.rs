@@ -0,0 +1,212 @@
+use anyhow::Context as _;
+use clap::{Args, ValueEnum};
+use gitlab::api::issues::{IssueState, ProjectIssues};
+use gitlab::api::projects::issues::notes::CreateIssueNote;
+use gitlab::api::projects::issues::{CreateIssue, EditIssue, IssueStateEvent};
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::oncall::api_key;
+use crate::template::{self, Vars};
+
+/// The `[status_page]` section: where stakeholders follow incidents.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StatusPageConfig {
+    /// An Atlassian Statuspage page; the key is `STATUSPAGE_API_KEY`.
+    Statuspage {
+        page_id: String,
+        #[serde(default = "statuspage_url")]
+        api_url: String,
+    },
+    /// Issues of a GitLab project, found by their title and labels.
+    GitlabIssue {
+        /// The project the issues live in; the one the command runs on if unset.
+        project: Option<String>,
+        #[serde(default = "incident_labels")]
+        labels: Vec<String>,
+    },
+}
+
+fn statuspage_url() -> String {
+    "https://api.statuspage.io/v1".to_owned()
+}
+
+fn incident_labels() -> Vec<String> {
+    vec!["incident".to_owned()]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Status {
+    Investigating,
+    Identified,
+    Monitoring,
+    Resolved,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Investigating => "investigating",
+            Status::Identified => "identified",
+            Status::Monitoring => "monitoring",
+            Status::Resolved => "resolved",
+        }
+    }
+}
+
+const DEFAULT_TITLE: &str = "Service disruption in {{ affected_version }}";
+const DEFAULT_MESSAGE: &str =
+    "A fix is on its way in {{ version }}; we expect it to be live within {{ eta }}.";
+
+#[derive(Debug, Clone, Args)]
+pub struct Update {
+    /// The release with the problem.
+    #[arg(long)]
+    pub affected_version: String,
+    /// The release with the fix.
+    #[arg(long)]
+    pub version: String,
+    /// When the fix should be live, e.g. `30 minutes`.
+    #[arg(long, default_value = "the hour")]
+    pub eta: String,
+    #[arg(long, value_enum, default_value_t = Status::Identified)]
+    pub status: Status,
+    /// A template for the update, with `{{ affected_version }}`, `{{ version }}`, `{{ eta }}` and `{{ status }}`.
+    #[arg(long, value_name = "PATH")]
+    pub template: Option<std::path::PathBuf>,
+}
+
+impl Update {
+    /// The incident's title and the text of this update.
+    fn render(&self) -> anyhow::Result<(String, String)> {
+        let vars = Vars::from([
+            ("affected_version".to_owned(), self.affected_version.clone()),
+            ("version".to_owned(), self.version.clone()),
+            ("eta".to_owned(), self.eta.clone()),
+            ("status".to_owned(), self.status.name().to_owned()),
+        ]);
+        let message = match &self.template {
+            Some(path) => template::render(&template::load(path)?, &vars)
+                .with_context(|| format!("{} does not render", path.display()))?,
+            None => template::render(DEFAULT_MESSAGE, &vars)?,
+        };
+        Ok((template::render(DEFAULT_TITLE, &vars)?, message))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StatuspageIncident {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    iid: u64,
+    title: String,
+    web_url: String,
+}
+
+/// Creates the status incident of `update`, or posts `update` to the open
+/// one with the same title.
+pub fn update(
+    client: &Client,
+    project: &str,
+    config: &StatusPageConfig,
+    update: &Update,
+) -> anyhow::Result<String> {
+    let (title, message) = update.render()?;
+    if client.is_replaying() {
+        return Ok(format!("replay: not posting {title:?} to the status page"));
+    }
+    match config {
+        StatusPageConfig::Statuspage { page_id, api_url } => {
+            let key = api_key("STATUSPAGE_API_KEY")?;
+            let auth = format!("OAuth {key}");
+            let unresolved: Vec<StatuspageIncident> = client
+                .external(
+                    http::Method::GET,
+                    &format!("{api_url}/pages/{page_id}/incidents/unresolved"),
+                )?
+                .header("Authorization", &auth)
+                .send()
+                .and_then(|rsp| rsp.error_for_status())
+                .and_then(|rsp| rsp.json())
+                .context("failed to list the Statuspage incidents")?;
+            let body = serde_json::json!({ "incident": {
+                "name": title,
+                "status": update.status.name(),
+                "body": message,
+            } });
+            let (request, done) = match unresolved.iter().find(|incident| incident.name == title) {
+                Some(incident) => (
+                    client.external(
+                        http::Method::PATCH,
+                        &format!("{api_url}/pages/{page_id}/incidents/{}", incident.id),
+                    )?,
+                    format!("updated the status incident {title:?}"),
+                ),
+                None => (
+                    client.external(
+                        http::Method::POST,
+                        &format!("{api_url}/pages/{page_id}/incidents"),
+                    )?,
+                    format!("opened the status incident {title:?}"),
+                ),
+            };
+            request
+                .header("Authorization", &auth)
+                .json(&body)
+                .send()
+                .and_then(|rsp| rsp.error_for_status())
+                .context("failed to update Statuspage")?;
+            Ok(done)
+        }
+        StatusPageConfig::GitlabIssue {
+            project: issues_project,
+            labels,
+        } => {
+            let issues_project = issues_project.as_deref().unwrap_or(project);
+            let status_label = format!("status::{}", update.status.name());
+            let endpoint = ProjectIssues::builder()
+                .project(issues_project)
+                .state(IssueState::Opened)
+                .labels(labels.iter().map(String::as_str))
+                .search(title.as_str())
+                .build()?;
+            let open: Vec<Issue> = api::paged(endpoint, api::Pagination::All).query(client)?;
+            let Some(issue) = open.into_iter().find(|issue| issue.title == title) else {
+                let issue: Issue = CreateIssue::builder()
+                    .project(issues_project)
+                    .title(title.as_str())
+                    .description(message.as_str())
+                    .labels(
+                        labels
+                            .iter()
+                            .map(String::as_str)
+                            .chain([status_label.as_str()]),
+                    )
+                    .build()?
+                    .query(client)?;
+                return Ok(format!("opened {}", issue.web_url));
+            };
+            let note = CreateIssueNote::builder()
+                .project(issues_project)
+                .issue(issue.iid)
+                .body(format!("**{}**: {message}", update.status.name()))
+                .build()?;
+            api::ignore(note).query(client)?;
+            let mut edit = EditIssue::builder();
+            edit.project(issues_project)
+                .issue(issue.iid)
+                .add_label(status_label.as_str());
+            if update.status == Status::Resolved {
+                edit.state_event(IssueStateEvent::Close);
+            }
+            api::ignore(edit.build()?).query(client)?;
+            Ok(format!("updated {}", issue.web_url))
+        }
+    }
+}
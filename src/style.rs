@@ -0,0 +1,123 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::platform;
+
+static STDOUT: AtomicBool = AtomicBool::new(false);
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn colored(self) -> bool {
+        match self {
+            Stream::Stdout => STDOUT.load(Ordering::Relaxed),
+            Stream::Stderr => STDERR.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Green,
+    Yellow,
+    Red,
+    Dim,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Red => "1;31",
+            Color::Dim => "2",
+        }
+    }
+}
+
+/// Decides once which streams get colors and hyperlinks: none with
+/// `--no-color` or in CI jobs, whose logs are read in a browser.
+pub fn init(no_color: bool) {
+    let allowed = !no_color && !platform::in_ci();
+    STDOUT.store(
+        allowed && platform::ansi(std::io::stdout().is_terminal()),
+        Ordering::Relaxed,
+    );
+    STDERR.store(
+        allowed && platform::ansi(std::io::stderr().is_terminal()),
+        Ordering::Relaxed,
+    );
+}
+
+pub fn colored(stream: Stream) -> bool {
+    stream.colored()
+}
+
+pub fn paint(stream: Stream, color: Color, text: &str) -> String {
+    if stream.colored() {
+        format!("\x1b[{}m{text}\x1b[0m", color.code())
+    } else {
+        text.to_owned()
+    }
+}
+
+/// `url` as an OSC 8 hyperlink, which terminals that know them make clickable.
+pub fn link(stream: Stream, url: &str) -> String {
+    if stream.colored() {
+        format!("\x1b]8;;{url}\x1b\\{url}\x1b]8;;\x1b\\")
+    } else {
+        url.to_owned()
+    }
+}
+
+/// How many columns `text` takes up, leaving out escape sequences.
+pub fn width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            width += 1;
+            continue;
+        }
+        match chars.next() {
+            // CSI, such as a color: up to the final letter.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            // OSC, such as a hyperlink: up to the string terminator.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_sequences_take_no_room() {
+        assert_eq!(width("\x1b[1;31mfailed\x1b[0m"), 6);
+        assert_eq!(
+            width("\x1b]8;;https://x.test/1\x1b\\https://x.test/1\x1b]8;;\x1b\\"),
+            16
+        );
+        assert_eq!(width("plain"), 5);
+    }
+}
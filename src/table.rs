@@ -0,0 +1,113 @@
+use crate::style;
+
+/// Renders rows as a plain-text table with left-aligned, padded columns;
+/// colors and links in cells do not count towards their width.
+pub fn render<const N: usize>(headers: [&str; N], rows: &[[String; N]]) -> String {
+    let mut widths = headers.map(style::width);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(style::width(cell));
+        }
+    }
+
+    let line = |cells: [&str; N]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - style::width(cell))))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+
+    let mut out = line(headers);
+    for row in rows {
+        out.push('\n');
+        out.push_str(&line(row.each_ref().map(String::as_str)));
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Table,
+    Csv,
+    /// An array of objects keyed by the lowercased column names.
+    Json,
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
+
+/// Renders rows as `format`, for output meant to be read by other tools too.
+pub fn render_as<const N: usize>(
+    format: Format,
+    headers: [&str; N],
+    rows: &[[String; N]],
+) -> String {
+    match format {
+        Format::Table => render(headers, rows),
+        Format::Csv => std::iter::once(headers.map(csv_field))
+            .chain(
+                rows.iter()
+                    .map(|row| row.each_ref().map(|cell| csv_field(cell))),
+            )
+            .map(|row| row.join(","))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Format::Json => {
+            let keys = headers.map(|header| header.to_lowercase().replace(' ', "_"));
+            let objects: Vec<serde_json::Map<_, _>> = rows
+                .iter()
+                .map(|row| {
+                    keys.iter()
+                        .cloned()
+                        .zip(row.iter().map(|cell| cell.clone().into()))
+                        .collect()
+                })
+                .collect();
+            serde_json::to_string_pretty(&objects).expect("strings always serialize")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colored_cells_line_up_with_plain_ones() {
+        let rows = [
+            ["\x1b[32mdone\x1b[0m".to_owned(), "a".to_owned()],
+            ["failed".to_owned(), "b".to_owned()],
+        ];
+        assert_eq!(
+            render(["RESULT", "STEP"], &rows),
+            "RESULT  STEP\n\x1b[32mdone\x1b[0m    a\nfailed  b"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_only_fields_that_need_it() {
+        let rows = [["a,b".to_owned(), "say \"hi\"".to_owned()]];
+        assert_eq!(
+            render_as(Format::Csv, ["NAME", "NOTE"], &rows),
+            "NAME,NOTE\n\"a,b\",\"say \"\"hi\"\"\""
+        );
+    }
+
+    #[test]
+    fn json_keys_are_the_lowercased_headers() {
+        let rows = [["7".to_owned(), "2024-01-01".to_owned()]];
+        assert_eq!(
+            render_as(Format::Json, ["ID", "LAST ACTIVITY"], &rows),
+            "[\n  {\n    \"id\": \"7\",\n    \"last_activity\": \"2024-01-01\"\n  }\n]"
+        );
+    }
+}
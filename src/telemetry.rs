@@ -0,0 +1,44 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::{filter::Targets, registry::LookupSpan, Layer};
+
+/// An OTLP/HTTP trace pipeline configured by the standard `OTEL_EXPORTER_OTLP_*`
+/// variables, if an endpoint is set.
+pub fn provider() -> anyhow::Result<Option<SdkTracerProvider>> {
+    let configured = [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ]
+    .iter()
+    .any(|var| std::env::var_os(var).is_some());
+    if !configured {
+        return Ok(None);
+    }
+    let exporter = SpanExporter::builder().with_http().build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_resource(
+            Resource::builder()
+                .with_service_name(env!("CARGO_PKG_NAME"))
+                .build(),
+        )
+        .with_batch_exporter(exporter)
+        .build();
+    Ok(Some(provider))
+}
+
+/// Exports our own spans down to debug level, which includes every GitLab
+/// API call, regardless of what the console shows.
+pub fn layer<S>(provider: &SdkTracerProvider) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+        .with_filter(
+            Targets::new()
+                .with_default(Level::INFO)
+                .with_target("gitlab_helper", Level::DEBUG),
+        )
+}
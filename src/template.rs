@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+
+pub type Vars = BTreeMap<String, String>;
+
+/// Plausible values for the variables workflow steps set, for previews.
+pub const SAMPLE_VARS: &[(&str, &str)] = &[
+    ("project", "sandbox/helper"),
+    ("latest_release", "1.3.0"),
+    ("latest_release_branch", "release/1.3.0"),
+    ("next_patch", "1.3.1"),
+    ("branch", "release/1.3.1"),
+    (
+        "context",
+        "### Context\n\n- Latest pipeline of `release/1.3.0`: [#4240](https://gitlab.example.com/sandbox/helper/-/pipelines/4240) **success**\n",
+    ),
+    (
+        "picks",
+        "### Cherry-picked commits\n\n- `1a2b3c4d` Fix the login redirect",
+    ),
+    ("mr_iid", "17"),
+    (
+        "mr_url",
+        "https://gitlab.example.com/sandbox/helper/-/merge_requests/17",
+    ),
+    (
+        "ci.pipeline_url",
+        "https://gitlab.example.com/sandbox/helper/-/pipelines/4241",
+    ),
+    (
+        "ci.job_url",
+        "https://gitlab.example.com/sandbox/helper/-/jobs/9001",
+    ),
+    ("ci.gitlab_user_login", "alice"),
+    ("pipeline_id", "4242"),
+    (
+        "pipeline_url",
+        "https://gitlab.example.com/sandbox/helper/-/pipelines/4242",
+    ),
+];
+
+/// Reads a template file such as a merge request description, with its
+/// line endings as GitLab expects them even if it was saved on Windows.
+pub fn load(path: &Path) -> anyhow::Result<String> {
+    let template = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(template.replace("\r\n", "\n"))
+}
+
+/// The CI job's predefined variable for `ci.<name>`: `CI_<NAME>`, or
+/// `GITLAB_USER_<...>` for `ci.gitlab_user_<...>`. Credentials such as
+/// `CI_JOB_TOKEN` are never exposed.
+fn ci_var(name: &str) -> Option<String> {
+    let name = name.strip_prefix("ci.")?.to_uppercase();
+    let key = if name.starts_with("GITLAB_USER_") {
+        name
+    } else {
+        format!("CI_{name}")
+    };
+    if ["TOKEN", "PASSWORD", "JWT"]
+        .iter()
+        .any(|secret| key.contains(secret))
+    {
+        return None;
+    }
+    std::env::var(key).ok()
+}
+
+/// Replaces every `{{ name }}` placeholder with its value from `vars`, or
+/// for `ci.*` names with the CI job's predefined variable.
+///
+/// All undefined placeholders are reported at once rather than one per run.
+pub fn render(template: &str, vars: &Vars) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut undefined = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            anyhow::bail!("unterminated placeholder in {template:?}");
+        };
+        let name = rest[start + 2..start + end].trim();
+        match vars.get(name).cloned().or_else(|| ci_var(name)) {
+            Some(value) => out.push_str(&value),
+            None => undefined.push(name.to_owned()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+
+    anyhow::ensure!(
+        undefined.is_empty(),
+        "undefined template variable(s): {}",
+        undefined.join(", ")
+    );
+    Ok(out)
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+
+use winnow::{
+    ascii::{space0, Caseless},
+    combinator::{alt, delimited, preceded, terminated},
+    error::{ContextError, ParseError, StrContext, StrContextValue},
+    prelude::*,
+    token::{literal, take_while},
+};
+
+#[derive(Debug, PartialEq)]
+pub enum Kind {
+    Feature,
+    Fix,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MergeRequest<'a> {
+    pub kind: Kind,
+    pub jira_id: &'a str,
+    pub title: &'a str,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Feature => f.write_str("feat"),
+            Kind::Fix => f.write_str("fix"),
+        }
+    }
+}
+
+/// The canonical spelling, e.g. `fix (ABC-123): Handle empty pages`.
+impl fmt::Display for MergeRequest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.kind, self.jira_id, self.title)
+    }
+}
+
+fn is_jira_id(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+pub fn parse_kind(input: &mut &str) -> PResult<Kind> {
+    alt((
+        literal(Caseless("fix")).map(|_| Kind::Fix),
+        // `feat` is a prefix of `feature`, so the longer spelling goes first.
+        literal(Caseless("feature")).map(|_| Kind::Feature),
+        literal(Caseless("feat")).map(|_| Kind::Feature),
+    ))
+    .context(StrContext::Label("kind"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "fix or feat",
+    )))
+    .parse_next(input)
+}
+
+pub fn parse_jira_id<'a>(input: &'_ mut &'a str) -> PResult<&'a str> {
+    (
+        space0,
+        delimited(
+            literal("("),
+            delimited(space0, take_while(1.., is_jira_id), space0),
+            literal(")"),
+        ),
+    )
+        .context(StrContext::Label("jira id"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "a valid jira id",
+        )))
+        .map(|(_, jira_id)| jira_id)
+        .parse_next(input)
+}
+
+pub fn parse_title<'a>(input: &'_ mut &'a str) -> PResult<&'a str> {
+    (
+        space0,
+        literal(':'),
+        preceded(space0, take_while(1.., |c: char| c.is_ascii())),
+    )
+        .context(StrContext::Label("title"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "any valid title",
+        )))
+        // The title runs to the end of the input, trailing whitespace included.
+        .map(|(_, _, title): (_, _, &'a str)| title.trim_end())
+        .parse_next(input)
+}
+
+pub fn parse_merge_request<'a>(
+    input: &'_ mut &'a str,
+) -> Result<MergeRequest<'a>, ParseError<&'a str, ContextError>> {
+    terminated(
+        (parse_kind, parse_jira_id, parse_title).map(|(kind, jira_id, title)| MergeRequest {
+            kind,
+            jira_id,
+            title,
+        }),
+        space0,
+    )
+    .parse(input)
+}
+
+/// Checks a merge request title against the `kind (JIRA-ID): title` convention.
+pub fn lint(title: &str) -> Result<(), String> {
+    parse_merge_request(&mut &*title)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// The Jira ID a branch such as `fix/JIRA-12-short-slug` is named after.
+pub fn jira_id_of_branch(branch: &str) -> Option<String> {
+    let name = branch.rsplit('/').next()?;
+    let mut words = name.split(['-', '_']);
+    let (key, number) = (words.next()?, words.next()?);
+    let is_key = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric());
+    let is_number = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+    (is_key && is_number).then(|| format!("{}-{number}", key.to_ascii_uppercase()))
+}
+
+/// The canonical title for a branch named like `fix/JIRA-12-short-slug`,
+/// e.g. `fix (JIRA-12): short slug`.
+pub fn from_branch(branch: &str) -> Option<String> {
+    let (kind, rest) = branch.split_once('/')?;
+    let kind = parse_kind.parse(kind).ok()?;
+    let jira_id = jira_id_of_branch(rest)?;
+    let title = rest
+        .split(['-', '_'])
+        .skip(2)
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = MergeRequest {
+        kind,
+        jira_id: &jira_id,
+        title: &title,
+    }
+    .to_string();
+    lint(&title).ok().map(|()| title)
+}
+
+/// Whether `title` is the one GitLab makes up from the source branch, such
+/// as `Fix/jira 12 short slug`, which nobody chose.
+pub fn is_derived_from(title: &str, branch: &str) -> bool {
+    let words = |text: &str| -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let title = title.strip_prefix("Draft:").unwrap_or(title);
+    // A title that already follows the convention has the same words.
+    lint(title.trim()).is_err() && words(title) == words(branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn kind() -> impl Strategy<Value = (Kind, String)> {
+        prop_oneof![
+            "(?i)fix".prop_map(|spelling| (Kind::Fix, spelling)),
+            "(?i)feat".prop_map(|spelling| (Kind::Feature, spelling)),
+            "(?i)feature".prop_map(|spelling| (Kind::Feature, spelling)),
+        ]
+    }
+
+    /// Printable ASCII without surrounding whitespace, which the grammar trims.
+    fn title() -> impl Strategy<Value = String> {
+        "[!-~]([ -~]{0,60}[!-~])?"
+    }
+
+    #[test]
+    fn titles_come_from_conventional_branch_names() {
+        assert_eq!(
+            from_branch("fix/jira-12-handle-empty_pages").as_deref(),
+            Some("fix (JIRA-12): handle empty pages")
+        );
+        assert_eq!(
+            from_branch("feature/ABC-7-export").as_deref(),
+            Some("feat (ABC-7): export")
+        );
+        assert_eq!(from_branch("fix/JIRA-12"), None);
+        assert_eq!(from_branch("chore/JIRA-12-bump"), None);
+        assert_eq!(from_branch("fix/handle-empty-pages"), None);
+    }
+
+    #[test]
+    fn gitlab_default_titles_are_recognized() {
+        let branch = "fix/JIRA-12-short-slug";
+        assert!(is_derived_from("Fix/jira 12 short slug", branch));
+        assert!(is_derived_from("Draft: Fix/JIRA-12 short slug", branch));
+        assert!(!is_derived_from("fix (JIRA-12): Short slug", branch));
+    }
+
+    proptest! {
+        #[test]
+        fn composed_titles_parse_back(
+            (kind, spelling) in kind(),
+            jira_id in "[A-Za-z0-9-]{1,16}",
+            title in title(),
+            spaces in proptest::collection::vec(" {0,3}", 6),
+        ) {
+            let composed = format!(
+                "{spelling}{}({}{jira_id}{}){}:{}{title}{}",
+                spaces[0], spaces[1], spaces[2], spaces[3], spaces[4], spaces[5]
+            );
+            let parsed = parse_merge_request(&mut composed.as_str()).unwrap();
+            prop_assert_eq!(parsed, MergeRequest { kind, jira_id: &jira_id, title: &title });
+        }
+
+        #[test]
+        fn display_is_canonical(
+            (kind, _) in kind(),
+            jira_id in "[A-Za-z0-9-]{1,16}",
+            title in title(),
+        ) {
+            let mr = MergeRequest { kind, jira_id: &jira_id, title: &title };
+            let displayed = mr.to_string();
+            prop_assert_eq!(parse_merge_request(&mut displayed.as_str()).unwrap(), mr);
+        }
+
+        #[test]
+        fn arbitrary_input_never_panics(input in any::<String>()) {
+            let _ = lint(&input);
+        }
+
+        #[test]
+        fn titles_without_a_kind_are_rejected(
+            prefix in "[a-z]{0,8}".prop_filter("a valid kind", |prefix| {
+                !["fix", "feat"].iter().any(|kind| prefix.starts_with(kind))
+            }),
+            jira_id in "[A-Za-z0-9-]{1,16}",
+            title in title(),
+        ) {
+            let input = format!("{prefix} ({jira_id}): {title}");
+            prop_assert!(lint(&input).is_err());
+        }
+    }
+}
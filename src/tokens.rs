@@ -0,0 +1,158 @@
+use chrono::NaiveDate;
+use gitlab::api::personal_access_tokens::PersonalAccessTokenSelf;
+use gitlab::api::projects::access_tokens::ProjectAccessTokens;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::{self, Client};
+use crate::endpoints::GroupAccessTokens;
+use crate::i18n;
+use crate::notify;
+use crate::table;
+
+#[derive(Debug, Deserialize)]
+struct AccessToken {
+    name: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+    expires_at: Option<NaiveDate>,
+    #[serde(default = "yes")]
+    active: bool,
+    #[serde(default)]
+    revoked: bool,
+}
+
+fn yes() -> bool {
+    true
+}
+
+struct Row {
+    owner: String,
+    token: AccessToken,
+}
+
+impl Row {
+    fn days_left(&self, today: NaiveDate) -> Option<i64> {
+        self.token
+            .expires_at
+            .map(|expires_at| (expires_at - today).num_days())
+    }
+}
+
+/// The helper's own token, if GitLab says what it is; job tokens cannot ask.
+fn own_token(client: &Client) -> anyhow::Result<Option<AccessToken>> {
+    match PersonalAccessTokenSelf::builder().build()?.query(client) {
+        Ok(token) => Ok(Some(token)),
+        Err(err)
+            if matches!(
+                client::status(&err),
+                Some(
+                    http::StatusCode::UNAUTHORIZED
+                        | http::StatusCode::FORBIDDEN
+                        | http::StatusCode::NOT_FOUND
+                )
+            ) =>
+        {
+            tracing::debug!("cannot look up the token in use: {err}");
+            Ok(None)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Lists the access tokens of `group` and `projects` and the helper's own
+/// token with their expiry, and fails if any expires within `within` days,
+/// after posting the list of those to `notify_url` if set.
+pub fn audit(
+    client: &Client,
+    group: Option<&str>,
+    projects: &[String],
+    within: u32,
+    notify_url: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    if let Some(token) = own_token(client)? {
+        rows.push(Row {
+            owner: "(this helper)".to_owned(),
+            token,
+        });
+    }
+    if let Some(group) = group {
+        let endpoint = GroupAccessTokens {
+            group: group.into(),
+        };
+        let tokens: Vec<AccessToken> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        rows.extend(tokens.into_iter().map(|token| Row {
+            owner: group.to_owned(),
+            token,
+        }));
+    }
+    for project in projects {
+        let endpoint = ProjectAccessTokens::builder()
+            .project(project.as_str())
+            .build()?;
+        let tokens: Vec<AccessToken> = api::paged(endpoint, api::Pagination::All).query(client)?;
+        rows.extend(tokens.into_iter().map(|token| Row {
+            owner: project.clone(),
+            token,
+        }));
+    }
+    rows.retain(|row| row.token.active && !row.token.revoked);
+
+    let today = chrono::Utc::now().date_naive();
+    rows.sort_by_key(|row| row.days_left(today).unwrap_or(i64::MAX));
+    let expiring: Vec<_> = rows
+        .iter()
+        .filter(|row| {
+            row.days_left(today)
+                .is_some_and(|days| days <= within.into())
+        })
+        .collect();
+
+    let table: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let (expires, left) = match (row.token.expires_at, row.days_left(today)) {
+                (Some(expires_at), Some(days)) => (expires_at.to_string(), days.to_string()),
+                _ => ("never".to_owned(), "-".to_owned()),
+            };
+            [
+                row.owner.clone(),
+                row.token.name.clone(),
+                row.token.scopes.join(","),
+                expires,
+                left,
+            ]
+        })
+        .collect();
+    println!(
+        "{}",
+        table::render(["OWNER", "TOKEN", "SCOPES", "EXPIRES", "DAYS LEFT"], &table)
+    );
+
+    if expiring.is_empty() {
+        return Ok(());
+    }
+    if let Some(url) = notify_url {
+        let mut message = i18n::format(
+            i18n::messages().tokens_expiring,
+            &[
+                ("count", &expiring.len().to_string()),
+                ("days", &within.to_string()),
+            ],
+        );
+        for row in &expiring {
+            if let (Some(expires_at), Some(days)) = (row.token.expires_at, row.days_left(today)) {
+                message.push_str(&format!(
+                    "\n- {} of {}: {expires_at} ({days} days)",
+                    row.token.name, row.owner
+                ));
+            }
+        }
+        notify::send(client, url, &message)?;
+    }
+    anyhow::bail!(
+        "{} access token(s) expire within {within} days",
+        expiring.len()
+    )
+}
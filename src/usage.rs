@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+/// How often a subcommand ran and failed, and for how long, summed over
+/// every run that wrote to the same file. Nothing here leaves the machine.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Usage {
+    commands: BTreeMap<String, Counters>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Counters {
+    runs: u64,
+    failures: u64,
+    total_ms: u64,
+    max_ms: u64,
+}
+
+/// Where to count: `--usage-file`, or next to the journal, so a CI job that
+/// keeps the journal as an artifact keeps the counts too.
+pub fn path(usage_file: Option<&Path>, journal: Option<&Path>) -> Option<PathBuf> {
+    usage_file
+        .map(Path::to_path_buf)
+        .or_else(|| journal.map(|journal| journal.with_file_name("usage.json")))
+}
+
+/// Adds one run of `command` to the counts in `path`.
+pub fn record(path: &Path, command: &str, failed: bool, took: Duration) -> anyhow::Result<()> {
+    let mut usage: Usage = match std::fs::read(path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .with_context(|| format!("{} is not a usage file", path.display()))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Usage::default(),
+        Err(err) => return Err(err).context(format!("failed to read {}", path.display())),
+    };
+    let counters = usage.commands.entry(command.to_owned()).or_default();
+    let took = u64::try_from(took.as_millis()).unwrap_or(u64::MAX);
+    counters.runs += 1;
+    counters.failures += u64::from(failed);
+    counters.total_ms = counters.total_ms.saturating_add(took);
+    counters.max_ms = counters.max_ms.max(took);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(&usage)?)
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_up_runs_of_each_command() {
+        let dir = std::env::temp_dir().join(format!("gitlab-helper-usage-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("usage.json");
+        let _ = std::fs::remove_file(&path);
+
+        record(&path, "emergency-patch", false, Duration::from_millis(300)).unwrap();
+        record(&path, "emergency-patch", true, Duration::from_millis(500)).unwrap();
+        record(&path, "compare", false, Duration::from_millis(20)).unwrap();
+
+        let usage: Usage = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let patch = &usage.commands["emergency-patch"];
+        assert_eq!((patch.runs, patch.failures), (2, 1));
+        assert_eq!((patch.total_ms, patch.max_ms), (800, 500));
+        assert_eq!(usage.commands["compare"].runs, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
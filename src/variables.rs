@@ -0,0 +1,71 @@
+use clap::Args;
+use gitlab::api::{self, projects::variables, Query};
+
+use crate::client::{self, Client};
+
+#[derive(Debug, Clone, Args)]
+pub struct SetVariable {
+    pub key: String,
+    pub value: String,
+    /// Only expose the variable to pipelines on protected branches and tags.
+    #[arg(long)]
+    pub protected: bool,
+    /// Mask the value in job logs.
+    #[arg(long)]
+    pub masked: bool,
+    /// Limit the variable to an environment scope, e.g. `production`.
+    #[arg(long)]
+    pub environment_scope: Option<String>,
+}
+
+/// Creates the CI/CD variable, or updates it in place if it already exists.
+pub fn set(client: &Client, project: &str, variable: &SetVariable) -> anyhow::Result<String> {
+    let filter = variable.environment_scope.as_deref().map(|scope| {
+        variables::ProjectVariableFilter::builder()
+            .environment_scope(scope)
+            .build()
+            .unwrap()
+    });
+
+    let mut existing = variables::ProjectVariable::builder();
+    existing.project(project).key(&variable.key);
+    if let Some(filter) = filter.clone() {
+        existing.filter(filter);
+    }
+    let exists = match api::ignore(existing.build()?).query(client) {
+        Ok(()) => true,
+        Err(err) if client::status(&err) == Some(http::StatusCode::NOT_FOUND) => false,
+        Err(err) => return Err(err.into()),
+    };
+
+    if exists {
+        let mut update = variables::UpdateProjectVariable::builder();
+        update
+            .project(project)
+            .key(&variable.key)
+            .value(&variable.value)
+            .protected(variable.protected)
+            .masked(variable.masked);
+        if let Some(scope) = &variable.environment_scope {
+            update.environment_scope(scope);
+        }
+        if let Some(filter) = filter {
+            update.filter(filter);
+        }
+        api::ignore(update.build()?).query(client)?;
+        Ok(format!("updated {}", variable.key))
+    } else {
+        let mut create = variables::CreateProjectVariable::builder();
+        create
+            .project(project)
+            .key(&variable.key)
+            .value(&variable.value)
+            .protected(variable.protected)
+            .masked(variable.masked);
+        if let Some(scope) = &variable.environment_scope {
+            create.environment_scope(scope);
+        }
+        api::ignore(create.build()?).query(client)?;
+        Ok(format!("created {}", variable.key))
+    }
+}
@@ -0,0 +1,270 @@
+use clap::{Args, ValueEnum};
+use gitlab::api::projects::hooks;
+use gitlab::api::{self, Query};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::table;
+use crate::workflow::Context;
+
+/// The events a project webhook can be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    Push,
+    TagPush,
+    MergeRequests,
+    Notes,
+    ConfidentialNotes,
+    Issues,
+    ConfidentialIssues,
+    Jobs,
+    Pipelines,
+    WikiPages,
+    Deployments,
+    Releases,
+}
+
+const EVENTS: &[HookEvent] = &[
+    HookEvent::Push,
+    HookEvent::TagPush,
+    HookEvent::MergeRequests,
+    HookEvent::Notes,
+    HookEvent::ConfidentialNotes,
+    HookEvent::Issues,
+    HookEvent::ConfidentialIssues,
+    HookEvent::Jobs,
+    HookEvent::Pipelines,
+    HookEvent::WikiPages,
+    HookEvent::Deployments,
+    HookEvent::Releases,
+];
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::Push => "push",
+            HookEvent::TagPush => "tag-push",
+            HookEvent::MergeRequests => "merge-requests",
+            HookEvent::Notes => "notes",
+            HookEvent::ConfidentialNotes => "confidential-notes",
+            HookEvent::Issues => "issues",
+            HookEvent::ConfidentialIssues => "confidential-issues",
+            HookEvent::Jobs => "jobs",
+            HookEvent::Pipelines => "pipelines",
+            HookEvent::WikiPages => "wiki-pages",
+            HookEvent::Deployments => "deployments",
+            HookEvent::Releases => "releases",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct AddHook {
+    /// Where GitLab sends the events.
+    pub url: String,
+    /// The events to send, e.g. `--event merge-requests,notes`.
+    #[arg(long = "event", value_enum, value_delimiter = ',', required = true)]
+    pub events: Vec<HookEvent>,
+    /// Sent back as `X-Gitlab-Token` so the receiver can tell the hook is genuine.
+    #[arg(long, env = "GITLAB_WEBHOOK_SECRET", hide_env_values = true)]
+    pub secret: Option<String>,
+    /// Do not verify the TLS certificate of the URL.
+    #[arg(long)]
+    pub insecure_ssl: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectHook {
+    id: u64,
+    url: String,
+    #[serde(default)]
+    push_events: bool,
+    #[serde(default)]
+    tag_push_events: bool,
+    #[serde(default)]
+    merge_requests_events: bool,
+    #[serde(default)]
+    note_events: bool,
+    #[serde(default)]
+    confidential_note_events: bool,
+    #[serde(default)]
+    issues_events: bool,
+    #[serde(default)]
+    confidential_issues_events: bool,
+    #[serde(default)]
+    job_events: bool,
+    #[serde(default)]
+    pipeline_events: bool,
+    #[serde(default)]
+    wiki_page_events: bool,
+    #[serde(default)]
+    deployment_events: bool,
+    #[serde(default)]
+    releases_events: bool,
+}
+
+impl ProjectHook {
+    fn sends(&self, event: HookEvent) -> bool {
+        match event {
+            HookEvent::Push => self.push_events,
+            HookEvent::TagPush => self.tag_push_events,
+            HookEvent::MergeRequests => self.merge_requests_events,
+            HookEvent::Notes => self.note_events,
+            HookEvent::ConfidentialNotes => self.confidential_note_events,
+            HookEvent::Issues => self.issues_events,
+            HookEvent::ConfidentialIssues => self.confidential_issues_events,
+            HookEvent::Jobs => self.job_events,
+            HookEvent::Pipelines => self.pipeline_events,
+            HookEvent::WikiPages => self.wiki_page_events,
+            HookEvent::Deployments => self.deployment_events,
+            HookEvent::Releases => self.releases_events,
+        }
+    }
+
+    fn events(&self) -> Vec<HookEvent> {
+        EVENTS
+            .iter()
+            .copied()
+            .filter(|&event| self.sends(event))
+            .collect()
+    }
+}
+
+fn describe(events: &[HookEvent]) -> String {
+    let names: Vec<_> = events.iter().map(|event| event.name()).collect();
+    names.join(",")
+}
+
+fn project_hooks(client: &Client, project: &str) -> anyhow::Result<Vec<ProjectHook>> {
+    let endpoint = hooks::Hooks::builder().project(project).build()?;
+    Ok(api::paged(endpoint, api::Pagination::All).query(client)?)
+}
+
+/// Every event is set explicitly, so an edited hook stops sending the ones
+/// that were dropped. `CreateHook` and `EditHook` share no builder trait.
+macro_rules! set_events {
+    ($builder:ident, $on:ident) => {
+        $builder
+            .push_events($on(HookEvent::Push))
+            .tag_push_events($on(HookEvent::TagPush))
+            .merge_requests_events($on(HookEvent::MergeRequests))
+            .note_events($on(HookEvent::Notes))
+            .confidential_note_events($on(HookEvent::ConfidentialNotes))
+            .issues_events($on(HookEvent::Issues))
+            .confidential_issues_events($on(HookEvent::ConfidentialIssues))
+            .job_events($on(HookEvent::Jobs))
+            .pipeline_events($on(HookEvent::Pipelines))
+            .wiki_page_events($on(HookEvent::WikiPages))
+            .deployment_events($on(HookEvent::Deployments))
+            .releases_events($on(HookEvent::Releases))
+    };
+}
+
+/// Adds the webhook, or brings the one with the same URL up to date, so
+/// onboarding can be run again without piling up duplicates.
+pub fn add(ctx: &Context, project: &str, hook: &AddHook) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let existing = project_hooks(client, project)?
+        .into_iter()
+        .find(|existing| existing.url == hook.url);
+    let mut events = hook.events.clone();
+    events.sort_by_key(|event| EVENTS.iter().position(|known| known == event));
+    events.dedup();
+    // GitLab never shows the secret again, so a hook with one always gets it re-sent.
+    if existing
+        .as_ref()
+        .is_some_and(|existing| existing.events() == events && hook.secret.is_none())
+    {
+        return Ok(format!("{} is already set up", hook.url));
+    }
+
+    let on = |event| events.contains(&event);
+    let ssl = !hook.insecure_ssl;
+    match existing {
+        Some(existing) => {
+            ctx.confirm(
+                project,
+                &[format!(
+                    "change webhook {} from {} to {}",
+                    hook.url,
+                    describe(&existing.events()),
+                    describe(&events)
+                )],
+            )?;
+            let mut edit = hooks::EditHook::builder();
+            edit.project(project)
+                .hook_id(existing.id)
+                .url(hook.url.as_str())
+                .enable_ssl_verification(ssl);
+            set_events!(edit, on);
+            if let Some(secret) = &hook.secret {
+                edit.token(secret.as_str());
+            }
+            api::ignore(edit.build()?).query(client)?;
+            Ok(format!("updated webhook {}", hook.url))
+        }
+        None => {
+            ctx.confirm(
+                project,
+                &[format!(
+                    "add webhook {} for {}",
+                    hook.url,
+                    describe(&events)
+                )],
+            )?;
+            let mut create = hooks::CreateHook::builder();
+            create
+                .project(project)
+                .url(hook.url.as_str())
+                .enable_ssl_verification(ssl);
+            set_events!(create, on);
+            if let Some(secret) = &hook.secret {
+                create.token(secret.as_str());
+            }
+            api::ignore(create.build()?).query(client)?;
+            Ok(format!("added webhook {}", hook.url))
+        }
+    }
+}
+
+/// Prints the webhooks of `project` and the events each one is sent.
+pub fn list(client: &Client, project: &str) -> anyhow::Result<()> {
+    let rows: Vec<_> = project_hooks(client, project)?
+        .into_iter()
+        .map(|hook| {
+            [
+                hook.id.to_string(),
+                hook.url.clone(),
+                describe(&hook.events()),
+            ]
+        })
+        .collect();
+    println!("{}", table::render(["ID", "URL", "EVENTS"], &rows));
+    Ok(())
+}
+
+/// Removes the webhooks whose ID or URL is `target`.
+pub fn remove(ctx: &Context, project: &str, target: &str) -> anyhow::Result<String> {
+    let client = ctx.client;
+    let matching: Vec<_> = project_hooks(client, project)?
+        .into_iter()
+        .filter(|hook| hook.url == target || hook.id.to_string() == target)
+        .collect();
+    if matching.is_empty() {
+        return Ok(format!("no webhook {target}"));
+    }
+    let plan: Vec<_> = matching
+        .iter()
+        .map(|hook| format!("remove webhook {} ({})", hook.id, hook.url))
+        .collect();
+    ctx.confirm(project, &plan)?;
+    for hook in &matching {
+        let endpoint = hooks::DeleteHook::builder()
+            .project(project)
+            .hook_id(hook.id)
+            .build()?;
+        api::ignore(endpoint).query(client)?;
+    }
+    Ok(format!("removed {} webhook(s)", matching.len()))
+}
@@ -0,0 +1,343 @@
+use std::time::Instant;
+
+use gitlab::api::projects::Project;
+use gitlab::api::{ApiError, Query};
+use serde::Deserialize;
+
+use crate::authorship::CommitConfig;
+use crate::cancel;
+use crate::client::{self, Client, RestError};
+use crate::git;
+use crate::hooks::{self, Hooks, Phase};
+use crate::journal::{Entry, Journal, Resource};
+use crate::notify::NotifyConfig;
+use crate::prompt;
+use crate::redact;
+use crate::style::{self, Color, Stream};
+use crate::table;
+use crate::template::Vars;
+
+/// Everything a workflow needs besides its own parameters.
+pub struct Context<'a> {
+    pub client: &'a Client,
+    pub hooks: &'a Hooks,
+    pub notify: &'a NotifyConfig,
+    pub commits: &'a CommitConfig,
+    pub journal: &'a Journal,
+    /// Where to create branches and pick commits instead of the API, if set;
+    /// see [`Context::git_for`].
+    pub git: Option<&'a git::Repo>,
+    /// Ask before the first mutation of every project.
+    pub confirm: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectPath {
+    path_with_namespace: String,
+}
+
+impl Context<'_> {
+    /// Shows what is about to happen to `project` and stops unless confirmed.
+    pub fn confirm(&self, project: &str, plan: &[String]) -> anyhow::Result<()> {
+        if !self.confirm || plan.is_empty() {
+            return Ok(());
+        }
+        let header = format!("About to make these changes to {project}:");
+        anyhow::ensure!(
+            prompt::confirm(&header, plan)?,
+            "cancelled; nothing was changed in {project}"
+        );
+        Ok(())
+    }
+
+    /// Performs a single step between its pre and post hooks, failing fast.
+    pub fn hooked<T>(
+        &self,
+        event: &hooks::Event,
+        action: impl FnOnce() -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        cancel::check()?;
+        let deadline = self.client.deadline();
+        self.hooks.run(Phase::Pre, event, deadline)?;
+        let value = action()?;
+        self.hooks.run(Phase::Post, event, deadline)?;
+        Ok(value)
+    }
+
+    /// Performs `step` of `project` and records it in the journal, unless the
+    /// journal being resumed shows it was already done; either way the entry
+    /// is returned.
+    pub fn journaled(
+        &self,
+        project: &str,
+        step: &str,
+        action: impl FnOnce() -> anyhow::Result<(Option<Resource>, Vars)>,
+    ) -> anyhow::Result<Entry> {
+        if let Some(entry) = self.journal.find(project, step) {
+            tracing::info!("already done: {step}");
+            return Ok(entry);
+        }
+        cancel::check()?;
+        let (created, vars) = action()?;
+        self.journal.record(project, step, created, vars)
+    }
+
+    /// The clone to create branches and pick commits of `project` in: the
+    /// one of `--local-git` if its `origin` is `project`, and `None`, which
+    /// goes through the API, if it is another project's.
+    pub fn git_for(&self, project: &str) -> anyhow::Result<Option<&git::Repo>> {
+        let Some(repo) = self.git else {
+            return Ok(None);
+        };
+        let path = match project.parse::<u64>() {
+            Ok(_) => {
+                let details: ProjectPath = Project::builder()
+                    .project(project)
+                    .build()?
+                    .query(self.client)?;
+                details.path_with_namespace
+            }
+            Err(_) => project.to_owned(),
+        };
+        if repo.is_clone_of(&path) {
+            return Ok(Some(repo));
+        }
+        tracing::warn!(
+            project,
+            "the clone's origin is {}, not {path}; going through the API",
+            repo.origin()
+        );
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Created,
+    Resumed,
+    /// With why.
+    Failed(String),
+    NotAttempted,
+}
+
+impl Outcome {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Outcome::Failed(_))
+    }
+}
+
+/// Where a created resource can be seen, if GitLab said or it can be told
+/// from the URL of another resource of the same project.
+fn web_url(resource: &Resource, project_url: Option<&str>) -> Option<String> {
+    match resource {
+        Resource::MergeRequest { web_url, .. } | Resource::Pipeline { web_url, .. } => {
+            Some(web_url.clone())
+        }
+        Resource::Branch { name } => project_url.map(|url| format!("{url}/-/tree/{name}")),
+        Resource::Commit { sha } => project_url.map(|url| format!("{url}/-/commit/{sha}")),
+        Resource::FeatureFlag { .. } => project_url.map(|url| format!("{url}/-/feature_flags")),
+    }
+}
+
+/// Logs how each step of `project` went and prints the outcomes as a table,
+/// linking to what the steps in `created`, one per step, made.
+pub fn summarize(project: &str, steps: &[(String, Outcome)], created: &[Option<Resource>]) {
+    let project_url = created
+        .iter()
+        .flatten()
+        .find_map(|resource| match resource {
+            Resource::MergeRequest { web_url, .. } | Resource::Pipeline { web_url, .. } => {
+                web_url.split_once("/-/").map(|(url, _)| url)
+            }
+            _ => None,
+        });
+    let mut rows = Vec::new();
+    for (index, (step, outcome)) in steps.iter().enumerate() {
+        let url = created
+            .get(index)
+            .and_then(Option::as_ref)
+            .and_then(|resource| web_url(resource, project_url))
+            .map(|url| style::link(Stream::Stdout, &url))
+            .unwrap_or_default();
+        let (result, color, details) = match outcome {
+            Outcome::Created => {
+                tracing::info!("done: {step}");
+                ("done", Color::Green, url)
+            }
+            Outcome::Resumed => {
+                tracing::info!("already done: {step}");
+                ("already done", Color::Dim, url)
+            }
+            Outcome::Failed(err) => {
+                tracing::warn!("failed: {step}");
+                ("failed", Color::Red, err.clone())
+            }
+            Outcome::NotAttempted => {
+                tracing::warn!("not attempted: {step}");
+                ("not attempted", Color::Yellow, String::new())
+            }
+        };
+        rows.push([
+            project.to_owned(),
+            step.clone(),
+            style::paint(Stream::Stdout, color, result),
+            redact::redact(&details).into_owned(),
+        ]);
+    }
+    println!(
+        "{}",
+        table::render(["PROJECT", "STEP", "RESULT", "DETAILS"], &rows)
+    );
+}
+
+/// The mutations of a run, in order, and how far it got with each of them.
+pub struct Run<'a> {
+    hooks: &'a Hooks,
+    deadline: Option<Instant>,
+    journal: &'a Journal,
+    project: &'a str,
+    steps: Vec<(String, Outcome)>,
+}
+
+impl<'a> Run<'a> {
+    pub fn new(
+        ctx: &Context<'a>,
+        project: &'a str,
+        steps: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Run {
+            hooks: ctx.hooks,
+            deadline: ctx.client.deadline(),
+            journal: ctx.journal,
+            project,
+            steps: steps
+                .into_iter()
+                .map(|step| (step, Outcome::NotAttempted))
+                .collect(),
+        }
+    }
+
+    /// Performs step `index` between its pre and post hooks. The steps after
+    /// it depend on it, so if it fails the rest are not attempted: the
+    /// summary is logged and the run fails. Steps the resumed journal
+    /// already has are skipped.
+    pub fn step(
+        &mut self,
+        index: usize,
+        event: &hooks::Event,
+        action: impl FnOnce() -> Result<Resource, ApiError<RestError>>,
+    ) -> anyhow::Result<()> {
+        self.perform(index, event, true, action)
+    }
+
+    /// Like [`Run::step`], but for a step nothing after it depends on: if it
+    /// fails for any reason other than running out of time or a vetoing pre
+    /// hook, it is logged and the run carries on with the next step.
+    pub fn best_effort(
+        &mut self,
+        index: usize,
+        event: &hooks::Event,
+        action: impl FnOnce() -> Result<Resource, ApiError<RestError>>,
+    ) -> anyhow::Result<()> {
+        self.perform(index, event, false, action)
+    }
+
+    fn perform(
+        &mut self,
+        index: usize,
+        event: &hooks::Event,
+        fatal: bool,
+        action: impl FnOnce() -> Result<Resource, ApiError<RestError>>,
+    ) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("step", index, name = self.steps[index].0).entered();
+        if self
+            .journal
+            .find(self.project, &self.steps[index].0)
+            .is_some()
+        {
+            self.steps[index].1 = Outcome::Resumed;
+            return Ok(());
+        }
+        if let Err(cancelled) = cancel::check() {
+            self.log_summary();
+            return Err(cancelled.into());
+        }
+        if let Err(err) = self.hooks.run(Phase::Pre, event, self.deadline) {
+            self.log_summary();
+            return Err(err.context(format!("refusing to {}", self.steps[index].0)));
+        }
+        let outcome = match action() {
+            Ok(created) => {
+                self.journal.record(
+                    self.project,
+                    &self.steps[index].0,
+                    Some(created),
+                    Vars::new(),
+                )?;
+                self.hooks.run(Phase::Post, event, self.deadline)?;
+                Outcome::Created
+            }
+            Err(err) if client::is_timeout(&err) => {
+                self.log_summary();
+                anyhow::bail!("aborted while trying to {}: {err}", self.steps[index].0);
+            }
+            Err(err) => {
+                tracing::warn!("failed to {}: {err}", self.steps[index].0);
+                let details = match client::hint(&err) {
+                    Some(hint) => format!("{err} ({hint})"),
+                    None => err.to_string(),
+                };
+                if fatal {
+                    self.steps[index].1 = Outcome::Failed(details.clone());
+                    self.log_summary();
+                    anyhow::bail!(
+                        "failed to {}, so the steps after it were not attempted: {details}",
+                        self.steps[index].0
+                    );
+                }
+                Outcome::Failed(details)
+            }
+        };
+        self.steps[index].1 = outcome;
+        Ok(())
+    }
+
+    /// The steps the resumed journal does not have yet.
+    pub fn pending(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .map(|(step, _)| step)
+            .filter(|step| self.journal.find(self.project, step).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// What the steps done so far created, in order, resumed ones included.
+    pub fn created(&self) -> Vec<Resource> {
+        self.steps
+            .iter()
+            .filter_map(|(step, _)| self.journal.find(self.project, step)?.created)
+            .collect()
+    }
+
+    /// Logs the summary and fails if any step did, after the rest were tried.
+    pub fn finish(&self) -> anyhow::Result<()> {
+        self.log_summary();
+        let failed = self
+            .steps
+            .iter()
+            .filter(|(_, outcome)| outcome.is_failed())
+            .count();
+        anyhow::ensure!(failed == 0, "{failed} of {} steps failed", self.steps.len());
+        Ok(())
+    }
+
+    pub fn log_summary(&self) {
+        let created: Vec<_> = self
+            .steps
+            .iter()
+            .map(|(step, _)| self.journal.find(self.project, step)?.created)
+            .collect();
+        summarize(self.project, &self.steps, &created);
+    }
+}
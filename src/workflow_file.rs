@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use gitlab::api::{
+    self,
+    projects::{
+        merge_requests::CreateMergeRequest,
+        pipelines::{CreatePipeline, PipelineVariable},
+        repository::branches::CreateBranch,
+    },
+    Query,
+};
+use serde::Deserialize;
+
+use crate::cancel;
+use crate::hooks::Event;
+use crate::journal::Resource;
+use crate::origin;
+use crate::template::{self, Vars};
+use crate::workflow::{self, Context, Outcome};
+use crate::{emergency, notify};
+
+pub const DEFAULT_PATH: &str = "workflows.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowFile {
+    pub workflows: BTreeMap<String, Workflow>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workflow {
+    pub description: Option<String>,
+    pub steps: Vec<Step>,
+}
+
+/// A built-in step; string parameters are `{{ var }}` templates over the
+/// variables set by `--var` and by earlier steps.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "step", rename_all = "kebab-case")]
+pub enum Step {
+    /// Sets `latest_release`, `latest_release_branch` and `next_patch`.
+    FindLatestRelease,
+    /// Sets `branch`.
+    CreateBranch {
+        branch: String,
+        #[serde(rename = "ref")]
+        ref_: String,
+    },
+    /// Sets `mr_iid` and `mr_url`.
+    CreateMr {
+        source: String,
+        target: String,
+        title: String,
+        #[serde(default)]
+        description: String,
+        /// A file to render as the description instead, e.g. `.gitlab/patch.md`.
+        description_template: Option<PathBuf>,
+        assignee: Option<String>,
+    },
+    Notify {
+        message: String,
+    },
+    /// Sets `pipeline_id` and `pipeline_url`.
+    TriggerPipeline {
+        #[serde(rename = "ref")]
+        ref_: String,
+        #[serde(default)]
+        variables: BTreeMap<String, String>,
+    },
+}
+
+impl Step {
+    fn name(&self) -> &'static str {
+        match self {
+            Step::FindLatestRelease => "find-latest-release",
+            Step::CreateBranch { .. } => "create-branch",
+            Step::CreateMr { .. } => "create-mr",
+            Step::Notify { .. } => "notify",
+            Step::TriggerPipeline { .. } => "trigger-pipeline",
+        }
+    }
+
+    /// What the step does, with its templates unrendered.
+    fn summary(&self) -> String {
+        match self {
+            Step::FindLatestRelease => "find the latest release".to_owned(),
+            Step::CreateBranch { branch, ref_ } => format!("create branch {branch} from {ref_}"),
+            Step::CreateMr {
+                source,
+                target,
+                assignee,
+                ..
+            } => match assignee {
+                Some(assignee) => {
+                    format!("open a merge request {source} -> {target} for {assignee}")
+                }
+                None => format!("open a merge request {source} -> {target}"),
+            },
+            Step::Notify { message } => format!("send {message:?}"),
+            Step::TriggerPipeline { ref_, .. } => format!("trigger a pipeline on {ref_}"),
+        }
+    }
+}
+
+impl WorkflowFile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> anyhow::Result<&Workflow> {
+        self.workflows.get(name).with_context(|| {
+            let known = self.workflows.keys().cloned().collect::<Vec<_>>();
+            format!("no workflow named {name:?}; defined: {}", known.join(", "))
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Created {
+    iid: u64,
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+    web_url: String,
+}
+
+/// Performs `step`, returning what it created on GitLab, if anything.
+fn execute(
+    ctx: &Context,
+    project: &str,
+    step: &Step,
+    vars: &mut Vars,
+) -> anyhow::Result<Option<Resource>> {
+    let client = ctx.client;
+    let render = |template: &str| template::render(template, vars);
+    match step {
+        Step::FindLatestRelease => {
+            let latest = emergency::latest_release(client, project)?;
+            let next = semver::Version::new(latest.major, latest.minor, latest.patch + 1);
+            vars.insert("latest_release".into(), latest.to_string());
+            vars.insert("latest_release_branch".into(), format!("release/{latest}"));
+            vars.insert("next_patch".into(), next.to_string());
+            Ok(None)
+        }
+        Step::CreateBranch { branch, ref_ } => {
+            let (branch, ref_) = (render(branch)?, render(ref_)?);
+            let endpoint = CreateBranch::builder()
+                .project(project)
+                .branch(&branch)
+                .ref_(&ref_)
+                .build()?;
+            let event = Event::BranchCreated {
+                project,
+                branch: &branch,
+                ref_: &ref_,
+            };
+            ctx.hooked(&event, || Ok(api::ignore(endpoint).query(client)?))?;
+            vars.insert("branch".into(), branch.clone());
+            Ok(Some(Resource::Branch { name: branch }))
+        }
+        Step::CreateMr {
+            source,
+            target,
+            title,
+            description,
+            description_template,
+            assignee,
+        } => {
+            let (source, target, title) = (render(source)?, render(target)?, render(title)?);
+            let description = match description_template {
+                Some(_) if !description.is_empty() => {
+                    anyhow::bail!("set either description or description_template, not both")
+                }
+                Some(path) => render(&template::load(path)?)
+                    .with_context(|| format!("{} does not render", path.display()))?,
+                None => render(description)?,
+            };
+            let mut endpoint = CreateMergeRequest::builder();
+            endpoint
+                .project(project)
+                .source_branch(&source)
+                .target_branch(&target)
+                .title(&title)
+                .description(origin::sign(&description));
+            if let Some(assignee) = assignee {
+                let assignee = render(assignee)?;
+                endpoint.assignee(
+                    assignee
+                        .parse::<u64>()
+                        .with_context(|| format!("assignee {assignee:?} is not a user ID"))?,
+                );
+            }
+            let endpoint = endpoint.build()?;
+            let event = Event::MrCreated {
+                project,
+                source_branch: &source,
+                target_branch: &target,
+                title: &title,
+            };
+            let mr: Created = ctx.hooked(&event, || Ok(endpoint.query(client)?))?;
+            origin::note(client, project, mr.iid);
+            vars.insert("mr_iid".into(), mr.iid.to_string());
+            vars.insert("mr_url".into(), mr.web_url.clone());
+            Ok(Some(Resource::MergeRequest {
+                iid: mr.iid,
+                web_url: mr.web_url,
+            }))
+        }
+        Step::Notify { message } => {
+            let message = render(message)?;
+            let Some(webhook_url) = ctx.notify.webhook_url() else {
+                anyhow::bail!("notify needs notify.webhook_url or NOTIFY_WEBHOOK_URL");
+            };
+            let event = Event::NotificationSent {
+                project,
+                message: &message,
+            };
+            ctx.hooked(&event, || notify::send(ctx.client, &webhook_url, &message))?;
+            Ok(None)
+        }
+        Step::TriggerPipeline { ref_, variables } => {
+            let ref_ = render(ref_)?;
+            let variables = variables
+                .iter()
+                .map(|(key, value)| {
+                    Ok(PipelineVariable::builder()
+                        .key(key.as_str())
+                        .value(render(value)?)
+                        .build()?)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let endpoint = CreatePipeline::builder()
+                .project(project)
+                .ref_(&ref_)
+                .variables(variables.into_iter())
+                .build()?;
+            let pipeline: Pipeline = endpoint.query(client)?;
+            vars.insert("pipeline_id".into(), pipeline.id.to_string());
+            vars.insert("pipeline_url".into(), pipeline.web_url.clone());
+            Ok(Some(Resource::Pipeline {
+                id: pipeline.id,
+                web_url: pipeline.web_url,
+            }))
+        }
+    }
+}
+
+pub fn run(
+    ctx: &Context,
+    name: &str,
+    workflow: &Workflow,
+    project: &str,
+    mut vars: Vars,
+) -> anyhow::Result<String> {
+    vars.insert("project".into(), project.to_owned());
+    if let Some(description) = &workflow.description {
+        tracing::info!("running {name}: {description}");
+    }
+    let step_name = |index: usize, step: &Step| format!("step {index} ({})", step.name());
+    let summarize = |outcomes: &[(String, Outcome)]| {
+        let created: Vec<_> = workflow
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| ctx.journal.find(project, &step_name(index, step))?.created)
+            .collect();
+        workflow::summarize(project, outcomes, &created);
+    };
+    let plan: Vec<_> = workflow
+        .steps
+        .iter()
+        .enumerate()
+        .filter(|(index, step)| {
+            ctx.journal
+                .find(project, &step_name(*index, step))
+                .is_none()
+        })
+        .map(|(_, step)| step.summary())
+        .collect();
+    ctx.confirm(project, &plan)?;
+
+    let mut outcomes: Vec<_> = workflow
+        .steps
+        .iter()
+        .map(|step| (step.summary(), Outcome::NotAttempted))
+        .collect();
+    for (index, step) in workflow.steps.iter().enumerate() {
+        let _span = tracing::info_span!("step", index, name = step.name()).entered();
+        let step_name = step_name(index, step);
+        let resumed = ctx.journal.find(project, &step_name).is_some();
+        let result = ctx.journaled(project, &step_name, || {
+            let created = execute(ctx, project, step, &mut vars)?;
+            Ok((created, vars.clone()))
+        });
+        match result {
+            Ok(entry) => {
+                vars = entry.vars;
+                outcomes[index].1 = if resumed {
+                    Outcome::Resumed
+                } else {
+                    Outcome::Created
+                };
+                tracing::info!("done");
+            }
+            // Nothing later depends on a notification having gone out.
+            Err(err) if matches!(step, Step::Notify { .. }) && !err.is::<cancel::Cancelled>() => {
+                tracing::warn!("{step_name} of {name} failed: {err:#}");
+                outcomes[index].1 = Outcome::Failed(format!("{err:#}"));
+            }
+            Err(err) => {
+                outcomes[index].1 = Outcome::Failed(format!("{err:#}"));
+                summarize(&outcomes);
+                return Err(err.context(format!("{step_name} of {name} failed")));
+            }
+        }
+    }
+    summarize(&outcomes);
+    let failed = outcomes
+        .iter()
+        .filter(|(_, outcome)| outcome.is_failed())
+        .count();
+    anyhow::ensure!(
+        failed == 0,
+        "{failed} of {} steps of {name} failed",
+        outcomes.len()
+    );
+    Ok(format!("{name}: {} steps", workflow.steps.len()))
+}
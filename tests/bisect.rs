@@ -0,0 +1,99 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn commit(id: &str) -> serde_json::Value {
+    serde_json::json!({ "id": id, "short_id": &id[..4], "title": format!("Change {id}") })
+}
+
+#[test]
+fn narrows_down_the_commit_that_broke_the_job() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", "good0")
+            .query_param("to", "ccc3");
+        then.status(200).json_body(serde_json::json!({
+            "commits": [commit("aaa1"), commit("bbb2"), commit("ccc3")],
+        }));
+    });
+    // The middle commit has no pipeline yet, the first one has.
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/pipelines")
+            .query_param("sha", "bbb2");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/pipelines")
+            .query_param("sha", "aaa1");
+        then.status(200).json_body(serde_json::json!([{ "id": 1 }]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/pipelines/1/jobs");
+        then.status(200)
+            .json_body(serde_json::json!([{ "name": "test", "status": "success" }]));
+    });
+    let branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches")
+            .form_urlencoded_tuple("branch", "bisect/bbb2")
+            .form_urlencoded_tuple("ref", "bbb2");
+        then.status(201)
+            .json_body(serde_json::json!({ "name": "bisect/bbb2" }));
+    });
+    let pipeline = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/pipeline")
+            .form_urlencoded_tuple("ref", "bisect/bbb2")
+            .form_urlencoded_tuple("variables[][value]", "test");
+        then.status(201).json_body(serde_json::json!({ "id": 2 }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/pipelines/2/jobs");
+        then.status(200).json_body(serde_json::json!([
+            { "name": "lint", "status": "success" },
+            { "name": "test", "status": "failed" },
+        ]));
+    });
+    let cleanup = server.mock(|when, then| {
+        when.method(DELETE)
+            .path("/api/v4/projects/42/repository/branches/bisect%2Fbbb2");
+        then.status(204);
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/commits/bbb2/merge_requests");
+        then.status(200).json_body(serde_json::json!([{
+            "iid": 12,
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/12",
+        }]));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "pipeline-bisect",
+        "--good",
+        "good0",
+        "--bad",
+        "ccc3",
+        "--job",
+        "test",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    branch.assert();
+    pipeline.assert();
+    cleanup.assert();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("bbb2 Change bbb2"), "{stdout}");
+    assert!(stdout.contains("!12"), "{stdout}");
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn reports_each_column_of_the_board() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/boards");
+        then.status(200).json_body(serde_json::json!([
+            { "id": 1, "name": "Development", "lists": [] },
+            {
+                "id": 2,
+                "name": "Release 1.4",
+                "milestone": { "title": "v1.4.0" },
+                "labels": [],
+                "lists": [
+                    { "id": 20, "label": { "name": "Review" }, "position": 1 },
+                    { "id": 10, "label": { "name": "Doing" }, "position": 0 },
+                ],
+            },
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/issues")
+            .query_param("state", "opened")
+            .query_param("milestone_id", "v1.4.0");
+        then.status(200).json_body(serde_json::json!([
+            { "iid": 1, "title": "Untriaged", "labels": [], "created_at": "2024-05-01T00:00:00Z" },
+            { "iid": 2, "title": "In progress", "labels": ["Doing"], "created_at": "2024-05-01T00:00:00Z" },
+            { "iid": 3, "title": "In review", "labels": ["Review", "bug"], "created_at": "2024-05-01T00:00:00Z" },
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/issues")
+            .query_param("state", "closed");
+        then.status(200)
+            .json_body(serde_json::json!([{ "iid": 4, "title": "Done", "created_at": "2024-05-01T00:00:00Z" }]));
+    });
+    let events = server.mock(|when, then| {
+        when.method(GET).path_includes("/resource_label_events");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "board-report",
+        "--board",
+        "Release 1.4",
+        "--format",
+        "json",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let columns: Vec<_> = report["columns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|column| {
+            (
+                column["name"].as_str().unwrap(),
+                column["count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(columns, [("Open", 1), ("Doing", 1), ("Review", 1)]);
+    assert_eq!(report["closed"], 1);
+    events.assert_calls(2);
+}
+
+#[test]
+fn an_unknown_board_lists_the_boards() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/boards");
+        then.status(200)
+            .json_body(serde_json::json!([{ "id": 1, "name": "Development" }]));
+    });
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "board-report", "--board", "Sprint"]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("no board named \"Sprint\"; the boards are: Development"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const TEMPLATE: &str = r##"
+[[labels]]
+name = "type::fix"
+color = "#d9534f"
+
+[[labels]]
+name = "type::feature"
+color = "#5cb85c"
+
+[[badges]]
+name = "pipeline"
+link_url = "https://gitlab.example.com/%{project_path}/-/pipelines"
+image_url = "https://gitlab.example.com/%{project_path}/badges/%{default_branch}/pipeline.svg"
+
+[[webhooks]]
+url = "https://bot.example.com/webhook"
+events = ["merge_requests", "notes"]
+secret_env = "BOT_SECRET"
+"##;
+
+fn template() -> std::path::PathBuf {
+    let path = temp_dir("bootstrap").join("bootstrap.toml");
+    std::fs::write(&path, TEMPLATE).unwrap();
+    path
+}
+
+#[test]
+fn creates_only_what_is_missing() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/labels");
+        then.status(200)
+            .json_body(serde_json::json!([{ "id": 1, "name": "type::fix", "color": "#d9534f" }]));
+    });
+    let create_label = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/labels")
+            .form_urlencoded_tuple("name", "type::feature");
+        then.status(201).json_body(serde_json::json!({ "id": 2 }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/badges");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let create_badge = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/badges")
+            .form_urlencoded_tuple("name", "pipeline");
+        then.status(201).json_body(serde_json::json!({ "id": 5 }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/hooks");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let create_hook = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/hooks")
+            .form_urlencoded_tuple("token", "s3cret");
+        then.status(201).json_body(serde_json::json!({ "id": 3 }));
+    });
+
+    let path = template();
+    let output = run(helper(&server).env("BOT_SECRET", "s3cret").args([
+        "--project",
+        PROJECT,
+        "bootstrap",
+        "--file",
+        path.to_str().unwrap(),
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    create_label.assert_calls(1);
+    create_badge.assert();
+    create_hook.assert();
+}
+
+#[test]
+fn a_missing_secret_stops_before_any_change() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let labels = server.mock(|when, then| {
+        when.path_includes("/labels");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+
+    let path = template();
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "bootstrap",
+        "--file",
+        path.to_str().unwrap(),
+    ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("$BOT_SECRET"),
+        "{}",
+        stderr(&output)
+    );
+    labels.assert_calls(0);
+}
@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, stderr, PROJECT};
+
+#[test]
+fn sigterm_stops_a_wait_without_merging() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7/approvals");
+        then.status(200)
+            .json_body(serde_json::json!({ "approved": false, "approvals_left": 1 }));
+    });
+    let mr = server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7");
+        then.status(200).json_body(serde_json::json!({
+            "iid": 7,
+            "title": "Fix the login",
+            "state": "opened",
+            "sha": "abc123",
+            "source_branch": "fix-login",
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/7",
+            "blocking_discussions_resolved": true,
+            "head_pipeline": { "status": "success" },
+        }));
+    });
+    let merge = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/7/merge");
+        then.status(200).json_body(serde_json::json!({ "iid": 7 }));
+    });
+
+    let child = helper(&server)
+        .args([
+            "--project",
+            PROJECT,
+            "merge-when-ready",
+            "--mr",
+            "7",
+            "--poll",
+            "1m",
+        ])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    while mr.calls() == 0 {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let killed = std::process::Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(143), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("stopped by SIGTERM"),
+        "{}",
+        stderr(&output)
+    );
+    merge.assert_calls(0);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn publishes_the_check_on_the_commit() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let status = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/statuses/abc123")
+            .form_urlencoded_tuple("state", "failed")
+            .form_urlencoded_tuple("name", "jira-check")
+            .form_urlencoded_tuple("target_url", "https://jira.example.com/browse/ABC-1");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+
+    let output = run(helper(&server).env("CI_COMMIT_SHA", "abc123").args([
+        "--project",
+        PROJECT,
+        "set-status",
+        "--name",
+        "jira-check",
+        "--state",
+        "failed",
+        "--target-url",
+        "https://jira.example.com/browse/ABC-1",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    status.assert();
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+fn check_compat(server: &MockServer, command: &str) -> std::process::Output {
+    let dir = temp_dir("compat");
+    let config = dir.join("config.toml");
+    std::fs::write(&config, format!("[check_compat]\ncommand = {command}\n")).unwrap();
+    run(helper(server)
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "check-compat"])
+        .args([
+            "--base",
+            "release/1.3.0",
+            "--head",
+            "release/1.3.1",
+            "--mr",
+            "5",
+        ])
+        .arg("--junit")
+        .arg(dir.join("junit.xml")))
+}
+
+#[test]
+fn a_breaking_change_is_posted_and_fails_the_check() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let note = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests/5/notes")
+            .body_includes("breaks+the+API")
+            .body_includes("removed+Client%3A%3Alogin+since+release%2F1.3.0");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+
+    let output = check_compat(
+        &server,
+        r#"["sh", "-c", "echo removed Client::login since {{ base }}; exit 1"]"#,
+    );
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("release/1.3.1 breaks compatibility with release/1.3.0"),
+        "{}",
+        stderr(&output)
+    );
+    note.assert();
+}
+
+#[test]
+fn a_compatible_patch_passes() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let note = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests/5/notes")
+            .body_includes("keeps+the+API");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+
+    let output = check_compat(&server, r#"["true"]"#);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    note.assert();
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+#[test]
+fn prints_and_reports_the_changed_components() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/5/diffs");
+        then.status(200).json_body(serde_json::json!([
+            { "old_path": "web/src/app.ts", "new_path": "web/src/app.ts" },
+        ]));
+    });
+    let dir = temp_dir("components");
+    let config = dir.join("config.toml");
+    std::fs::write(
+        &config,
+        r#"
+[[components]]
+name = "billing"
+paths = ["services/billing/"]
+
+[[components]]
+name = "web"
+paths = ["web/"]
+"#,
+    )
+    .unwrap();
+    let dotenv = dir.join("components.env");
+
+    let output = run(helper(&server)
+        .env("CI_MERGE_REQUEST_IID", "5")
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "changed-components", "--dotenv"])
+        .arg(&dotenv));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "web\n");
+    assert_eq!(
+        std::fs::read_to_string(&dotenv).unwrap(),
+        "CHANGED_COMPONENTS=web\nCHANGED_BILLING=false\nCHANGED_WEB=true\n"
+    );
+}
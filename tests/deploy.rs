@@ -0,0 +1,68 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn a_stored_deploy_token_is_not_printed() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let create = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/deploy_tokens")
+            .form_urlencoded_tuple("name", "registry-pull")
+            .form_urlencoded_tuple("scopes[]", "read_registry")
+            .form_urlencoded_tuple("expires_at", "2031-01-31");
+        then.status(201).json_body(serde_json::json!({
+            "id": 1,
+            "username": "gitlab+deploy-token-1",
+            "token": "gldt-s3cret",
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_includes("/api/v4/projects/99/variables/");
+        then.status(404)
+            .json_body(serde_json::json!({ "message": "404 Variable Not Found" }));
+    });
+    let store_token = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/99/variables")
+            .form_urlencoded_tuple("key", "REGISTRY_TOKEN")
+            .form_urlencoded_tuple("value", "gldt-s3cret")
+            .form_urlencoded_tuple("masked", "true");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+    let store_username = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/99/variables")
+            .form_urlencoded_tuple("key", "REGISTRY_TOKEN_USERNAME")
+            .form_urlencoded_tuple("value", "gitlab+deploy-token-1");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "deploy-token",
+        "create",
+        "registry-pull",
+        "--scope",
+        "read-registry",
+        "--expires-at",
+        "2031-01-31",
+        "--store-in",
+        "99",
+        "--variable",
+        "REGISTRY_TOKEN",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    create.assert();
+    store_token.assert();
+    store_username.assert();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("gldt-s3cret"), "{stdout}");
+    assert!(!stderr(&output).contains("gldt-s3cret"));
+}
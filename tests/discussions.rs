@@ -0,0 +1,83 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+fn note(author: &str, body: &str, resolvable: bool, resolved: bool) -> serde_json::Value {
+    serde_json::json!({
+        "body": body,
+        "author": { "username": author },
+        "resolvable": resolvable,
+        "resolved": resolved,
+    })
+}
+
+#[test]
+fn unresolved_discussions_fail_the_check() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let mut on_a_line = note("bob", "This leaks the token", true, false);
+    on_a_line["position"] = serde_json::json!({
+        "new_path": "src/auth.rs", "new_line": 12, "old_path": "src/auth.rs", "old_line": null,
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/5/discussions");
+        then.status(200).json_body(serde_json::json!([
+            { "id": "a1", "notes": [note("alice", "Rename this", true, true)] },
+            { "id": "b2", "notes": [on_a_line, note("alice", "Will fix", true, false)] },
+            { "id": "c3", "notes": [note("ci-bot", "added 1 commit", false, false)] },
+        ]));
+    });
+    let report = temp_dir("discussions-junit").join("check-discussions.xml");
+
+    let output = run(helper(&server)
+        .args([
+            "--project",
+            PROJECT,
+            "check-discussions",
+            "--mr",
+            "5",
+            "--junit",
+        ])
+        .arg(&report));
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("1 of 2 discussion(s) of !5 are unresolved"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("@bob: This leaks the token (src/auth.rs:12, discussion b2)"),
+        "{stderr}"
+    );
+    let report = std::fs::read_to_string(&report).unwrap();
+    assert!(
+        report.contains("<testsuite name=\"check-discussions\" tests=\"2\" failures=\"1\">"),
+        "{report}"
+    );
+    assert!(
+        report.contains("name=\"@alice: Rename this\"/>"),
+        "{report}"
+    );
+}
+
+#[test]
+fn resolved_discussions_pass() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/5/discussions");
+        then.status(200).json_body(serde_json::json!([
+            { "id": "a1", "notes": [note("alice", "Rename this", true, true)] },
+        ]));
+    });
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "check-discussions", "--mr", "5"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn mr(server: &MockServer, description: &str) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/9");
+        then.status(200)
+            .json_body(serde_json::json!({ "iid": 9, "description": description }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/9/commits");
+        then.status(200).json_body(serde_json::json!([
+            { "short_id": "5e6f", "title": "feat: Export boards", "message": "feat: Export boards\n" },
+            { "short_id": "1a2b", "title": "fix (ABC-1): Handle empty pages", "message": "fix (ABC-1): Handle empty pages\n\nThe API returns [] for them.\n" },
+        ]));
+    });
+}
+
+#[test]
+fn the_empty_section_is_filled_from_the_commits() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mr(
+        &server,
+        "### What does this change do?\n\n### How to test this change?",
+    );
+    let edit = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/9")
+            .form_urlencoded_tuple(
+                "description",
+                "### What does this change do?\n\n\
+                 **Features**\n\n- Export boards (`5e6f`)\n\n\
+                 **Fixes**\n\n- Handle empty pages (`1a2b`)\n  The API returns [] for them.\n\n\
+                 ### How to test this change?",
+            );
+        then.status(200).json_body(serde_json::json!({}));
+    });
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "draft-description", "--mr", "9"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    edit.assert();
+}
+
+#[test]
+fn a_written_section_is_left_alone() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mr(
+        &server,
+        "### What does this change do?\n\nExports boards.\n",
+    );
+    let edit = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/9");
+        then.status(200).json_body(serde_json::json!({}));
+    });
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "draft-description", "--mr", "9"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    edit.assert_calls(0);
+    assert!(stderr(&output).contains("already says what it does"));
+}
@@ -0,0 +1,609 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+const BRANCHES: &str =
+    "GET_projects_42_repository_branches__regex_release_2F_5Cd_2B_5C._5Cd_2B_5C._5Cd_2B";
+
+fn merge_request_to<'a>(server: &'a MockServer, target: &str, status: u16) -> httpmock::Mock<'a> {
+    let fixture = common::fixture("POST_projects_42_merge_requests");
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("source_branch", "release/1.3.1")
+            .form_urlencoded_tuple("target_branch", target)
+            .form_urlencoded_tuple("title", "EMERGENCY PRODUCTION PATCH (release/1.3.0)")
+            .form_urlencoded_tuple("assignee_id", "7");
+        if status == fixture.status {
+            then.status(status).json_body(fixture.body);
+        } else {
+            then.status(status).json_body(
+                serde_json::json!({ "message": ["Another open merge request already exists"] }),
+            );
+        }
+    })
+}
+
+#[test]
+fn patches_the_latest_release_into_every_target() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let branches = mount(&server, BRANCHES);
+    let create_branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches")
+            .form_urlencoded_tuple("branch", "release/1.3.1")
+            .form_urlencoded_tuple("ref", "release/1.3.0");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    let to_master = merge_request_to(&server, "master", 201);
+    let to_dev = merge_request_to(&server, "dev", 201);
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    branches.assert();
+    create_branch.assert();
+    to_master.assert();
+    to_dev.assert();
+    assert!(stderr(&output).contains("release/1.3.1 from release/1.3.0"));
+}
+
+#[test]
+fn the_compare_summary_goes_into_each_description() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    for target in ["master", "dev"] {
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/v4/projects/42/repository/compare")
+                .query_param("from", "release/1.3.0")
+                .query_param("to", target);
+            then.status(200).json_body(serde_json::json!({
+                "commits": [{ "id": format!("{target}1"), "short_id": "abc", "title": "Ship it", "author_name": "Alice" }],
+                "diffs": [{ "new_path": format!("{target}/file.rs") }],
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!(
+                "/api/v4/projects/42/repository/commits/{target}1/merge_requests"
+            ));
+            then.status(200).json_body(serde_json::json!([]));
+        });
+    }
+    let with_summary = |target: &str| {
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v4/projects/42/merge_requests")
+                .form_urlencoded_tuple("target_branch", target)
+                .body_includes(format!("{target}%2Ffile.rs"));
+            then.status(201)
+                .json_body(common::fixture("POST_projects_42_merge_requests").body);
+        })
+    };
+    let to_master = with_summary("master");
+    let to_dev = with_summary("dev");
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "emergency-patch", "--compare-summary"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    to_master.assert();
+    to_dev.assert();
+}
+
+#[test]
+fn cherry_picks_the_fix_onto_the_new_branch() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/commits/1a2b3c4d");
+        then.status(200).json_body(serde_json::json!({
+            "id": "1a2b3c4d5e6f",
+            "short_id": "1a2b3c4d",
+            "title": "Fix the login redirect",
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    let pick = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits/1a2b3c4d5e6f/cherry_pick")
+            .form_urlencoded_tuple("branch", "release/1.3.1");
+        then.status(201).json_body(serde_json::json!({
+            "id": "9f8e7d6c5b4a",
+            "short_id": "9f8e7d6c",
+            "title": "Fix the login redirect",
+        }));
+    });
+    let noting_the_pick = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .body_includes("Cherry-picked+commits")
+            .body_includes("Fix+the+login+redirect");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "emergency-patch",
+        "--cherry-pick",
+        "1a2b3c4d",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    pick.assert();
+    noting_the_pick.assert_calls(2);
+}
+
+#[test]
+fn the_description_shows_the_state_of_production() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/pipelines")
+            .query_param("ref", "release/1.3.0");
+        then.status(200).json_body(serde_json::json!([{
+            "id": 4240,
+            "status": "failed",
+            "web_url": "https://gitlab.example.com/p/-/pipelines/4240",
+        }]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/deployments")
+            .query_param("environment", "production");
+        then.status(200).json_body(serde_json::json!([{
+            "sha": "0123456789abcdef",
+            "ref": "release/1.3.0",
+            "created_at": "2024-06-01T09:00:00Z",
+            "finished_at": "2024-06-01T09:12:00Z",
+            "environment": { "name": "production" },
+        }]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", "0123456789abcdef");
+        then.status(200).json_body(serde_json::json!({
+            "commits": [{ "id": "c1", "short_id": "c1", "title": "Tune the cache", "author_name": "Alice" }],
+        }));
+    });
+    let with_context = |target: &str| {
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v4/projects/42/merge_requests")
+                .form_urlencoded_tuple("target_branch", target)
+                .body_includes("**failed**")
+                .body_includes("2024-06-01+09%3A12+UTC")
+                .body_includes("Tune+the+cache");
+            then.status(201)
+                .json_body(common::fixture("POST_projects_42_merge_requests").body);
+        })
+    };
+    let to_master = with_context("master");
+    let to_dev = with_context("dev");
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    to_master.assert();
+    to_dev.assert();
+}
+
+#[test]
+fn a_failed_branch_stops_the_run() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    let create_branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(400)
+            .json_body(serde_json::json!({ "message": "Branch already exists" }));
+    });
+    let to_master = merge_request_to(&server, "master", 201);
+    let to_dev = merge_request_to(&server, "dev", 201);
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    create_branch.assert();
+    to_master.assert_calls(0);
+    to_dev.assert_calls(0);
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("failed: create branch release/1.3.1"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("not attempted: open a merge request release/1.3.1 -> master"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Branch already exists"), "{stderr}");
+}
+
+#[test]
+fn a_failed_pick_opens_no_merge_requests() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    mount(&server, "POST_projects_42_repository_branches");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/commits/1a2b3c4d");
+        then.status(200).json_body(serde_json::json!({
+            "id": "1a2b3c4d5e6f",
+            "short_id": "1a2b3c4d",
+            "title": "Fix the login redirect",
+        }));
+    });
+    let pick = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits/1a2b3c4d5e6f/cherry_pick");
+        then.status(400).json_body(serde_json::json!({
+            "message": "Sorry, we cannot cherry-pick this commit automatically.",
+        }));
+    });
+    let merge_requests = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/merge_requests");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "emergency-patch",
+        "--cherry-pick",
+        "1a2b3c4d",
+    ]));
+
+    assert!(!output.status.success());
+    pick.assert();
+    merge_requests.assert_calls(0);
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("failed: cherry-pick 1a2b3c4d onto release/1.3.1"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn a_failed_merge_request_does_not_stop_the_others() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    mount(&server, "POST_projects_42_repository_branches");
+    let to_master = merge_request_to(&server, "master", 400);
+    let to_dev = merge_request_to(&server, "dev", 201);
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    to_master.assert();
+    to_dev.assert();
+    assert!(
+        stderr(&output).contains("1 of 3 steps failed"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn resuming_skips_the_steps_already_done() {
+    let journal = common::temp_dir("resume").join("journal.json");
+
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    let create_branch = mount(&server, "POST_projects_42_repository_branches");
+    let to_master = merge_request_to(&server, "master", 201);
+    let mut to_dev = merge_request_to(&server, "dev", 503);
+    let output = run(helper(&server)
+        .args(["--project", PROJECT, "--journal"])
+        .arg(&journal)
+        .arg("emergency-patch"));
+    assert!(!output.status.success());
+    let stderr_text = stderr(&output);
+    assert!(stderr_text.contains("failed: open a merge request release/1.3.1 -> dev"));
+    assert!(stderr_text.contains("retry with --resume"), "{stderr_text}");
+    to_dev.delete();
+
+    let output = run(helper(&server)
+        .args(["--project", PROJECT, "--journal"])
+        .arg(&journal)
+        .arg("emergency-patch"));
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("already exists"),
+        "{}",
+        stderr(&output)
+    );
+
+    let to_dev = merge_request_to(&server, "dev", 201);
+    let output = run(helper(&server)
+        .args(["--project", PROJECT, "--resume"])
+        .arg(&journal)
+        .arg("emergency-patch"));
+    assert!(output.status.success(), "{}", stderr(&output));
+    create_branch.assert_calls(1);
+    to_master.assert_calls(1);
+    to_dev.assert_calls(1);
+}
+
+#[test]
+fn records_fixtures_in_the_replayed_format() {
+    let dir = common::temp_dir("record");
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    mount(&server, "POST_projects_42_repository_branches");
+    merge_request_to(&server, "master", 201);
+    merge_request_to(&server, "dev", 201);
+
+    let output = run(helper(&server)
+        .args(["--project", PROJECT, "--record-fixtures"])
+        .arg(&dir)
+        .arg("emergency-patch"));
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    for name in [
+        "GET_user",
+        BRANCHES,
+        "POST_projects_42_repository_branches",
+        "POST_projects_42_merge_requests",
+    ] {
+        let recorded: common::Fixture = serde_json::from_slice(
+            &std::fs::read(dir.join(format!("{name}.json")))
+                .unwrap_or_else(|err| panic!("{name} was not recorded: {err}")),
+        )
+        .unwrap();
+        let expected = common::fixture(name);
+        assert_eq!(
+            (
+                &recorded.method,
+                &recorded.path,
+                &recorded.query,
+                recorded.status
+            ),
+            (
+                &expected.method,
+                &expected.path,
+                &expected.query,
+                expected.status
+            ),
+            "{name}"
+        );
+        assert_eq!(recorded.body, expected.body, "{name}");
+    }
+    let contents = std::fs::read_to_string(dir.join("GET_user.json")).unwrap();
+    assert!(!contents.contains(common::TOKEN));
+}
+
+#[test]
+fn a_hung_pre_hook_is_killed_and_stops_the_run() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    let create_branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201);
+    });
+    let config = common::temp_dir("hooks").join("config.toml");
+    std::fs::write(
+        &config,
+        r#"
+[[hooks]]
+event = "branch_created"
+phase = "pre"
+command = ["sh", "-c", "echo from the hook; exec sleep 30"]
+timeout = "300ms"
+"#,
+    )
+    .unwrap();
+
+    let started = std::time::Instant::now();
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "emergency-patch",
+    ]));
+
+    assert!(!output.status.success());
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    assert!(stderr(&output).contains("killed the Pre branch_created hook"));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("from the hook"));
+    create_branch.assert_calls(0);
+}
+
+#[test]
+fn renders_the_description_template() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    let described = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("description", "Patch 1.3.1 of release/1.3.0 in 42.");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+    let template = common::temp_dir("template").join("patch.md");
+    std::fs::write(
+        &template,
+        "Patch {{ next_patch }} of {{ latest_release_branch }} in {{ project }}.",
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .args([
+            "--project",
+            PROJECT,
+            "emergency-patch",
+            "--description-template",
+        ])
+        .arg(&template));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    described.assert_calls(2);
+}
+
+#[test]
+fn an_sla_labels_the_merge_requests() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    let labelled = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("labels", "emergency");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+    let config = common::temp_dir("sla").join("config.toml");
+    std::fs::write(&config, "[emergency_patch]\nsla = \"4h\"\n").unwrap();
+
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "emergency-patch",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    labelled.assert_calls(2);
+}
+
+#[test]
+fn the_description_is_in_the_configured_locale() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    let hungarian = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .body_includes("Mit+csin%C3%A1l+ez+a+v%C3%A1ltoztat%C3%A1s");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+    let config = common::temp_dir("locale").join("config.toml");
+    std::fs::write(&config, "locale = \"hu\"\n").unwrap();
+
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "emergency-patch",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    hungarian.assert_calls(2);
+}
+
+#[test]
+fn a_patch_cut_during_a_deploy_freeze_is_flagged() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_repository_branches").body);
+    });
+    let flagged = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("labels", "deploy-freeze")
+            .body_includes("Created+during+a+deploy+freeze");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+
+    let output = run(helper(&server)
+        .env("GITLAB_CI", "true")
+        .env("CI_PROJECT_ID", "42")
+        .env("CI_DEPLOY_FREEZE", "true")
+        .args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    flagged.assert_calls(2);
+}
+
+#[test]
+fn another_projects_deploy_freeze_is_not_this_ones() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    mount(&server, "POST_projects_42_repository_branches");
+    let periods = server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/freeze_periods");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let flagged = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .body_includes("deploy-freeze");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+    let to_master = merge_request_to(&server, "master", 201);
+    let to_dev = merge_request_to(&server, "dev", 201);
+
+    let output = run(helper(&server)
+        .env("GITLAB_CI", "true")
+        .env("CI_PROJECT_ID", "7")
+        .env("CI_PROJECT_PATH", "platform/deployer")
+        .env("CI_DEPLOY_FREEZE", "true")
+        .args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    periods.assert();
+    flagged.assert_calls(0);
+    to_master.assert();
+    to_dev.assert();
+}
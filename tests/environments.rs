@@ -0,0 +1,73 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn lists_the_last_deployment_and_health_of_each_environment() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/environments")
+            .query_param("states", "available");
+        then.status(200)
+            .json_body(serde_json::json!([{ "id": 1 }, { "id": 2 }, { "id": 3 }]));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/environments/1");
+        then.status(200).json_body(serde_json::json!({
+            "id": 1,
+            "name": "production",
+            "external_url": server.url("/up"),
+            "last_deployment": {
+                "ref": "release/1.3.0",
+                "sha": "0123456789abcdef",
+                "created_at": "2024-05-01T09:50:00Z",
+                "finished_at": "2024-05-01T10:00:00Z",
+                "user": { "username": "alice" },
+            },
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/environments/2");
+        then.status(200).json_body(serde_json::json!({
+            "id": 2,
+            "name": "staging",
+            "external_url": server.url("/down"),
+            "last_deployment": {
+                "ref": "dev",
+                "sha": "fedcba9876543210",
+                "created_at": "2024-05-02T08:00:00Z",
+                "user": { "username": "bob" },
+            },
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/environments/3");
+        then.status(200)
+            .json_body(serde_json::json!({ "id": 3, "name": "review/x" }));
+    });
+    let up = server.mock(|when, then| {
+        when.method(GET).path("/up");
+        then.status(200);
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/down");
+        then.status(503);
+    });
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "envs", "status", "--format", "csv"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "PROJECT,ENVIRONMENT,REF,DEPLOYED,BY,HEALTH\n\
+         42,production,release/1.3.0 (01234567),2024-05-01 10:00 UTC,@alice,up (200)\n\
+         42,staging,dev (fedcba98),2024-05-02 08:00 UTC,@bob,down (503)\n\
+         42,review/x,never deployed,-,-,-\n"
+    );
+    up.assert();
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const EPIC_URL: &str = "https://gitlab.example.com/groups/release-team/-/epics/5";
+
+fn config(name: &str) -> std::path::PathBuf {
+    let path = temp_dir(name).join("config.toml");
+    std::fs::write(&path, "group = \"release-team\"\n").unwrap();
+    path
+}
+
+fn epic(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/groups/release-team/epics/5");
+        then.status(200).json_body(serde_json::json!({
+            "id": 500,
+            "iid": 5,
+            "title": "Release 1.4",
+            "web_url": EPIC_URL,
+        }));
+    });
+}
+
+#[test]
+fn links_only_what_is_not_linked_yet() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    epic(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/issues")
+            .query_param("milestone_id", "v1.4.0");
+        then.status(200).json_body(serde_json::json!([
+            { "iid": 1, "title": "Linked", "state": "opened", "epic": { "id": 500 } },
+            { "iid": 2, "title": "Loose", "state": "opened", "epic": null },
+        ]));
+    });
+    let linked = server.mock(|when, then| {
+        when.method(PUT).path("/api/v4/projects/42/issues/1");
+        then.status(200).json_body(serde_json::json!({}));
+    });
+    let loose = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/issues/2")
+            .form_urlencoded_tuple("epic_id", "500");
+        then.status(200).json_body(serde_json::json!({}));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests")
+            .query_param("milestone", "v1.4.0");
+        then.status(200)
+            .json_body(serde_json::json!([{ "iid": 7 }, { "iid": 8 }]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7/notes");
+        then.status(200).json_body(serde_json::json!([
+            { "body": format!("Part of epic {EPIC_URL} for v1.4.0") },
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/8/notes");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let mentioned = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests/7/notes");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+    let mention = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests/8/notes")
+            .form_urlencoded_tuple("body", format!("Part of epic {EPIC_URL} for v1.4.0"));
+        then.status(201).json_body(serde_json::json!({}));
+    });
+
+    let config = config("epic-link");
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "epic",
+        "link",
+        "--epic",
+        "5",
+        "--milestone",
+        "v1.4.0",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    loose.assert();
+    linked.assert_calls(0);
+    mention.assert();
+    mentioned.assert_calls(0);
+}
+
+#[test]
+fn status_counts_the_closed_issues() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    epic(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/groups/release-team/epics/5/issues");
+        then.status(200).json_body(serde_json::json!([
+            { "iid": 1, "title": "Done", "state": "closed", "references": { "full": "app#1" } },
+            { "iid": 2, "title": "Also done", "state": "closed", "references": { "full": "app#2" } },
+            { "iid": 3, "title": "Open", "state": "opened", "references": { "full": "api#3" } },
+        ]));
+    });
+
+    let config = config("epic-status");
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "epic-status",
+        "--epic",
+        "5",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("api#3"));
+    assert!(
+        stderr(&output).contains("&5 Release 1.4: 2 of 3 issue(s) closed (66%)"),
+        "{}",
+        stderr(&output)
+    );
+}
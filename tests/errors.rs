@@ -0,0 +1,115 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, run, stderr, PROJECT, TOKEN};
+
+#[test]
+fn rejected_token_fails_before_doing_anything() {
+    let server = MockServer::start();
+    let user = server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/user")
+            .header("private-token", TOKEN);
+        then.status(401)
+            .json_body(serde_json::json!({ "message": "401 Unauthorized" }));
+    });
+    let anything_else = server.mock(|when, then| {
+        when.path_matches(r"^/api/v4/projects/");
+        then.status(500);
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    user.assert();
+    anything_else.assert_calls(0);
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("failed to authenticate with the personal access token from --token"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("401 Unauthorized"), "{stderr}");
+    assert!(!stderr.contains(TOKEN), "the token leaked: {stderr}");
+}
+
+#[test]
+fn no_release_branches_is_an_error() {
+    let server = MockServer::start();
+    common::mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let create_branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201);
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    create_branch.assert_calls(0);
+    assert!(stderr(&output).contains("No branches found based on the release/x.x.x pattern"));
+}
+
+fn failing_status(status: u16, body: serde_json::Value) -> String {
+    let server = MockServer::start();
+    common::mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/statuses/abc123");
+        then.status(status).json_body(body);
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "set-status",
+        "--sha",
+        "abc123",
+        "--name",
+        "jira-check",
+        "--state",
+        "success",
+    ]));
+
+    assert!(!output.status.success());
+    stderr(&output)
+}
+
+#[test]
+fn a_missing_project_hints_at_a_typo_or_no_access() {
+    let stderr = failing_status(
+        404,
+        serde_json::json!({ "message": "404 Project Not Found" }),
+    );
+
+    assert!(stderr.contains("hint: check the project path"), "{stderr}");
+}
+
+#[test]
+fn a_missing_scope_names_the_scope_to_use() {
+    let stderr = failing_status(
+        403,
+        serde_json::json!({
+            "error": "insufficient_scope",
+            "error_description": "The request requires higher privileges than provided by the access token.",
+        }),
+    );
+
+    assert!(stderr.contains("hint: the token lacks a scope"), "{stderr}");
+    assert!(stderr.contains("`api`"), "{stderr}");
+}
+
+#[test]
+fn an_unrecognized_error_gets_no_hint() {
+    let stderr = failing_status(
+        500,
+        serde_json::json!({ "message": "500 Internal Server Error" }),
+    );
+
+    assert!(!stderr.contains("hint:"), "{stderr}");
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const FLAG: &str = "/api/v4/projects/42/feature_flags/kill_switch_x";
+
+#[test]
+fn a_kill_switch_is_turned_off_in_one_environment_and_announced() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path(FLAG);
+        then.status(200).json_body(serde_json::json!({
+            "name": "kill_switch_x",
+            "active": true,
+            "strategies": [{
+                "id": 1,
+                "name": "default",
+                "parameters": {},
+                "scopes": [
+                    { "id": 10, "environment_scope": "production" },
+                    { "id": 11, "environment_scope": "staging" },
+                ],
+            }],
+        }));
+    });
+    let edit = server.mock(|when, then| {
+        when.method(PUT).path(FLAG).json_body(serde_json::json!({
+            "active": true,
+            "strategies": [{ "id": 1, "scopes": [{ "id": 10, "_destroy": true }] }],
+        }));
+        then.status(200).json_body(serde_json::json!({}));
+    });
+    let hook = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hook")
+            .json_body_includes(r#"{ "text": "Feature flag `kill_switch_x` of 42 turned off in production by @alice" }"#);
+        then.status(200);
+    });
+
+    let journal = temp_dir("feature-flags").join("journal.json");
+    let output = run(helper(&server)
+        .env("NOTIFY_WEBHOOK_URL", server.url("/hook"))
+        .env("GITLAB_USER_LOGIN", "alice")
+        .args(["--project", PROJECT, "--journal"])
+        .arg(&journal)
+        .args([
+            "feature-flag",
+            "disable",
+            "--name",
+            "kill_switch_x",
+            "--env",
+            "production",
+        ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    edit.assert();
+    hook.assert();
+    let journal = std::fs::read_to_string(&journal).unwrap();
+    assert!(
+        journal.contains(r#""kind": "feature_flag""#) && journal.contains(r#""active": false"#),
+        "{journal}"
+    );
+}
+
+#[test]
+fn a_flag_that_already_is_off_is_left_alone() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path(FLAG);
+        then.status(200)
+            .json_body(serde_json::json!({ "name": "kill_switch_x", "active": false }));
+    });
+    let edit = server.mock(|when, then| {
+        when.method(PUT).path(FLAG);
+        then.status(200);
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "feature-flag",
+        "disable",
+        "--name",
+        "kill_switch_x",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    edit.assert_calls(0);
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+fn raw_file(server: &MockServer, path: &str, content: Option<&str>) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/api/v4/projects/42/repository/files/{path}/raw"))
+            .query_param("ref", "main");
+        match content {
+            Some(content) => then.status(200).body(content),
+            None => then
+                .status(404)
+                .json_body(serde_json::json!({ "message": "404 File Not Found" })),
+        };
+    });
+}
+
+#[test]
+fn changed_files_land_in_one_commit() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    raw_file(&server, "VERSION", Some("1.2.3\n"));
+    raw_file(&server, "CHANGELOG.md", Some("# Changelog\n"));
+    raw_file(&server, "docs%2F1.2.4.md", None);
+    let commit = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits")
+            .form_urlencoded_tuple("branch", "main")
+            .body_includes("actions%5B%5D%5Baction%5D=update&actions%5B%5D%5Bfile_path%5D=VERSION")
+            .body_includes(
+                "actions%5B%5D%5Baction%5D=create&actions%5B%5D%5Bfile_path%5D=docs%2F1.2.4.md",
+            )
+            .body_excludes("CHANGELOG.md");
+        then.status(201)
+            .json_body(serde_json::json!({ "id": "f00d" }));
+    });
+
+    let dir = temp_dir("files");
+    std::fs::write(dir.join("VERSION"), "1.2.4\n").unwrap();
+    std::fs::write(dir.join("CHANGELOG.md"), "# Changelog\n").unwrap();
+    std::fs::write(dir.join("notes.md"), "Fixes\n").unwrap();
+    let output = run(helper(&server).current_dir(&dir).args([
+        "--project",
+        PROJECT,
+        "file",
+        "put",
+        "VERSION",
+        "CHANGELOG.md",
+        "docs/1.2.4.md=notes.md",
+        "--branch",
+        "main",
+        "-m",
+        "Release 1.2.4",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    commit.assert();
+}
+
+#[test]
+fn nothing_is_committed_when_the_files_are_up_to_date() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    raw_file(&server, "VERSION", Some("1.2.4\n"));
+    let commit = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits");
+        then.status(201);
+    });
+
+    let dir = temp_dir("files");
+    std::fs::write(dir.join("VERSION"), "1.2.4\n").unwrap();
+    let output = run(helper(&server).current_dir(&dir).args([
+        "--project",
+        PROJECT,
+        "file",
+        "put",
+        "VERSION",
+        "--branch",
+        "main",
+        "-m",
+        "Release 1.2.4",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    commit.assert_calls(0);
+}
+
+#[test]
+fn commits_carry_the_bot_identity_and_trailers() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    raw_file(&server, "VERSION", Some("1.2.3\n"));
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/branches/fix%2FJIRA-9-bump");
+        then.status(404)
+            .json_body(serde_json::json!({ "message": "404 Branch Not Found" }));
+    });
+    let commit = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits")
+            .form_urlencoded_tuple("author_name", "Release Bot")
+            .form_urlencoded_tuple("author_email", "bot@example.com")
+            .form_urlencoded_tuple(
+                "commit_message",
+                "Bump the version\n\nCo-authored-by: Alice <alice@example.com>\nJira: JIRA-9\nGenerated-by: gitlab-ci-helper",
+            );
+        then.status(201)
+            .json_body(serde_json::json!({ "id": "f00d" }));
+    });
+
+    let dir = temp_dir("files-identity");
+    std::fs::write(dir.join("VERSION"), "1.2.4\n").unwrap();
+    std::fs::write(
+        dir.join("config.toml"),
+        "[commits]\nname = \"Release Bot\"\nemail = \"bot@example.com\"\n",
+    )
+    .unwrap();
+    let output = run(helper(&server)
+        .current_dir(&dir)
+        .env("GITLAB_USER_NAME", "Alice")
+        .env("GITLAB_USER_EMAIL", "alice@example.com")
+        .args([
+            "--config",
+            "config.toml",
+            "--project",
+            PROJECT,
+            "file",
+            "put",
+            "VERSION",
+            "--branch",
+            "fix/JIRA-9-bump",
+            "--start-branch",
+            "main",
+            "-m",
+            "Bump the version",
+        ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    commit.assert();
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn creates_a_freeze_period() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let created = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/freeze_periods")
+            .form_urlencoded_tuple("freeze_start", "0 23 * * 5")
+            .form_urlencoded_tuple("freeze_end", "0 7 * * 1")
+            .form_urlencoded_tuple("cron_timezone", "Europe/Budapest");
+        then.status(201).json_body(serde_json::json!({ "id": 3 }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "freeze",
+        "create",
+        "--start",
+        "0 23 * * 5",
+        "--end",
+        "0 7 * * 1",
+        "--timezone",
+        "Europe/Budapest",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    created.assert();
+}
+
+#[test]
+fn refuses_an_invalid_cron_expression() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let created = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/freeze_periods");
+        then.status(201).json_body(serde_json::json!({ "id": 3 }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "freeze",
+        "create",
+        "--start",
+        "0 23 * *",
+        "--end",
+        "0 7 * * 1",
+    ]));
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("is not a cron expression"));
+    created.assert_calls(0);
+}
+
+#[test]
+fn lists_which_periods_are_on() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/freeze_periods");
+        then.status(200).json_body(serde_json::json!([
+            {
+                "id": 1,
+                "freeze_start": "* * * * *",
+                "freeze_end": "0 0 1 1 *",
+                "cron_timezone": "UTC",
+            },
+            {
+                "id": 2,
+                "freeze_start": "0 0 1 1 *",
+                "freeze_end": "* * * * *",
+                "cron_timezone": "UTC",
+            },
+        ]));
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "freeze", "list"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.contains("* * * * *") && line.contains("until")),
+        "{stdout}"
+    );
+    assert!(
+        stdout.lines().any(|line| line.trim_end().ends_with("no")),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn deletes_a_freeze_period() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let deleted = server.mock(|when, then| {
+        when.method(DELETE)
+            .path("/api/v4/projects/42/freeze_periods/3");
+        then.status(204);
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "freeze", "delete", "3"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    deleted.assert();
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn tag(server: &MockServer, name: &str, committed_date: &str) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/api/v4/projects/42/repository/tags/{name}"));
+        then.status(200)
+            .json_body(serde_json::json!({ "commit": { "committed_date": committed_date } }));
+    });
+}
+
+fn compare(server: &MockServer, from: &str, to: &str, commits: &[&str]) {
+    let commits: Vec<_> = commits
+        .iter()
+        .map(|id| serde_json::json!({ "id": id }))
+        .collect();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", from)
+            .query_param("to", to);
+        then.status(200)
+            .json_body(serde_json::json!({ "commits": commits, "diffs": [] }));
+    });
+}
+
+#[test]
+fn exports_every_merge_request_between_the_tags() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42");
+        then.status(200).json_body(serde_json::json!({
+            "path_with_namespace": "team/app",
+            "default_branch": "main",
+        }));
+    });
+    tag(&server, "v1.2.0", "2024-05-01T10:00:00Z");
+    tag(&server, "v1.3.0", "2024-06-01T10:00:00Z");
+    compare(&server, "v1.2.0", "v1.3.0", &["abc123"]);
+    server.mock(|when, then| {
+        when.method(POST).path("/api/graphql");
+        then.status(200)
+            .json_body(serde_json::json!({ "data": { "project": {
+            "mergeRequests": {
+                "nodes": [{
+                    "iid": "7",
+                    "title": "fix(PROJ-12): Handle empty tags",
+                    "webUrl": "https://gitlab.example.com/team/app/-/merge_requests/7",
+                    "author": { "username": "alice" },
+                    "labels": { "nodes": [] },
+                    "mergedAt": "2024-05-10T12:00:00Z",
+                    "mergeCommitSha": "abc123",
+                    "squashCommitSha": null,
+                    "approvedBy": { "nodes": [{ "username": "bob" }, { "username": "carol" }] },
+                    "headPipeline": { "id": "gid://gitlab/Ci::Pipeline/991" },
+                }],
+                "pageInfo": { "hasNextPage": false, "endCursor": null },
+            },
+        } } }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "export-history",
+        "--since",
+        "v1.2.0",
+        "--until",
+        "v1.3.0",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some("MR,MERGED AT,KIND,JIRA ID,TITLE,AUTHOR,APPROVERS,MERGE SHA,PIPELINE,URL,PROJECT")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("!7,2024-05-10T12:00:00+00:00,fix,PROJ-12,Handle empty tags,alice,bob carol,abc123,991,https://gitlab.example.com/team/app/-/merge_requests/7,42")
+    );
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{fixture, helper, mount, run, stderr, temp_dir, PROJECT};
+
+#[test]
+fn the_patch_is_noted_on_the_opsgenie_incident() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(
+        &server,
+        "GET_projects_42_repository_branches__regex_release_2F_5Cd_2B_5C._5Cd_2B_5C._5Cd_2B",
+    );
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(fixture("POST_projects_42_repository_branches").body);
+    });
+    let mr = fixture("POST_projects_42_merge_requests").body;
+    let web_url = mr["web_url"].as_str().unwrap().to_owned();
+    server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/merge_requests");
+        then.status(201).json_body(mr);
+    });
+    let note = server.mock(|when, then| {
+        when.method(POST)
+            .path("/opsgenie/v1/incidents/inc-7/notes")
+            .header("Authorization", "GenieKey og-key")
+            .body_includes("release/1.3.1 cut from release/1.3.0")
+            .body_includes(format!("Merge request: {web_url}"));
+        then.status(202)
+            .json_body(serde_json::json!({ "result": "Request will be processed" }));
+    });
+
+    let config = temp_dir("incident").join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[incident]\nprovider = \"opsgenie\"\napi_url = \"{}/opsgenie\"\n",
+            server.base_url()
+        ),
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("OPSGENIE_API_KEY", "og-key")
+        .arg("--config")
+        .arg(&config)
+        .args([
+            "--project",
+            PROJECT,
+            "emergency-patch",
+            "--incident",
+            "inc-7",
+        ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    note.assert();
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+#[test]
+fn opens_an_incident_linked_to_the_patch_merge_requests() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let create = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/issues")
+            .form_urlencoded_tuple("title", "[S1] Checkout is down")
+            .form_urlencoded_tuple("issue_type", "incident")
+            .form_urlencoded_tuple("labels", "incident,severity::S1")
+            .form_urlencoded_tuple(
+                "description",
+                "**Severity:** S1\n\nCheckout is down\n\n### Merge requests\n\n- !7\n- !8",
+            );
+        then.status(201).json_body(serde_json::json!({
+            "iid": 31,
+            "web_url": "https://gitlab.example.com/group/project/-/issues/31",
+        }));
+    });
+    let journal = temp_dir("incident-issue").join("journal.json");
+    std::fs::write(
+        &journal,
+        serde_json::json!({
+            "command": "emergency-patch",
+            "entries": [
+                { "project": "42", "step": "create branch release/1.3.1", "at": 1,
+                  "created": { "kind": "branch", "name": "release/1.3.1" } },
+                { "project": "42", "step": "open a merge request release/1.3.1 -> master", "at": 2,
+                  "created": { "kind": "merge_request", "iid": 8, "web_url": "https://gitlab.example.com/mr/8" } },
+            ],
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .args([
+            "--project",
+            PROJECT,
+            "create-incident-issue",
+            "--severity",
+            "S1",
+            "--summary",
+            "Checkout is down",
+            "--mr",
+            "7",
+            "--from-journal",
+        ])
+        .arg(&journal));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    create.assert();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "31\n");
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+fn config() -> std::path::PathBuf {
+    let path = temp_dir("iterations").join("config.toml");
+    std::fs::write(&path, "group = \"team\"\n").unwrap();
+    path
+}
+
+fn iterations(server: &MockServer, iterations: serde_json::Value) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/groups/team/iterations")
+            .query_param("state", "current");
+        then.status(200).json_body(iterations);
+    });
+}
+
+#[test]
+fn the_closed_issues_join_the_running_iteration() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    iterations(
+        &server,
+        serde_json::json!([{
+            "id": 77,
+            "title": null,
+            "start_date": "2024-05-06",
+            "due_date": "2024-05-19",
+        }]),
+    );
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/9/closes_issues");
+        then.status(200).json_body(serde_json::json!([
+            { "iid": 3, "project_id": 42, "iteration": { "id": 77 } },
+            { "iid": 4, "project_id": 43, "iteration": null },
+        ]));
+    });
+    let assigned = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/issues/3/notes");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+    let assign = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/43/issues/4/notes")
+            .form_urlencoded_tuple("body", "/iteration *iteration:77");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+
+    let config = config();
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "assign-iteration",
+        "--mr",
+        "9",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assign.assert();
+    assigned.assert_calls(0);
+    assert!(stderr(&output).contains("2024-05-06 - 2024-05-19"));
+}
+
+#[test]
+fn several_running_iterations_need_a_choice() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    iterations(
+        &server,
+        serde_json::json!([
+            { "id": 77, "title": "Backend 12", "start_date": "2024-05-06", "due_date": "2024-05-19" },
+            { "id": 80, "title": "Mobile 4", "start_date": "2024-05-01", "due_date": "2024-05-28" },
+        ]),
+    );
+    let notes = server.mock(|when, then| {
+        when.method(POST).path_includes("/notes");
+        then.status(201);
+    });
+
+    let config = config();
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "assign-iteration",
+        "--mr",
+        "9",
+    ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("77 (Backend 12 (2024-05-06 - 2024-05-19)), 80 (Mobile 4"),
+        "{}",
+        stderr(&output)
+    );
+    notes.assert_calls(0);
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+fn tag(server: &MockServer, name: &str, committed_date: &str) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/api/v4/projects/42/repository/tags/{name}"));
+        then.status(200)
+            .json_body(serde_json::json!({ "commit": { "committed_date": committed_date } }));
+    });
+}
+
+fn compare(server: &MockServer, from: &str, to: &str, commits: &[&str]) {
+    let commits: Vec<_> = commits
+        .iter()
+        .map(|id| serde_json::json!({ "id": id }))
+        .collect();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", from)
+            .query_param("to", to);
+        then.status(200)
+            .json_body(serde_json::json!({ "commits": commits, "diffs": [] }));
+    });
+}
+
+fn merge_request(iid: u64, title: &str) -> serde_json::Value {
+    serde_json::json!({
+        "iid": iid.to_string(),
+        "title": title,
+        "webUrl": format!("https://gitlab.example.com/team/app/-/merge_requests/{iid}"),
+        "labels": { "nodes": [] },
+        "mergedAt": "2024-05-10T12:00:00Z",
+        "squashCommitSha": format!("{iid}abc"),
+    })
+}
+
+fn issue(server: &MockServer, key: &str, status: &str, category: &str, fix_version: &str) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/jira/rest/api/2/issue/{key}"))
+            .header("authorization", "Bearer jira-token");
+        then.status(200).json_body(serde_json::json!({
+            "key": key,
+            "fields": {
+                "status": { "name": status, "statusCategory": { "key": category } },
+                "fixVersions": [{ "name": fix_version }],
+            },
+        }));
+    });
+}
+
+#[test]
+fn reports_tickets_whose_state_disagrees_with_the_release() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42");
+        then.status(200).json_body(serde_json::json!({
+            "path_with_namespace": "team/app",
+            "default_branch": "main",
+        }));
+    });
+    tag(&server, "v1.3.0", "2024-05-01T10:00:00Z");
+    tag(&server, "v1.4.0", "2024-06-01T10:00:00Z");
+    compare(&server, "v1.3.0", "v1.4.0", &["7abc", "8abc", "9abc"]);
+    server.mock(|when, then| {
+        when.method(POST).path("/api/graphql");
+        then.status(200)
+            .json_body(serde_json::json!({ "data": { "project": {
+            "mergeRequests": {
+                "nodes": [
+                    merge_request(7, "feat(PROJ-1): Export to CSV"),
+                    merge_request(8, "fix(PROJ-2): Handle empty tags"),
+                    merge_request(9, "Bump dependencies"),
+                ],
+                "pageInfo": { "hasNextPage": false, "endCursor": null },
+            },
+        } } }));
+    });
+    issue(&server, "PROJ-1", "Done", "done", "1.4.0");
+    issue(&server, "PROJ-2", "In Progress", "indeterminate", "1.4.0");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/jira/rest/api/2/search")
+            .query_param(
+                "jql",
+                "project = \"PROJ\" AND fixVersion = \"1.4.0\" AND statusCategory = Done",
+            );
+        then.status(200).json_body(serde_json::json!({
+            "total": 2,
+            "issues": [
+                {
+                    "key": "PROJ-1",
+                    "fields": {
+                        "status": { "name": "Done", "statusCategory": { "key": "done" } },
+                        "fixVersions": [{ "name": "1.4.0" }],
+                    },
+                },
+                {
+                    "key": "PROJ-3",
+                    "fields": {
+                        "status": { "name": "Done", "statusCategory": { "key": "done" } },
+                        "fixVersions": [{ "name": "1.4.0" }],
+                    },
+                },
+            ],
+        }));
+    });
+    let config = temp_dir("jira").join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[jira]\nurl = \"{}\"\nproject = \"PROJ\"\n",
+            server.url("/jira")
+        ),
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("JIRA_API_TOKEN", "jira-token")
+        .arg("--config")
+        .arg(&config)
+        .args([
+            "--project",
+            PROJECT,
+            "jira-report",
+            "--version",
+            "1.4.0",
+            "--from",
+            "v1.3.0",
+            "--format",
+            "csv",
+        ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("2 ticket(s) of 1.4.0 disagree with what v1.4.0 ships"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "ISSUE,MR,STATUS,FIX VERSIONS,PROBLEM\n\
+         PROJ-1,!7,Done,1.4.0,-\n\
+         PROJ-2,!8,In Progress,1.4.0,shipped but In Progress\n\
+         -,!9,-,-,no Jira ID in its title\n\
+         PROJ-3,-,Done,1.4.0,done in 1.4.0 but not in the release\n"
+    );
+}
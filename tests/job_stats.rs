@@ -0,0 +1,65 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn reports_the_cache_times_of_the_latest_runs() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/jobs");
+        then.status(200).json_body(serde_json::json!([
+            { "id": 3, "name": "build", "status": "success", "duration": 200.0,
+              "started_at": "2024-05-02T10:00:00Z", "artifacts": [{ "size": 4096 }] },
+            { "id": 2, "name": "test", "status": "success", "duration": 60.0 },
+            { "id": 1, "name": "build", "status": "failed", "duration": 400.0,
+              "started_at": "2024-05-01T10:00:00Z" },
+        ]));
+    });
+    let trace = |id: u64, restore: u64| {
+        server.mock(move |when, then| {
+            when.method(GET)
+                .path(format!("/api/v4/projects/42/jobs/{id}/trace"));
+            then.status(200).body(format!(
+                "\x1b[0Ksection_start:1000:restore_cache\r\x1b[0KRestoring cache\n\
+                 \x1b[0Ksection_end:{}:restore_cache\r\x1b[0K\n",
+                1000 + restore
+            ));
+        })
+    };
+    trace(3, 12);
+    trace(1, 80);
+    let other = server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/jobs/2/trace");
+        then.status(200).body("");
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "job-stats",
+        "--job",
+        "build",
+        "--format",
+        "csv",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("42,3,2024-05-02 10:00,success,200,4,12,"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("42,1,2024-05-01 10:00,failed,400,0,80,"),
+        "{stdout}"
+    );
+    assert!(
+        stderr(&output).contains("duration: 400s -> 200s (-50%)"),
+        "{}",
+        stderr(&output)
+    );
+    other.assert_calls(0);
+}
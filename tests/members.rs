@@ -0,0 +1,71 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn flags_direct_maintainers_outside_the_approved_group() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/groups/platform/members");
+        then.status(200).json_body(serde_json::json!([
+            { "id": 1, "username": "alice", "name": "Alice", "state": "active", "access_level": 50 },
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/members");
+        then.status(200).json_body(serde_json::json!([
+            { "id": 1, "username": "alice", "name": "Alice", "state": "active", "access_level": 40 },
+            { "id": 2, "username": "bob", "name": "Bob", "state": "active", "access_level": 40 },
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/members/all");
+        then.status(200).json_body(serde_json::json!([
+            { "id": 1, "username": "alice", "name": "Alice", "state": "active", "access_level": 40 },
+            { "id": 2, "username": "bob", "name": "Bob", "state": "active", "access_level": 40 },
+            { "id": 3, "username": "carol", "name": "Carol", "state": "active", "access_level": 50 },
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path_includes("/api/v4/users/");
+        then.status(200).json_body(serde_json::json!({ "id": 1 }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "audit-members",
+        "--approved-group",
+        "platform",
+        "--format",
+        "json",
+    ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("1 membership(s) flagged"),
+        "{}",
+        stderr(&output)
+    );
+    let members: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let flags: Vec<_> = members
+        .iter()
+        .map(|member| {
+            (
+                member["username"].as_str().unwrap(),
+                member["flags"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        flags,
+        [
+            ("alice", ""),
+            ("bob", "maintainer outside the approved groups"),
+            ("carol", ""),
+        ]
+    );
+}
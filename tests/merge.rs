@@ -0,0 +1,175 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const URL: &str = "https://gitlab.example.com/group/project/-/merge_requests/7";
+
+fn mr(pipeline: &str) -> serde_json::Value {
+    serde_json::json!({
+        "iid": 7,
+        "title": "Fix the login",
+        "state": "opened",
+        "sha": "abc123",
+        "source_branch": "fix-login",
+        "web_url": URL,
+        "blocking_discussions_resolved": true,
+        "head_pipeline": { "status": pipeline },
+    })
+}
+
+fn approvals(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7/approvals");
+        then.status(200)
+            .json_body(serde_json::json!({ "approved": true, "approvals_left": 0 }));
+    });
+}
+
+#[test]
+fn waits_for_the_pipeline_then_merges_as_configured() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    approvals(&server);
+    let mut running = server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7");
+        then.status(200).json_body(mr("running"));
+    });
+    let merge = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/7/merge")
+            .form_urlencoded_tuple("sha", "abc123")
+            .form_urlencoded_tuple("squash", "true")
+            .form_urlencoded_tuple("squash_commit_message", "Fix the login (!7)")
+            .form_urlencoded_tuple("should_remove_source_branch", "true");
+        then.status(200).json_body(serde_json::json!({ "iid": 7 }));
+    });
+
+    let config = temp_dir("merge").join("config.toml");
+    std::fs::write(
+        &config,
+        "[merge]\nsquash = true\nmessage = \"{{ title }} (!{{ iid }})\"\n",
+    )
+    .unwrap();
+
+    let mut command = helper(&server);
+    command.arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "merge-when-ready",
+        "--mr",
+        "7",
+        "--remove-source-branch",
+        "--poll",
+        "200ms",
+    ]);
+    let child = command
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    while running.calls() == 0 {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    running.delete();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7");
+        then.status(200).json_body(mr("success"));
+    });
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    merge.assert();
+}
+
+#[test]
+fn a_failed_pipeline_stops_the_wait() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    approvals(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7");
+        then.status(200).json_body(mr("failed"));
+    });
+    let merge = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/7/merge");
+        then.status(200);
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "merge-when-ready", "--mr", "7"]));
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("failed"), "{}", stderr(&output));
+    merge.assert_calls(0);
+}
+
+fn conventional(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7");
+        let mut mr = mr("success");
+        mr["title"] = "fix (ABC-12): Handle empty pages".into();
+        mr["source_branch"] = "fix/ABC-12-empty-pages".into();
+        mr["description"] = "Also closes abc-40 and OTHER-3.".into();
+        then.status(200).json_body(mr);
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7/approvals");
+        then.status(200).json_body(serde_json::json!({
+            "approved": true,
+            "approvals_left": 0,
+            "approved_by": [
+                { "user": { "name": "Alice", "username": "alice" } },
+                { "user": { "name": "Bob", "username": "bob" } },
+            ],
+        }));
+    });
+}
+
+const MESSAGE: &str = "fix (ABC-12): Handle empty pages
+
+Jira: ABC-12, ABC-40
+Approved-by: Alice (@alice)
+Approved-by: Bob (@bob)
+Merge-request: !7 https://gitlab.example.com/group/project/-/merge_requests/7";
+
+#[test]
+fn the_merge_message_has_the_title_jira_ids_and_approvers() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    conventional(&server);
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "build-merge-message", "--mr", "7"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{MESSAGE}\n")
+    );
+}
+
+#[test]
+fn merges_use_the_built_message_by_default() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    conventional(&server);
+    let merge = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/7/merge")
+            .form_urlencoded_tuple("merge_commit_message", MESSAGE);
+        then.status(200).json_body(serde_json::json!({ "iid": 7 }));
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "merge-when-ready", "--mr", "7"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    merge.assert();
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, offline, run, stderr, temp_dir, PROJECT};
+
+const RULES: &str = r#"
+[[mr_rules]]
+name = "migrations"
+paths = ["migrations/"]
+require_changes = ["CHANGELOG.md"]
+require_labels = ["db"]
+"#;
+
+fn merge_request(server: &MockServer, labels: serde_json::Value, paths: &[&str]) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/5");
+        then.status(200).json_body(serde_json::json!({
+            "iid": 5,
+            "title": "feat(PROJ-3): Add the orders table",
+            "labels": labels,
+        }));
+    });
+    let diffs: Vec<_> = paths
+        .iter()
+        .map(|path| serde_json::json!({ "old_path": path, "new_path": path }))
+        .collect();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/5/diffs");
+        then.status(200).json_body(serde_json::json!(diffs));
+    });
+}
+
+fn check_mr(server: &MockServer) -> std::process::Output {
+    let config = temp_dir("mr-rules").join("config.toml");
+    std::fs::write(&config, RULES).unwrap();
+    run(helper(server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "check-mr",
+        "--mr",
+        "5",
+    ]))
+}
+
+#[test]
+fn a_migration_without_its_changelog_and_label_fails() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    merge_request(&server, serde_json::json!([]), &["migrations/0002.sql"]);
+
+    let output = check_mr(&server);
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("needs a change to CHANGELOG.md"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("needs the ~db label"), "{stderr}");
+}
+
+#[test]
+fn a_complete_migration_passes() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    merge_request(
+        &server,
+        serde_json::json!(["db"]),
+        &["migrations/0002.sql", "CHANGELOG.md"],
+    );
+
+    let output = check_mr(&server);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn the_checks_go_into_the_junit_report() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    merge_request(&server, serde_json::json!([]), &["migrations/0002.sql"]);
+    let dir = temp_dir("mr-rules-junit");
+    let config = dir.join("config.toml");
+    std::fs::write(&config, RULES).unwrap();
+    let report = dir.join("check-mr.xml");
+
+    let output = run(helper(&server)
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "check-mr", "--mr", "5", "--junit"])
+        .arg(&report));
+
+    assert!(!output.status.success());
+    let report = std::fs::read_to_string(&report).unwrap();
+    assert!(
+        report.contains("<testsuite name=\"check-mr\" tests=\"2\" failures=\"1\">"),
+        "{report}"
+    );
+    assert!(report.contains("<testcase classname=\"check-mr\" name=\"title\"/>"));
+    assert!(report.contains(
+        "migrations: needs a change to CHANGELOG.md\nmigrations: needs the ~db label</failure>"
+    ));
+}
+
+#[test]
+fn lint_title_reports_the_title_without_the_api() {
+    let report = temp_dir("lint-title-junit").join("lint-title.xml");
+
+    let output = run(offline()
+        .env("CI_MERGE_REQUEST_TITLE", "Fix the login")
+        .args(["lint-title", "--junit"])
+        .arg(&report));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("the title breaks the naming convention"),
+        "{}",
+        stderr(&output)
+    );
+    let report = std::fs::read_to_string(&report).unwrap();
+    assert!(
+        report.contains("<testsuite name=\"lint-title\" tests=\"1\" failures=\"1\">"),
+        "{report}"
+    );
+
+    let output = run(offline().args(["lint-title", "--title", "fix (JIRA-12): Fix the login"]));
+    assert!(output.status.success(), "{}", stderr(&output));
+}
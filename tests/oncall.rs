@@ -0,0 +1,70 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{fixture, helper, mount, run, stderr, temp_dir, PROJECT};
+
+#[test]
+fn the_emergency_patch_goes_to_whoever_pagerduty_has_on_call() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(
+        &server,
+        "GET_projects_42_repository_branches__regex_release_2F_5Cd_2B_5C._5Cd_2B_5C._5Cd_2B",
+    );
+    let oncalls = server.mock(|when, then| {
+        when.method(GET)
+            .path("/pagerduty/oncalls")
+            .header("Authorization", "Token token=pd-key")
+            .query_param("schedule_ids[]", "PSCHED1");
+        then.status(200).json_body(serde_json::json!({
+            "oncalls": [{ "user": { "email": "bob@example.com" } }],
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/users")
+            .query_param("username", "bob");
+        then.status(200)
+            .json_body(serde_json::json!([{ "id": 31, "username": "bob" }]));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(fixture("POST_projects_42_repository_branches").body);
+    });
+    let assigned = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("assignee_id", "31");
+        then.status(201)
+            .json_body(fixture("POST_projects_42_merge_requests").body);
+    });
+
+    let config = temp_dir("oncall").join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            r#"
+[oncall]
+provider = "pagerduty"
+schedule = "PSCHED1"
+api_url = "{}/pagerduty"
+users = {{ "bob@example.com" = "bob" }}
+"#,
+            server.base_url()
+        ),
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("PAGERDUTY_API_TOKEN", "pd-key")
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    oncalls.assert();
+    assigned.assert_calls(2);
+}
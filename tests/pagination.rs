@@ -0,0 +1,71 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr};
+
+/// GitLab's default page size, which the client asks for explicitly.
+const PER_PAGE: usize = 100;
+
+fn projects(range: std::ops::Range<usize>) -> serde_json::Value {
+    range
+        .map(|index| {
+            serde_json::json!({
+                "id": 1000 + index,
+                "path_with_namespace": format!("sandbox/service-{index}"),
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn follows_every_page_of_group_projects() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let first = server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/groups/sandbox/projects")
+            .query_param("include_subgroups", "true")
+            .query_param("archived", "false")
+            .query_param("page", "1");
+        then.status(200)
+            .header("x-next-page", "2")
+            .json_body(projects(0..PER_PAGE));
+    });
+    let second = server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/groups/sandbox/projects")
+            .query_param("page", "2");
+        then.status(200).json_body(projects(PER_PAGE..PER_PAGE + 3));
+    });
+    let lookups = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(r"^/api/v4/projects/sandbox%2Fservice-\d+/variables/DEPLOY_TARGET$");
+        then.status(404)
+            .json_body(serde_json::json!({ "message": "404 Variable Not Found" }));
+    });
+    let creates = server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(r"^/api/v4/projects/sandbox%2Fservice-\d+/variables$")
+            .form_urlencoded_tuple("key", "DEPLOY_TARGET");
+        then.status(201)
+            .json_body(serde_json::json!({ "key": "DEPLOY_TARGET", "value": "eu-west" }));
+    });
+
+    let output = run(helper(&server).args([
+        "--group",
+        "sandbox",
+        "variables",
+        "set",
+        "DEPLOY_TARGET",
+        "eu-west",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    first.assert();
+    second.assert();
+    lookups.assert_calls(PER_PAGE + 3);
+    creates.assert_calls(PER_PAGE + 3);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("sandbox/service-102"), "{stdout}");
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir};
+
+#[test]
+fn the_user_config_applies_outside_a_checkout() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let status = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/statuses/abc123");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+    let home = temp_dir("config-home");
+    std::fs::create_dir_all(home.join("gitlab-helper")).unwrap();
+    std::fs::write(
+        home.join("gitlab-helper").join("config.toml"),
+        "projects = [\"42\"]\n",
+    )
+    .unwrap();
+
+    let output = run(helper(&server).env("XDG_CONFIG_HOME", &home).args([
+        "set-status",
+        "--sha",
+        "abc123",
+        "--name",
+        "lint",
+        "--state",
+        "success",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    status.assert();
+}
+
+#[test]
+fn a_profile_picks_its_own_projects() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let status = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/statuses/abc123");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+    let config = temp_dir("profile").join("config.toml");
+    std::fs::write(
+        &config,
+        "projects = [\"7\"]\n\n[profile.staging]\nprojects = [\"42\"]\n",
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("GLCH_PROFILE", "staging")
+        .arg("--config")
+        .arg(&config)
+        .args([
+            "set-status",
+            "--sha",
+            "abc123",
+            "--name",
+            "lint",
+            "--state",
+            "success",
+        ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    status.assert();
+}
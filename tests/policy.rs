@@ -0,0 +1,189 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const POLICY: &str = "[policy]\nprotected_ref = true\nusers = [\"alice\"]\n";
+
+#[test]
+fn a_feature_branch_pipeline_cannot_cut_a_patch() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let branches = server.mock(|when, then| {
+        when.path_includes("/repository/branches");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let config = temp_dir("policy").join("config.toml");
+    std::fs::write(&config, POLICY).unwrap();
+
+    let output = run(helper(&server)
+        .env("CI_COMMIT_REF_NAME", "feature/login")
+        .env("CI_COMMIT_REF_PROTECTED", "false")
+        .env("GITLAB_USER_LOGIN", "mallory")
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains(
+            "feature/login is not a protected branch or tag; @mallory is not one of the allowed users"
+        ),
+        "{}",
+        stderr(&output)
+    );
+    branches.assert_calls(0);
+}
+
+#[test]
+fn an_allowed_user_on_a_protected_branch_may_run_workflows() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(serde_json::json!({ "name": "hotfix" }));
+    });
+    let dir = temp_dir("policy-ok");
+    let config = dir.join("config.toml");
+    std::fs::write(&config, POLICY).unwrap();
+    let workflows = dir.join("workflows.toml");
+    std::fs::write(
+        &workflows,
+        "[workflows.hotfix]\nsteps = [{ step = \"create-branch\", branch = \"hotfix\", ref = \"master\" }]\n",
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("CI_COMMIT_REF_NAME", "master")
+        .env("CI_COMMIT_REF_PROTECTED", "true")
+        .env("GITLAB_USER_LOGIN", "alice")
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "run", "hotfix", "--file"])
+        .arg(&workflows));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    branch.assert();
+}
+
+#[test]
+fn an_emergency_patch_waits_for_the_approval_variable() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let branches = server.mock(|when, then| {
+        when.path_includes("/repository/branches");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+
+    let output = run(helper(&server).env("PATCH_APPROVAL", "not-yet").args([
+        "--project",
+        PROJECT,
+        "--require-approval",
+        "PATCH_APPROVAL=approved-by-second-engineer",
+        "emergency-patch",
+    ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("$PATCH_APPROVAL does not hold the approval"),
+        "{}",
+        stderr(&output)
+    );
+    branches.assert_calls(0);
+}
+
+#[test]
+fn the_approval_variable_lets_the_workflow_run() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(serde_json::json!({ "name": "hotfix" }));
+    });
+    let workflows = temp_dir("approval").join("workflows.toml");
+    std::fs::write(
+        &workflows,
+        "[workflows.hotfix]\nsteps = [{ step = \"create-branch\", branch = \"hotfix\", ref = \"master\" }]\n",
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("PATCH_APPROVAL", "approved-by-second-engineer")
+        .env(
+            "GITLAB_HELPER_REQUIRE_APPROVAL",
+            "PATCH_APPROVAL=approved-by-second-engineer",
+        )
+        .args(["--project", PROJECT, "run", "hotfix", "--file"])
+        .arg(&workflows));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    branch.assert();
+}
+
+#[test]
+fn promoting_a_release_candidate_waits_for_the_approval_variable() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let repository = server.mock(|when, then| {
+        when.path_includes("/repository/");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "--require-approval",
+        "PROMOTE_APPROVAL=approved",
+        "promote-rc",
+        "--rc",
+        "release/1.4.0-rc.2",
+    ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("refusing to run promote-rc: $PROMOTE_APPROVAL is not set"),
+        "{}",
+        stderr(&output)
+    );
+    repository.assert_calls(0);
+}
+
+#[test]
+fn a_feature_flag_is_not_flipped_from_a_feature_branch() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let flags = server.mock(|when, then| {
+        when.path_includes("/feature_flags");
+        then.status(200).json_body(serde_json::json!({}));
+    });
+    let config = temp_dir("policy-flag").join("config.toml");
+    std::fs::write(&config, POLICY).unwrap();
+
+    let output = run(helper(&server)
+        .env("CI_COMMIT_REF_NAME", "feature/login")
+        .env("CI_COMMIT_REF_PROTECTED", "false")
+        .env("GITLAB_USER_LOGIN", "alice")
+        .arg("--config")
+        .arg(&config)
+        .args([
+            "--project",
+            PROJECT,
+            "feature-flag",
+            "disable",
+            "--name",
+            "kill_switch_x",
+        ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("refusing to run feature-flag as [policy] says"),
+        "{}",
+        stderr(&output)
+    );
+    flags.assert_calls(0);
+}
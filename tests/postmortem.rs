@@ -0,0 +1,147 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+fn incident(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/issues/31");
+        then.status(200).json_body(serde_json::json!({
+            "iid": 31,
+            "title": "[S1] Checkout is down",
+            "web_url": "https://gitlab.example.com/group/project/-/issues/31",
+            "created_at": "2024-05-01T10:00:00Z",
+            "closed_at": "2024-05-01T11:30:00Z",
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/issues/31/related_merge_requests");
+        then.status(200)
+            .json_body(serde_json::json!([{ "iid": 7 }]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7");
+        then.status(200).json_body(serde_json::json!({
+            "iid": 7,
+            "title": "EMERGENCY PRODUCTION PATCH (release/1.3.0)",
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/7",
+            "target_branch": "master",
+            "created_at": "2024-05-01T10:20:00Z",
+            "merged_at": "2024-05-01T11:00:00Z",
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7/pipelines");
+        then.status(200).json_body(serde_json::json!([{
+            "id": 900,
+            "status": "success",
+            "web_url": "https://gitlab.example.com/group/project/-/pipelines/900",
+            "updated_at": "2024-05-01T10:45:00Z",
+        }]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/deployments")
+            .query_param("updated_after", "2024-05-01T10:00:00Z");
+        then.status(200).json_body(serde_json::json!([{
+            "id": 55,
+            "status": "success",
+            "environment": { "name": "production" },
+            "updated_at": "2024-05-01T11:10:00Z",
+        }]));
+    });
+}
+
+#[test]
+fn the_timeline_runs_from_the_branch_to_the_deployment() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    incident(&server);
+    let journal = temp_dir("postmortem").join("journal.json");
+    std::fs::write(
+        &journal,
+        serde_json::json!({
+            "command": "emergency-patch",
+            "entries": [{
+                "project": "42",
+                "step": "create branch release/1.3.1",
+                // 2024-05-01T10:15:00Z
+                "at": 1714558500,
+                "created": { "kind": "branch", "name": "release/1.3.1" },
+            }],
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .args([
+            "--project",
+            PROJECT,
+            "postmortem",
+            "scaffold",
+            "--incident",
+            "31",
+            "--journal",
+        ])
+        .arg(&journal));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let document = String::from_utf8_lossy(&output.stdout);
+    let timeline: Vec<_> = document
+        .lines()
+        .filter(|line| line.starts_with("| 2024"))
+        .collect();
+    assert_eq!(
+        timeline,
+        [
+            "| 2024-05-01 10:00:00 | Incident [#31](https://gitlab.example.com/group/project/-/issues/31) opened |",
+            "| 2024-05-01 10:15:00 | Helper: create branch release/1.3.1 |",
+            "| 2024-05-01 10:20:00 | [!7](https://gitlab.example.com/group/project/-/merge_requests/7) opened into `master`: EMERGENCY PRODUCTION PATCH (release/1.3.0) |",
+            "| 2024-05-01 10:45:00 | Pipeline [#900](https://gitlab.example.com/group/project/-/pipelines/900) of !7: success |",
+            "| 2024-05-01 11:00:00 | !7 merged |",
+            "| 2024-05-01 11:10:00 | Deployment #55 to production: success |",
+            "| 2024-05-01 11:30:00 | Incident #31 closed |",
+        ]
+    );
+    assert!(document.contains("- Resolved: 2024-05-01 11:30 UTC (after 1h 30m)"));
+    assert!(document.contains("## Action items"));
+}
+
+#[test]
+fn a_new_wiki_page_is_created() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    incident(&server);
+    let edit = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/wikis/postmortems%2Fincident-31");
+        then.status(404)
+            .json_body(serde_json::json!({ "message": "404 Wiki Page Not Found" }));
+    });
+    let create = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/wikis")
+            .form_urlencoded_tuple("title", "postmortems/incident-31");
+        then.status(201)
+            .json_body(serde_json::json!({ "slug": "postmortems/incident-31" }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "postmortem",
+        "scaffold",
+        "--incident",
+        "31",
+        "--wiki",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    edit.assert();
+    create.assert();
+}
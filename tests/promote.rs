@@ -0,0 +1,132 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+const SHA: &str = "abc1234567890abc1234567890abc1234567890a";
+
+fn not_found(server: &MockServer, path: &str) {
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/api/v4/projects/42/{path}"));
+        then.status(404)
+            .json_body(serde_json::json!({ "message": "404 Not Found" }));
+    });
+}
+
+fn rc_branch(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/branches/release%2F1.4.0-rc.2");
+        then.status(200).json_body(
+            serde_json::json!({ "name": "release/1.4.0-rc.2", "commit": { "id": SHA } }),
+        );
+    });
+}
+
+#[test]
+fn the_release_candidate_becomes_the_release() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    rc_branch(&server);
+    not_found(&server, "repository/branches/release%2F1.4.0");
+    not_found(&server, "repository/tags/v1.4.0");
+    not_found(&server, "repository/branches/announce%2F1.4.0");
+    not_found(&server, "repository/files/announcements%2F1.4.0.md/raw");
+    let branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches")
+            .form_urlencoded_tuple("branch", "release/1.4.0")
+            .form_urlencoded_tuple("ref", SHA);
+        then.status(201).json_body(serde_json::json!({}));
+    });
+    let tag = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/tags")
+            .form_urlencoded_tuple("tag_name", "v1.4.0")
+            .form_urlencoded_tuple("ref", SHA);
+        then.status(201).json_body(serde_json::json!({}));
+    });
+    let pipeline = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/pipeline")
+            .body_includes("ref=v1.4.0")
+            .body_includes("DEPLOY");
+        then.status(201).json_body(serde_json::json!({
+            "id": 5, "web_url": "https://gitlab.example.com/p/-/pipelines/5",
+        }));
+    });
+    let commit = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits")
+            .form_urlencoded_tuple("branch", "announce/1.4.0")
+            .form_urlencoded_tuple("start_branch", "master");
+        then.status(201)
+            .json_body(serde_json::json!({ "id": "f00d" }));
+    });
+    let mr = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("source_branch", "announce/1.4.0")
+            .form_urlencoded_tuple("target_branch", "master");
+        then.status(201).json_body(serde_json::json!({
+            "iid": 8, "web_url": "https://gitlab.example.com/p/-/merge_requests/8",
+        }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "promote-rc",
+        "--rc",
+        "release/1.4.0-rc.2",
+        "--var",
+        "DEPLOY=production",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    for mock in [branch, tag, pipeline, commit, mr] {
+        mock.assert();
+    }
+}
+
+#[test]
+fn a_release_branch_with_other_commits_is_not_touched() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    rc_branch(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/branches/release%2F1.4.0");
+        then.status(200)
+            .json_body(serde_json::json!({ "name": "release/1.4.0", "commit": { "id": "d1ff" } }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", "release/1.4.0-rc.2")
+            .query_param("to", "release/1.4.0");
+        then.status(200)
+            .json_body(serde_json::json!({ "commits": [{ "id": "d1ff" }] }));
+    });
+    let writes = server.mock(|when, then| {
+        when.method(POST);
+        then.status(201);
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "promote-rc",
+        "--rc",
+        "release/1.4.0-rc.2",
+    ]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("release/1.4.0 has 1 commit(s)"),
+        "{}",
+        stderr(&output)
+    );
+    writes.assert_calls(0);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+fn behind(server: &MockServer, target: &str, source: &str, commits: usize) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", target)
+            .query_param("to", source);
+        let commits: Vec<_> = (0..commits)
+            .map(|i| serde_json::json!({ "id": format!("c{i}") }))
+            .collect();
+        then.status(200)
+            .json_body(serde_json::json!({ "commits": commits }));
+    });
+}
+
+fn open_mrs(server: &MockServer, source: &str, mrs: serde_json::Value) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests")
+            .query_param("source_branch", source);
+        then.status(200).json_body(mrs);
+    });
+}
+
+fn mr(server: &MockServer, iid: u64, has_conflicts: bool) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/api/v4/projects/42/merge_requests/{iid}"));
+        then.status(200).json_body(serde_json::json!({
+            "iid": iid,
+            "title": "Merge",
+            "state": "opened",
+            "sha": "abc123",
+            "source_branch": "release/1.3",
+            "web_url": format!("https://gitlab.example.com/p/-/merge_requests/{iid}"),
+            "blocking_discussions_resolved": true,
+            "has_conflicts": has_conflicts,
+            "detailed_merge_status": if has_conflicts { "conflict" } else { "mergeable" },
+            "head_pipeline": { "status": "success" },
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path(format!(
+            "/api/v4/projects/42/merge_requests/{iid}/approvals"
+        ));
+        then.status(200)
+            .json_body(serde_json::json!({ "approved": true, "approvals_left": 0 }));
+    });
+}
+
+#[test]
+fn merges_along_the_chain_and_stops_at_a_conflict() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    behind(&server, "release/1.3", "release/1.2.5", 2);
+    behind(&server, "release/1.4", "release/1.3", 0);
+    behind(&server, "dev", "release/1.4", 1);
+    open_mrs(&server, "release/1.2.5", serde_json::json!([]));
+    open_mrs(
+        &server,
+        "release/1.4",
+        serde_json::json!([{ "iid": 8, "web_url": "https://gitlab.example.com/p/-/merge_requests/8" }]),
+    );
+    let created = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("source_branch", "release/1.2.5")
+            .form_urlencoded_tuple("target_branch", "release/1.3")
+            .form_urlencoded_tuple("title", "Merge release/1.2.5 into release/1.3");
+        then.status(201).json_body(serde_json::json!({
+            "iid": 7, "web_url": "https://gitlab.example.com/p/-/merge_requests/7",
+        }));
+    });
+    mr(&server, 7, false);
+    mr(&server, 8, true);
+    let merged = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/7/merge")
+            .form_urlencoded_tuple("squash", "false")
+            .form_urlencoded_tuple("should_remove_source_branch", "false");
+        then.status(200).json_body(serde_json::json!({ "iid": 7 }));
+    });
+    let note = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests/8/notes")
+            .body_includes("git+merge+origin%2Frelease%2F1.4");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+    let not_merged = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/merge_requests/8/merge");
+        then.status(200);
+    });
+
+    let config = temp_dir("propagate").join("config.toml");
+    std::fs::write(
+        &config,
+        "[propagate]\nchain = [\"release/1.3\", \"release/1.4\", \"dev\"]\n",
+    )
+    .unwrap();
+    let output = run(helper(&server).arg("--config").arg(&config).args([
+        "--project",
+        PROJECT,
+        "propagate-fix",
+        "--from",
+        "release/1.2.5",
+    ]));
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("release/1.4 conflicts with dev")
+            && stderr.contains("after merging into release/1.3"),
+        "{stderr}"
+    );
+    created.assert();
+    merged.assert();
+    note.assert();
+    not_merged.assert_calls(0);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const PACKAGE: &str = "/api/v4/projects/42/packages/generic/provenance/v1.4.0";
+
+#[test]
+fn the_signed_provenance_is_attached_to_the_release() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let uploads = ["provenance.json", "provenance.json.sig"].map(|file| {
+        server.mock(|when, then| {
+            when.method(PUT).path(format!("{PACKAGE}/{file}"));
+            then.status(201)
+                .json_body(serde_json::json!({ "message": "201 Created" }));
+        })
+    });
+    let links = ["provenance.json", "provenance.json.sig"].map(|file| {
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v4/projects/42/releases/v1.4.0/assets/links")
+                .form_urlencoded_tuple("name", file)
+                .form_urlencoded_tuple("url", server.url(format!("{PACKAGE}/{file}")));
+            then.status(201).json_body(serde_json::json!({ "id": 1 }));
+        })
+    });
+
+    let dir = temp_dir("provenance");
+    std::fs::write(dir.join("app"), "abc").unwrap();
+    let config = dir.join("config.toml");
+    std::fs::write(
+        &config,
+        "[provenance]\nsign = [\"sh\", \"-c\", \"echo signed > {{ signature }}\"]\n",
+    )
+    .unwrap();
+    let output = run(helper(&server)
+        .env("CI_COMMIT_SHA", "abc123")
+        .env("CI_PIPELINE_ID", "4242")
+        .env("CI_JOB_ID", "9001")
+        .env("CI_RUNNER_DESCRIPTION", "shared-runner-1")
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "provenance", "--tag", "v1.4.0"])
+        .arg("--output")
+        .arg(dir.join("provenance.json"))
+        .arg(dir.join("app")));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let document: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("provenance.json")).unwrap()).unwrap();
+    assert_eq!(document["commit"], "abc123");
+    assert_eq!(document["pipeline"]["id"], "4242");
+    assert_eq!(document["runner"]["description"], "shared-runner-1");
+    assert_eq!(
+        document["artifacts"],
+        serde_json::json!([{
+            "name": "app",
+            "sha256": "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        }])
+    );
+    for mock in uploads.iter().chain(&links) {
+        mock.assert();
+    }
+}
+
+#[test]
+fn a_failed_signature_attaches_nothing() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let upload = server.mock(|when, then| {
+        when.method(PUT).path_includes("/packages/generic/");
+        then.status(201);
+    });
+
+    let dir = temp_dir("provenance");
+    std::fs::write(dir.join("app"), "abc").unwrap();
+    let config = dir.join("config.toml");
+    std::fs::write(
+        &config,
+        "[provenance]\nsign = [\"sh\", \"-c\", \"echo no key >&2; exit 1\"]\n",
+    )
+    .unwrap();
+    let output = run(helper(&server)
+        .env("CI_COMMIT_SHA", "abc123")
+        .env("CI_PIPELINE_ID", "4242")
+        .env("CI_JOB_ID", "9001")
+        .arg("--config")
+        .arg(&config)
+        .args(["--project", PROJECT, "provenance", "--tag", "v1.4.0"])
+        .arg("--output")
+        .arg(dir.join("provenance.json"))
+        .arg(dir.join("app")));
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("no key"), "{}", stderr(&output));
+    upload.assert_calls(0);
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn tag(server: &MockServer, name: &str, committed_date: &str) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/api/v4/projects/42/repository/tags/{name}"));
+        then.status(200)
+            .json_body(serde_json::json!({ "commit": { "committed_date": committed_date } }));
+    });
+}
+
+fn compare(server: &MockServer, from: &str, to: &str, commits: &[&str]) {
+    let commits: Vec<_> = commits
+        .iter()
+        .map(|id| serde_json::json!({ "id": id }))
+        .collect();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", from)
+            .query_param("to", to);
+        then.status(200)
+            .json_body(serde_json::json!({ "commits": commits, "diffs": [] }));
+    });
+}
+
+fn merged(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42");
+        then.status(200).json_body(serde_json::json!({
+            "path_with_namespace": "team/app",
+            "default_branch": "main",
+        }));
+    });
+    tag(server, "v1.3.0", "2024-05-01T10:00:00Z");
+    tag(server, "v1.4.0", "2024-06-01T10:00:00Z");
+    compare(server, "v1.3.0", "v1.4.0", &["7abc", "8abc", "9abc"]);
+    let mr = |iid: u64, title: &str, description: &str| {
+        serde_json::json!({
+            "iid": iid.to_string(),
+            "title": title,
+            "description": description,
+            "webUrl": format!("https://gitlab.example.com/team/app/-/merge_requests/{iid}"),
+            "labels": { "nodes": [] },
+            "mergedAt": "2024-05-10T12:00:00Z",
+            "squashCommitSha": format!("{iid}abc"),
+        })
+    };
+    server.mock(|when, then| {
+        when.method(POST).path("/api/graphql");
+        then.status(200)
+            .json_body(serde_json::json!({ "data": { "project": {
+            "mergeRequests": {
+                "nodes": [
+                    mr(7, "feat(PROJ-1): Export to CSV", "BREAKING CHANGE: the XLS export is gone"),
+                    mr(8, "fix(PROJ-2): Handle empty tags", ""),
+                    mr(9, "Bump dependencies", ""),
+                ],
+                "pageInfo": { "hasNextPage": false, "endCursor": null },
+            },
+        } } }));
+    });
+}
+
+fn release_notes(server: &MockServer, format: &str) -> String {
+    let output = run(helper(server).args([
+        "--project",
+        PROJECT,
+        "generate-release-notes",
+        "--from",
+        "v1.3.0",
+        "--to",
+        "v1.4.0",
+        "--format",
+        format,
+    ]));
+    assert!(output.status.success(), "{}", stderr(&output));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn writes_the_angular_preset_markdown() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    merged(&server);
+
+    assert_eq!(
+        release_notes(&server, "conventional"),
+        "## 1.4.0 (2024-06-01)\n\n\n\
+         ### ⚠ BREAKING CHANGES\n\n\
+         * the XLS export is gone\n\n\n\
+         ### Features\n\n\
+         * **PROJ-1:** Export to CSV ([!7](https://gitlab.example.com/team/app/-/merge_requests/7))\n\n\n\
+         ### Bug Fixes\n\n\
+         * **PROJ-2:** Handle empty tags ([!8](https://gitlab.example.com/team/app/-/merge_requests/8))\n"
+    );
+}
+
+#[test]
+fn writes_the_conventional_changelog_writer_context() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    merged(&server);
+
+    let context: serde_json::Value =
+        serde_json::from_str(&release_notes(&server, "conventional-json")).unwrap();
+    assert_eq!(context["version"], "1.4.0");
+    assert_eq!(context["date"], "2024-06-01");
+    assert_eq!(context["commitGroups"][0]["title"], "Features");
+    let feature = &context["commitGroups"][0]["commits"][0];
+    assert_eq!(feature["header"], "feat(PROJ-1): Export to CSV");
+    assert_eq!(feature["hash"], "7abc");
+    assert_eq!(context["commitGroups"][1]["commits"][0]["scope"], "PROJ-2");
+    assert_eq!(
+        context["noteGroups"][0]["notes"][0]["text"],
+        "the XLS export is gone"
+    );
+}
+
+#[test]
+fn the_merge_requests_are_those_in_the_tag_range() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42");
+        then.status(200).json_body(serde_json::json!({
+            "path_with_namespace": "team/app",
+            "default_branch": "main",
+        }));
+    });
+    tag(&server, "v1.3.0", "2024-05-01T10:00:00Z");
+    tag(&server, "v1.4.0", "2024-06-01T10:00:00Z");
+    compare(&server, "v1.3.0", "v1.4.0", &["def456"]);
+    let mr = |iid: u64, merged_at: &str, merge_commit_sha: &str| {
+        serde_json::json!({
+            "iid": iid.to_string(),
+            "title": format!("fix(PROJ-{iid}): Fix number {iid}"),
+            "webUrl": format!("https://gitlab.example.com/team/app/-/merge_requests/{iid}"),
+            "labels": { "nodes": [] },
+            "mergedAt": merged_at,
+            "mergeCommitSha": merge_commit_sha,
+        })
+    };
+    let merged = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/graphql")
+            .body_includes(r#""after":"2024-05-01T10:00:00Z""#)
+            .body_includes(r#""before":"2024-06-02T10:00:00Z""#);
+        then.status(200)
+            .json_body(serde_json::json!({ "data": { "project": {
+            "mergeRequests": {
+                "nodes": [
+                    // Merged a moment after v1.3.0 was cut from its merge commit.
+                    mr(6, "2024-05-01T10:00:02Z", "abc123"),
+                    // v1.4.0 points at the merge commit, a moment before the merge.
+                    mr(7, "2024-06-01T10:00:02Z", "def456"),
+                    mr(8, "2024-06-01T12:00:00Z", "0ff0ff"),
+                ],
+                "pageInfo": { "hasNextPage": false, "endCursor": null },
+            },
+        } } }));
+    });
+
+    let notes = release_notes(&server, "markdown");
+
+    merged.assert();
+    assert!(notes.contains("[!7]"), "{notes}");
+    assert!(!notes.contains("[!6]"), "{notes}");
+    assert!(!notes.contains("[!8]"), "{notes}");
+}
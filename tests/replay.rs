@@ -0,0 +1,106 @@
+mod common;
+
+use common::{fixture_dir, offline, run, stderr, PROJECT};
+
+#[test]
+fn rehearses_an_emergency_patch_without_gitlab() {
+    let journal = common::temp_dir("replay").join("journal.json");
+
+    let output = run(offline()
+        .args(["--project", PROJECT, "--replay"])
+        .arg(fixture_dir())
+        .arg("--journal")
+        .arg(&journal)
+        .arg("emergency-patch"));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("release/1.3.1 from release/1.3.0"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("replay: not sending POST projects/42/merge_requests"),
+        "{stderr}"
+    );
+    let journal = std::fs::read_to_string(&journal).unwrap();
+    assert!(
+        journal.contains("https://gitlab.example.com/sandbox/helper/-/merge_requests/17"),
+        "{journal}"
+    );
+}
+
+#[test]
+fn unrecorded_changes_are_stubbed() {
+    let dir = common::temp_dir("stub");
+    std::fs::copy(
+        fixture_dir().join("GET_user.json"),
+        dir.join("GET_user.json"),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("GET_projects_42_variables_DEPLOY_TARGET.json"),
+        r#"{ "method": "GET", "path": "projects/42/variables/DEPLOY_TARGET", "status": 404, "body": { "message": "404 Variable Not Found" } }"#,
+    )
+    .unwrap();
+
+    let output = run(offline()
+        .args(["--project", PROJECT, "--replay"])
+        .arg(&dir)
+        .args(["variables", "set", "DEPLOY_TARGET", "eu-west"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("replay: not sending POST projects/42/variables"));
+}
+
+#[test]
+fn unrecorded_reads_fail_loudly() {
+    let dir = common::temp_dir("missing");
+    std::fs::copy(
+        fixture_dir().join("GET_user.json"),
+        dir.join("GET_user.json"),
+    )
+    .unwrap();
+
+    let output = run(offline()
+        .args(["--project", PROJECT, "--replay"])
+        .arg(&dir)
+        .arg("emergency-patch"));
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("no fixture recorded for GET projects/42/repository/branches"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn notifications_are_not_sent() {
+    let dir = common::temp_dir("notify");
+    std::fs::copy(
+        fixture_dir().join("GET_user.json"),
+        dir.join("GET_user.json"),
+    )
+    .unwrap();
+    let workflows = dir.join("workflows.toml");
+    std::fs::write(
+        &workflows,
+        r#"
+[workflows.announce]
+steps = [{ step = "notify", message = "rehearsal" }]
+"#,
+    )
+    .unwrap();
+
+    // Nothing listens there, so a sent notification would fail the run.
+    let output = run(offline()
+        .env("NOTIFY_WEBHOOK_URL", "http://127.0.0.1:9/hook")
+        .args(["--project", PROJECT, "--replay"])
+        .arg(&dir)
+        .args(["run", "announce", "--file"])
+        .arg(&workflows));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("replay: not sending the notification \"rehearsal\""));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+#[test]
+fn reads_are_retried_on_server_errors() {
+    let server = MockServer::start();
+    let user = server.mock(|when, then| {
+        when.method(GET).path("/api/v4/user");
+        then.status(503);
+    });
+
+    let output =
+        run(helper(&server).args(["--retries", "2", "--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    user.assert_calls(3);
+    assert!(stderr(&output).contains("retrying"));
+}
+
+#[test]
+fn retry_after_is_honoured_for_rate_limits() {
+    let server = MockServer::start();
+    let user = server.mock(|when, then| {
+        when.method(GET).path("/api/v4/user");
+        then.status(429).header("retry-after", "0");
+    });
+
+    let output =
+        run(helper(&server).args(["--retries", "1", "--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    user.assert_calls(2);
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let server = MockServer::start();
+    let user = server.mock(|when, then| {
+        when.method(GET).path("/api/v4/user");
+        then.status(403);
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    user.assert_calls(1);
+}
+
+#[test]
+fn mutations_are_never_retried() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(
+        &server,
+        "GET_projects_42_repository_branches__regex_release_2F_5Cd_2B_5C._5Cd_2B_5C._5Cd_2B",
+    );
+    let create_branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(502);
+    });
+    let merge_requests = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/merge_requests");
+        then.status(502);
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "emergency-patch"]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("failed to create branch release/1.3.1"),
+        "{}",
+        stderr(&output)
+    );
+    create_branch.assert_calls(1);
+    merge_requests.assert_calls(0);
+}
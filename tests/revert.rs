@@ -0,0 +1,149 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+const URL: &str = "https://gitlab.example.com/group/project/-/merge_requests/7";
+
+#[test]
+fn reverts_a_merged_merge_request_on_its_target() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7");
+        then.status(200).json_body(serde_json::json!({
+            "iid": 7,
+            "title": "Cache the login page",
+            "state": "merged",
+            "target_branch": "master",
+            "web_url": URL,
+            "merge_commit_sha": "abcdef1234567890",
+        }));
+    });
+    let branch = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches")
+            .form_urlencoded_tuple("branch", "revert/abcdef12-on-master")
+            .form_urlencoded_tuple("ref", "master");
+        then.status(201)
+            .json_body(serde_json::json!({ "name": "revert/abcdef12-on-master" }));
+    });
+    let revert = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits/abcdef1234567890/revert")
+            .form_urlencoded_tuple("branch", "revert/abcdef12-on-master");
+        then.status(201).json_body(serde_json::json!({
+            "id": "0123456789",
+            "short_id": "01234567",
+            "title": "Revert \"Cache the login page\"",
+            "web_url": "https://gitlab.example.com/group/project/-/commit/0123456789",
+        }));
+    });
+    let mr = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("source_branch", "revert/abcdef12-on-master")
+            .form_urlencoded_tuple("target_branch", "master")
+            .form_urlencoded_tuple("title", "Revert \"Cache the login page\"")
+            .body_includes("merge_requests%2F7");
+        then.status(201).json_body(serde_json::json!({
+            "iid": 8,
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/8",
+        }));
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "revert", "--mr", "7"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    branch.assert();
+    revert.assert();
+    mr.assert();
+}
+
+#[test]
+fn a_commit_needs_a_target() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+
+    let output =
+        run(helper(&server).args(["--project", PROJECT, "revert", "--commit", "abcdef12"]));
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("--target"), "{}", stderr(&output));
+}
+
+#[test]
+fn a_failing_target_does_not_stop_the_others() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/commits/abcdef12");
+        then.status(200).json_body(serde_json::json!({
+            "id": "abcdef1234567890",
+            "short_id": "abcdef12",
+            "title": "Cache the login page",
+            "web_url": "https://gitlab.example.com/group/project/-/commit/abcdef12",
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(serde_json::json!({ "name": "revert" }));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits/abcdef1234567890/revert")
+            .form_urlencoded_tuple("branch", "revert/abcdef12-on-release-1.2");
+        then.status(400).json_body(
+            serde_json::json!({ "message": "Sorry, we cannot revert this commit automatically." }),
+        );
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits/abcdef1234567890/revert")
+            .form_urlencoded_tuple("branch", "revert/abcdef12-on-master");
+        then.status(201).json_body(serde_json::json!({
+            "id": "0123456789",
+            "short_id": "01234567",
+            "title": "Revert",
+            "web_url": "https://gitlab.example.com/group/project/-/commit/0123456789",
+        }));
+    });
+    let to_master = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("target_branch", "master");
+        then.status(201).json_body(serde_json::json!({
+            "iid": 8,
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/8",
+        }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "--parallel",
+        "2",
+        "revert",
+        "--commit",
+        "abcdef12",
+        "--target",
+        "master",
+        "--target",
+        "release/1.2",
+    ]));
+
+    assert!(!output.status.success());
+    to_master.assert();
+    assert!(
+        stderr(&output).contains("1 of 2 targets failed"),
+        "{}",
+        stderr(&output)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("TARGET"), "{stdout}");
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+const GOOD: &str = "aaaa1111aaaa1111aaaa1111aaaa1111aaaa1111";
+const BAD: &str = "bbbb2222bbbb2222bbbb2222bbbb2222bbbb2222";
+
+fn deployments(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/deployments")
+            .query_param("environment", "production")
+            .query_param("status", "success");
+        then.status(200).json_body(serde_json::json!([
+            { "id": 3, "ref": "main", "sha": BAD, "deployable": { "id": 30, "name": "deploy" } },
+            { "id": 2, "ref": "main", "sha": BAD, "deployable": { "id": 20, "name": "deploy" } },
+            { "id": 1, "ref": "main", "sha": GOOD, "deployable": { "id": 10, "name": "deploy" } },
+        ]));
+    });
+}
+
+fn raw_file(server: &MockServer, path: &str, ref_: &str, content: &str) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/api/v4/projects/42/repository/files/{path}/raw"))
+            .query_param("ref", ref_);
+        then.status(200).body(content);
+    });
+}
+
+#[test]
+fn opens_a_merge_request_back_to_the_last_good_deployment() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    deployments(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/branches/main");
+        then.status(200)
+            .json_body(serde_json::json!({ "name": "main" }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/branches/rollback%2Fproduction-aaaa1111");
+        then.status(404)
+            .json_body(serde_json::json!({ "message": "404 Branch Not Found" }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/compare")
+            .query_param("from", GOOD)
+            .query_param("to", "main")
+            .query_param("straight", "true");
+        then.status(200).json_body(serde_json::json!({ "diffs": [
+            { "old_path": "src/app.rs", "new_path": "src/app.rs" },
+            { "old_path": "src/new.rs", "new_path": "src/new.rs", "new_file": true },
+        ] }));
+    });
+    raw_file(&server, "src%2Fapp.rs", GOOD, "good\n");
+    raw_file(&server, "src%2Fapp.rs", "main", "bad\n");
+    raw_file(&server, "src%2Fnew.rs", "main", "new\n");
+    let commit = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/commits")
+            .form_urlencoded_tuple("branch", "rollback/production-aaaa1111")
+            .form_urlencoded_tuple("start_branch", "main")
+            .body_includes(
+                "actions%5B%5D%5Baction%5D=delete&actions%5B%5D%5Bfile_path%5D=src%2Fnew.rs",
+            )
+            .body_includes(
+                "actions%5B%5D%5Baction%5D=update&actions%5B%5D%5Bfile_path%5D=src%2Fapp.rs",
+            );
+        then.status(201)
+            .json_body(serde_json::json!({ "id": "c0ffee" }));
+    });
+    let mr = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("source_branch", "rollback/production-aaaa1111")
+            .form_urlencoded_tuple("target_branch", "main")
+            .form_urlencoded_tuple("title", "Roll back production to main (aaaa1111)");
+        then.status(201).json_body(serde_json::json!({
+            "iid": 12, "web_url": "https://gitlab.example.com/p/-/merge_requests/12",
+        }));
+    });
+    let notified = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hook")
+            .body_includes("Rolling back production of 42 from `bbbb2222` to `main` (aaaa1111)")
+            .body_includes("merge_requests/12");
+        then.status(200);
+    });
+
+    let output = run(helper(&server)
+        .env("NOTIFY_WEBHOOK_URL", server.url("/hook"))
+        .args([
+            "--project",
+            PROJECT,
+            "rollback-deploy",
+            "--env",
+            "production",
+        ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    commit.assert();
+    mr.assert();
+    notified.assert();
+}
+
+#[test]
+fn redeploys_the_last_good_deployment() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    deployments(&server);
+    let retried = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/jobs/10/retry");
+        then.status(201).json_body(serde_json::json!({
+            "id": 11, "web_url": "https://gitlab.example.com/p/-/jobs/11",
+        }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "rollback-deploy",
+        "--env",
+        "production",
+        "--redeploy",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("jobs/11"), "{}", stderr(&output));
+    retried.assert();
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn runner(server: &MockServer, id: u64, details: serde_json::Value) {
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/api/v4/runners/{id}"));
+        then.status(200).json_body(details);
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/api/v4/runners/{id}/jobs"))
+            .query_param("status", "running");
+        then.status(200)
+            .json_body(serde_json::json!([{ "id": 100 + id }]));
+    });
+}
+
+#[test]
+fn flags_pending_jobs_whose_tags_no_runner_has() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/runners");
+        then.status(200)
+            .json_body(serde_json::json!([{ "id": 1 }, { "id": 2 }]));
+    });
+    runner(
+        &server,
+        1,
+        serde_json::json!({
+            "id": 1, "description": "docker-1", "runner_type": "project_type",
+            "status": "online", "run_untagged": true, "tag_list": ["docker"],
+        }),
+    );
+    runner(
+        &server,
+        2,
+        serde_json::json!({
+            "id": 2, "description": "gpu-1", "runner_type": "group_type",
+            "status": "offline", "tag_list": ["gpu"],
+        }),
+    );
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/jobs")
+            .query_param("scope[]", "pending");
+        then.status(200).json_body(serde_json::json!([
+            {
+                "id": 7, "name": "build", "tag_list": ["docker"],
+                "created_at": "2024-05-01T10:00:00Z",
+                "web_url": "https://gitlab.example.com/group/project/-/jobs/7",
+            },
+            {
+                "id": 8, "name": "train", "tag_list": ["gpu"],
+                "created_at": "2024-05-01T10:00:00Z",
+                "web_url": "https://gitlab.example.com/group/project/-/jobs/8",
+            },
+        ]));
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "runners", "status"]));
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("docker-1"), "{stdout}");
+    assert!(stdout.contains("train"), "{stdout}");
+    assert!(!stdout.contains("jobs/7"), "{stdout}");
+    assert!(
+        stderr(&output).contains("1 pending job(s)"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn mr_commits(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/5/commits");
+        then.status(200).json_body(serde_json::json!([
+            { "id": "aaa111", "short_id": "aaa", "title": "Signed", "author_name": "Alice", "author_email": "alice@example.com" },
+            { "id": "bbb222", "short_id": "bbb", "title": "Bump deps", "author_name": "renovate", "author_email": "bot@example.com" },
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/commits/aaa111/signature");
+        then.status(200).json_body(
+            serde_json::json!({ "signature_type": "SSH", "verification_status": "verified" }),
+        );
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/repository/commits/bbb222/signature");
+        then.status(404)
+            .json_body(serde_json::json!({ "message": "404 Signature Not Found" }));
+    });
+}
+
+#[test]
+fn an_unsigned_commit_fails_the_check() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mr_commits(&server);
+
+    let output = run(helper(&server).args(["--project", PROJECT, "check-signatures", "--mr", "5"]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("1 of 2 commit(s)"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn allowed_authors_need_no_signature() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mr_commits(&server);
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "check-signatures",
+        "--mr",
+        "5",
+        "--allow-author",
+        "bot@example.com",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+}
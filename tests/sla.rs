@@ -0,0 +1,92 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn open_emergency_mrs(server: &MockServer) {
+    let now = chrono::Utc::now();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests")
+            .query_param("state", "opened")
+            .query_param("labels", "emergency");
+        then.status(200).json_body(serde_json::json!([
+            {
+                "iid": 7,
+                "web_url": "https://gitlab.example.com/group/project/-/merge_requests/7",
+                "target_branch": "master",
+                "created_at": (now - chrono::Duration::hours(5)).to_rfc3339(),
+                "assignee": { "username": "alice" },
+            },
+            {
+                "iid": 8,
+                "web_url": "https://gitlab.example.com/group/project/-/merge_requests/8",
+                "target_branch": "dev",
+                "created_at": (now - chrono::Duration::hours(1)).to_rfc3339(),
+                "assignee": null,
+            },
+        ]));
+    });
+}
+
+#[test]
+fn an_overdue_merge_request_is_escalated_and_fails_the_check() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    open_emergency_mrs(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7/notes");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let escalate = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests/7/notes")
+            .body_includes("alice");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+    let on_time = server.mock(|when, then| {
+        when.path_includes("/merge_requests/8/notes");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "check-sla", "--sla", "4h"]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("1 emergency merge request(s) are open past the 4h SLA"),
+        "{}",
+        stderr(&output)
+    );
+    let table = String::from_utf8_lossy(&output.stdout);
+    assert!(table.contains("overdue by 1h"), "{table}");
+    assert!(
+        table.contains("due in 2h59m") || table.contains("due in 3h"),
+        "{table}"
+    );
+    escalate.assert();
+    on_time.assert_calls(0);
+}
+
+#[test]
+fn an_escalated_merge_request_is_not_escalated_again() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    open_emergency_mrs(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/42/merge_requests/7/notes");
+        then.status(200)
+            .json_body(serde_json::json!([{ "body": "SLA missed: open for longer than 4h" }]));
+    });
+    let escalate = server.mock(|when, then| {
+        when.method(POST).path_includes("/notes");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+
+    let output = run(helper(&server).args(["--project", PROJECT, "check-sla", "--sla", "4h"]));
+
+    assert!(!output.status.success());
+    escalate.assert_calls(0);
+}
@@ -0,0 +1,226 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const BRANCHES: &str =
+    "GET_projects_42_repository_branches__regex_release_2F_5Cd_2B_5C._5Cd_2B_5C._5Cd_2B";
+
+#[test]
+fn the_work_branch_starts_from_the_latest_release() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    let created = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches")
+            .form_urlencoded_tuple("branch", "fix/JIRA-55-handle-empty-pages")
+            .form_urlencoded_tuple("ref", "release/1.3.0");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "start-fix",
+        "--jira",
+        "JIRA-55",
+        "--summary",
+        "Handle empty pages",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    created.assert();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "git fetch origin fix/JIRA-55-handle-empty-pages\ngit checkout fix/JIRA-55-handle-empty-pages\n"
+    );
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .envs(IDENTITY)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {args:?}: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_owned()
+}
+
+const IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "Release Bot"),
+    ("GIT_AUTHOR_EMAIL", "bot@example.com"),
+    ("GIT_COMMITTER_NAME", "Release Bot"),
+    ("GIT_COMMITTER_EMAIL", "bot@example.com"),
+];
+
+/// A bare `origin` of `group/app` with `release/1.3.0`, and a clone of it.
+fn clone(name: &str) -> (PathBuf, PathBuf) {
+    let dir = temp_dir(name);
+    let (origin, clone) = (dir.join("group/app.git"), dir.join("clone"));
+    git(
+        &dir,
+        &["init", "--quiet", "--bare", origin.to_str().unwrap()],
+    );
+    git(
+        &dir,
+        &[
+            "clone",
+            "--quiet",
+            origin.to_str().unwrap(),
+            clone.to_str().unwrap(),
+        ],
+    );
+    std::fs::write(clone.join("app.txt"), "v1.3.0\n").unwrap();
+    git(&clone, &["add", "app.txt"]);
+    git(&clone, &["commit", "--quiet", "-m", "Release 1.3.0"]);
+    git(
+        &clone,
+        &["push", "--quiet", "origin", "HEAD:refs/heads/release/1.3.0"],
+    );
+    (origin, clone)
+}
+
+/// Project 42 as `path`, which the clone's `origin` is checked against.
+fn project_at<'a>(server: &'a MockServer, path: &str) -> httpmock::Mock<'a> {
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42");
+        then.status(200)
+            .json_body(serde_json::json!({ "id": 42, "path_with_namespace": path }));
+    })
+}
+
+#[test]
+fn with_local_git_the_branch_is_pushed_from_the_clone() {
+    let (origin, clone) = clone("start-fix-local");
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    project_at(&server, "group/app");
+    let api = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+
+    let output = run(helper(&server).current_dir(&clone).args([
+        "--project",
+        PROJECT,
+        "--local-git",
+        "start-fix",
+        "--jira",
+        "JIRA-55",
+        "--summary",
+        "Handle empty pages",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    api.assert_calls(0);
+    assert_eq!(
+        git(&origin, &["rev-parse", "fix/JIRA-55-handle-empty-pages"]),
+        git(&origin, &["rev-parse", "release/1.3.0"])
+    );
+}
+
+#[test]
+fn a_clone_of_another_project_is_not_pushed_to() {
+    let (origin, clone) = clone("start-fix-other");
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    project_at(&server, "group/other-app");
+    let api = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201).json_body(serde_json::json!({}));
+    });
+
+    let output = run(helper(&server).current_dir(&clone).args([
+        "--project",
+        PROJECT,
+        "--local-git",
+        "start-fix",
+        "--jira",
+        "JIRA-55",
+        "--summary",
+        "Handle empty pages",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    api.assert();
+    assert!(
+        git(&origin, &["branch", "--list", "fix/*"]).is_empty(),
+        "the branch was pushed to the clone's origin"
+    );
+    assert!(
+        stderr(&output).contains("not group/other-app; going through the API"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn with_local_git_a_conflicting_pick_names_the_files() {
+    let (_, clone) = clone("pick-local");
+    std::fs::write(clone.join("app.txt"), "v1.3.0\nfixed on dev\n").unwrap();
+    git(&clone, &["commit", "--quiet", "-am", "Fix on dev"]);
+    let fix = git(&clone, &["rev-parse", "HEAD"]);
+    git(
+        &clone,
+        &["push", "--quiet", "origin", "HEAD:refs/heads/dev"],
+    );
+    git(&clone, &["reset", "--quiet", "--hard", "HEAD~1"]);
+    std::fs::write(clone.join("app.txt"), "v1.3.0\nchanged on release\n").unwrap();
+    git(&clone, &["commit", "--quiet", "-am", "Change on release"]);
+    git(
+        &clone,
+        &["push", "--quiet", "origin", "HEAD:refs/heads/release/1.3.0"],
+    );
+
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    mount(&server, BRANCHES);
+    project_at(&server, "group/app");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_includes(format!("/repository/commits/{fix}"));
+        then.status(200).json_body(serde_json::json!({
+            "id": fix, "short_id": &fix[..8], "title": "Fix on dev",
+        }));
+    });
+    let merge_requests = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/merge_requests");
+        then.status(201)
+            .json_body(common::fixture("POST_projects_42_merge_requests").body);
+    });
+    let api_pick = server.mock(|when, then| {
+        when.method(POST).path_includes("/cherry_pick");
+        then.status(201);
+    });
+
+    let output = run(helper(&server).current_dir(&clone).args([
+        "--project",
+        PROJECT,
+        "--local-git",
+        "emergency-patch",
+        "--cherry-pick",
+        &fix,
+    ]));
+
+    assert!(!output.status.success());
+    api_pick.assert_calls(0);
+    merge_requests.assert_calls(0);
+    let stderr = stderr(&output);
+    assert!(stderr.contains("it conflicts in:"), "{stderr}");
+    assert!(stderr.contains("app.txt"), "{stderr}");
+}
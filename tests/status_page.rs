@@ -0,0 +1,141 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+const TITLE: &str = "Service disruption in 1.3.0";
+
+fn config(name: &str, contents: &str) -> std::path::PathBuf {
+    let path = temp_dir(name).join("config.toml");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn update(server: &MockServer, config: &std::path::Path, status: &str) -> std::process::Output {
+    run(helper(server)
+        .env("STATUSPAGE_API_KEY", "sp-key")
+        .arg("--config")
+        .arg(config)
+        .args([
+            "--project",
+            PROJECT,
+            "status-page",
+            "update",
+            "--affected-version",
+            "1.3.0",
+            "--version",
+            "1.3.1",
+            "--eta",
+            "20 minutes",
+            "--status",
+            status,
+        ]))
+}
+
+#[test]
+fn opens_a_status_issue_when_none_is_open() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v4/projects/ops%2Fstatus/issues")
+            .query_param("state", "opened");
+        then.status(200).json_body(serde_json::json!([]));
+    });
+    let create = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/ops%2Fstatus/issues")
+            .form_urlencoded_tuple("title", TITLE)
+            .form_urlencoded_tuple("labels", "incident,status::identified")
+            .body_includes("within+20+minutes");
+        then.status(201).json_body(serde_json::json!({
+            "iid": 4,
+            "title": TITLE,
+            "web_url": "https://gitlab.example.com/ops/status/-/issues/4",
+        }));
+    });
+
+    let config = config(
+        "status-page-new",
+        "[status_page]\nprovider = \"gitlab_issue\"\nproject = \"ops/status\"\n",
+    );
+    let output = update(&server, &config, "identified");
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    create.assert();
+}
+
+#[test]
+fn resolving_notes_and_closes_the_open_issue() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/issues");
+        then.status(200).json_body(serde_json::json!([{
+            "iid": 4,
+            "title": TITLE,
+            "web_url": "https://gitlab.example.com/group/project/-/issues/4",
+        }]));
+    });
+    let note = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/issues/4/notes")
+            .body_includes("resolved");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+    let close = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/issues/4")
+            .form_urlencoded_tuple("state_event", "close")
+            .form_urlencoded_tuple("add_labels", "status::resolved");
+        then.status(200).json_body(serde_json::json!({ "iid": 4 }));
+    });
+    let create = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/issues");
+        then.status(201);
+    });
+
+    let config = config(
+        "status-page-resolve",
+        "[status_page]\nprovider = \"gitlab_issue\"\n",
+    );
+    let output = update(&server, &config, "resolved");
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    note.assert();
+    close.assert();
+    create.assert_calls(0);
+}
+
+#[test]
+fn updates_the_unresolved_statuspage_incident() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/statuspage/pages/p1/incidents/unresolved")
+            .header("Authorization", "OAuth sp-key");
+        then.status(200)
+            .json_body(serde_json::json!([{ "id": "inc-1", "name": TITLE }]));
+    });
+    let patch = server.mock(|when, then| {
+        when.method(PATCH)
+            .path("/statuspage/pages/p1/incidents/inc-1")
+            .json_body_includes(r#"{ "incident": { "status": "monitoring" } }"#);
+        then.status(200)
+            .json_body(serde_json::json!({ "id": "inc-1" }));
+    });
+
+    let config = config(
+        "status-page-statuspage",
+        &format!(
+            "[status_page]\nprovider = \"statuspage\"\npage_id = \"p1\"\napi_url = \"{}/statuspage\"\n",
+            server.base_url()
+        ),
+    );
+    let output = update(&server, &config, "monitoring");
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    patch.assert();
+}
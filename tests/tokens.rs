@@ -0,0 +1,67 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+fn in_days(days: i64) -> String {
+    (chrono::Utc::now().date_naive() + chrono::Duration::days(days)).to_string()
+}
+
+fn project_tokens(server: &MockServer, tokens: serde_json::Value) {
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/personal_access_tokens/self");
+        then.status(200).json_body(serde_json::json!({
+            "name": "helper", "scopes": ["api"], "expires_at": in_days(200), "active": true,
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/access_tokens");
+        then.status(200).json_body(tokens);
+    });
+}
+
+#[test]
+fn a_token_expiring_soon_fails_the_audit() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    project_tokens(
+        &server,
+        serde_json::json!([
+            { "name": "release-bot", "scopes": ["api"], "expires_at": in_days(10), "active": true },
+            { "name": "old-bot", "scopes": ["api"], "expires_at": in_days(3), "active": false, "revoked": true },
+            { "name": "reader", "scopes": ["read_api"], "expires_at": null, "active": true },
+        ]),
+    );
+
+    let output = run(helper(&server).args(["--project", PROJECT, "audit-tokens"]));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("1 access token(s) expire within 30 days"),
+        "{}",
+        stderr(&output)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("release-bot") && stdout.contains("never"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("old-bot"), "{stdout}");
+}
+
+#[test]
+fn tokens_outside_the_window_pass() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    project_tokens(
+        &server,
+        serde_json::json!([
+            { "name": "release-bot", "scopes": ["api"], "expires_at": in_days(10), "active": true },
+        ]),
+    );
+
+    let output = run(helper(&server).args(["--project", PROJECT, "audit-tokens", "--within", "7"]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+}
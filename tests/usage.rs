@@ -0,0 +1,39 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, temp_dir, PROJECT};
+
+#[test]
+fn counts_runs_and_failures_of_each_command() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let mut status = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/statuses/abc123");
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+    let usage = temp_dir("usage").join("usage.json");
+    let set_status = || {
+        run(helper(&server).arg("--usage-file").arg(&usage).args([
+            "--project",
+            PROJECT,
+            "set-status",
+            "--sha",
+            "abc123",
+            "--name",
+            "lint",
+            "--state",
+            "success",
+        ]))
+    };
+
+    assert!(set_status().status.success());
+    status.delete();
+    assert!(!set_status().status.success());
+
+    let counts: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&usage).unwrap()).unwrap();
+    assert_eq!(counts["commands"]["set-status"]["runs"], 2);
+    assert_eq!(counts["commands"]["set-status"]["failures"], 1);
+}
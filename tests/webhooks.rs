@@ -0,0 +1,112 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, PROJECT};
+
+const BOT: &str = "https://bot.example.com/webhook";
+
+fn existing_hooks(server: &MockServer, hooks: serde_json::Value) -> httpmock::Mock<'_> {
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v4/projects/42/hooks");
+        then.status(200).json_body(hooks);
+    })
+}
+
+#[test]
+fn adds_a_missing_webhook() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    existing_hooks(&server, serde_json::json!([]));
+    let create = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/hooks")
+            .form_urlencoded_tuple("url", BOT)
+            .form_urlencoded_tuple("merge_requests_events", "true")
+            .form_urlencoded_tuple("note_events", "true")
+            .form_urlencoded_tuple("push_events", "false")
+            .form_urlencoded_tuple("token", "s3cret");
+        then.status(201)
+            .json_body(serde_json::json!({ "id": 3, "url": BOT }));
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "hooks",
+        "add",
+        BOT,
+        "--event",
+        "merge-requests,notes",
+        "--secret",
+        "s3cret",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    create.assert();
+}
+
+#[test]
+fn adding_again_edits_the_webhook_in_place() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    existing_hooks(
+        &server,
+        serde_json::json!([{ "id": 3, "url": BOT, "push_events": true, "note_events": true }]),
+    );
+    let edit = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v4/projects/42/hooks/3")
+            .form_urlencoded_tuple("push_events", "false")
+            .form_urlencoded_tuple("note_events", "true")
+            .form_urlencoded_tuple("merge_requests_events", "true");
+        then.status(200)
+            .json_body(serde_json::json!({ "id": 3, "url": BOT }));
+    });
+    let create = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/hooks");
+        then.status(201);
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "hooks",
+        "add",
+        BOT,
+        "--event",
+        "notes,merge-requests",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    edit.assert();
+    create.assert_calls(0);
+}
+
+#[test]
+fn an_up_to_date_webhook_is_left_alone() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    existing_hooks(
+        &server,
+        serde_json::json!([{ "id": 3, "url": BOT, "note_events": true }]),
+    );
+    let edit = server.mock(|when, then| {
+        when.method(PUT).path("/api/v4/projects/42/hooks/3");
+        then.status(200);
+    });
+
+    let output = run(helper(&server).args([
+        "--project",
+        PROJECT,
+        "hooks",
+        "add",
+        BOT,
+        "--event",
+        "notes",
+    ]));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    edit.assert_calls(0);
+    assert!(stderr(&output).contains("already set up"));
+}
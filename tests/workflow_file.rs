@@ -0,0 +1,180 @@
+mod common;
+
+use httpmock::prelude::*;
+
+use common::{helper, mount, run, stderr, temp_dir, PROJECT};
+
+#[test]
+fn a_failed_notification_does_not_stop_the_workflow() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/repository/branches");
+        then.status(201)
+            .json_body(serde_json::json!({ "name": "hotfix" }));
+    });
+    let hook = server.mock(|when, then| {
+        when.method(POST).path("/hook");
+        then.status(500);
+    });
+    let mr = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/merge_requests");
+        then.status(201).json_body(serde_json::json!({
+            "iid": 3,
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/3",
+        }));
+    });
+
+    let workflows = temp_dir("workflow-notify").join("workflows.toml");
+    std::fs::write(
+        &workflows,
+        r#"
+[workflows.hotfix]
+steps = [
+    { step = "create-branch", branch = "hotfix", ref = "master" },
+    { step = "notify", message = "hotfix started" },
+    { step = "create-mr", source = "hotfix", target = "master", title = "Hotfix" },
+]
+"#,
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("NOTIFY_WEBHOOK_URL", server.url("/hook"))
+        .args(["--project", PROJECT, "run", "hotfix", "--file"])
+        .arg(&workflows));
+
+    assert!(!output.status.success());
+    hook.assert();
+    mr.assert();
+    assert!(
+        stderr(&output).contains("1 of 3 steps of hotfix failed"),
+        "{}",
+        stderr(&output)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("send \"hotfix started\""), "{stdout}");
+    assert!(stdout.contains("failed"), "{stdout}");
+}
+
+#[test]
+fn templates_see_the_predefined_ci_variables() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let mr = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple(
+                "description",
+                "Opened by alice from https://gitlab.example.com/p/-/pipelines/7",
+            );
+        then.status(201).json_body(serde_json::json!({
+            "iid": 3,
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/3",
+        }));
+    });
+
+    let workflows = temp_dir("workflow-ci-vars").join("workflows.toml");
+    std::fs::write(
+        &workflows,
+        r#"
+[workflows.hotfix]
+steps = [
+    { step = "create-mr", source = "hotfix", target = "master", title = "Hotfix", description = "Opened by {{ ci.gitlab_user_login }} from {{ ci.pipeline_url }}" },
+]
+"#,
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("GITLAB_USER_LOGIN", "alice")
+        .env(
+            "CI_PIPELINE_URL",
+            "https://gitlab.example.com/p/-/pipelines/7",
+        )
+        .args(["--project", PROJECT, "run", "hotfix", "--file"])
+        .arg(&workflows));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    mr.assert();
+}
+
+#[test]
+fn the_job_token_is_not_a_template_variable() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let mr = server.mock(|when, then| {
+        when.method(POST).path("/api/v4/projects/42/merge_requests");
+        then.status(201);
+    });
+
+    let workflows = temp_dir("workflow-ci-token").join("workflows.toml");
+    std::fs::write(
+        &workflows,
+        r#"
+[workflows.leak]
+steps = [
+    { step = "create-mr", source = "a", target = "b", title = "{{ ci.job_token }}" },
+]
+"#,
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("CI_JOB_TOKEN", "secret-job-token")
+        .args(["--project", PROJECT, "run", "leak", "--file"])
+        .arg(&workflows));
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("undefined template variable(s): ci.job_token"),
+        "{}",
+        stderr(&output)
+    );
+    mr.assert_calls(0);
+}
+
+#[test]
+fn merge_requests_say_which_job_opened_them() {
+    let server = MockServer::start();
+    mount(&server, "GET_user");
+    let footer = "Created by job https://gitlab.example.com/p/-/jobs/9 triggered by @alice";
+    let mr = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests")
+            .form_urlencoded_tuple("description", format!("Fixes the login\n\n---\n\n{footer}"));
+        then.status(201).json_body(serde_json::json!({
+            "iid": 3,
+            "web_url": "https://gitlab.example.com/group/project/-/merge_requests/3",
+        }));
+    });
+    let note = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/v4/projects/42/merge_requests/3/notes")
+            .form_urlencoded_tuple("body", footer);
+        then.status(201).json_body(serde_json::json!({ "id": 1 }));
+    });
+
+    let workflows = temp_dir("workflow-origin").join("workflows.toml");
+    std::fs::write(
+        &workflows,
+        r#"
+[workflows.hotfix]
+steps = [
+    { step = "create-mr", source = "hotfix", target = "master", title = "Hotfix", description = "Fixes the login" },
+]
+"#,
+    )
+    .unwrap();
+
+    let output = run(helper(&server)
+        .env("CI_JOB_URL", "https://gitlab.example.com/p/-/jobs/9")
+        .env("GITLAB_USER_LOGIN", "alice")
+        .args(["--project", PROJECT, "run", "hotfix", "--file"])
+        .arg(&workflows));
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    mr.assert();
+    note.assert();
+}